@@ -0,0 +1,509 @@
+use std::path::{Path, PathBuf};
+use image::DynamicImage;
+use crate::core::{cropper, exporter, naming, validator};
+use crate::core::cropper::{CropOptions, FitMode};
+use crate::core::exporter::ExportItem;
+use crate::core::flipbook::Animation;
+use crate::core::icon_badge::IconBadge;
+use crate::core::image_format::{ColorDepth, OutputFormat};
+use crate::models::export_settings::{ExportSettings, IconStyle, JsonStyle};
+use crate::models::image_data::ImageData;
+use crate::models::image_size::ImageSize;
+use crate::models::painting::PaintingFrame;
+
+/*
+The stable, builder based entry points for building packs from other tools. Everything here is a
+thin layer over `core` so the app and library callers always produce identical packs.
+*/
+
+/// One source image and the crop and metadata it is exported with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Painting {
+    source: PathBuf,
+    size: ImageSize,
+    id: Option<String>,
+    filename: Option<String>,
+    name: Option<String>,
+    artist: Option<String>,
+    description: Option<String>,
+    frame: Option<PaintingFrame>,
+    weight: Option<u32>,
+    animation: Option<Animation>,
+    crop: CropOptions,
+}
+
+impl Painting {
+    /// Starts a painting cropped from `source` to `size`.
+    pub fn builder(source: impl Into<PathBuf>, size: ImageSize) -> PaintingBuilder {
+        PaintingBuilder {
+            painting: Painting {
+                source: source.into(),
+                size,
+                id: None,
+                filename: None,
+                name: None,
+                artist: None,
+                description: None,
+                frame: None,
+                weight: None,
+                animation: None,
+                crop: CropOptions::default(),
+            },
+        }
+    }
+
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    pub fn size(&self) -> ImageSize {
+        self.size
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn artist(&self) -> Option<&str> {
+        self.artist.as_deref()
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    // Positions start at 1 so the pack order and `{index}` token follow insertion order.
+    fn to_export_item(&self, order: u32) -> ExportItem {
+        let mut data = ImageData::new(self.size);
+        data.id = self.id.clone();
+        data.filename = self.filename.clone();
+        data.name = self.name.clone();
+        data.artist = self.artist.clone();
+        data.description = self.description.clone();
+        data.frame = self.frame.clone();
+        data.weight = self.weight;
+        data.animation = self.animation;
+        data.deskew = self.crop.deskew;
+        data.inset_percent = self.crop.inset_percent;
+        data.bleed_percent = self.crop.bleed_percent;
+        data.order = order;
+        ExportItem { source_path: self.source.to_string_lossy().to_string(), data }
+    }
+}
+
+/// Builder for [`Painting`]. Unset fields are filled from pack defaults at export time.
+#[derive(Debug, Clone)]
+pub struct PaintingBuilder {
+    painting: Painting,
+}
+
+impl PaintingBuilder {
+    /// Display name. Defaults to the source file name.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.painting.name = Some(name.into());
+        self
+    }
+
+    /// Artist. Defaults to the exporter's default artist.
+    pub fn artist(mut self, artist: impl Into<String>) -> Self {
+        self.painting.artist = Some(artist.into());
+        self
+    }
+
+    /// Flavor text shown in game, for mod releases that support painting descriptions.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.painting.description = Some(description.into());
+        self
+    }
+
+    /// Custom frame tint and/or texture, for mod releases that support painting frames.
+    pub fn frame(mut self, frame: PaintingFrame) -> Self {
+        self.painting.frame = Some(frame);
+        self
+    }
+
+    /// How often the painting turns up when one is placed at random, relative to the default
+    /// of 1, for mod releases that support painting weights.
+    pub fn weight(mut self, weight: u32) -> Self {
+        self.painting.weight = Some(weight);
+        self
+    }
+
+    /// Plays the source as a flipbook: its frames stacked top to bottom, see `core::flipbook`.
+    pub fn animation(mut self, animation: Animation) -> Self {
+        self.painting.animation = Some(animation);
+        self
+    }
+
+    /// Painting ID; sanitized on export. Defaults to the filename, then the name.
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.painting.id = Some(id.into());
+        self
+    }
+
+    /// Value of the `{filename}` token in the exporter's filename template.
+    pub fn filename(mut self, filename: impl Into<String>) -> Self {
+        self.painting.filename = Some(filename.into());
+        self
+    }
+
+    /// Straighten a tilted photo before cropping.
+    pub fn deskew(mut self, deskew: bool) -> Self {
+        self.painting.crop.deskew = deskew;
+        self
+    }
+
+    /// Trim this share of each edge off the source before cropping, e.g. a scan's white border.
+    pub fn inset_percent(mut self, percent: f32) -> Self {
+        self.painting.crop.inset_percent = percent;
+        self
+    }
+
+    /// Over-crop by this share of each edge to hide edge artifacts.
+    pub fn bleed_percent(mut self, percent: f32) -> Self {
+        self.painting.crop.bleed_percent = percent;
+        self
+    }
+
+    pub fn build(self) -> Painting {
+        self.painting
+    }
+}
+
+/// A pack's metadata and paintings, in the order they are written to custompaintings.json.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pack {
+    name: String,
+    version: String,
+    id: String,
+    description: String,
+    paintings: Vec<Painting>,
+}
+
+impl Pack {
+    pub fn builder(name: impl Into<String>) -> PackBuilder {
+        PackBuilder {
+            name: name.into(),
+            version: String::from("1.0.0"),
+            id: None,
+            description: String::new(),
+            paintings: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// The sanitized pack ID.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn paintings(&self) -> &[Painting] {
+        &self.paintings
+    }
+}
+
+/// Builder for [`Pack`].
+#[derive(Debug, Clone)]
+pub struct PackBuilder {
+    name: String,
+    version: String,
+    id: Option<String>,
+    description: String,
+    paintings: Vec<Painting>,
+}
+
+impl PackBuilder {
+    /// Defaults to `1.0.0`.
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// Defaults to the sanitized pack name.
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    pub fn painting(mut self, painting: Painting) -> Self {
+        self.paintings.push(painting);
+        self
+    }
+
+    pub fn paintings(mut self, paintings: impl IntoIterator<Item = Painting>) -> Self {
+        self.paintings.extend(paintings);
+        self
+    }
+
+    /// Errors if the name or ID is blank or the pack has no paintings.
+    pub fn build(self) -> Result<Pack, String> {
+        let name = self.name.trim().to_string();
+        if name.is_empty() {
+            return Err(String::from("The pack needs a name"));
+        }
+        let id = validator::sanitize_id(self.id.as_deref().unwrap_or(&name));
+        if id.is_empty() {
+            return Err(format!("Could not derive a pack ID from \"{}\"", name));
+        }
+        if self.paintings.is_empty() {
+            return Err(String::from("The pack needs at least one painting"));
+        }
+        Ok(Pack {
+            name,
+            version: self.version,
+            id,
+            description: self.description,
+            paintings: self.paintings,
+        })
+    }
+}
+
+/// Produces painting crops from source images. By default crops are centred and as large as the source allows.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cropper {
+    options: CropOptions,
+}
+
+impl Cropper {
+    pub fn new() -> Self {
+        Cropper::default()
+    }
+
+    /// Straighten every source before cropping.
+    pub fn deskew(mut self, deskew: bool) -> Self {
+        self.options.deskew = deskew;
+        self
+    }
+
+    /// Trim this share of each edge off every source before cropping.
+    pub fn inset_percent(mut self, percent: f32) -> Self {
+        self.options.inset_percent = percent;
+        self
+    }
+
+    /// Over-crop by this share of each edge.
+    pub fn bleed_percent(mut self, percent: f32) -> Self {
+        self.options.bleed_percent = percent;
+        self
+    }
+
+    /// Letterbox or stretch sources to each shape instead of cropping them.
+    pub fn fit_mode(mut self, fit: FitMode) -> Self {
+        self.options.fit = fit;
+        self
+    }
+
+    /// Crops `source` to one painting shape.
+    pub fn crop(&self, source: impl AsRef<Path>, size: ImageSize) -> Result<DynamicImage, String> {
+        let path = source.as_ref().to_string_lossy();
+        cropper::crop_single_image(&path, &size, &self.options).map_err(|e| format!("Failed to crop {}: {}", path, e))
+    }
+
+    /// Crops `source` to every painting shape, in `ImageSize::iter()` order.
+    pub fn crop_all(&self, source: impl AsRef<Path>) -> Result<Vec<(ImageSize, DynamicImage)>, String> {
+        let path = source.as_ref().to_string_lossy();
+        let crops = cropper::generate_cropped_images(&path, &[self.options])
+            .map_err(|e| format!("Failed to crop {}: {}", path, e))?;
+        Ok(ImageSize::iter().copied().zip(crops).collect())
+    }
+}
+
+/// Writes packs to disk. Wraps [`ExportSettings`]; see its fields for what each option does.
+#[derive(Debug, Clone)]
+pub struct Exporter {
+    output_dir: PathBuf,
+    settings: ExportSettings,
+}
+
+impl Exporter {
+    /// Exports into `output_dir`, one folder per pack, with default settings.
+    pub fn new(output_dir: impl Into<PathBuf>) -> Self {
+        Exporter { output_dir: output_dir.into(), settings: ExportSettings::default() }
+    }
+
+    /// Replaces all settings at once.
+    pub fn settings(mut self, settings: ExportSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Artist for paintings that don't set one.
+    pub fn default_artist(mut self, artist: impl Into<String>) -> Self {
+        self.settings.default_artist = artist.into();
+        self
+    }
+
+    /// Texture density; full resolution when not set.
+    pub fn pixels_per_block(mut self, pixels_per_block: u32) -> Self {
+        self.settings.pixels_per_block = Some(pixels_per_block);
+        self
+    }
+
+    /// Scale textures wider than `max_width` down to it; `None` keeps the source resolution.
+    /// 1024 by default.
+    pub fn max_texture_width(mut self, max_width: Option<u32>) -> Self {
+        self.settings.max_texture_width = max_width;
+        self
+    }
+
+    /// Keep block sizes the source is too small to fill at the chosen density. Off by default.
+    pub fn force_undersized_variants(mut self, force: bool) -> Self {
+        self.settings.force_undersized_variants = force;
+        self
+    }
+
+    /// See `naming::render_filename` for the available tokens.
+    pub fn filename_template(mut self, template: impl Into<String>) -> Self {
+        self.settings.filename_template = template.into();
+        self
+    }
+
+    pub fn icon_style(mut self, icon_style: IconStyle) -> Self {
+        self.settings.icon_style = icon_style;
+        self
+    }
+
+    /// Stamp pack initials, the version or custom text onto the pack icon.
+    pub fn icon_badge(mut self, icon_badge: IconBadge) -> Self {
+        self.settings.icon_badge = Some(icon_badge);
+        self
+    }
+
+    /// Layout of custompaintings.json; pretty printed with a 2 space indent by default.
+    pub fn json_style(mut self, json_style: JsonStyle) -> Self {
+        self.settings.json_style = json_style;
+        self
+    }
+
+    /// Texture format; the export fails if the target mod release can't load it.
+    pub fn output_format(mut self, output_format: OutputFormat) -> Self {
+        self.settings.output_format = output_format;
+        self
+    }
+
+    /// Colour depth of the textures; paintings that need transparency still get an alpha channel.
+    pub fn color_depth(mut self, color_depth: ColorDepth) -> Self {
+        self.settings.color_depth = color_depth;
+        self
+    }
+
+    /// Refuse to export packs with more than `cap` paintings, counting every block size.
+    pub fn painting_cap(mut self, cap: usize) -> Self {
+        self.settings.painting_cap = Some(cap);
+        self
+    }
+
+    /// Write the pack as one `.zip` archive instead of a folder; `export` then returns its path.
+    pub fn archive(mut self, archive: bool) -> Self {
+        self.settings.archive = archive;
+        self
+    }
+
+    /// Also write `preview.png`, every painting side by side at relative scale, into the pack.
+    pub fn preview_strip(mut self, preview_strip: bool) -> Self {
+        self.settings.preview_strip = preview_strip;
+        self
+    }
+
+    /// Save a copy of any existing pack in the output folder before overwriting it.
+    pub fn backup_existing(mut self, backup: bool) -> Self {
+        self.settings.backup_existing = backup;
+        self
+    }
+
+    /// Writes the pack and returns the folder it was written to.
+    pub fn export(&self, pack: &Pack) -> Result<PathBuf, String> {
+        naming::validate_filename_template(&self.settings.filename_template)?;
+        if let Some(missing) = pack.paintings.iter().find(|p| !p.source.is_file()) {
+            return Err(format!("Source image {} does not exist", missing.source.display()));
+        }
+
+        let output_dir = exporter::resolve_export_path(&self.output_dir.to_string_lossy(), &pack.id, &pack.version);
+        let items = pack.paintings.iter()
+            .enumerate()
+            .map(|(index, painting)| painting.to_export_item(index as u32 + 1))
+            .collect();
+        exporter::export(
+            pack.name.clone(),
+            pack.version.clone(),
+            pack.id.clone(),
+            pack.description.clone(),
+            items,
+            &output_dir,
+            &self.settings,
+        )?;
+        Ok(PathBuf::from(exporter::pack_output_path(&output_dir, &pack.name, &self.settings)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, RgbaImage};
+    use std::{env, fs};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_dir() -> PathBuf {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = env::temp_dir().join(format!("test_api_{:x}", nanos));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_pack_builder_validates() {
+        let painting = Painting::builder("a.png", ImageSize::Square).build();
+        let pack = Pack::builder("Forest Pack").painting(painting.clone()).build().unwrap();
+        assert_eq!(pack.id(), "forest_pack");
+        assert_eq!(pack.version(), "1.0.0");
+
+        assert!(Pack::builder("  ").painting(painting).build().is_err());
+        assert!(Pack::builder("Empty").build().unwrap_err().contains("at least one painting"));
+    }
+
+    #[test]
+    fn test_build_and_export_pack() {
+        let dir = temp_dir();
+        let source = dir.join("valley.png");
+        RgbaImage::new(800, 400).save(&source).unwrap();
+
+        let crops = Cropper::new().crop_all(&source).unwrap();
+        assert_eq!(crops.len(), 5);
+        assert_eq!(crops[1].1.dimensions(), (800, 400));
+
+        let pack = Pack::builder("Valley Pack")
+            .description("Wide views")
+            .painting(Painting::builder(&source, ImageSize::Wide).name("Valley").build())
+            .build()
+            .unwrap();
+        let pack_dir = Exporter::new(&dir)
+            .default_artist("Me")
+            .pixels_per_block(16)
+            .export(&pack)
+            .unwrap();
+
+        assert_eq!(pack_dir, dir.join("Valley_Pack"));
+        let small = image::open(pack_dir.join("images/Valley_2x1.png")).unwrap();
+        assert_eq!(small.dimensions(), (32, 16));
+        let json = fs::read_to_string(pack_dir.join("custompaintings.json")).unwrap();
+        assert!(json.contains(r#""artist": "Me""#));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
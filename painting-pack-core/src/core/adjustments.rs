@@ -0,0 +1,91 @@
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+// Smallest difference the unsharp mask acts on, so flat areas and noise aren't sharpened.
+const SHARPEN_THRESHOLD: i32 = 2;
+
+/*
+Tone and detail tweaks applied to an exported texture after it has been scaled to its block size.
+Every field defaults to leaving the texture as it is.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(default)]
+pub struct Adjustments {
+    // Unsharp mask radius in pixels, 0-10; 0 is off. Small textures lose the most detail when downscaled.
+    pub sharpen: f32,
+    // Added to every colour channel, -255 to 255.
+    pub brightness: i32,
+    // Percent, -100 to 100; positive values increase contrast.
+    pub contrast: f32,
+}
+
+impl Adjustments {
+    // Errors on values outside the documented ranges.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=10.0).contains(&self.sharpen) {
+            return Err(String::from("Sharpening must be between 0 and 10"));
+        }
+        if !(-255..=255).contains(&self.brightness) {
+            return Err(String::from("Brightness must be between -255 and 255"));
+        }
+        if !(-100.0..=100.0).contains(&self.contrast) {
+            return Err(String::from("Contrast must be between -100 and 100"));
+        }
+        Ok(())
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Adjustments::default()
+    }
+
+    // The texture with brightness, then contrast, then sharpening applied.
+    pub fn apply(&self, texture: &DynamicImage) -> DynamicImage {
+        let mut adjusted = texture.clone();
+        if self.brightness != 0 {
+            adjusted = adjusted.brighten(self.brightness);
+        }
+        if self.contrast != 0.0 {
+            adjusted = adjusted.adjust_contrast(self.contrast);
+        }
+        if self.sharpen > 0.0 {
+            adjusted = adjusted.unsharpen(self.sharpen, SHARPEN_THRESHOLD);
+        }
+        adjusted
+    }
+}
+
+// Adjustments for one block size of a painting, e.g. extra sharpening on the 1x1 only.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SizeAdjustment {
+    pub width: u32,
+    pub height: u32,
+    pub adjustments: Adjustments,
+}
+
+// The adjustments set for the `width` x `height` variant in `overrides`, if any.
+pub fn for_size(overrides: &[SizeAdjustment], width: u32, height: u32) -> Option<&Adjustments> {
+    overrides
+        .iter()
+        .find(|adjustment| (adjustment.width, adjustment.height) == (width, height))
+        .map(|adjustment| &adjustment.adjustments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, Rgba, RgbaImage};
+
+    #[test]
+    fn test_adjustments_apply_only_where_set() {
+        let texture = DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255])));
+        assert_eq!(Adjustments::default().apply(&texture), texture);
+
+        let brighter = Adjustments { brightness: 20, ..Adjustments::default() }.apply(&texture);
+        assert_eq!(brighter.get_pixel(0, 0), Rgba([120, 120, 120, 255]));
+
+        let overrides = [SizeAdjustment { width: 1, height: 1, adjustments: Adjustments { sharpen: 1.5, ..Adjustments::default() } }];
+        assert_eq!(for_size(&overrides, 1, 1).map(|adjustments| adjustments.sharpen), Some(1.5));
+        assert!(for_size(&overrides, 2, 2).is_none());
+        assert!(Adjustments { contrast: 150.0, ..Adjustments::default() }.validate().is_err());
+    }
+}
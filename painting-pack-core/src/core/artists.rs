@@ -0,0 +1,86 @@
+use serde::Serialize;
+
+// One spelling of an artist exactly as it appears in the library, and how many paintings use it.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ArtistVariant {
+    pub artist: String,
+    pub count: usize,
+}
+
+/*
+Spellings that probably name the same artist ("van gogh", "Van Gogh ", "V. van Gogh"), with the
+most used one, trimmed, suggested as the name to merge them into.
+*/
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ArtistGroup {
+    pub suggested: String,
+    pub total: usize,
+    // Most used first.
+    pub variants: Vec<ArtistVariant>,
+}
+
+/*
+What two spellings of one artist have in common: lowercase words without punctuation, leaving out
+initials, so case, spacing and an abbreviated first name don't tell them apart.
+*/
+pub fn artist_key(artist: &str) -> String {
+    let words: Vec<String> = artist
+        .split(|c: char| c.is_whitespace() || c == '.' || c == ',')
+        .map(|word| word.chars().filter(|c| c.is_alphanumeric() || *c == '-' || *c == '\'').collect::<String>().to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect();
+    let without_initials: Vec<&str> = words.iter().map(String::as_str).filter(|word| word.chars().count() > 1).collect();
+    // A name made only of initials is kept whole.
+    if without_initials.is_empty() { words.join(" ") } else { without_initials.join(" ") }
+}
+
+/*
+Every distinct artist string with its count, grouped by `artist_key`. Groups come most used first,
+then in order of first appearance; a group with more than one variant is worth merging.
+*/
+pub fn group_artists<'a>(artists: impl IntoIterator<Item = &'a str>) -> Vec<ArtistGroup> {
+    let mut groups: Vec<(String, ArtistGroup)> = Vec::new();
+    for artist in artists {
+        let key = artist_key(artist);
+        let index = match groups.iter().position(|(existing, _)| *existing == key) {
+            Some(index) => index,
+            None => {
+                groups.push((key, ArtistGroup { suggested: String::new(), total: 0, variants: Vec::new() }));
+                groups.len() - 1
+            }
+        };
+        let group = &mut groups[index].1;
+        group.total += 1;
+        match group.variants.iter_mut().find(|variant| variant.artist == artist) {
+            Some(variant) => variant.count += 1,
+            None => group.variants.push(ArtistVariant { artist: artist.to_string(), count: 1 }),
+        }
+    }
+
+    let mut groups: Vec<ArtistGroup> = groups.into_iter().map(|(_, group)| group).collect();
+    for group in &mut groups {
+        group.variants.sort_by_key(|variant| std::cmp::Reverse(variant.count));
+        group.suggested = group.variants[0].artist.split_whitespace().collect::<Vec<&str>>().join(" ");
+    }
+    groups.sort_by_key(|group| std::cmp::Reverse(group.total));
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spellings_of_one_artist_are_grouped() {
+        let library = ["Van Gogh", "van gogh", "Monet", "Van Gogh ", "V. van Gogh", "Van Gogh", "C. Monet", "Mojang Studios"];
+        let groups = group_artists(library);
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].suggested, "Van Gogh");
+        assert_eq!(groups[0].total, 5);
+        let variants: Vec<(&str, usize)> = groups[0].variants.iter().map(|variant| (variant.artist.as_str(), variant.count)).collect();
+        assert_eq!(variants, vec![("Van Gogh", 2), ("van gogh", 1), ("Van Gogh ", 1), ("V. van Gogh", 1)]);
+        assert_eq!(groups[1].variants.len(), 2);
+        assert_eq!(artist_key("J. R."), "j r");
+    }
+}
@@ -0,0 +1,98 @@
+use serde::Serialize;
+use crate::core::{exporter, font};
+
+// Outcome of checking one asset built into the app.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AssetCheck {
+    pub asset: &'static str,
+    pub ok: bool,
+    // What was found, or what is wrong with it.
+    pub detail: String,
+}
+
+/*
+Result of checking the assets built into this build of the app. Run on every start, so a build
+that was updated in place is checked before its first export rather than failing partway through.
+*/
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AssetReport {
+    pub version: &'static str,
+    pub checks: Vec<AssetCheck>,
+}
+
+impl AssetReport {
+    pub fn healthy(&self) -> bool {
+        self.checks.iter().all(|check| check.ok)
+    }
+
+    // One line per asset that failed its check.
+    pub fn problems(&self) -> Vec<String> {
+        self.checks.iter().filter(|check| !check.ok).map(|check| format!("{}: {}", check.asset, check.detail)).collect()
+    }
+}
+
+/*
+Checks that `bytes` is an icon packs can fall back to: a PNG that decodes to a square image.
+Returns its size.
+*/
+pub fn check_icon(bytes: &[u8]) -> Result<String, String> {
+    if bytes.is_empty() {
+        return Err("The icon is empty".to_string());
+    }
+    let icon = image::load_from_memory_with_format(bytes, image::ImageFormat::Png)
+        .map_err(|e| format!("The icon isn't a readable PNG: {}", e))?;
+    if icon.width() == 0 || icon.width() != icon.height() {
+        return Err(format!("The icon is {}x{} px, but must be square", icon.width(), icon.height()));
+    }
+    Ok(format!("{}x{} px PNG", icon.width(), icon.height()))
+}
+
+// Checks that the pixel font has a glyph for every printable character.
+pub fn check_font() -> Result<String, String> {
+    let blank = font::blank_glyphs();
+    if !blank.is_empty() {
+        return Err(format!("No glyph for {}", blank.iter().map(|c| format!("'{}'", c)).collect::<Vec<_>>().join(", ")));
+    }
+    Ok("Every printable ASCII character has a glyph".to_string())
+}
+
+/*
+Checks the default pack icon and the pixel font used for text paintings and icon badges.
+*/
+pub fn check_bundled_assets() -> AssetReport {
+    let check = |asset: &'static str, result: Result<String, String>| match result {
+        Ok(detail) => AssetCheck { asset, ok: true, detail },
+        Err(detail) => AssetCheck { asset, ok: false, detail },
+    };
+    AssetReport {
+        version: env!("CARGO_PKG_VERSION"),
+        checks: vec![
+            check("Default pack icon", check_icon(exporter::DEFAULT_ICON)),
+            check("Pixel font", check_font()),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_assets_pass_and_broken_icons_are_reported() {
+        let report = check_bundled_assets();
+        assert!(report.healthy(), "{:?}", report.problems());
+        assert_eq!(report.checks.len(), 2);
+
+        assert!(check_icon(&[]).is_err());
+        assert!(check_icon(&exporter::DEFAULT_ICON[..exporter::DEFAULT_ICON.len() / 2]).is_err());
+        let mut wide = Vec::new();
+        image::DynamicImage::new_rgba8(4, 2)
+            .write_to(&mut std::io::Cursor::new(&mut wide), image::ImageFormat::Png)
+            .unwrap();
+        assert_eq!(check_icon(&wide), Err("The icon is 4x2 px, but must be square".to_string()));
+
+        let broken = AssetReport { version: "0", checks: vec![AssetCheck { asset: "Icon", ok: false, detail: "Missing".to_string() }] };
+        assert!(!broken.healthy());
+        assert_eq!(broken.problems(), ["Icon: Missing"]);
+    }
+}
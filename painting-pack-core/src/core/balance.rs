@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use crate::models::image_size::ImageSize;
+
+// How much of the pack should use one painting shape. Weights are relative, not percentages.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SizeWeight {
+    pub size: ImageSize,
+    pub weight: f32,
+}
+
+// A varied mix that leans towards the shapes most photos crop well to.
+pub fn default_distribution() -> Vec<SizeWeight> {
+    vec![
+        SizeWeight { size: ImageSize::Square, weight: 0.3 },
+        SizeWeight { size: ImageSize::Wide, weight: 0.25 },
+        SizeWeight { size: ImageSize::LongRectangle, weight: 0.15 },
+        SizeWeight { size: ImageSize::Tall, weight: 0.2 },
+        SizeWeight { size: ImageSize::TallRectangle, weight: 0.1 },
+    ]
+}
+
+fn aspect(size: ImageSize) -> f32 {
+    let (w, h) = size.get_size()[0];
+    w as f32 / h as f32
+}
+
+// How badly an image would be cropped to fit a shape; 0 means a perfect fit.
+fn crop_cost(image_aspect: f32, size: ImageSize) -> f32 {
+    (image_aspect.ln() - aspect(size).ln()).abs()
+}
+
+/*
+Turns relative weights into a whole number of paintings per shape that adds up to `count`,
+handing out the remainders to the shapes with the largest fractional share.
+*/
+fn quotas(distribution: &[SizeWeight], count: usize) -> Vec<usize> {
+    let total: f32 = distribution.iter().map(|w| w.weight.max(0.0)).sum();
+    if total <= 0.0 {
+        return vec![0; distribution.len()];
+    }
+
+    let exact: Vec<f32> = distribution.iter().map(|w| w.weight.max(0.0) / total * count as f32).collect();
+    let mut quotas: Vec<usize> = exact.iter().map(|e| e.floor() as usize).collect();
+    let mut by_remainder: Vec<usize> = (0..exact.len()).collect();
+    by_remainder.sort_by(|a, b| (exact[*b] - exact[*b].floor()).total_cmp(&(exact[*a] - exact[*a].floor())));
+    let missing = count - quotas.iter().sum::<usize>();
+    for index in by_remainder.into_iter().take(missing) {
+        quotas[index] += 1;
+    }
+    quotas
+}
+
+/*
+Chooses one shape per image so the pack follows the target distribution while each image goes
+to the shape that crops it least. The best (image, shape) fits are assigned first until each
+shape's quota is used up. `aspects` are source width / height ratios.
+*/
+pub fn assign_sizes(aspects: &[f32], distribution: &[SizeWeight]) -> Vec<ImageSize> {
+    let mut remaining = quotas(distribution, aspects.len());
+
+    let mut pairs: Vec<(usize, usize, f32)> = Vec::new();
+    for (image, image_aspect) in aspects.iter().enumerate() {
+        for (shape, weight) in distribution.iter().enumerate() {
+            pairs.push((image, shape, crop_cost(*image_aspect, weight.size)));
+        }
+    }
+    pairs.sort_by(|a, b| a.2.total_cmp(&b.2));
+
+    let mut assigned: Vec<Option<ImageSize>> = vec![None; aspects.len()];
+    for (image, shape, _) in pairs {
+        if assigned[image].is_none() && remaining[shape] > 0 {
+            assigned[image] = Some(distribution[shape].size);
+            remaining[shape] -= 1;
+        }
+    }
+
+    // Only reachable when every weight is zero: fall back to the best fit.
+    assigned
+        .into_iter()
+        .zip(aspects)
+        .map(|(size, image_aspect)| {
+            size.unwrap_or_else(|| {
+                *ImageSize::iter()
+                    .min_by(|a, b| crop_cost(*image_aspect, **a).total_cmp(&crop_cost(*image_aspect, **b)))
+                    .unwrap()
+            })
+        })
+        .collect()
+}
+
+// Images more than this much wider than tall (or taller than wide) count as landscape (or portrait).
+const ORIENTATION_TOLERANCE: f32 = 1.2;
+
+// 1 for landscape, -1 for portrait, 0 for roughly square.
+fn orientation(aspect: f32) -> i8 {
+    if aspect > ORIENTATION_TOLERANCE {
+        1
+    } else if aspect < 1.0 / ORIENTATION_TOLERANCE {
+        -1
+    } else {
+        0
+    }
+}
+
+// An image given one shape for a whole batch that leans the other way, e.g. a portrait photo in Wide.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct OrientationWarning {
+    pub group_index: usize,
+    pub name: String,
+    pub assigned: ImageSize,
+    // Shapes that would crop the image less, best first.
+    pub suggested: Vec<ImageSize>,
+}
+
+/*
+The shapes that fit an image better than `size`, best first, if the image and `size` lean
+opposite ways (a portrait in a landscape shape or the other way round). `None` if they don't;
+square shapes and roughly square images never conflict.
+*/
+pub fn orientation_conflict(image_aspect: f32, size: ImageSize) -> Option<Vec<ImageSize>> {
+    if orientation(image_aspect) * orientation(aspect(size)) >= 0 {
+        return None;
+    }
+    let assigned_cost = crop_cost(image_aspect, size);
+    let mut better: Vec<ImageSize> = ImageSize::iter()
+        .copied()
+        .filter(|shape| crop_cost(image_aspect, *shape) < assigned_cost && orientation(image_aspect) * orientation(aspect(*shape)) >= 0)
+        .collect();
+    better.sort_by(|a, b| crop_cost(image_aspect, *a).total_cmp(&crop_cost(image_aspect, *b)));
+    Some(better)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quotas_sum_to_count() {
+        let quotas = quotas(&default_distribution(), 40);
+        assert_eq!(quotas.iter().sum::<usize>(), 40);
+        assert_eq!(quotas, vec![12, 10, 6, 8, 4]);
+    }
+
+    #[test]
+    fn test_images_go_to_the_shape_they_fit() {
+        let distribution = vec![
+            SizeWeight { size: ImageSize::Wide, weight: 1.0 },
+            SizeWeight { size: ImageSize::Tall, weight: 1.0 },
+        ];
+        let sizes = assign_sizes(&[0.5, 2.1, 0.6, 1.9], &distribution);
+        assert_eq!(sizes, vec![ImageSize::Tall, ImageSize::Wide, ImageSize::Tall, ImageSize::Wide]);
+    }
+
+    #[test]
+    fn test_only_opposite_orientations_conflict() {
+        // A 3:4 portrait forced into Wide should go Tall or 3x4 instead.
+        assert_eq!(orientation_conflict(0.75, ImageSize::Wide), Some(vec![ImageSize::TallRectangle, ImageSize::Square, ImageSize::Tall]));
+        assert_eq!(orientation_conflict(2.5, ImageSize::Tall).unwrap()[0], ImageSize::Wide);
+        assert_eq!(orientation_conflict(0.75, ImageSize::Tall), None);
+        assert_eq!(orientation_conflict(1.1, ImageSize::Wide), None);
+        assert_eq!(orientation_conflict(0.3, ImageSize::Square), None);
+    }
+
+    #[test]
+    fn test_distribution_spreads_identical_images() {
+        // Forty identical square photos shouldn't all end up square.
+        let sizes = assign_sizes(&[1.0; 40], &default_distribution());
+        let squares = sizes.iter().filter(|s| **s == ImageSize::Square).count();
+        let wides = sizes.iter().filter(|s| **s == ImageSize::Wide).count();
+        assert_eq!(squares, 12);
+        assert_eq!(wides, 10);
+    }
+}
@@ -0,0 +1,52 @@
+use serde::Serialize;
+use crate::core::{hot_folder, lint::{self, ModRules}};
+
+/*
+What this build can do and what the targeted pack format allows, so the frontend can hide
+options that would only fail at export time. Optional features are reported as compiled in;
+HEIC, RAW and AI upscaling have no implementation in this tree yet and are always `false`.
+*/
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Capabilities {
+    pub heic_import: bool,
+    pub raw_import: bool,
+    pub ai_upscale: bool,
+    // wgpu resizing (`gpu` feature). Falls back to the CPU at runtime if no adapter is found.
+    pub gpu_resize: bool,
+    // API-backed metadata suggestions (`suggest-api` feature); the offline suggester is always there.
+    pub suggest_api: bool,
+    // Source file extensions the import dialog and watched folders accept.
+    pub import_extensions: &'static [&'static str],
+    // Mod releases a pack can target, oldest first.
+    pub mod_versions: Vec<&'static str>,
+    // Limits of the targeted release, including its texture formats.
+    pub pack_format: &'static ModRules,
+}
+
+pub fn capabilities(target_mod_version: &str) -> Capabilities {
+    Capabilities {
+        heic_import: false,
+        raw_import: false,
+        ai_upscale: false,
+        gpu_resize: cfg!(feature = "gpu"),
+        suggest_api: cfg!(feature = "suggest-api"),
+        import_extensions: hot_folder::SUPPORTED_EXTENSIONS,
+        mod_versions: lint::RULES.iter().map(|rules| rules.mod_version).collect(),
+        pack_format: lint::rules_for(target_mod_version),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities_follow_the_target_and_build() {
+        let old = capabilities("1.0.0");
+        assert!(!old.pack_format.painting_descriptions);
+        assert_eq!(old.gpu_resize, cfg!(feature = "gpu"));
+        assert_eq!(old.mod_versions.last().copied(), Some(lint::latest_version()));
+
+        assert!(capabilities(lint::latest_version()).pack_format.painting_descriptions);
+    }
+}
@@ -0,0 +1,166 @@
+use image::{imageops, DynamicImage, Rgba, RgbaImage};
+use serde::Deserialize;
+use crate::models::image_size::ImageSize;
+
+// Longest side of a generated collage, matching the exporter's texture cap.
+const COLLAGE_LONG_SIDE: u32 = 1024;
+
+// One cell of a custom layout, in fractions (0.0-1.0) of the area inside the border.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct CollageCell {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub enum CollageLayout {
+    // Images fill the grid left-to-right, top-to-bottom.
+    Grid { columns: u32, rows: u32 },
+    // One cell per image, in the same order as the images.
+    Custom(Vec<CollageCell>),
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct CollageOptions {
+    pub layout: CollageLayout,
+    // Painting shape of the finished collage; its first block size sets the aspect ratio.
+    pub target: ImageSize,
+    // Space between cells and around the outside, in output pixels.
+    pub gap: u32,
+    pub border: u32,
+    // RGBA colour shown in the gaps and border.
+    pub background: [u8; 4],
+}
+
+// Pixel rectangles (x, y, w, h) for each cell of the layout.
+fn cell_rects(options: &CollageOptions, width: u32, height: u32) -> Result<Vec<(u32, u32, u32, u32)>, String> {
+    let inner_w = width.saturating_sub(2 * options.border);
+    let inner_h = height.saturating_sub(2 * options.border);
+
+    let rects: Vec<(u32, u32, u32, u32)> = match &options.layout {
+        CollageLayout::Grid { columns, rows } => {
+            if *columns == 0 || *rows == 0 {
+                return Err(String::from("A collage grid needs at least one row and one column"));
+            }
+            let cell_w = inner_w.saturating_sub((columns - 1) * options.gap) / columns;
+            let cell_h = inner_h.saturating_sub((rows - 1) * options.gap) / rows;
+            (0..rows * columns)
+                .map(|i| {
+                    let (col, row) = (i % columns, i / columns);
+                    (
+                        options.border + col * (cell_w + options.gap),
+                        options.border + row * (cell_h + options.gap),
+                        cell_w,
+                        cell_h,
+                    )
+                })
+                .collect()
+        }
+        CollageLayout::Custom(cells) => cells
+            .iter()
+            .map(|cell| {
+                let x = (cell.x.clamp(0.0, 1.0) * inner_w as f32) as u32;
+                let y = (cell.y.clamp(0.0, 1.0) * inner_h as f32) as u32;
+                let w = (cell.width.clamp(0.0, 1.0) * inner_w as f32) as u32;
+                let h = (cell.height.clamp(0.0, 1.0) * inner_h as f32) as u32;
+                // Half the gap is taken from each side so neighbouring cells end up `gap` apart.
+                let half_gap = options.gap / 2;
+                (
+                    options.border + x + half_gap,
+                    options.border + y + half_gap,
+                    w.saturating_sub(options.gap).min(inner_w.saturating_sub(x)),
+                    h.saturating_sub(options.gap).min(inner_h.saturating_sub(y)),
+                )
+            })
+            .collect(),
+    };
+
+    if rects.iter().any(|(_, _, w, h)| *w == 0 || *h == 0) {
+        return Err(String::from("The gap and border leave no room for the images"));
+    }
+    Ok(rects)
+}
+
+/*
+Lays 2-4 images out into a single new painting. Each image is scaled to cover its cell and
+centre-cropped, so nothing is stretched.
+*/
+pub fn compose(sources: &[DynamicImage], options: &CollageOptions) -> Result<DynamicImage, String> {
+    if !(2..=4).contains(&sources.len()) {
+        return Err(format!("A collage combines 2 to 4 images, {} were given", sources.len()));
+    }
+
+    let (width, height) = options.target.dimensions_for_long_side(COLLAGE_LONG_SIDE);
+    let rects = cell_rects(options, width, height)?;
+    if rects.len() < sources.len() {
+        return Err(format!("The layout has {} cells but {} images were chosen", rects.len(), sources.len()));
+    }
+    if matches!(options.layout, CollageLayout::Custom(_)) && rects.len() != sources.len() {
+        return Err(String::from("A custom layout needs exactly one cell per image"));
+    }
+
+    let mut canvas = RgbaImage::from_pixel(width, height, Rgba(options.background));
+    for (source, (x, y, w, h)) in sources.iter().zip(rects) {
+        let filled = source.resize_to_fill(w, h, imageops::FilterType::Lanczos3).to_rgba8();
+        imageops::overlay(&mut canvas, &filled, x as i64, y as i64);
+    }
+
+    Ok(DynamicImage::ImageRgba8(canvas))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(colour: [u8; 4]) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(50, 80, Rgba(colour)))
+    }
+
+    fn grid_options(gap: u32, border: u32) -> CollageOptions {
+        CollageOptions {
+            layout: CollageLayout::Grid { columns: 2, rows: 2 },
+            target: ImageSize::Square,
+            gap,
+            border,
+            background: [255, 255, 255, 255],
+        }
+    }
+
+    #[test]
+    fn test_grid_collage_places_images_in_order() {
+        let sources = vec![solid([255, 0, 0, 255]), solid([0, 255, 0, 255]), solid([0, 0, 255, 255]), solid([0, 0, 0, 255])];
+        let collage = compose(&sources, &grid_options(20, 10)).unwrap().to_rgba8();
+
+        assert_eq!(collage.dimensions(), (1024, 1024));
+        assert_eq!(collage.get_pixel(200, 200).0, [255, 0, 0, 255]); // top-left
+        assert_eq!(collage.get_pixel(800, 200).0, [0, 255, 0, 255]); // top-right
+        assert_eq!(collage.get_pixel(200, 800).0, [0, 0, 255, 255]); // bottom-left
+        assert_eq!(collage.get_pixel(800, 800).0, [0, 0, 0, 255]);   // bottom-right
+        assert_eq!(collage.get_pixel(5, 5).0, [255, 255, 255, 255]); // border
+        assert_eq!(collage.get_pixel(512, 200).0, [255, 255, 255, 255]); // gap
+    }
+
+    #[test]
+    fn test_wide_target_dimensions() {
+        let mut options = grid_options(0, 0);
+        options.target = ImageSize::Wide;
+        options.layout = CollageLayout::Grid { columns: 2, rows: 1 };
+        let collage = compose(&[solid([1, 1, 1, 255]), solid([2, 2, 2, 255])], &options).unwrap();
+        assert_eq!((collage.width(), collage.height()), (1024, 512));
+    }
+
+    #[test]
+    fn test_image_count_and_layout_errors() {
+        let one = vec![solid([0, 0, 0, 255])];
+        assert!(compose(&one, &grid_options(0, 0)).is_err());
+
+        let mut options = grid_options(0, 0);
+        options.layout = CollageLayout::Grid { columns: 1, rows: 1 };
+        assert!(compose(&[solid([0, 0, 0, 255]), solid([0, 0, 0, 255])], &options).is_err());
+
+        // Gaps that eat the whole canvas are rejected rather than producing empty cells
+        assert!(compose(&[solid([0, 0, 0, 255]), solid([0, 0, 0, 255])], &grid_options(2000, 0)).is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use serde::Serialize;
+
+/*
+What the frontend must show the user before a destructive operation goes ahead. The operation
+only runs when it is called again with `token`, so a mis-wired call can't lose data on its own.
+*/
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Confirmation {
+    pub token: String,
+    pub action: String,
+    // One line for the confirm dialog, e.g. "Overwrite 12 files in /packs/Forest_Pack?"
+    pub summary: String,
+    // Everything that would be deleted or overwritten.
+    pub impact: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Pending {
+    action: String,
+    target: String,
+    impact: Vec<String>,
+}
+
+/*
+Tokens handed out and not yet used. A token is good for one use, for the action and target it was
+issued for, and only while the impact is still exactly what the user was shown; if more files
+would be affected by the time it comes back, a fresh confirmation is issued instead.
+*/
+#[derive(Debug, Default)]
+pub struct Confirmations {
+    pending: HashMap<String, Pending>,
+}
+
+impl Confirmations {
+    /*
+    Returns `None` when the action may go ahead: nothing is at stake, or `token` confirms this
+    exact impact. Otherwise returns a confirmation for the user, and the action must not run.
+    */
+    pub fn require(
+        &mut self,
+        token: Option<&str>,
+        action: &str,
+        target: &str,
+        summary: String,
+        impact: Vec<String>,
+    ) -> Option<Confirmation> {
+        let confirmed = token
+            .and_then(|token| self.pending.remove(token))
+            .is_some_and(|pending| pending.action == action && pending.target == target && pending.impact == impact);
+        if confirmed || impact.is_empty() {
+            return None;
+        }
+
+        let token = format!("{:016x}", rand::random::<u64>());
+        self.pending.insert(
+            token.clone(),
+            Pending { action: action.to_string(), target: target.to_string(), impact: impact.clone() },
+        );
+        Some(Confirmation { token, action: action.to_string(), summary, impact })
+    }
+
+    // The target a pending token was issued for, e.g. the folder an export was headed to.
+    pub fn target_of(&self, token: &str) -> Option<String> {
+        self.pending.get(token).map(|pending| pending.target.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn impact(files: &[&str]) -> Vec<String> {
+        files.iter().map(|file| file.to_string()).collect()
+    }
+
+    #[test]
+    fn test_tokens_confirm_one_exact_impact_once() {
+        let mut confirmations = Confirmations::default();
+        assert!(confirmations.require(None, "clear", "library", String::new(), Vec::new()).is_none());
+
+        let issued = confirmations.require(None, "clear", "library", String::from("Clear?"), impact(&["a"])).unwrap();
+        assert_eq!(confirmations.target_of(&issued.token).as_deref(), Some("library"));
+        assert!(confirmations.require(Some(&issued.token), "clear", "library", String::new(), impact(&["a"])).is_none());
+        // Used up
+        assert!(confirmations.require(Some(&issued.token), "clear", "library", String::new(), impact(&["a"])).is_some());
+
+        // The impact grew since the user was asked
+        let issued = confirmations.require(None, "clear", "library", String::new(), impact(&["a"])).unwrap();
+        let reissued = confirmations.require(Some(&issued.token), "clear", "library", String::new(), impact(&["a", "b"])).unwrap();
+        assert_ne!(reissued.token, issued.token);
+
+        // Tokens don't carry over to other actions
+        assert!(confirmations.require(Some(&reissued.token), "export", "library", String::new(), impact(&["a", "b"])).is_some());
+    }
+}
@@ -0,0 +1,274 @@
+use std::fs::write;
+use std::path::Path;
+use image::{codecs::jpeg::JpegEncoder, DynamicImage, Rgb, RgbImage};
+use crate::core::{cropper, exporter::ExportItem, flipbook, resample};
+
+// A4 portrait, in PDF points (1/72 inch).
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 36.0;
+// Room at the top of every page for the pack's name and page number.
+const HEADER_HEIGHT: f32 = 40.0;
+const COLUMNS: usize = 3;
+const ROWS: usize = 4;
+// Thumbnails are drawn inside a box this tall, as wide as their cell allows.
+const THUMBNAIL_HEIGHT: f32 = 120.0;
+// Pixels per point of the embedded thumbnails; 2 prints sharply at about 150 dpi.
+const PIXELS_PER_POINT: f32 = 2.0;
+const JPEG_QUALITY: u8 = 85;
+
+// One source image on the sheet: its thumbnail and the text printed under it.
+#[derive(Debug, Clone)]
+pub struct SheetEntry {
+    // `None` if the source couldn't be read; an empty box is drawn instead.
+    pub thumbnail: Option<RgbImage>,
+    pub name: String,
+    pub artist: String,
+    // Every block size exported from the source, e.g. [(1, 1), (2, 2)].
+    pub sizes: Vec<(u32, u32)>,
+}
+
+/*
+One entry per source image in `items`, in order: its first crop as a thumbnail (the first frame of
+a flipbook), with the name and artist of that crop and the sizes of all its crops.
+*/
+pub fn entries_for_items(items: &[ExportItem], default_artist: &str) -> Vec<SheetEntry> {
+    let cell_width = (PAGE_WIDTH - 2.0 * MARGIN) / COLUMNS as f32 - 12.0;
+    let (max_width, max_height) = ((cell_width * PIXELS_PER_POINT) as u32, (THUMBNAIL_HEIGHT * PIXELS_PER_POINT) as u32);
+    let mut entries: Vec<(String, SheetEntry)> = Vec::new();
+    for item in items {
+        if let Some((_, entry)) = entries.iter_mut().find(|(source, _)| *source == item.source_path) {
+            entry.sizes.extend(item.data.get_sizes());
+            continue;
+        }
+        let options = item.data.crop_options();
+        let thumbnail = match cropper::crop_single_image(&item.source_path, &item.data.image_size, &options) {
+            Ok(crop) => Some(flatten(&resample::fit_within(&flipbook::frame(&crop, options.frames, 0), max_width, max_height))),
+            Err(e) => {
+                eprintln!("[CONTACT SHEET] No thumbnail for {}: {}", item.source_path, e);
+                None
+            }
+        };
+        let artist = item.data.artist.clone().filter(|artist| !artist.trim().is_empty()).unwrap_or_else(|| default_artist.to_string());
+        entries.push((item.source_path.clone(), SheetEntry {
+            thumbnail,
+            name: item.data.name.clone().unwrap_or_default(),
+            artist,
+            sizes: item.data.get_sizes().to_vec(),
+        }));
+    }
+    entries.into_iter().map(|(_, entry)| entry).collect()
+}
+
+// The image on white, as JPEG has no transparency.
+fn flatten(image: &DynamicImage) -> RgbImage {
+    let rgba = image.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let over_white = |channel: u8| ((channel as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8;
+        Rgb([over_white(r), over_white(g), over_white(b)])
+    })
+}
+
+// PDF objects, numbered from 1 in the order they were reserved.
+struct PdfObjects {
+    bodies: Vec<Vec<u8>>,
+}
+
+impl PdfObjects {
+    fn reserve(&mut self) -> usize {
+        self.bodies.push(Vec::new());
+        self.bodies.len()
+    }
+
+    fn set(&mut self, id: usize, body: Vec<u8>) {
+        self.bodies[id - 1] = body;
+    }
+
+    fn add(&mut self, body: Vec<u8>) -> usize {
+        let id = self.reserve();
+        self.set(id, body);
+        id
+    }
+
+    // The finished file, with its cross-reference table pointing at every object.
+    fn finish(self, root: usize) -> Vec<u8> {
+        let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::new();
+        for (index, body) in self.bodies.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n", index + 1).bytes());
+            pdf.extend(body);
+            pdf.extend(b"\nendobj\n");
+        }
+        let xref = pdf.len();
+        pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1).bytes());
+        for offset in offsets {
+            pdf.extend(format!("{:010} 00000 n \n", offset).bytes());
+        }
+        pdf.extend(format!("trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n", self.bodies.len() + 1, root, xref).bytes());
+        pdf
+    }
+}
+
+fn stream(dictionary: &str, data: &[u8]) -> Vec<u8> {
+    let mut body = format!("<< {} /Length {} >>\nstream\n", dictionary, data.len()).into_bytes();
+    body.extend(data);
+    body.extend(b"\nendstream");
+    body
+}
+
+/*
+`text` as a PDF string in the standard fonts' WinAnsi encoding, cut short with "..." if it would
+run past `max_width` points at `size`. Characters outside Latin-1 print as '?'. Widths are
+estimated from Helvetica's average character width.
+*/
+fn pdf_string(text: &str, size: f32, max_width: f32) -> Vec<u8> {
+    let max_chars = (max_width / (size * 0.55)) as usize;
+    let mut chars: Vec<char> = text.chars().collect();
+    if chars.len() > max_chars {
+        chars.truncate(max_chars.saturating_sub(3));
+        chars.extend(['.', '.', '.']);
+    }
+    let mut string = vec![b'('];
+    for c in chars {
+        match c {
+            '(' | ')' | '\\' => string.extend([b'\\', c as u8]),
+            ' '..='~' => string.push(c as u8),
+            '\u{a0}'..='\u{ff}' => string.push(c as u32 as u8),
+            c if c.is_whitespace() => string.push(b' '),
+            _ => string.push(b'?'),
+        }
+    }
+    string.push(b')');
+    string
+}
+
+// Drawing operators for one line of text with its baseline starting at `x`, `y`.
+fn text_line(content: &mut Vec<u8>, font: &str, size: f32, x: f32, y: f32, text: &str, max_width: f32) {
+    content.extend(format!("BT /{} {} Tf {:.2} {:.2} Td ", font, size, x, y).bytes());
+    content.extend(pdf_string(text, size, max_width));
+    content.extend(b" Tj ET\n");
+}
+
+fn format_sizes(sizes: &[(u32, u32)]) -> String {
+    sizes.iter().map(|(width, height)| format!("{}x{}", width, height)).collect::<Vec<_>>().join(", ")
+}
+
+/*
+A printable PDF contact sheet: every entry's thumbnail with its name, artist and block sizes
+underneath, twelve to an A4 page, under a header with `title`, `subtitle` and the page number.
+Uses only the PDF standard fonts, so it opens anywhere without embedded font files.
+*/
+pub fn render(title: &str, subtitle: &str, entries: &[SheetEntry]) -> Result<Vec<u8>, String> {
+    let mut objects = PdfObjects { bodies: Vec::new() };
+    let catalog = objects.reserve();
+    let pages = objects.reserve();
+    let regular = objects.add(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec());
+    let bold = objects.add(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_vec());
+
+    let per_page = COLUMNS * ROWS;
+    let page_count = entries.len().div_ceil(per_page).max(1);
+    let cell_width = (PAGE_WIDTH - 2.0 * MARGIN) / COLUMNS as f32;
+    let cell_height = (PAGE_HEIGHT - 2.0 * MARGIN - HEADER_HEIGHT) / ROWS as f32;
+    let box_width = cell_width - 12.0;
+    let mut page_ids = Vec::new();
+
+    for page in 0..page_count {
+        let mut content = Vec::new();
+        let mut images = Vec::new();
+        let header_top = PAGE_HEIGHT - MARGIN;
+        text_line(&mut content, "F2", 14.0, MARGIN, header_top - 14.0, title, PAGE_WIDTH - 2.0 * MARGIN);
+        let page_label = format!("{} - page {} of {}", subtitle, page + 1, page_count);
+        text_line(&mut content, "F1", 9.0, MARGIN, header_top - 28.0, &page_label, PAGE_WIDTH - 2.0 * MARGIN);
+
+        for (slot, entry) in entries.iter().skip(page * per_page).take(per_page).enumerate() {
+            let x = MARGIN + (slot % COLUMNS) as f32 * cell_width;
+            let top = PAGE_HEIGHT - MARGIN - HEADER_HEIGHT - (slot / COLUMNS) as f32 * cell_height;
+            let box_bottom = top - THUMBNAIL_HEIGHT;
+            content.extend(format!("0.8 G 0.5 w {:.2} {:.2} {:.2} {:.2} re S\n", x, box_bottom, box_width, THUMBNAIL_HEIGHT).bytes());
+
+            if let Some(thumbnail) = &entry.thumbnail {
+                let mut jpeg = Vec::new();
+                JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY)
+                    .encode_image(thumbnail)
+                    .map_err(|e| format!("Failed to encode a contact sheet thumbnail: {}", e))?;
+                let dictionary = format!(
+                    "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /DCTDecode",
+                    thumbnail.width(), thumbnail.height()
+                );
+                let image = objects.add(stream(&dictionary, &jpeg));
+                // Centred in its box, never scaled past the box or its own resolution.
+                let scale = (box_width / thumbnail.width() as f32).min(THUMBNAIL_HEIGHT / thumbnail.height() as f32).min(1.0 / PIXELS_PER_POINT);
+                let (width, height) = (thumbnail.width() as f32 * scale, thumbnail.height() as f32 * scale);
+                let (image_x, image_y) = (x + (box_width - width) / 2.0, box_bottom + (THUMBNAIL_HEIGHT - height) / 2.0);
+                content.extend(format!("q {:.2} 0 0 {:.2} {:.2} {:.2} cm /Im{} Do Q\n", width, height, image_x, image_y, images.len()).bytes());
+                images.push(image);
+            } else {
+                text_line(&mut content, "F1", 8.0, x + 6.0, box_bottom + THUMBNAIL_HEIGHT / 2.0, "Image not found", box_width - 12.0);
+            }
+
+            content.extend(b"0 g\n");
+            text_line(&mut content, "F2", 10.0, x, box_bottom - 13.0, &entry.name, box_width);
+            text_line(&mut content, "F1", 9.0, x, box_bottom - 25.0, &entry.artist, box_width);
+            text_line(&mut content, "F1", 8.0, x, box_bottom - 36.0, &format_sizes(&entry.sizes), box_width);
+        }
+
+        let contents = objects.add(stream("", &content));
+        let xobjects: String = images.iter().enumerate().map(|(index, id)| format!("/Im{} {} 0 R ", index, id)).collect();
+        page_ids.push(objects.add(format!(
+            "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 {} 0 R /F2 {} 0 R >> /XObject << {}>> >> /Contents {} 0 R >>",
+            pages, PAGE_WIDTH, PAGE_HEIGHT, regular, bold, xobjects, contents
+        ).into_bytes()));
+    }
+
+    let kids: Vec<String> = page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+    objects.set(pages, format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), page_ids.len()).into_bytes());
+    objects.set(catalog, format!("<< /Type /Catalog /Pages {} 0 R >>", pages).into_bytes());
+    Ok(objects.finish(catalog))
+}
+
+// Renders the contact sheet and writes it to `path`. Returns the number of entries on it.
+pub fn write_contact_sheet(path: &Path, title: &str, subtitle: &str, entries: &[SheetEntry]) -> Result<usize, String> {
+    let pdf = render(title, subtitle, entries)?;
+    write(path, pdf).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(entries.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{image_data::ImageData, image_size::ImageSize};
+    use image::RgbaImage;
+
+    #[test]
+    fn test_contact_sheet_lists_every_source_with_a_valid_xref() {
+        let path = std::env::temp_dir().join(format!("contact_sheet_test_{}.png", std::process::id()));
+        RgbaImage::from_pixel(64, 64, image::Rgba([200, 40, 40, 255])).save(&path).unwrap();
+        let item = |size: ImageSize, name: &str| {
+            let mut data = ImageData::new(size);
+            data.name = Some(name.to_string());
+            ExportItem { source_path: path.to_string_lossy().to_string(), data }
+        };
+        let mut items = vec![item(ImageSize::Square, "Sunset (study)"), item(ImageSize::Wide, "Sunset (study)")];
+        items.push(ExportItem { source_path: String::from("/missing.png"), data: ImageData::new(ImageSize::Tall) });
+
+        let entries = entries_for_items(&items, "Ana");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].artist, "Ana");
+        assert_eq!(entries[0].sizes.len(), ImageSize::Square.get_size().len() + ImageSize::Wide.get_size().len());
+        assert!(entries[0].thumbnail.is_some() && entries[1].thumbnail.is_none());
+
+        let pdf = render("Gallery 1.0.0", "2 images", &entries).unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-1.4") && text.ends_with("%%EOF\n"));
+        assert!(text.contains("(Sunset \\(study\\))") && text.contains("/DCTDecode"));
+        let startxref: usize = text.rsplit("startxref\n").next().unwrap().lines().next().unwrap().parse().unwrap();
+        assert!(pdf[startxref..].starts_with(b"xref"));
+
+        // Thirteen sources need a second page.
+        let many = vec![entries[1].clone(); 13];
+        assert_eq!(String::from_utf8_lossy(&render("Gallery", "", &many).unwrap()).matches("/Type /Page ").count(), 2);
+        std::fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,150 @@
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+use serde::Serialize;
+use crate::core::cropper::{self, CropOptions, FitMode};
+use crate::models::image_size::ImageSize;
+
+// Longest side of the heatmap thumbnail.
+pub const HEATMAP_SIZE: u32 = 64;
+// An unselected shape is only suggested if it keeps this many more percent of the source.
+const BETTER_FIT_MARGIN: f32 = 10.0;
+// Heatmap ends: discarded by every selected crop, and kept by all of them.
+const DISCARDED_COLOUR: [f32; 3] = [220.0, 40.0, 40.0];
+const KEPT_COLOUR: [f32; 3] = [40.0, 200.0, 80.0];
+
+// How much of the source one shape's crop throws away.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CropLoss {
+    pub image_size: ImageSize,
+    pub selected: bool,
+    // Share of the source's area outside the crop, 0 to 100.
+    pub discarded_percent: f32,
+}
+
+/*
+The aspect-ratio fit of one source image: the loss of every shape, and the unselected shape that
+would keep clearly more of the art than any selected one, if there is such a shape.
+*/
+#[derive(Debug, Clone)]
+pub struct AspectReport {
+    pub crops: Vec<CropLoss>,
+    pub better_fit: Option<ImageSize>,
+    // The source, tinted green where every selected crop keeps it and red where none do.
+    pub heatmap: DynamicImage,
+}
+
+/*
+Builds the report for `source` (already deskewed if the image is) with `selected` shapes chosen.
+`shapes` holds each shape's options in `ImageSize::iter()` order, as for
+`cropper::generate_cropped_images`. Losses are measured on the full resolution crop rectangles,
+inset, bleed, offset and manual rects included; letterboxed and stretched shapes keep everything.
+*/
+pub fn aspect_report(source: &DynamicImage, selected: &[ImageSize], shapes: &[CropOptions]) -> AspectReport {
+    let dims = source.dimensions();
+    let source_area = (dims.0 as f32 * dims.1 as f32).max(1.0);
+    let first = shapes.first().copied().unwrap_or_default();
+    let rects: Vec<(ImageSize, (u32, u32, u32, u32))> = ImageSize::iter()
+        .enumerate()
+        .map(|(index, size)| {
+            let options = shapes.get(index).unwrap_or(&first);
+            let rect = match options.fit {
+                FitMode::Crop => cropper::crop_rect(dims, size.get_size()[0], options),
+                FitMode::Letterbox { .. } | FitMode::Stretch => (0, 0, dims.0, dims.1),
+            };
+            (*size, rect)
+        })
+        .collect();
+
+    let crops: Vec<CropLoss> = rects
+        .iter()
+        .map(|(size, (_, _, width, height))| CropLoss {
+            image_size: *size,
+            selected: selected.contains(size),
+            discarded_percent: 100.0 * (1.0 - *width as f32 * *height as f32 / source_area),
+        })
+        .collect();
+
+    let least_loss = |want_selected: bool| {
+        crops
+            .iter()
+            .filter(|crop| crop.selected == want_selected)
+            .min_by(|a, b| a.discarded_percent.total_cmp(&b.discarded_percent))
+    };
+    let better_fit = match (least_loss(false), least_loss(true)) {
+        (Some(unselected), Some(best_selected)) if unselected.discarded_percent + BETTER_FIT_MARGIN <= best_selected.discarded_percent => {
+            Some(unselected.image_size)
+        }
+        _ => None,
+    };
+
+    let selected_rects: Vec<(u32, u32, u32, u32)> = rects.iter().filter(|(size, _)| selected.contains(size)).map(|(_, rect)| *rect).collect();
+    AspectReport { crops, better_fit, heatmap: heatmap(source, &selected_rects) }
+}
+
+// Tints a thumbnail of `source` by how many of `rects` (in source pixels) keep each pixel.
+fn heatmap(source: &DynamicImage, rects: &[(u32, u32, u32, u32)]) -> DynamicImage {
+    let thumbnail = source.thumbnail(HEATMAP_SIZE, HEATMAP_SIZE).to_luma8();
+    let (width, height) = thumbnail.dimensions();
+    let (source_width, source_height) = source.dimensions();
+    let heatmap = RgbaImage::from_fn(width, height, |x, y| {
+        // Centre of the thumbnail pixel, in source pixels.
+        let sx = ((x as f32 + 0.5) * source_width as f32 / width as f32) as u32;
+        let sy = ((y as f32 + 0.5) * source_height as f32 / height as f32) as u32;
+        let kept = rects.iter().filter(|(rx, ry, rw, rh)| sx >= *rx && sx < rx + rw && sy >= *ry && sy < ry + rh).count();
+        let share = if rects.is_empty() { 0.0 } else { kept as f32 / rects.len() as f32 };
+        let luma = thumbnail.get_pixel(x, y)[0] as f32;
+        let channel = |c: usize| {
+            let tint = DISCARDED_COLOUR[c] + (KEPT_COLOUR[c] - DISCARDED_COLOUR[c]) * share;
+            ((luma + tint) / 2.0) as u8
+        };
+        Rgba([channel(0), channel(1), channel(2), 255])
+    });
+    DynamicImage::ImageRgba8(heatmap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_measures_loss_and_suggests_a_better_shape() {
+        // A 2:1 panorama cropped square throws away half the image.
+        let source = DynamicImage::ImageRgba8(RgbaImage::from_pixel(200, 100, Rgba([128, 128, 128, 255])));
+        let report = aspect_report(&source, &[ImageSize::Square], &[CropOptions::default()]);
+
+        let loss = |size: ImageSize| report.crops.iter().find(|crop| crop.image_size == size).unwrap().discarded_percent;
+        assert!((loss(ImageSize::Square) - 50.0).abs() < 0.01);
+        assert!(loss(ImageSize::Wide).abs() < 0.01);
+        assert_eq!(report.better_fit, Some(ImageSize::Wide));
+
+        // Left edge is outside the square crop, the centre inside it.
+        let heatmap = report.heatmap.to_rgba8();
+        assert_eq!(heatmap.dimensions(), (HEATMAP_SIZE, HEATMAP_SIZE / 2));
+        assert!(heatmap.get_pixel(0, 16)[0] > heatmap.get_pixel(0, 16)[1]);
+        assert!(heatmap.get_pixel(32, 16)[1] > heatmap.get_pixel(32, 16)[0]);
+
+        // Already on the best shape: nothing to suggest.
+        assert_eq!(aspect_report(&source, &[ImageSize::Wide], &[CropOptions::default()]).better_fit, None);
+    }
+
+    #[test]
+    fn test_report_follows_each_shapes_fit_and_rect() {
+        let source = DynamicImage::ImageRgba8(RgbaImage::from_pixel(200, 100, Rgba([128, 128, 128, 255])));
+        let letterboxed = CropOptions { fit: FitMode::Letterbox { pad: cropper::PadColour::Transparent }, ..CropOptions::default() };
+        let small_rect = CropOptions { rect: Some(cropper::CropRect { x: 0, y: 0, width: 50, height: 50 }), ..CropOptions::default() };
+        let mut shapes = vec![CropOptions::default(); ImageSize::iter().len()];
+        let square = ImageSize::iter().position(|size| *size == ImageSize::Square).unwrap();
+        shapes[square] = letterboxed;
+        let report = aspect_report(&source, &[ImageSize::Square], &shapes);
+        let loss = |report: &AspectReport, size: ImageSize| report.crops.iter().find(|crop| crop.image_size == size).unwrap().discarded_percent;
+        assert_eq!(loss(&report, ImageSize::Square), 0.0);
+        assert_eq!(report.better_fit, None);
+
+        shapes[square] = small_rect;
+        let report = aspect_report(&source, &[ImageSize::Square], &shapes);
+        assert!((loss(&report, ImageSize::Square) - 87.5).abs() < 0.01);
+        // Only the top-left corner is kept.
+        let heatmap = report.heatmap.to_rgba8();
+        assert!(heatmap.get_pixel(4, 4)[1] > heatmap.get_pixel(4, 4)[0]);
+        assert!(heatmap.get_pixel(32, 16)[0] > heatmap.get_pixel(32, 16)[1]);
+    }
+}
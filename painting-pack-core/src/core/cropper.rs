@@ -0,0 +1,695 @@
+use crate::core::{deskew, flipbook, low_memory, palette, resample};
+use crate::models::image_size::ImageSize;
+use image::{imageops, GenericImageView, DynamicImage, ImageDecoder, ImageReader, Limits, Rgba, RgbaImage};
+use image::metadata::Orientation;
+use serde::{Deserialize, Serialize};
+
+// Neither margin may eat more than a quarter of each side, so half the source always survives.
+pub const MAX_MARGIN_PERCENT: f32 = 25.0;
+// How far a manual crop's aspect ratio may be off its shape's, to allow for rounding to whole pixels.
+const RECT_RATIO_TOLERANCE: f64 = 0.01;
+
+/*
+Where one crop's window sits in the room the source leaves around it, per axis from -1 (against
+the left or top edge) through 0 (centred, the default) to 1 (against the right or bottom edge).
+Lets the user nudge a crop that cuts off the subject without changing its size.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(default)]
+pub struct CropOffset {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl CropOffset {
+    pub fn is_centred(&self) -> bool {
+        self.x == 0.0 && self.y == 0.0
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(-1.0..=1.0).contains(&self.x) || !(-1.0..=1.0).contains(&self.y) {
+            return Err(String::from("The crop offset must be between -1 and 1 on each axis"));
+        }
+        Ok(())
+    }
+}
+
+/*
+A crop window chosen by hand, e.g. by dragging a box over the image, in pixels of the source as
+it is cropped (upright, straightened if deskew is on, one frame of a flipbook) at full resolution,
+even when low-memory mode decodes it smaller. It replaces the automatic ratio crop, so inset,
+bleed and offset don't apply to it.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl CropRect {
+    pub fn fits(&self, image_dims: (u32, u32)) -> bool {
+        self.width > 0
+            && self.height > 0
+            && self.x.checked_add(self.width).is_some_and(|right| right <= image_dims.0)
+            && self.y.checked_add(self.height).is_some_and(|bottom| bottom <= image_dims.1)
+    }
+
+    // The same window in a copy of the source scaled by `scale`, rounded inwards so it still fits.
+    pub fn scaled(&self, scale: f64) -> CropRect {
+        let (x, y) = ((self.x as f64 * scale) as u32, (self.y as f64 * scale) as u32);
+        let right = ((self.x + self.width) as f64 * scale) as u32;
+        let bottom = ((self.y + self.height) as f64 * scale) as u32;
+        CropRect { x, y, width: right.saturating_sub(x).max(1), height: bottom.saturating_sub(y).max(1) }
+    }
+
+    // Errors unless the window lies within a source `image_dims` big and has the ratio of `target_size` blocks.
+    pub fn validate(&self, image_dims: (u32, u32), target_size: (u32, u32)) -> Result<(), String> {
+        if !self.fits(image_dims) {
+            return Err(format!(
+                "A {}x{} px crop at {},{} doesn't fit in the {}x{} px source",
+                self.width, self.height, self.x, self.y, image_dims.0, image_dims.1
+            ));
+        }
+        let expected = target_size.0 as f64 / target_size.1 as f64;
+        let actual = self.width as f64 / self.height as f64;
+        if (actual / expected - 1.0).abs() > RECT_RATIO_TOLERANCE {
+            return Err(format!("A {}:{} painting can't be cropped to {}x{} px", target_size.0, target_size.1, self.width, self.height));
+        }
+        Ok(())
+    }
+}
+
+// What fills the bars around a letterboxed painting.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PadColour {
+    #[default]
+    Transparent,
+    Solid { rgb: palette::Colour },
+    // The colour covering most of the image (see `palette::dominant_colour`), so the bars blend in.
+    Dominant,
+}
+
+impl PadColour {
+    fn pixel(&self, image: &DynamicImage) -> Rgba<u8> {
+        match self {
+            PadColour::Transparent => Rgba([0, 0, 0, 0]),
+            PadColour::Solid { rgb: [r, g, b] } => Rgba([*r, *g, *b, 255]),
+            PadColour::Dominant => {
+                let [r, g, b] = palette::dominant_colour(image);
+                Rgba([r, g, b, 255])
+            }
+        }
+    }
+}
+
+/*
+How a source is brought to its shape's aspect ratio. `Crop` (the default) cuts the ratio out of
+it; `Letterbox` keeps the whole image and pads it out to the ratio; `Stretch` keeps the whole
+image and scales it to the ratio, distorting it. The inset still trims the source first in every
+mode, but bleed, offset and manual rects only apply to `Crop`.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum FitMode {
+    #[default]
+    Crop,
+    Letterbox { pad: PadColour },
+    Stretch,
+}
+
+/*
+How a source is prepared before and around the ratio crop. `inset_percent` trims that much of
+each edge off the source first (white borders on scans); `bleed_percent` then over-crops the
+ratio crop by that much per edge, hiding soft or fringed edges. Both default to 0. A source with
+`frames` above 1 is a flipbook: every frame is cropped the same way and stacked again. `offset`
+moves the crop window off centre, `rect` replaces it and `fit` can keep the whole image instead;
+all three belong to a single shape's crop (see `ImageData`).
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(default)]
+pub struct CropOptions {
+    pub deskew: bool,
+    pub inset_percent: f32,
+    pub bleed_percent: f32,
+    // Animation frames stacked top to bottom in the source; 0 or 1 for a still image.
+    pub frames: u32,
+    pub offset: CropOffset,
+    pub rect: Option<CropRect>,
+    pub fit: FitMode,
+}
+
+impl CropOptions {
+    // Errors on margins outside 0..=MAX_MARGIN_PERCENT, so bad input is caught before cropping.
+    pub fn validate(&self) -> Result<(), String> {
+        for (label, value) in [("Inset", self.inset_percent), ("Bleed", self.bleed_percent)] {
+            if !(0.0..=MAX_MARGIN_PERCENT).contains(&value) {
+                return Err(format!("{} must be between 0 and {}%", label, MAX_MARGIN_PERCENT));
+            }
+        }
+        self.offset.validate()
+    }
+
+    // Size of one frame of a source `image_dims` big, which is what gets cropped.
+    pub fn frame_dimensions(&self, image_dims: (u32, u32)) -> (u32, u32) {
+        (image_dims.0, image_dims.1 / self.frames.max(1))
+    }
+
+    /*
+    These options for cutting from the source at `path` as `open_source` decodes it. A manual rect
+    is kept in full-resolution pixels, so in low-memory mode it is scaled down with the source.
+    */
+    pub fn for_decoded_source(&self, path: &str) -> CropOptions {
+        let limit = low_memory::decode_limit();
+        if self.rect.is_none() || limit.is_none() {
+            return *self;
+        }
+        match full_resolution_dimensions(path) {
+            Ok(full_dims) => self.for_decode(full_dims, limit),
+            Err(_) => *self,
+        }
+    }
+
+    // These options for a source `full_dims` big once it is decoded under `limit` (see `low_memory::decode_limit`).
+    pub fn for_decode(&self, full_dims: (u32, u32), limit: Option<u32>) -> CropOptions {
+        let scale = low_memory::decode_scale(full_dims, limit);
+        match self.rect {
+            Some(rect) if scale < 1.0 => CropOptions { rect: Some(rect.scaled(scale)), ..*self },
+            _ => *self,
+        }
+    }
+}
+
+// Decodes a source image upright (EXIF orientation applied, as for working copies), straightening
+// it first if the user turned deskew on for it. Capped in size in low-memory mode.
+pub fn open_source(path: &str, deskew: bool) -> Result<DynamicImage, image::ImageError> {
+    let img = match low_memory::decode_limits() {
+        Some(limits) => low_memory::cap_decoded(decode_upright(path, limits)?),
+        None => open_full_resolution(path)?,
+    };
+    Ok(if deskew { deskew::straighten(img) } else { img })
+}
+
+// Decodes a source image upright at its full size whatever the memory mode, for sources whose
+// pixel measurements matter (e.g. a sprite sheet's margins).
+pub fn open_full_resolution(path: &str) -> Result<DynamicImage, image::ImageError> {
+    decode_upright(path, Limits::default())
+}
+
+// Decodes a source upright, refusing it before any pixels are decoded if it is bigger than `limits` allow.
+fn decode_upright(path: &str, mut limits: Limits) -> Result<DynamicImage, image::ImageError> {
+    let mut reader = ImageReader::open(path)?.with_guessed_format()?;
+    reader.limits(limits.clone());
+    let mut decoder = reader.into_decoder()?;
+    // `into_decoder` only checks the dimensions; the decoded size is checked here.
+    limits.reserve(decoder.total_bytes())?;
+    let orientation = decoder.orientation()?;
+    let mut img = DynamicImage::from_decoder(decoder)?;
+    img.apply_orientation(orientation);
+    Ok(img)
+}
+
+// Size of the source at `path` upright and at full resolution, read from its header without decoding it.
+pub fn full_resolution_dimensions(path: &str) -> Result<(u32, u32), image::ImageError> {
+    let mut decoder = ImageReader::open(path)?.with_guessed_format()?.into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let (width, height) = decoder.dimensions();
+    Ok(match orientation {
+        Orientation::Rotate90 | Orientation::Rotate270 | Orientation::Rotate90FlipH | Orientation::Rotate270FlipH => (height, width),
+        _ => (width, height),
+    })
+}
+
+/*
+Size of the source at `path` as manual crop rects are measured: upright, straightened and one
+frame, at full resolution whatever the memory mode.
+*/
+pub fn source_dimensions(path: &str, options: &CropOptions) -> Result<(u32, u32), image::ImageError> {
+    let dims = if options.deskew {
+        deskew::straighten(open_full_resolution(path)?).dimensions()
+    } else {
+        full_resolution_dimensions(path)?
+    };
+    Ok(options.frame_dimensions(dims))
+}
+
+/*
+Used as an intermediary function to get proper crop dimensions of a given image.
+Also used by the linter to predict texture sizes without decoding the image.
+*/
+pub(crate) fn calculate_crop_dimensions(image_dims: (u32, u32), target_size: (u32, u32)) -> (u32, u32, u32, u32) {
+    let (width, height) = image_dims;
+    let (img_width, img_height) = target_size;
+
+    // Use cross-multiplication to avoid floating point math and integer division errors. Technically more performant?
+    // We cast to u64 to prevent overflow when multiplying dimensions.
+    let is_image_wider_than_ratio =
+        (width as u64 * img_height as u64) >= (height as u64 * img_width as u64);
+
+    let scale_factor: u32 = match is_image_wider_than_ratio {
+        // If the image is wider than the target ratio, the height is the limiting dimension.
+        true => height / img_height,
+        // If the image is taller, the width is the limiting dimension.
+        false => width / img_width,
+    };
+
+    let crop_width: u32 = img_width * scale_factor;
+    let crop_height: u32 = img_height * scale_factor;
+    let width_start: u32 = (width - crop_width) / 2;
+    let height_start: u32 = (height - crop_height) / 2;
+
+    (width_start, height_start, crop_width, crop_height)
+}
+
+// Pixels a margin of `percent` takes off one edge of a side `length` pixels long.
+fn margin(length: u32, percent: f32) -> u32 {
+    (length as f32 * percent.clamp(0.0, MAX_MARGIN_PERCENT) / 100.0) as u32
+}
+
+// The part of an image left once the inset is trimmed off, as (x, y, width, height).
+fn inset_area(image_dims: (u32, u32), options: &CropOptions) -> (u32, u32, u32, u32) {
+    let (inset_x, inset_y) = (margin(image_dims.0, options.inset_percent), margin(image_dims.1, options.inset_percent));
+    (inset_x, inset_y, image_dims.0 - 2 * inset_x, image_dims.1 - 2 * inset_y)
+}
+
+// The smallest whole-block multiple of `target_size` that an `image_dims` image fits in whole.
+fn fit_canvas(image_dims: (u32, u32), target_size: (u32, u32)) -> (u32, u32) {
+    let scale = image_dims.0.div_ceil(target_size.0).max(image_dims.1.div_ceil(target_size.1)).max(1);
+    (target_size.0 * scale, target_size.1 * scale)
+}
+
+// Moves `start` towards `min` (offset -1) or `max` (offset 1) by the share `offset` of the way.
+fn offset_start(start: u32, min: u32, max: u32, offset: f32) -> u32 {
+    let offset = offset.clamp(-1.0, 1.0);
+    if offset < 0.0 {
+        start - (start.saturating_sub(min) as f32 * -offset).round() as u32
+    } else {
+        start + (max.saturating_sub(start) as f32 * offset).round() as u32
+    }
+}
+
+/*
+The ratio crop of an image after the inset is trimmed, over-cropped by the bleed and moved by
+the offset, which never pushes it past the inset or the bleed. Returns (x, y, width, height) in
+source pixels, like `calculate_crop_dimensions`. A manual `rect` is used as it is instead, unless
+it no longer fits the image (e.g. the source was replaced), when the automatic crop stands in.
+*/
+pub fn crop_rect(image_dims: (u32, u32), target_size: (u32, u32), options: &CropOptions) -> (u32, u32, u32, u32) {
+    if let Some(rect) = options.rect.filter(|rect| rect.fits(image_dims)) {
+        return (rect.x, rect.y, rect.width, rect.height);
+    }
+    let (inset_x, inset_y, inner_width, inner_height) = inset_area(image_dims, options);
+    let inner = (inner_width, inner_height);
+    let (x, y, width, height) = calculate_crop_dimensions(inner, target_size);
+
+    // Shrinking both sides by the same share keeps the ratio; re-fit to keep whole-block multiples.
+    let (bleed_x, bleed_y) = (margin(width, options.bleed_percent), margin(height, options.bleed_percent));
+    let (bx, by, width, height) = calculate_crop_dimensions((width - 2 * bleed_x, height - 2 * bleed_y), target_size);
+    let (x, y) = (inset_x + x + bleed_x + bx, inset_y + y + bleed_y + by);
+    let max_x = (inset_x + inner.0).saturating_sub(bleed_x + width);
+    let max_y = (inset_y + inner.1).saturating_sub(bleed_y + height);
+    (
+        offset_start(x, inset_x + bleed_x, max_x, options.offset.x),
+        offset_start(y, inset_y + bleed_y, max_y, options.offset.y),
+        width,
+        height,
+    )
+}
+
+/*
+Width and height of what one frame of an `image_dims` source is cut down to: the crop of
+`crop_rect`, or the padded or stretched whole image for the other fit modes.
+*/
+pub fn output_dimensions(image_dims: (u32, u32), target_size: (u32, u32), options: &CropOptions) -> (u32, u32) {
+    match options.fit {
+        FitMode::Crop => {
+            let (_, _, width, height) = crop_rect(image_dims, target_size, options);
+            (width, height)
+        }
+        FitMode::Letterbox { .. } | FitMode::Stretch => {
+            let (_, _, width, height) = inset_area(image_dims, options);
+            fit_canvas((width, height), target_size)
+        }
+    }
+}
+
+// The colour letterbox bars around `image` are filled with; transparent in the other modes.
+fn pad_pixel(image: &DynamicImage, options: &CropOptions) -> Rgba<u8> {
+    match options.fit {
+        FitMode::Letterbox { pad } => pad.pixel(image),
+        FitMode::Crop | FitMode::Stretch => Rgba([0, 0, 0, 0]),
+    }
+}
+
+// Brings one frame to the ratio of `target_size` blocks the way `options.fit` says.
+fn fit_frame(frame: &DynamicImage, target_size: (u32, u32), options: &CropOptions, pad: Rgba<u8>) -> DynamicImage {
+    if options.fit == FitMode::Crop {
+        let (x, y, width, height) = crop_rect(frame.dimensions(), target_size, options);
+        return DynamicImage::ImageRgba8(frame.view(x, y, width, height).to_image());
+    }
+    let (x, y, width, height) = inset_area(frame.dimensions(), options);
+    let inner = frame.crop_imm(x, y, width, height);
+    let (canvas_width, canvas_height) = fit_canvas((width, height), target_size);
+    if options.fit == FitMode::Stretch {
+        return DynamicImage::ImageRgba8(resample::resize_exact(&inner, canvas_width, canvas_height).to_rgba8());
+    }
+    let mut canvas = RgbaImage::from_pixel(canvas_width, canvas_height, pad);
+    imageops::overlay(&mut canvas, &inner.to_rgba8(), ((canvas_width - width) / 2) as i64, ((canvas_height - height) / 2) as i64);
+    DynamicImage::ImageRgba8(canvas)
+}
+
+/*
+Generates a vector of all 5 cropped image variants from a single source file path.
+This is used to create transient images for Base64 preview generation.
+These images are NOT stored in the application state to conserve memory.
+`shapes` holds the options of each variant in `ImageSize::iter()` order, so every crop can have
+its own offset, manual rect and fit mode; the source-wide settings (deskew, frames) are taken
+from the first. Variants past the end of `shapes` use the first one's options.
+*/
+pub fn generate_cropped_images(path: &str, shapes: &[CropOptions]) -> Result<Vec<DynamicImage>, image::ImageError> {
+    let mut cropped_images: Vec<DynamicImage> = Vec::new();
+    let source_options = shapes.first().copied().unwrap_or_default();
+    // In low-memory mode previews are made one source at a time.
+    let _slot = low_memory::preview_slot();
+    let img = open_source(path, source_options.deskew)?;
+    // Flipbooks are previewed by their first frame.
+    let img = if source_options.frames > 1 { flipbook::frame(&img, source_options.frames, 0) } else { img };
+
+    for (index, size_variant) in ImageSize::iter().enumerate() {
+        let target_size = size_variant.get_size()[0];
+        let options = shapes.get(index).unwrap_or(&source_options).for_decoded_source(path);
+        let crop_preview = fit_frame(&img, target_size, &options, pad_pixel(&img, &options));
+
+        cropped_images.push(crop_preview);
+    }
+    Ok(cropped_images)
+}
+
+/*
+Generates a single cropped image variant from a source file path.
+This is used during the final export process to re-generate images on-demand.
+*/
+pub fn crop_single_image(
+    path: &str,
+    image_size: &ImageSize,
+    options: &CropOptions,
+) -> Result<DynamicImage, image::ImageError> {
+    let img = open_source(path, options.deskew)?;
+    Ok(crop_opened_image(&img, image_size, &options.for_decoded_source(path)))
+}
+
+/*
+Same as `crop_single_image` for a source already decoded by `open_source` with the same deskew
+setting, so several variants can be cut from one decode. Pass `options` through
+`for_decoded_source` first.
+*/
+pub fn crop_opened_image(img: &DynamicImage, image_size: &ImageSize, options: &CropOptions) -> DynamicImage {
+    let target_size = image_size.get_size()[0];
+
+    if options.frames > 1 {
+        // Every frame is padded in the first frame's colour, so the bars don't flicker.
+        let pad = pad_pixel(&flipbook::frame(img, options.frames, 0), options);
+        let frames: Vec<DynamicImage> = (0..options.frames)
+            .map(|index| fit_frame(&flipbook::frame(img, options.frames, index), target_size, options, pad))
+            .collect();
+        return flipbook::build_flipbook(&frames).expect("a flipbook has at least two frames");
+    }
+    fit_frame(img, target_size, options, pad_pixel(img, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*; // Import functions from parent module
+    use image::{GenericImageView, RgbaImage};
+    use std::fs;
+    use std::path::PathBuf;
+
+    // Use the real ImageSize from the crate models to ensure types match the functions under test
+    use crate::models::image_size::ImageSize;
+
+    // --- Test Image Helper ---
+    // This struct creates a dummy image file when created
+    // and automatically deletes it when it goes out of scope (using Drop)
+    struct TestImage {
+        path: PathBuf,
+    }
+
+    impl TestImage {
+        fn new(path_str: &str, width: u32, height: u32) -> Self {
+            let img = RgbaImage::new(width, height);
+            let path = PathBuf::from(path_str);
+            img.save(&path).expect("Failed to save test image");
+            Self { path }
+        }
+
+        fn path_str(&self) -> &str {
+            self.path.to_str().expect("Path is not valid UTF-8")
+        }
+    }
+
+    impl Drop for TestImage {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.path); // Ignore error on cleanup
+        }
+    }
+
+    // --- Unit Tests for calculate_crop_dimensions ---
+
+    #[test]
+    fn test_calc_crop_wider_image() {
+        // 16:9 image (1600x900), 4:3 target
+        let img_dims = (1600, 900);
+        let target_size = (4, 3);
+
+        // Crop should be based on height
+        // scale_factor = 900 / 3 = 300
+        // crop_width = 4 * 300 = 1200
+        // crop_height = 3 * 300 = 900
+        // width_start = (1600 - 1200) / 2 = 200
+        // height_start = (900 - 900) / 2 = 0
+        let (x, y, w, h) = calculate_crop_dimensions(img_dims, target_size);
+        assert_eq!((x, y, w, h), (200, 0, 1200, 900));
+    }
+
+    #[test]
+    fn test_calc_crop_taller_image() {
+        // 9:16 image (900x1600), 4:3 target
+        let img_dims = (900, 1600);
+        let target_size = (4, 3);
+
+        // Crop should be based on width
+        // scale_factor = 900 / 4 = 225
+        // crop_width = 4 * 225 = 900
+        // crop_height = 3 * 225 = 675
+        // width_start = (900 - 900) / 2 = 0
+        // height_start = (1600 - 675) / 2 = 462
+        let (x, y, w, h) = calculate_crop_dimensions(img_dims, target_size);
+        assert_eq!((x, y, w, h), (0, 462, 900, 675));
+    }
+
+    #[test]
+    fn test_calc_crop_same_ratio() {
+        // 4:3 image (800x600), 4:3 target
+        let img_dims = (800, 600);
+        let target_size = (4, 3);
+
+        // Crop should be the full image
+        // scale_factor = 600 / 3 = 200
+        // crop_width = 4 * 200 = 800
+        // crop_height = 3 * 200 = 600
+        // width_start = (800 - 800) / 2 = 0
+        // height_start = (600 - 600) / 2 = 0
+        let (x, y, w, h) = calculate_crop_dimensions(img_dims, target_size);
+        assert_eq!((x, y, w, h), (0, 0, 800, 600));
+    }
+
+    #[test]
+    fn test_calc_crop_square_target_from_landscape() {
+        // 16:9 image (1600x900), 1:1 target
+        let img_dims = (1600, 900);
+        let target_size = (1, 1);
+
+        // Crop should be based on height
+        // scale_factor = 900 / 1 = 900
+        // crop_width = 1 * 900 = 900
+        // crop_height = 1 * 900 = 900
+        // width_start = (1600 - 900) / 2 = 350
+        // height_start = (900 - 900) / 2 = 0
+        let (x, y, w, h) = calculate_crop_dimensions(img_dims, target_size);
+        assert_eq!((x, y, w, h), (350, 0, 900, 900));
+    }
+
+    #[test]
+    fn test_crop_rect_applies_inset_then_bleed() {
+        // 10% inset trims 100x60 off each edge: the 4:3 crop of 800x480 is 640x480
+        let inset = CropOptions { inset_percent: 10.0, ..CropOptions::default() };
+        assert_eq!(crop_rect((1000, 600), (4, 3), &inset), (180, 60, 640, 480));
+
+        // 5% bleed then takes 32x24 off each edge of that crop
+        let both = CropOptions { bleed_percent: 5.0, ..inset };
+        assert_eq!(crop_rect((1000, 600), (4, 3), &both), (212, 84, 576, 432));
+
+        assert_eq!(crop_rect((800, 600), (4, 3), &CropOptions::default()), calculate_crop_dimensions((800, 600), (4, 3)));
+        assert!(CropOptions { inset_percent: 30.0, ..CropOptions::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_crop_offset_moves_the_window_within_the_margins() {
+        // The 600x600 square of an 1000x600 source has 400 px to spare horizontally
+        let left = CropOptions { offset: CropOffset { x: -1.0, y: 0.0 }, ..CropOptions::default() };
+        assert_eq!(crop_rect((1000, 600), (1, 1), &left), (0, 0, 600, 600));
+        let nudged = CropOptions { offset: CropOffset { x: 0.5, y: 1.0 }, ..CropOptions::default() };
+        assert_eq!(crop_rect((1000, 600), (1, 1), &nudged), (300, 0, 600, 600));
+
+        // Never past the inset and bleed: 10% inset and 5% bleed leave 100 + 24 px on the left
+        let inset = CropOptions { inset_percent: 10.0, bleed_percent: 5.0, offset: CropOffset { x: -1.0, y: 1.0 }, ..CropOptions::default() };
+        assert_eq!(crop_rect((1000, 600), (1, 1), &inset), (124, 84, 432, 432));
+        assert!(CropOptions { offset: CropOffset { x: 1.5, y: 0.0 }, ..CropOptions::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_letterbox_and_stretch_keep_the_whole_image() {
+        let mut source = RgbaImage::from_pixel(300, 100, image::Rgba([200, 40, 40, 255]));
+        source.put_pixel(0, 0, image::Rgba([0, 0, 255, 255]));
+        let source = DynamicImage::ImageRgba8(source);
+
+        let letterbox = CropOptions { fit: FitMode::Letterbox { pad: PadColour::Transparent }, ..CropOptions::default() };
+        let boxed = crop_opened_image(&source, &ImageSize::Square, &letterbox);
+        assert_eq!(boxed.dimensions(), (300, 300));
+        assert_eq!(output_dimensions((300, 100), (1, 1), &letterbox), (300, 300));
+        assert_eq!(boxed.get_pixel(150, 10), image::Rgba([0, 0, 0, 0]));
+        assert_eq!(boxed.get_pixel(0, 100), image::Rgba([0, 0, 255, 255]));
+
+        let solid = CropOptions { fit: FitMode::Letterbox { pad: PadColour::Solid { rgb: [1, 2, 3] } }, ..CropOptions::default() };
+        assert_eq!(crop_opened_image(&source, &ImageSize::Square, &solid).get_pixel(150, 299), image::Rgba([1, 2, 3, 255]));
+        let dominant = CropOptions { fit: FitMode::Letterbox { pad: PadColour::Dominant }, ..CropOptions::default() };
+        assert_eq!(crop_opened_image(&source, &ImageSize::Square, &dominant).get_pixel(150, 0), image::Rgba([200, 40, 40, 255]));
+
+        // Stretched, nothing is cut off or padded
+        let stretch = CropOptions { fit: FitMode::Stretch, ..CropOptions::default() };
+        let stretched = crop_opened_image(&source, &ImageSize::Tall, &stretch);
+        assert_eq!(stretched.dimensions(), (300, 600));
+        assert_eq!(stretched.get_pixel(150, 599)[3], 255);
+    }
+
+    #[test]
+    fn test_manual_crop_rect_replaces_the_ratio_crop() {
+        let rect = CropRect { x: 10, y: 20, width: 400, height: 200 };
+        let options = CropOptions { inset_percent: 10.0, offset: CropOffset { x: 1.0, y: 0.0 }, rect: Some(rect), ..CropOptions::default() };
+        assert_eq!(crop_rect((1000, 600), (2, 1), &options), (10, 20, 400, 200));
+        // Falls back to the automatic crop once the source is too small for it.
+        assert_eq!(crop_rect((300, 300), (2, 1), &options), crop_rect((300, 300), (2, 1), &CropOptions { rect: None, ..options }));
+
+        assert!(rect.validate((1000, 600), (2, 1)).is_ok());
+        assert!(CropRect { width: 402, ..rect }.validate((1000, 600), (2, 1)).is_ok());
+        assert_eq!(rect.validate((1000, 600), (1, 1)), Err(String::from("A 1:1 painting can't be cropped to 400x200 px")));
+        assert!(rect.validate((400, 600), (2, 1)).unwrap_err().contains("doesn't fit"));
+        assert!(CropRect { width: 0, height: 0, ..rect }.validate((1000, 600), (2, 1)).is_err());
+    }
+
+    #[test]
+    fn test_sources_over_the_decode_limits_are_refused_before_decoding() {
+        let test_img = TestImage::new("test_decode_limits.png", 200, 100);
+        let mut by_bytes = Limits::default();
+        by_bytes.max_alloc = Some(200 * 100 * 4 - 1);
+        assert!(matches!(decode_upright(test_img.path_str(), by_bytes), Err(image::ImageError::Limits(_))));
+        let mut by_side = Limits::default();
+        by_side.max_image_width = Some(199);
+        assert!(matches!(decode_upright(test_img.path_str(), by_side), Err(image::ImageError::Limits(_))));
+        assert_eq!(decode_upright(test_img.path_str(), Limits::default()).unwrap().dimensions(), (200, 100));
+    }
+
+    #[test]
+    fn test_manual_crop_rect_stays_in_full_resolution_pixels_in_low_memory_mode() {
+        // Twice the decode limit wide, so low-memory mode halves it; only the far right is red.
+        let path = std::env::temp_dir().join(format!("low_memory_rect_{}.png", std::process::id()));
+        RgbaImage::from_fn(2 * low_memory::MAX_DECODE_DIMENSION, 64, |x, _| {
+            if x >= 7900 { Rgba([255, 0, 0, 255]) } else { Rgba([0, 0, 255, 255]) }
+        })
+        .save(&path)
+        .unwrap();
+        let path = path.to_str().unwrap();
+        let rect = CropRect { x: 8000, y: 0, width: 64, height: 64 };
+        let options = CropOptions { rect: Some(rect), ..CropOptions::default() };
+
+        // The rect is measured against the full-resolution source.
+        let dims = source_dimensions(path, &options).unwrap();
+        assert_eq!(dims, (8192, 64));
+        assert!(rect.validate(dims, (1, 1)).is_ok());
+
+        // Decoded as low-memory mode would, the rect shrinks with the source and still finds the red.
+        let limit = Some(low_memory::MAX_DECODE_DIMENSION);
+        let capped = low_memory::cap_to(open_full_resolution(path).unwrap(), low_memory::MAX_DECODE_DIMENSION);
+        let _ = fs::remove_file(path);
+        let scaled = options.for_decode(dims, limit);
+        assert_eq!(scaled.rect, Some(CropRect { x: 4000, y: 0, width: 32, height: 32 }));
+        let crop = crop_opened_image(&capped, &ImageSize::Square, &scaled);
+        assert_eq!(crop.dimensions(), (32, 32));
+        assert_eq!(crop.get_pixel(16, 16), Rgba([255, 0, 0, 255]));
+        // Outside low-memory mode the rect is used as it is.
+        assert_eq!(options.for_decode(dims, None), options);
+    }
+
+    // --- Integration Tests for public functions ---
+
+    #[test]
+    fn test_crop_single_image_success() {
+        // 800x600 (4:3) image
+        let test_img = TestImage::new("test_single.png", 800, 600);
+        // 1:1 target (Square)
+        let size = ImageSize::Square; 
+        
+        let result = crop_single_image(test_img.path_str(), &size, &CropOptions::default());
+        assert!(result.is_ok());
+        let cropped = result.unwrap();
+
+        // Expected: crop based on height (600)
+        // scale = 600 / 1 = 600
+        // crop_w = 1 * 600 = 600
+        // crop_h = 1 * 600 = 600
+        // Final image dimensions should be 600x600
+        assert_eq!(cropped.dimensions(), (600, 600));
+    }
+
+    #[test]
+    fn test_generate_cropped_images_success() {
+        // 1600x900 (16:9) image
+        let test_img = TestImage::new("test_generate.png", 1600, 900);
+        
+        let result = generate_cropped_images(test_img.path_str(), &[CropOptions::default()]);
+        assert!(result.is_ok());
+        let cropped_vec = result.unwrap();
+
+        // ImageSize::iter() returns 5 variants
+        assert_eq!(cropped_vec.len(), 5);
+
+        // Check dimensions for each variant based on 1600x900 source
+        // 1. Square (1:1) -> 900x900
+        assert_eq!(cropped_vec[0].dimensions(), (900, 900));
+        // 2. Wide (2:1) -> 1600x800
+        assert_eq!(cropped_vec[1].dimensions(), (1600, 800));
+        // 3. LongRectangle (4:3) -> 1200x900
+        assert_eq!(cropped_vec[2].dimensions(), (1200, 900));
+        // 4. Tall (1:2) -> 450x900
+        assert_eq!(cropped_vec[3].dimensions(), (450, 900));
+        // 5. TallRectangle (3:4) -> 675x900
+        assert_eq!(cropped_vec[4].dimensions(), (675, 900));
+    }
+
+    #[test]
+    fn test_crop_image_file_not_found() {
+        let result = crop_single_image("nonexistent_file.png", &ImageSize::Square, &CropOptions::default());
+        assert!(result.is_err());
+        // Check that it's an I/O error (which `open` returns for missing files)
+        assert!(matches!(result.unwrap_err(), image::ImageError::IoError(_)));
+    }
+
+    #[test]
+    fn test_generate_images_file_not_found() {
+        let result = generate_cropped_images("nonexistent_file.png", &[CropOptions::default()]);
+        assert!(result.is_err());
+        assert!(matches!(result.unwrap_err(), image::ImageError::IoError(_)));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use image::DynamicImage;
+use crate::core::cropper;
+
+// A source as `cropper::open_source` decodes it: its path and whether it is straightened.
+type SourceKey = (String, bool);
+
+struct Entry {
+    image: Arc<OnceLock<Result<Arc<DynamicImage>, String>>>,
+    // Users still expected to ask for the image; it is dropped once the last one is done.
+    uses_left: usize,
+}
+
+/*
+Decoded sources shared by everything an export cuts from them, so each source file is opened and
+decoded once per export however many block sizes, items or packs use it. Every expected use is
+registered up front and released when done with; a source leaves the cache after its last use, so
+only the sources being worked on are held in memory. Safe to share between export threads: a
+source asked for by two threads at once is decoded by one while the other waits.
+*/
+#[derive(Default)]
+pub struct DecodeCache {
+    entries: Mutex<HashMap<SourceKey, Entry>>,
+}
+
+impl DecodeCache {
+    // A cache expecting one use per `(path, deskew)` listed; list a source again for every use.
+    pub fn new<'a>(uses: impl IntoIterator<Item = (&'a str, bool)>) -> Self {
+        let mut entries: HashMap<SourceKey, Entry> = HashMap::new();
+        for (path, deskew) in uses {
+            entries
+                .entry((path.to_string(), deskew))
+                .or_insert_with(|| Entry { image: Arc::default(), uses_left: 0 })
+                .uses_left += 1;
+        }
+        DecodeCache { entries: Mutex::new(entries) }
+    }
+
+    // The decoded source, decoding it on first request. A source that wasn't registered is kept until released once.
+    pub fn get(&self, path: &str, deskew: bool) -> Result<Arc<DynamicImage>, String> {
+        let slot = {
+            let mut entries = self.entries.lock().unwrap();
+            let entry = entries
+                .entry((path.to_string(), deskew))
+                .or_insert_with(|| Entry { image: Arc::default(), uses_left: 1 });
+            entry.image.clone()
+        };
+        // Decoded outside the lock, so other sources aren't held up.
+        slot.get_or_init(|| {
+            cropper::open_source(path, deskew)
+                .map(Arc::new)
+                .map_err(|e| format!("Failed to open {}: {}", path, e))
+        })
+        .clone()
+    }
+
+    // Marks one use of a source as done, dropping the decoded image after the last.
+    pub fn release(&self, path: &str, deskew: bool) {
+        let mut entries = self.entries.lock().unwrap();
+        let key = (path.to_string(), deskew);
+        if let Some(entry) = entries.get_mut(&key) {
+            entry.uses_left = entry.uses_left.saturating_sub(1);
+            if entry.uses_left == 0 {
+                entries.remove(&key);
+            }
+        }
+    }
+
+    // Sources currently held, decoded or not.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_source_is_decoded_once_and_dropped_after_its_last_use() {
+        let path = std::env::temp_dir().join(format!("decode_cache_test_{}.png", std::process::id()));
+        RgbaImage::new(8, 4).save(&path).unwrap();
+        let path = path.to_string_lossy().to_string();
+        let cache = DecodeCache::new([(path.as_str(), false), (path.as_str(), false)]);
+
+        let first = cache.get(&path, false).unwrap();
+        cache.release(&path, false);
+        let second = cache.get(&path, false).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(cache.len(), 1);
+        cache.release(&path, false);
+        assert!(cache.is_empty());
+
+        assert!(cache.get("/no/such/source.png", false).is_err());
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,174 @@
+use image::{imageops, DynamicImage, Rgba, RgbaImage};
+
+// Only small tilts are corrected; anything larger is almost certainly intentional.
+const MAX_SKEW_DEGREES: f32 = 10.0;
+const COARSE_STEP_DEGREES: f32 = 0.5;
+const FINE_STEP_DEGREES: f32 = 0.1;
+// Tilts smaller than this aren't worth resampling the image for.
+const MIN_CORRECTION_DEGREES: f32 = 0.15;
+// Edge detection runs on a copy no larger than this.
+const ANALYSIS_SIZE: u32 = 512;
+
+/*
+Estimates how far the content is tilted, in degrees. Positive means lines that should be level
+run downhill to the right. Strong edges are projected onto rows and columns at each candidate
+angle; the straight edges of a frame, canvas or horizon pile up into sharp peaks when the angle
+matches, while texture stays spread out. Returns 0 if there are no clear straight edges.
+*/
+pub fn detect_skew(image: &DynamicImage) -> f32 {
+    let small = if image.width() > ANALYSIS_SIZE || image.height() > ANALYSIS_SIZE {
+        image.thumbnail(ANALYSIS_SIZE, ANALYSIS_SIZE)
+    } else {
+        image.clone()
+    };
+    let gray = small.to_luma32f();
+    let (width, height) = gray.dimensions();
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+    let at = |x: u32, y: u32| gray.get_pixel(x, y).0[0];
+
+    // (x, y, |gx|, |gy|) for every pixel on an edge
+    let mut edges: Vec<(f32, f32, f32, f32)> = Vec::new();
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            // Sobel gradient
+            let gx = (at(x + 1, y - 1) + 2.0 * at(x + 1, y) + at(x + 1, y + 1)) - (at(x - 1, y - 1) + 2.0 * at(x - 1, y) + at(x - 1, y + 1));
+            let gy = (at(x - 1, y + 1) + 2.0 * at(x, y + 1) + at(x + 1, y + 1)) - (at(x - 1, y - 1) + 2.0 * at(x, y - 1) + at(x + 1, y - 1));
+            edges.push((x as f32, y as f32, gx.abs(), gy.abs()));
+        }
+    }
+
+    // Only strong edges vote, so noise and soft gradients don't drown out the real lines.
+    let strongest = edges.iter().map(|e| e.2.max(e.3)).fold(0.0, f32::max);
+    if strongest <= 0.0 {
+        return 0.0;
+    }
+    edges.retain(|e| e.2.max(e.3) >= strongest * 0.3);
+
+    // Horizontal edges are projected onto rows, vertical ones onto columns.
+    let offset = (width + height) as f32;
+    let score = |degrees: f32| -> f32 {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let mut rows = vec![0.0f32; 2 * (width + height) as usize + 1];
+        let mut columns = rows.clone();
+        for &(x, y, gx, gy) in &edges {
+            rows[(y * cos - x * sin + offset).round() as usize] += gy;
+            columns[(x * cos + y * sin + offset).round() as usize] += gx;
+        }
+        rows.iter().chain(columns.iter()).map(|v| v * v).sum()
+    };
+
+    // Coarse search over the whole range, then refine around the best coarse angle.
+    let best = |candidates: Vec<f32>| -> f32 {
+        candidates
+            .into_iter()
+            .map(|angle| (angle, score(angle)))
+            // Ties go to the smallest correction.
+            .fold((0.0f32, f32::MIN), |best, (angle, s)| if s > best.1 || (s == best.1 && angle.abs() < best.0.abs()) { (angle, s) } else { best })
+            .0
+    };
+    let coarse_steps = (MAX_SKEW_DEGREES / COARSE_STEP_DEGREES) as i32;
+    let coarse = best((-coarse_steps..=coarse_steps).map(|i| i as f32 * COARSE_STEP_DEGREES).collect());
+    let fine_steps = (COARSE_STEP_DEGREES / FINE_STEP_DEGREES) as i32;
+    best((-fine_steps..=fine_steps)
+        .map(|i| coarse + i as f32 * FINE_STEP_DEGREES)
+        .filter(|angle| angle.abs() <= MAX_SKEW_DEGREES)
+        .collect())
+}
+
+// Samples with bilinear filtering; outside the image counts as transparent.
+fn sample(image: &RgbaImage, x: f32, y: f32) -> Rgba<u8> {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (x - x0, y - y0);
+    let pixel = |px: f32, py: f32| -> [f32; 4] {
+        if px < 0.0 || py < 0.0 || px >= image.width() as f32 || py >= image.height() as f32 {
+            [0.0; 4]
+        } else {
+            image.get_pixel(px as u32, py as u32).0.map(|c| c as f32)
+        }
+    };
+    let (a, b, c, d) = (pixel(x0, y0), pixel(x0 + 1.0, y0), pixel(x0, y0 + 1.0), pixel(x0 + 1.0, y0 + 1.0));
+    let mut out = [0u8; 4];
+    for i in 0..4 {
+        let top = a[i] + (b[i] - a[i]) * tx;
+        let bottom = c[i] + (d[i] - c[i]) * tx;
+        out[i] = (top + (bottom - top) * ty).round().clamp(0.0, 255.0) as u8;
+    }
+    Rgba(out)
+}
+
+/*
+Rotates the image by `degrees` about its centre (positive turns the content clockwise) and crops
+to the largest centred rectangle that contains no empty corners.
+*/
+pub fn rotate_and_crop(image: &DynamicImage, degrees: f32) -> DynamicImage {
+    let source = image.to_rgba8();
+    let (width, height) = source.dimensions();
+    let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+    let (sin, cos) = degrees.to_radians().sin_cos();
+
+    let rotated = RgbaImage::from_fn(width, height, |x, y| {
+        // Map each output pixel back into the source.
+        let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+        let sx = cos * dx + sin * dy + cx - 0.5;
+        let sy = -sin * dx + cos * dy + cy - 0.5;
+        sample(&source, sx, sy)
+    });
+
+    let (sin, cos) = (sin.abs(), cos.abs());
+    let crop_w = width as f32 * cos - height as f32 * sin;
+    let crop_h = height as f32 * cos - width as f32 * sin;
+    if crop_w < 3.0 || crop_h < 3.0 {
+        return DynamicImage::ImageRgba8(rotated);
+    }
+    // Stay a pixel inside so no half-transparent edge pixels from the bilinear sampling survive.
+    let (crop_w, crop_h) = (crop_w.floor() as u32 - 2, crop_h.floor() as u32 - 2);
+    let cropped = imageops::crop_imm(&rotated, (width - crop_w) / 2, (height - crop_h) / 2, crop_w, crop_h).to_image();
+    DynamicImage::ImageRgba8(cropped)
+}
+
+// Detects the tilt and corrects it. Images that are already level are returned unchanged.
+pub fn straighten(image: DynamicImage) -> DynamicImage {
+    let skew = detect_skew(&image);
+    if skew.abs() < MIN_CORRECTION_DEGREES {
+        return image;
+    }
+    println!("[DESKEW] correcting a tilt of {:.2} degrees", skew);
+    rotate_and_crop(&image, -skew)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::GenericImageView;
+
+    // A white canvas with a thick dark frame, like a photographed painting.
+    fn framed_canvas() -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(400, 300, |x, y| {
+            let in_frame = !(40..360).contains(&x) || !(40..260).contains(&y);
+            if in_frame { Rgba([30, 20, 10, 255]) } else { Rgba([240, 240, 230, 255]) }
+        }))
+    }
+
+    #[test]
+    fn test_level_image_is_left_alone() {
+        let image = framed_canvas();
+        assert!(detect_skew(&image).abs() < MIN_CORRECTION_DEGREES);
+        assert_eq!(straighten(image.clone()).dimensions(), image.dimensions());
+    }
+
+    #[test]
+    fn test_detects_and_corrects_tilt() {
+        let tilted = rotate_and_crop(&framed_canvas(), 3.0);
+        let skew = detect_skew(&tilted);
+        assert!((skew - 3.0).abs() < 0.5, "detected {}", skew);
+
+        let straightened = straighten(tilted.clone());
+        let remaining = detect_skew(&straightened);
+        assert!(remaining.abs() < 0.5, "remaining {}", remaining);
+        // Cropping removed the empty corners the rotation introduced
+        assert!(straightened.width() < tilted.width());
+        assert!(straightened.to_rgba8().pixels().all(|p| p.0[3] > 0));
+    }
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+use crate::models::export_settings::ExportSettings;
+
+// Older records are dropped once the history grows past this.
+pub const MAX_EXPORT_RECORDS: usize = 100;
+
+/*
+One finished export: where the pack went and the exact settings it was written with, so it can be
+written again the same way later.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportRecord {
+    pub record_id: u64,
+    // Unix seconds.
+    pub finished_at: u64,
+    pub pack_name: String,
+    pub pack_id: String,
+    pub version: String,
+    // The resolved folder the pack was written into.
+    pub export_path: String,
+    pub settings: ExportSettings,
+    // Files the export left in its pack folders (or archives).
+    pub file_count: usize,
+    pub duration_ms: u64,
+}
+
+// Every finished export of a project, oldest first. Saved with the project.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ExportHistory {
+    records: Vec<ExportRecord>,
+    next_id: u64,
+}
+
+impl ExportHistory {
+    // Stores a record, assigning its ID, and returns that ID.
+    pub fn push(&mut self, mut record: ExportRecord) -> u64 {
+        self.next_id += 1;
+        record.record_id = self.next_id;
+        self.records.push(record);
+        if self.records.len() > MAX_EXPORT_RECORDS {
+            let excess = self.records.len() - MAX_EXPORT_RECORDS;
+            self.records.drain(..excess);
+        }
+        self.next_id
+    }
+
+    // Newest first.
+    pub fn list(&self) -> Vec<ExportRecord> {
+        self.records.iter().rev().cloned().collect()
+    }
+
+    pub fn get(&self, record_id: u64) -> Option<&ExportRecord> {
+        self.records.iter().find(|record| record.record_id == record_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(version: &str) -> ExportRecord {
+        ExportRecord {
+            record_id: 0,
+            finished_at: 0,
+            pack_name: String::from("Pack"),
+            pack_id: String::from("pack"),
+            version: version.to_string(),
+            export_path: String::from("/packs"),
+            settings: ExportSettings::default(),
+            file_count: 3,
+            duration_ms: 10,
+        }
+    }
+
+    #[test]
+    fn test_records_are_listed_newest_first_and_capped() {
+        let mut history = ExportHistory::default();
+        let first = history.push(record("1.0.0"));
+        history.push(record("1.1.0"));
+        assert_eq!(history.list()[0].version, "1.1.0");
+        assert_eq!(history.get(first).unwrap().version, "1.0.0");
+
+        for _ in 0..MAX_EXPORT_RECORDS {
+            history.push(record("2.0.0"));
+        }
+        assert_eq!(history.list().len(), MAX_EXPORT_RECORDS);
+        assert!(history.get(first).is_none());
+    }
+}
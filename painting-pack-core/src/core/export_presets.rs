@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use crate::core::naming;
+use crate::models::export_settings::ExportSettings;
+
+/*
+A named, saved set of export options, so a recurring export (e.g. a weekly pack update) can be
+repeated without filling in the options again. It keeps the whole `ExportSettings`, so options
+added later are remembered too.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportPreset {
+    pub name: String,
+    // Folder to export into without asking. `None` asks every time.
+    #[serde(default)]
+    pub export_path: Option<String>,
+    pub settings: ExportSettings,
+}
+
+/*
+Checks a preset before it is saved, with the same rules the export itself would apply.
+*/
+pub fn validate_preset(preset: &ExportPreset) -> Result<(), String> {
+    if preset.name.trim().is_empty() {
+        return Err(String::from("An export preset needs a name"));
+    }
+    if preset.export_path.as_deref().is_some_and(|path| path.trim().is_empty()) {
+        return Err(String::from("The preset's export folder is blank"));
+    }
+    if preset.settings.pixels_per_block == Some(0) {
+        return Err(String::from("An export preset needs at least 1 pixel per block"));
+    }
+    naming::validate_filename_template(&preset.settings.filename_template)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_preset() {
+        let mut preset = ExportPreset {
+            name: String::from("Weekly update"),
+            export_path: Some(String::from("/packs")),
+            settings: ExportSettings::default(),
+        };
+        assert_eq!(validate_preset(&preset), Ok(()));
+
+        preset.settings.pixels_per_block = Some(0);
+        assert!(validate_preset(&preset).is_err());
+
+        preset.settings.pixels_per_block = None;
+        preset.name = String::from("  ");
+        assert!(validate_preset(&preset).is_err());
+    }
+}
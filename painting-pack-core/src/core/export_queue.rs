@@ -0,0 +1,299 @@
+use std::sync::{mpsc, Arc, Mutex, Weak};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Instant;
+use serde::Serialize;
+use crate::core::export_history::ExportRecord;
+use crate::core::export_resume::ExportSpec;
+use crate::core::timestamp;
+use crate::core::exporter::{self, ExportItem, ExportReport, ExportStage};
+use crate::error::ExportError;
+use crate::models::export_settings::ExportSettings;
+
+// Receives the stages of one export job as they happen, e.g. to forward them to the frontend.
+pub type StageSink = Arc<dyn Fn(ExportStage) + Send + Sync>;
+// Receives the record of a job that finished, e.g. to add it to the project's export history.
+pub type FinishedSink = Arc<dyn Fn(&ExportRecord) + Send + Sync>;
+
+/*
+The export jobs running right now, so `cancel_all` can stop them before their next source image.
+Every job gets its own cancel flag when it starts and gives it up when it ends, so a cancel only
+ever stops the jobs that were running when it was asked for: it can't be cleared by another job
+starting, and it doesn't carry over to jobs started later. Clones share the same list, so the app
+keeps one in its managed state and hands a clone to every job.
+*/
+#[derive(Clone, Default)]
+pub struct RunningExports(Arc<Mutex<Vec<Weak<AtomicBool>>>>);
+
+impl RunningExports {
+    // A fresh cancel flag for a job that is starting, listed for as long as the job holds it.
+    fn register(&self) -> Arc<AtomicBool> {
+        let flag = Arc::new(AtomicBool::new(false));
+        let mut running = self.0.lock().unwrap();
+        running.retain(|flag| flag.strong_count() > 0);
+        running.push(Arc::downgrade(&flag));
+        flag
+    }
+
+    // Cancels every job running right now. Returns how many there were.
+    pub fn cancel_all(&self) -> usize {
+        let mut running = self.0.lock().unwrap();
+        running.retain(|flag| flag.strong_count() > 0);
+        for flag in running.iter().filter_map(Weak::upgrade) {
+            flag.store(true, Ordering::Relaxed);
+        }
+        running.len()
+    }
+}
+
+/*
+Everything needed to write one pack, captured when the job is queued. Later edits to the project
+don't affect a queued job, so different packs and settings can be lined up one after another.
+*/
+#[derive(Clone)]
+pub struct ExportJob {
+    pub pack_name: String,
+    pub version: String,
+    pub id: String,
+    pub description: String,
+    pub items: Vec<ExportItem>,
+    pub export_path: String,
+    pub settings: ExportSettings,
+    // Where to report the job's progress, if anyone is listening.
+    pub on_stage: Option<StageSink>,
+    // Lists the job while it runs so it can be stopped midway; without one it always runs to the end.
+    pub running: Option<RunningExports>,
+    // Told about the finished export, for jobs whose caller doesn't wait for `run` to return.
+    pub on_finished: Option<FinishedSink>,
+    // Raised when the job was validated (e.g. lint issues), passed on in its report.
+    pub warnings: Vec<String>,
+}
+
+impl ExportJob {
+    // The job that re-runs an interrupted export; the exporter keeps what it already wrote.
+    pub fn resume(spec: ExportSpec) -> Self {
+        ExportJob {
+            pack_name: spec.pack_name,
+            version: spec.version,
+            id: spec.id,
+            description: spec.description,
+            items: spec.items,
+            export_path: spec.export_path,
+            settings: spec.settings,
+            on_stage: None,
+            running: None,
+            on_finished: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    // Writes the pack and returns the record of the finished export (without an ID yet) and what it wrote.
+    pub fn run(self) -> Result<(ExportRecord, ExportReport), ExportError> {
+        let on_stage = self.on_stage.unwrap_or_else(|| Arc::new(|_| {}));
+        // Dropped when the job ends, which takes it off the running list.
+        let cancel = self.running.map(|running| running.register()).unwrap_or_default();
+        let started = Instant::now();
+        let mut report = exporter::export_cancellable(
+            self.pack_name.clone(),
+            self.version.clone(),
+            self.id.clone(),
+            self.description,
+            self.items,
+            &self.export_path,
+            &self.settings,
+            &*on_stage,
+            &cancel,
+        )?;
+        report.warnings.splice(0..0, self.warnings);
+
+        let record = ExportRecord {
+            record_id: 0,
+            finished_at: timestamp::unix_seconds(),
+            file_count: exporter::existing_pack_files(&self.export_path, &self.pack_name, &self.settings).len(),
+            pack_name: self.pack_name,
+            pack_id: self.id,
+            version: self.version,
+            export_path: self.export_path,
+            settings: self.settings,
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+        if let Some(on_finished) = &self.on_finished {
+            on_finished(&record);
+        }
+        Ok((record, report))
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Finished,
+    Cancelled,
+    Failed { error: String },
+}
+
+// What the frontend sees of a job.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct JobSummary {
+    pub job_id: u64,
+    pub pack_name: String,
+    pub export_path: String,
+    pub status: JobStatus,
+}
+
+/*
+Runs queued export jobs one at a time on a background worker thread. Every status change is
+reported through `on_update`, which the app uses to emit an event to the frontend.
+*/
+pub struct ExportQueue {
+    jobs: Arc<Mutex<Vec<JobSummary>>>,
+    sender: mpsc::Sender<(u64, ExportJob)>,
+    next_id: AtomicU64,
+}
+
+fn set_status(jobs: &Mutex<Vec<JobSummary>>, job_id: u64, status: JobStatus) -> Option<JobSummary> {
+    let mut jobs = jobs.lock().unwrap();
+    let job = jobs.iter_mut().find(|job| job.job_id == job_id)?;
+    job.status = status;
+    Some(job.clone())
+}
+
+impl ExportQueue {
+    pub fn start(on_update: impl Fn(&JobSummary) + Send + 'static) -> Self {
+        let jobs: Arc<Mutex<Vec<JobSummary>>> = Arc::new(Mutex::new(Vec::new()));
+        let (sender, receiver) = mpsc::channel::<(u64, ExportJob)>();
+
+        let worker_jobs = Arc::clone(&jobs);
+        std::thread::spawn(move || {
+            // Ends when the queue (and with it the sender) is dropped.
+            for (job_id, job) in receiver {
+                if let Some(summary) = set_status(&worker_jobs, job_id, JobStatus::Running) {
+                    on_update(&summary);
+                }
+                // A bug that panics in one job shouldn't stop the worker for the jobs after it.
+                let status = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| job.run())) {
+                    Ok(Ok(_)) => JobStatus::Finished,
+                    Ok(Err(ExportError::Cancelled)) => JobStatus::Cancelled,
+                    Ok(Err(error)) => JobStatus::Failed { error: error.to_string() },
+                    Err(_) => JobStatus::Failed { error: String::from("The export stopped unexpectedly") },
+                };
+                if let Some(summary) = set_status(&worker_jobs, job_id, status) {
+                    on_update(&summary);
+                }
+            }
+        });
+
+        ExportQueue { jobs, sender, next_id: AtomicU64::new(1) }
+    }
+
+    // Adds a job to the end of the queue and returns its ID.
+    pub fn enqueue(&self, job: ExportJob) -> Result<u64, String> {
+        let job_id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.jobs.lock().unwrap().push(JobSummary {
+            job_id,
+            pack_name: job.pack_name.clone(),
+            export_path: job.export_path.clone(),
+            status: JobStatus::Queued,
+        });
+        self.sender.send((job_id, job)).map_err(|_| String::from("The export worker has stopped"))?;
+        Ok(job_id)
+    }
+
+    pub fn jobs(&self) -> Vec<JobSummary> {
+        self.jobs.lock().unwrap().clone()
+    }
+
+    // Forgets finished, cancelled and failed jobs so the list only shows outstanding work.
+    pub fn clear_finished(&self) {
+        self.jobs
+            .lock()
+            .unwrap()
+            .retain(|job| matches!(job.status, JobStatus::Queued | JobStatus::Running));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn job(pack_name: &str, export_path: &str) -> ExportJob {
+        ExportJob {
+            pack_name: pack_name.to_string(),
+            version: String::from("1.0.0"),
+            id: pack_name.to_lowercase(),
+            description: String::new(),
+            items: Vec::new(),
+            export_path: export_path.to_string(),
+            settings: ExportSettings::default(),
+            on_stage: None,
+            running: None,
+            on_finished: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_jobs_run_in_order_and_report_status() {
+        let (updates_tx, updates_rx) = mpsc::channel();
+        let queue = ExportQueue::start(move |summary: &JobSummary| {
+            updates_tx.send(summary.clone()).unwrap();
+        });
+
+        let dir = std::env::temp_dir().join(format!("export_queue_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (records_tx, records_rx) = mpsc::channel();
+        let mut first_job = job("First", &dir.to_string_lossy());
+        first_job.on_finished = Some(Arc::new(move |record: &ExportRecord| records_tx.send(record.clone()).unwrap()));
+        let first = queue.enqueue(first_job).unwrap();
+        let second = queue.enqueue(job("Second", &dir.to_string_lossy())).unwrap();
+
+        let updates: Vec<JobSummary> = (0..4).map(|_| updates_rx.recv_timeout(Duration::from_secs(10)).unwrap()).collect();
+        let order: Vec<(u64, JobStatus)> = updates.into_iter().map(|u| (u.job_id, u.status)).collect();
+        assert_eq!(order, vec![
+            (first, JobStatus::Running),
+            (first, JobStatus::Finished),
+            (second, JobStatus::Running),
+            (second, JobStatus::Finished),
+        ]);
+        assert!(dir.join("First/custompaintings.json").exists());
+        assert!(dir.join("Second/custompaintings.json").exists());
+        let record = records_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(record.pack_name, "First");
+        assert!(record.file_count > 0);
+
+        queue.clear_finished();
+        assert!(queue.jobs().is_empty());
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_cancel_stops_only_the_jobs_running_at_the_time() {
+        let running = RunningExports::default();
+        let first = running.register();
+        let second = running.register();
+        assert_eq!(running.cancel_all(), 2);
+        assert!(first.load(Ordering::Relaxed) && second.load(Ordering::Relaxed));
+
+        // A job starting afterwards neither inherits the cancel nor clears the others'.
+        let later = running.register();
+        assert!(!later.load(Ordering::Relaxed));
+        assert!(first.load(Ordering::Relaxed));
+
+        // Finished jobs drop off the list.
+        drop(first);
+        drop(second);
+        assert_eq!(running.cancel_all(), 1);
+        drop(later);
+        assert_eq!(running.cancel_all(), 0);
+
+        // A job run after a cancel goes through to the end.
+        let dir = std::env::temp_dir().join(format!("export_queue_cancel_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut after = job("After", &dir.to_string_lossy());
+        after.running = Some(running.clone());
+        assert!(after.run().is_ok());
+        assert!(dir.join("After/custompaintings.json").exists());
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,123 @@
+use std::fs::{read_to_string, remove_dir_all, write};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::core::{exporter::ExportItem, lint, timestamp};
+use crate::models::export_settings::ExportSettings;
+use crate::models::painting::Painting;
+
+// Written into a pack's staging folder while it is being exported.
+pub const MANIFEST_FILE: &str = ".export-progress.json";
+
+/*
+A pack folder is written into `<pack folder>.staging` next to it and only moved into place once
+it is complete, so a failed export never leaves a half-written pack where the game loads it.
+*/
+pub fn staging_dir(pack_dir: &str) -> String {
+    format!("{}.staging", pack_dir)
+}
+
+/*
+The inputs of one export, exactly as they were passed in. An unfinished pack is only resumed by
+an export with the very same inputs, so a resumed pack never mixes paintings from two versions
+of the project.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportSpec {
+    pub pack_name: String,
+    pub version: String,
+    pub id: String,
+    pub description: String,
+    pub items: Vec<ExportItem>,
+    pub export_path: String,
+    pub settings: ExportSettings,
+}
+
+impl ExportSpec {
+    // Paintings the export writes per pack, before undersized variants are left out.
+    pub fn painting_count(&self) -> usize {
+        lint::painting_count(&self.items)
+    }
+}
+
+/*
+Progress of one pack folder: the export it belongs to and every painting already written, in
+the order they were written. Saved after each painting, so a crash loses at most the one being
+encoded.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportManifest {
+    pub spec: ExportSpec,
+    pub started_at: u64,
+    pub completed: Vec<Painting>,
+}
+
+pub fn manifest_path(pack_dir: &str) -> PathBuf {
+    Path::new(&staging_dir(pack_dir)).join(MANIFEST_FILE)
+}
+
+// The progress left in the staging folder of a pack by an export that didn't finish, if any.
+pub fn load(pack_dir: &str) -> Option<ExportManifest> {
+    let json = read_to_string(manifest_path(pack_dir)).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+// An export that stopped part way, as offered to the user for resuming.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct InterruptedExport {
+    pub pack_dir: String,
+    pub pack_name: String,
+    pub export_path: String,
+    pub started_at: u64,
+    // Paintings written before it stopped, out of `total`.
+    pub completed: usize,
+    pub total: usize,
+}
+
+/*
+The interrupted exports among `pack_dirs`, i.e. folders still holding export progress. An export
+with a Lite variant leaves progress in both its folders; it is listed once.
+*/
+pub fn find_interrupted(pack_dirs: &[String]) -> Vec<InterruptedExport> {
+    let mut found: Vec<(ExportSpec, InterruptedExport)> = Vec::new();
+    for pack_dir in pack_dirs {
+        let Some(manifest) = load(pack_dir) else { continue };
+        if found.iter().any(|(spec, _)| *spec == manifest.spec) {
+            continue;
+        }
+        let interrupted = InterruptedExport {
+            pack_dir: pack_dir.clone(),
+            pack_name: manifest.spec.pack_name.clone(),
+            export_path: manifest.spec.export_path.clone(),
+            started_at: manifest.started_at,
+            completed: manifest.completed.len(),
+            total: manifest.spec.painting_count(),
+        };
+        found.push((manifest.spec, interrupted));
+    }
+    found.into_iter().map(|(_, interrupted)| interrupted).collect()
+}
+
+// Removes the staging folder of a pack with its progress and partial files, e.g. when the user gives up on resuming it.
+pub fn remove(pack_dir: &str) {
+    let _ = remove_dir_all(staging_dir(pack_dir));
+}
+
+impl ExportManifest {
+    pub fn new(spec: ExportSpec) -> Self {
+        ExportManifest { spec, started_at: timestamp::unix_seconds(), completed: Vec::new() }
+    }
+
+    pub fn save(&self, pack_dir: &str) -> Result<(), String> {
+        let path = manifest_path(pack_dir);
+        let json = serde_json::to_string(self).map_err(|e| format!("Failed to serialize export progress: {}", e))?;
+        write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    // The painting written earlier under `id`, if its image is still in `images_dir`.
+    pub fn completed_painting(&self, id: &str, images_dir: &str) -> Option<&Painting> {
+        self.completed
+            .iter()
+            .find(|painting| painting.id == id)
+            .filter(|painting| Path::new(images_dir).join(&painting.filename).is_file())
+    }
+}
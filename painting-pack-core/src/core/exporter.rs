@@ -0,0 +1,1772 @@
+use std::fs::{copy, create_dir_all, read_dir, remove_dir_all, remove_file, rename, File};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use image::{DynamicImage, ImageFormat};
+use std::io::{BufWriter, Cursor, Write};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+use serde::{Deserialize, Serialize};
+use rayon::prelude::*;
+use base64::{Engine as _, engine::general_purpose};
+use crate::models::pack_list::PackList;
+use crate::models::painting::{self, Painting};
+use crate::models::image_data::ImageData;
+use crate::models::export_settings::{ExportSettings, IconStyle, JsonStyle, PaintingOrder};
+use crate::models::image_size::ImageSize;
+use crate::core::export_resume::{self, ExportManifest, ExportSpec};
+use crate::core::decode_cache::DecodeCache;
+use crate::core::session_lock::SessionLock;
+use crate::error::ExportError;
+use crate::core::{adjustments, cropper, flipbook, icon_badge, icon_generator, image_format::{self, ColorDepth, ImageEncoder, PngEncoder}, lint, low_memory, naming, natural_sort, pack_json, palette, preview_strip, resample, timestamp, validator};
+
+// Load in the default icon to bianary so the file is contained in the executable
+pub(crate) const DEFAULT_ICON: &[u8] = include_bytes!("../../assets/icon.png");
+
+// Side length of generated pack icons.
+const ICON_SIZE: u32 = 128;
+
+// Longest side of the crop previews sent to the frontend.
+pub const PREVIEW_MAX_SIZE: u32 = 512;
+
+/*
+One step of an export, reported as it starts so a live log can show exactly where a slow or
+stuck export is. Sizes are in blocks.
+*/
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum ExportStage {
+    // A pack folder is about to be written (the Lite variant is a second pack).
+    StartingPack { pack_name: String, pack_dir: String },
+    Decoding { source: String },
+    Resizing { file: String, width: u32, height: u32 },
+    Encoding { file: String },
+    // A painting kept from an interrupted run of the same export instead of being written again.
+    AlreadyWritten { id: String },
+    // A block size left out because the source is too small to fill it at the chosen density.
+    SkippedVariant { id: String, width: u32, height: u32, reason: String },
+    // A painting ID or image file name another painting of the pack already had, and the
+    // numbered one used instead so neither overwrites the other.
+    RenamedDuplicate { original: String, renamed: String },
+    // Sent after each painting of a pack is done with, whether written, kept or skipped. `file` is
+    // blank for skipped paintings.
+    Progress { done: usize, total: usize, file: String, percent: u32 },
+    WritingJson,
+    WritingIcon,
+    WritingPreviewStrip,
+    Finished,
+}
+
+impl ExportStage {
+    // The line kept in the export report for stages that mean the pack isn't quite what was asked for.
+    fn warning(&self) -> Option<String> {
+        match self {
+            ExportStage::SkippedVariant { id, reason, .. } => Some(format!("Skipped {}: {}", id, reason)),
+            ExportStage::RenamedDuplicate { original, renamed } => Some(format!("{} was already used, renamed to {}", original, renamed)),
+            _ => None,
+        }
+    }
+}
+
+/*
+What a finished export wrote, for callers that want more than the stages as they went by.
+Covers every pack the export wrote, i.e. the Lite variant as well.
+*/
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ExportReport {
+    // The pack folders or archives written, the Lite variant first.
+    pub outputs: Vec<String>,
+    // Paintings listed in the packs' custompaintings.json files.
+    pub paintings: usize,
+    // Of those, paintings kept from an interrupted run rather than written again.
+    pub resumed: usize,
+    // Skipped block sizes and renamed duplicates, one line each.
+    pub warnings: Vec<String>,
+}
+
+impl ExportReport {
+    fn absorb(&mut self, other: ExportReport) {
+        self.outputs.extend(other.outputs);
+        self.paintings += other.paintings;
+        self.resumed += other.resumed;
+        self.warnings.extend(other.warnings);
+    }
+}
+
+/*
+This creates Base64 previews from a Vec<DynamicImage> for the Tauri frontend.
+The images are passed in directly and are not retrieved from app state.
+*/
+pub fn generate_base64_previews(image_list: &[DynamicImage]) -> Vec<String> {
+    image_list.iter().map(|preview_image| preview_data_uri(&encode_preview_png(preview_image))).collect()
+}
+
+// A crop scaled down to preview size and encoded as PNG.
+pub fn encode_preview_png(image: &DynamicImage) -> Vec<u8> {
+    let mut image_buffer: Vec<u8> = Vec::new();
+
+    // The grid only shows thumbnails; encoding full resolution crops is what made big imports slow.
+    let preview_image = resample::fit_within(image, PREVIEW_MAX_SIZE, PREVIEW_MAX_SIZE);
+
+    // Write the image's PNG data into our in-memory buffer
+    preview_image.write_to(
+        &mut Cursor::new(&mut image_buffer),
+        ImageFormat::Png,
+    ).expect("Failed to write image to buffer");
+    image_buffer
+}
+
+// Formats encoded PNG bytes as a Base64 Data URI the frontend can show directly.
+pub fn preview_data_uri(png: &[u8]) -> String {
+    format!("data:image/png;base64,{}", general_purpose::STANDARD.encode(png))
+}
+
+/*
+Writes icon.png in the configured style from `icon_items`, the first paintings of the pack. Falls
+back to the bundled icon when there's no painting to use or it can't be read. A non-empty `badge`
+is stamped onto whichever icon is used.
+*/
+fn write_icon(out: &mut PackWriter, style: IconStyle, icon_items: &[ExportItem], badge: Option<&str>) -> Result<(), ExportError> {
+    let icon = match (style, icon_items.first()) {
+        (IconStyle::FirstPainting, Some(item)) => {
+            let options = item.data.crop_options();
+            match cropper::crop_single_image(&item.source_path, &ImageSize::Square, &options) {
+                // A flipbook's icon is its first frame.
+                Ok(square) => Some(resample::resize_exact(&flipbook::frame(&square, options.frames, 0), ICON_SIZE, ICON_SIZE)),
+                Err(e) => {
+                    eprintln!("[EXPORT] Couldn't build the icon from {}: {}", item.source_path, e);
+                    None
+                }
+            }
+        }
+        (IconStyle::Collage2x2, _) => icon_generator::collage_icon(icon_items, 2, ICON_SIZE),
+        (IconStyle::Collage3x3, _) => icon_generator::collage_icon(icon_items, 3, ICON_SIZE),
+        _ => None,
+    };
+    let icon = match (icon, badge) {
+        (Some(icon), Some(badge)) => icon_badge::draw_badge(&icon, badge),
+        (Some(icon), None) => icon,
+        (None, Some(badge)) => {
+            let default_icon = image::load_from_memory(DEFAULT_ICON)
+                .map_err(|e| ExportError::Source { path: String::from("the bundled icon"), message: e.to_string() })?;
+            icon_badge::draw_badge(&default_icon, badge)
+        }
+        (None, None) => {
+            return out.write_file("icon.png", |file| file.write_all(DEFAULT_ICON).map_err(|e| e.to_string()));
+        }
+    };
+    out.write_file("icon.png", |file| PngEncoder.write(&icon, ColorDepth::Rgba32, file))
+}
+
+// A fresh `<pack_path>.backups/<timestamp>/` folder. Two exports within the same second get a
+// numeric suffix instead of clobbering each other.
+fn new_backup_dir(pack_path: &str) -> Result<String, ExportError> {
+    let stamp = timestamp::format_compact(timestamp::unix_seconds());
+    let mut backup_dir = format!("{}.backups/{}", pack_path, stamp);
+    let mut attempt = 2;
+    while Path::new(&backup_dir).exists() {
+        backup_dir = format!("{}.backups/{}_{}", pack_path, stamp, attempt);
+        attempt += 1;
+    }
+    create_dir_all(&backup_dir).map_err(|e| ExportError::write(&backup_dir, e))?;
+    Ok(backup_dir)
+}
+
+/*
+Copies the custompaintings.json (and optionally the images) of a pack that is about to be
+overwritten into `<pack_dir>.backups/<timestamp>/`, next to the pack rather than inside it,
+so a bad export can be rolled back by hand. Returns the backup folder if one was made.
+*/
+fn backup_existing_pack(pack_dir: &str, backup_images: bool) -> Result<Option<String>, ExportError> {
+    let json_path = format!("{}/custompaintings.json", pack_dir);
+    if !Path::new(&json_path).exists() {
+        return Ok(None);
+    }
+
+    let backup_dir = new_backup_dir(pack_dir)?;
+    let backup_json = format!("{}/custompaintings.json", backup_dir);
+    copy(&json_path, &backup_json).map_err(|e| ExportError::write(&backup_json, e))?;
+
+    let images_dir = format!("{}/images", pack_dir);
+    if backup_images && Path::new(&images_dir).is_dir() {
+        let backup_images_dir = format!("{}/images", backup_dir);
+        create_dir_all(&backup_images_dir).map_err(|e| ExportError::write(&backup_images_dir, e))?;
+        let entries = read_dir(&images_dir).map_err(|e| ExportError::Source { path: images_dir.clone(), message: e.to_string() })?;
+        for entry in entries.flatten() {
+            if entry.path().is_file() {
+                let backup_image = Path::new(&backup_images_dir).join(entry.file_name());
+                copy(entry.path(), &backup_image).map_err(|e| ExportError::write(&backup_image, e))?;
+            }
+        }
+    }
+
+    Ok(Some(backup_dir))
+}
+
+fn write_json (painting_list: &PackList<Painting>, out: &mut PackWriter, style: &JsonStyle) -> Result<(), ExportError> {
+    let json_data = pack_json::render(painting_list, style)
+        .map_err(|message| ExportError::Encode { file: String::from("custompaintings.json"), message })?;
+    out.write_file("custompaintings.json", |file| file.write_all(json_data.as_bytes()).map_err(|e| e.to_string()))
+}
+
+/*
+Where a pack's files go: the staging folder of a pack folder (see `export_resume::staging_dir`),
+or a zip archive each file is streamed into as it is encoded. Paths are relative to the pack root.
+*/
+enum PackWriter {
+    Folder { pack_dir: String, staging: String },
+    Archive(Box<ZipWriter<BufWriter<File>>>),
+}
+
+impl PackWriter {
+    fn write_file(&mut self, relative_path: &str, contents: impl FnOnce(&mut dyn Write) -> Result<(), String>) -> Result<(), ExportError> {
+        match self {
+            PackWriter::Folder { staging, .. } => {
+                let path = Path::new(staging).join(relative_path);
+                let file = File::create(&path).map_err(|e| ExportError::write(&path, e))?;
+                let mut file = BufWriter::new(file);
+                contents(&mut file)
+                    .and_then(|()| file.flush().map_err(|e| e.to_string()))
+                    .map_err(|e| ExportError::write(&path, e))
+            }
+            PackWriter::Archive(archive) => {
+                // Textures are already compressed; deflating them again only costs time.
+                let method = if relative_path.ends_with(".json") || relative_path.ends_with(".mcmeta") {
+                    CompressionMethod::Deflated
+                } else {
+                    CompressionMethod::Stored
+                };
+                archive
+                    .start_file(relative_path, SimpleFileOptions::default().compression_method(method))
+                    .map_err(|e| ExportError::write(relative_path, e))?;
+                contents(archive.as_mut()).map_err(|e| ExportError::write(relative_path, e))
+            }
+        }
+    }
+
+    // The pack folder, for the export manifest; archives are written in one go and never resumed.
+    fn pack_dir(&self) -> Option<&str> {
+        match self {
+            PackWriter::Folder { pack_dir, .. } => Some(pack_dir),
+            PackWriter::Archive(_) => None,
+        }
+    }
+
+    // The folder the files are written into until the pack is complete.
+    fn staging(&self) -> Option<&str> {
+        match self {
+            PackWriter::Folder { staging, .. } => Some(staging),
+            PackWriter::Archive(_) => None,
+        }
+    }
+}
+
+// This new struct is used to package all necessary data for a single exportable image.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportItem {
+    pub source_path: String,
+    pub data: ImageData,
+}
+
+
+// Treats blank strings the same as missing values.
+fn non_blank(value: Option<&str>) -> Option<String> {
+    value.map(str::trim).filter(|v| !v.is_empty()).map(str::to_string)
+}
+
+/*
+Fills in any missing name, artist, ID or filename from pack-wide defaults so a single blank field
+doesn't abort the export. Names fall back to the source file name, IDs and filenames fall back to
+each other and then to the name, and artists fall back to the default artist. Only errors when
+nothing can be derived.
+*/
+pub fn apply_defaults(items: &mut [ExportItem], settings: &ExportSettings) -> Result<(), String> {
+    let default_artist = non_blank(Some(&settings.default_artist));
+
+    for item in items.iter_mut() {
+        let stem = Path::new(&item.source_path).file_stem().map(|s| s.to_string_lossy().to_string());
+
+        let name = non_blank(item.data.name.as_deref())
+            .or_else(|| non_blank(stem.as_deref()))
+            .ok_or_else(|| format!("Could not derive a painting name for {}", item.source_path))?;
+        let artist = non_blank(item.data.artist.as_deref())
+            .or_else(|| default_artist.clone())
+            .ok_or_else(|| format!("\"{}\" has no artist and no default artist is set", name))?;
+        let id = non_blank(item.data.id.as_deref())
+            .or_else(|| non_blank(item.data.filename.as_deref()))
+            .unwrap_or_else(|| name.clone());
+        let filename = non_blank(item.data.filename.as_deref()).unwrap_or_else(|| id.clone());
+
+        item.data.name = Some(name);
+        item.data.artist = Some(artist);
+        item.data.id = Some(id);
+        item.data.filename = Some(filename);
+    }
+
+    Ok(())
+}
+
+// Size of the texture written for a crop of the given dimensions under the `max_width` texture cap.
+pub(crate) fn texture_dimensions(crop_width: u32, crop_height: u32, max_width: Option<u32>) -> (u32, u32) {
+    match max_width {
+        Some(max_width) if crop_width > max_width => (max_width, (crop_height as u64 * max_width as u64 / crop_width as u64) as u32),
+        _ => (crop_width, crop_height),
+    }
+}
+
+/*
+Why a `width` x `height` block variant can't be filled from a crop this size at `pixels_per_block`
+without the game upscaling it, or `None` if it can.
+*/
+pub(crate) fn undersized_reason(crop_width: u32, crop_height: u32, width: u32, height: u32, pixels_per_block: u32) -> Option<String> {
+    let (needed_width, needed_height) = (width * pixels_per_block, height * pixels_per_block);
+    (crop_width < needed_width || crop_height < needed_height).then(|| format!(
+        "source crop is {}x{} px but {}x{} blocks at {} px per block needs {}x{}",
+        crop_width, crop_height, width, height, pixels_per_block, needed_width, needed_height
+    ))
+}
+
+// Upper bound on the threads encoding textures; every one holds a decoded source in memory.
+const MAX_EXPORT_THREADS: usize = 8;
+
+// One block size of a source, worked out on an export thread and waiting to be written.
+enum PreparedVariant {
+    // Left out because the crop is too small for it at the chosen density.
+    Undersized(String),
+    // The encoded texture, and whether it was scaled down to the density first.
+    Encoded { bytes: Vec<u8>, resized: bool },
+}
+
+// Threads for one export: the machine's cores, up to `MAX_EXPORT_THREADS`, or just one in low-memory mode.
+fn export_threads() -> usize {
+    if low_memory::enabled() {
+        return 1;
+    }
+    std::thread::available_parallelism().map_or(1, |n| n.get()).min(MAX_EXPORT_THREADS)
+}
+
+/*
+Crops and encodes every block size of one item that the manifest doesn't already hold, taking the
+decoded source from `decode_cache`. `None` marks sizes kept from an interrupted run; the source
+isn't asked for if all of them are.
+*/
+#[allow(clippy::too_many_arguments)]
+fn prepare_item(
+    item: &ExportItem,
+    decode_cache: &DecodeCache,
+    settings: &ExportSettings,
+    harmony_palette: Option<(&[palette::Colour], f32)>,
+    pixels_per_block: Option<u32>,
+    manifest: &ExportManifest,
+    images_dir: Option<&str>,
+    base_id: &str,
+) -> Result<Vec<Option<PreparedVariant>>, ExportError> {
+    let frames = item.data.animation.map_or(1, |animation| animation.frames);
+    let encoder = settings.output_format.encoder();
+    let options = item.data.crop_options().for_decoded_source(&item.source_path);
+    let mut decoded: Option<(DynamicImage, u32, u32)> = None;
+
+    let variants = item.data.get_sizes().iter().map(|(width, height)| {
+        let id = format!("{}_{}x{}", base_id, &width, &height);
+        if images_dir.is_some_and(|dir| manifest.completed_painting(&id, dir).is_some()) {
+            return Ok(None);
+        }
+
+        if decoded.is_none() {
+            // Re-create the image from the source path on-demand for export and make it mutable.
+            let source = decode_cache
+                .get(&item.source_path, options.deskew)
+                .map_err(|message| ExportError::Source { path: item.source_path.clone(), message })?;
+            let mut painting = cropper::crop_opened_image(&source, &item.data.image_size, &options);
+
+            let (crop_width, crop_height) = (painting.width(), painting.height() / frames);
+            if let Some(max_width) = settings.max_texture_width.filter(|max_width| painting.width() > *max_width) {
+                painting = resample::scale_to_width(&painting, max_width, settings.resample_filter);
+            }
+
+            if let Some((colours, strength)) = harmony_palette {
+                painting = palette::harmonize(&painting, colours, strength);
+            }
+            decoded = Some((painting, crop_width, crop_height));
+        }
+        let (painting, crop_width, crop_height) = decoded.as_ref().expect("decoded above");
+
+        if let Some(reason) = pixels_per_block.and_then(|ppb| undersized_reason(*crop_width, *crop_height, *width, *height, ppb)) {
+            if !settings.force_undersized_variants {
+                return Ok(Some(PreparedVariant::Undersized(reason)));
+            }
+        }
+        let (texture, resized) = match pixels_per_block {
+            // Reduced density variants get one texture per block size; never upscale.
+            Some(ppb) if painting.width() > width * ppb => {
+                (Cow::Owned(resample::resize_with(painting, width * ppb, height * ppb * frames, settings.resample_filter)), true)
+            }
+            _ => (Cow::Borrowed(painting), false),
+        };
+        // Per-size adjustments act on the final texture, after any downscaling.
+        let texture = match adjustments::for_size(&item.data.size_adjustments, *width, *height) {
+            Some(adjustments) if !adjustments.is_identity() => Cow::Owned(adjustments.apply(&texture)),
+            _ => texture,
+        };
+        let mut bytes = Vec::new();
+        let depth = settings.color_depth.resolve(&texture);
+        encoder.write(&texture, depth, &mut bytes).map_err(|message| ExportError::Encode { file: id, message })?;
+        Ok(Some(PreparedVariant::Encoded { bytes, resized }))
+    }).collect();
+    decode_cache.release(&item.source_path, options.deskew);
+    variants
+}
+
+/*
+The sanitized base ID of every item, numbered `_2`, `_3`, ... where one of its paintings would
+otherwise get the same ID as a painting of an earlier item (e.g. two sources with the same file
+name). The crops of one library image share a base ID without clashing, as their sizes differ.
+Each renaming is reported through `on_stage`.
+*/
+fn unique_base_ids(items: &[ExportItem], on_stage: &dyn Fn(ExportStage)) -> Vec<String> {
+    let mut used: HashSet<String> = HashSet::new();
+    items
+        .iter()
+        .map(|item| {
+            let base_id = validator::sanitize_id(item.data.id.as_deref().unwrap_or_default());
+            let sizes = item.data.get_sizes();
+            let ids = |base: &str| sizes.iter().map(|(width, height)| format!("{}_{}x{}", base, width, height)).collect::<Vec<String>>();
+            let mut unique = base_id.clone();
+            let mut suffix = 2;
+            while ids(&unique).iter().any(|id| used.contains(id)) {
+                unique = format!("{}_{}", base_id, suffix);
+                suffix += 1;
+            }
+            if unique != base_id {
+                println!("[EXPORT] The ID {} is already taken, using {} instead", base_id, unique);
+                on_stage(ExportStage::RenamedDuplicate { original: base_id, renamed: unique.clone() });
+            }
+            used.extend(ids(&unique));
+            unique
+        })
+        .collect()
+}
+
+/*
+Writes the textures of every item. Sources are decoded and encoded a few at a time on a bounded
+thread pool; the textures of each batch are then written, named and reported in item order, so
+the pack, its manifest and the stages come out the same as from a serial export.
+*/
+#[allow(clippy::too_many_arguments)]
+fn write_images(
+    painting_list: &mut PackList<Painting>,
+    image_list: Vec<ExportItem>,
+    out: &mut PackWriter,
+    settings: &ExportSettings,
+    harmony_palette: Option<(&[palette::Colour], f32)>,
+    pixels_per_block: Option<u32>,
+    decode_cache: &DecodeCache,
+    manifest: &mut ExportManifest,
+    on_stage: &dyn Fn(ExportStage),
+    cancel: &AtomicBool,
+) -> Result<ExportReport, ExportError> {
+    let mut report = ExportReport::default();
+    let warnings = RefCell::new(Vec::new());
+    let on_stage = &|stage: ExportStage| {
+        if let Some(warning) = stage.warning() {
+            warnings.borrow_mut().push(warning);
+        }
+        on_stage(stage)
+    };
+
+    let images_dir = out.staging().map(|dir| format!("{}/images", dir));
+    if let Some(images_dir) = &images_dir {
+        create_dir_all(images_dir).map_err(|e| ExportError::write(images_dir, e))?;
+    }
+    let pack_id = painting_list.id.clone();
+    let descriptions_supported = lint::rules_for(&settings.target_mod_version).painting_descriptions;
+    let frames_supported = lint::rules_for(&settings.target_mod_version).painting_frames;
+    let weights_supported = lint::rules_for(&settings.target_mod_version).painting_weights;
+    let encoder = settings.output_format.encoder();
+    // Image file names already used in this pack, so no texture overwrites another.
+    let mut file_names = naming::UniqueNames::default();
+    let base_ids = unique_base_ids(&image_list, on_stage);
+    let total = lint::painting_count(&image_list);
+    let mut handled = 0;
+    let mut report_progress = |file: &str| {
+        handled += 1;
+        on_stage(ExportStage::Progress { done: handled, total, file: file.to_string(), percent: (handled * 100 / total.max(1)) as u32 });
+    };
+
+    let threads = export_threads();
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .map_err(|e| format!("Failed to start the export threads: {}", e))?;
+
+    // One batch per round keeps at most `threads` decoded sources in memory.
+    for (batch_index, batch) in image_list.chunks(threads).enumerate() {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(ExportError::Cancelled);
+        }
+        let shared_manifest: &ExportManifest = manifest;
+        let batch_ids = &base_ids[batch_index * threads..batch_index * threads + batch.len()];
+        let prepared: Result<Vec<Vec<Option<PreparedVariant>>>, ExportError> = pool.install(|| {
+            batch
+                .par_iter()
+                .zip(batch_ids.par_iter())
+                .map(|(item, base_id)| {
+                    if cancel.load(Ordering::Relaxed) {
+                        return Ok(Vec::new());
+                    }
+                    prepare_item(item, decode_cache, settings, harmony_palette, pixels_per_block, shared_manifest, images_dir.as_deref(), base_id)
+                })
+                .collect()
+        });
+        if cancel.load(Ordering::Relaxed) {
+            return Err(ExportError::Cancelled);
+        }
+        let prepared = prepared?;
+
+        for (offset, (item, variants)) in batch.iter().zip(prepared).enumerate() {
+            let index = batch_index * threads + offset;
+            let mut variants = variants.into_iter();
+            let mut reported_decoding = false;
+            // Whether a file has been named after this item's source yet, so its own later sizes
+            // aren't reported as clashing with it.
+            let mut source_named = false;
+
+            for (width, height) in item.data.get_sizes() {
+                let variant = variants.next().flatten();
+
+                let sanitized_id = &base_ids[index];
+                let raw_name = item.data.name.as_deref().unwrap_or_default();
+
+                let id: String = format!("{}_{}x{}", sanitized_id, &width, &height);
+                if let Some(done) = images_dir.as_ref().and_then(|dir| manifest.completed_painting(&id, dir)).cloned() {
+                    // Keep later duplicates numbered the way the interrupted run numbered them.
+                    file_names.claim(Path::new(&done.filename).file_stem().unwrap_or_default().to_string_lossy().as_ref());
+                    source_named = true;
+                    on_stage(ExportStage::AlreadyWritten { id });
+                    report_progress(&done.filename);
+                    painting_list.add_painting(done);
+                    report.resumed += 1;
+                    continue;
+                }
+                let Some(variant) = variant else {
+                    return Err(ExportError::Invalid { message: format!("{} went missing from the pack while it was being exported", id) });
+                };
+
+                if !reported_decoding {
+                    on_stage(ExportStage::Decoding { source: item.source_path.clone() });
+                    reported_decoding = true;
+                }
+                let (bytes, resized) = match variant {
+                    PreparedVariant::Undersized(reason) => {
+                        println!("[EXPORT] Skipping {}: {}", id, reason);
+                        on_stage(ExportStage::SkippedVariant { id, width: *width, height: *height, reason });
+                        report_progress("");
+                        continue;
+                    }
+                    PreparedVariant::Encoded { bytes, resized } => (bytes, resized),
+                };
+                let base_filename: String = if settings.preserve_source_filenames {
+                    // Keep the source's own name; every size after the first gets a numbered suffix.
+                    let stem = Path::new(&item.source_path).file_stem().unwrap_or_default().to_string_lossy();
+                    let safe_stem = naming::strip_unsafe_filename_chars(&stem);
+                    let claimed = file_names.claim(&safe_stem);
+                    // Only a name another source already took is a clash worth reporting.
+                    if claimed != safe_stem && !source_named {
+                        println!("[EXPORT] {} already used, writing {} instead", safe_stem, claimed);
+                        on_stage(ExportStage::RenamedDuplicate { original: safe_stem, renamed: claimed.clone() });
+                    }
+                    source_named = true;
+                    claimed
+                } else {
+                    let rendered = naming::render_filename(
+                        &settings.filename_template,
+                        &naming::FilenameTokens {
+                            pack_id: &pack_id,
+                            // Library order when the caller tracks it, otherwise the position in this export.
+                            index: if item.data.order > 0 { item.data.order as usize } else { index + 1 },
+                            id: sanitized_id,
+                            filename: item.data.filename.as_deref().unwrap_or_default(),
+                            name: raw_name,
+                            width: *width,
+                            height: *height,
+                        },
+                    )?;
+                    // Templates without `{id}` can give two paintings the same file name.
+                    let claimed = file_names.claim(&rendered);
+                    if claimed != rendered {
+                        println!("[EXPORT] {} already used, writing {} instead", rendered, claimed);
+                        on_stage(ExportStage::RenamedDuplicate { original: rendered, renamed: claimed.clone() });
+                    }
+                    claimed
+                };
+
+                let file = format!("{}.{}", base_filename, encoder.extension());
+                let save_path = format!("images/{}", &file);
+                if resized {
+                    on_stage(ExportStage::Resizing { file: file.clone(), width: *width, height: *height });
+                }
+                on_stage(ExportStage::Encoding { file });
+                out.write_file(&save_path, |file| file.write_all(&bytes).map_err(|e| e.to_string()))?;
+                if let Some(animation) = &item.data.animation {
+                    out.write_file(&format!("{}.mcmeta", save_path), |file| file.write_all(animation.mcmeta().as_bytes()).map_err(|e| e.to_string()))?;
+                }
+
+                let painting_meta: Painting = Painting {
+                    id,
+                    filename: format!("{}.{}", base_filename, encoder.extension()),
+                    name: naming::normalize_name(
+                        raw_name,
+                        settings.name_case,
+                        settings.strip_unsupported_glyphs,
+                    ),
+                    artist: item.data.artist.clone().unwrap_or_default(),
+                    width: *width,
+                    height: *height,
+                    description: if descriptions_supported { non_blank(item.data.description.as_deref()) } else { None },
+                    frame: item.data.frame.clone().filter(|frame| frames_supported && !frame.is_empty()),
+                    // The default weight of 1 is left out.
+                    weight: item.data.weight.filter(|weight| weights_supported && *weight != 1),
+                };
+                report_progress(&painting_meta.filename);
+                painting_list.add_painting(painting_meta.clone());
+                manifest.completed.push(painting_meta);
+                if let Some(Err(e)) = out.pack_dir().map(|dir| manifest.save(dir)) {
+                    eprintln!("[EXPORT] {}", e);
+                }
+            }
+        }
+    }
+    report.paintings = painting_list.paintings().len();
+    report.warnings = warnings.into_inner();
+    Ok(report)
+}
+
+
+/*
+The export folder with its `{pack_id}`, `{version}` and `{date}` tokens filled in for this pack and
+today. Paths without tokens are returned as they are.
+*/
+pub fn resolve_export_path(export_path: &str, id: &str, version: &str) -> String {
+    let date = timestamp::format_date(timestamp::unix_seconds());
+    naming::render_export_path(export_path, &validator::sanitize_id(id), version, &date)
+}
+
+// Folder a pack is written to: the pack name with spaces replaced, under `export_path`.
+pub fn pack_dir(export_path: &str, pack_name: &str) -> String {
+    format!("{}/{}", export_path, pack_name.replace(' ', "_"))
+}
+
+// What an export of `pack_name` produces: its pack folder, or that name with `.zip` for archives.
+pub fn pack_output_path(export_path: &str, pack_name: &str, settings: &ExportSettings) -> String {
+    let pack_dir = pack_dir(export_path, pack_name);
+    if settings.archive { format!("{}.zip", pack_dir) } else { pack_dir }
+}
+
+/*
+Opens `archive_path` for a pack to be streamed into. The archive is written under a temporary name
+and only renamed into place by `finish_archive`, so a failed export never leaves a broken archive
+over a good one. With backups on, an existing archive is copied into `<archive>.backups/` first.
+*/
+fn start_archive(archive_path: &str, backup_existing: bool) -> Result<PackWriter, ExportError> {
+    if backup_existing && Path::new(archive_path).is_file() {
+        let file_name = Path::new(archive_path).file_name().unwrap_or_default();
+        let backup = Path::new(&new_backup_dir(archive_path)?).join(file_name);
+        copy(archive_path, &backup).map_err(|e| ExportError::write(&backup, e))?;
+    }
+    if let Some(parent) = Path::new(archive_path).parent() {
+        create_dir_all(parent).map_err(|e| ExportError::write(parent, e))?;
+    }
+    let part_path = format!("{}.part", archive_path);
+    let file = File::create(&part_path).map_err(|e| ExportError::write(&part_path, e))?;
+    Ok(PackWriter::Archive(Box::new(ZipWriter::new(BufWriter::new(file)))))
+}
+
+/*
+Removes what a failed or cancelled export wrote: the staging folder with the export manifest, or a
+half written archive with its temporary file. A pack already at `pack_dir` was never touched.
+*/
+fn discard_partial_pack(out: PackWriter, pack_dir: &str) {
+    let (partial, result) = match out {
+        PackWriter::Archive(archive) => {
+            drop(archive);
+            let part_path = format!("{}.part", pack_dir);
+            let result = remove_file(&part_path);
+            (part_path, result)
+        }
+        PackWriter::Folder { staging, .. } => {
+            let result = remove_dir_all(&staging);
+            (staging, result)
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("[EXPORT] Failed to remove the partial pack {}: {}", partial, e);
+    }
+}
+
+/*
+Moves a complete staging folder into place as `pack_dir`. A pack already there is moved aside
+first and only deleted once the new one is in place, so a failed move leaves the old pack as it
+was. Files of the old pack that the new one doesn't write go with it; backups keep a copy.
+*/
+fn finish_staging(staging: &str, pack_dir: &str) -> Result<(), ExportError> {
+    let _ = remove_file(Path::new(staging).join(export_resume::MANIFEST_FILE));
+    let replaced = format!("{}.replaced", pack_dir);
+    let had_pack = Path::new(pack_dir).exists();
+    if had_pack {
+        // Left over if an earlier export stopped right here.
+        let _ = remove_dir_all(&replaced);
+        rename(pack_dir, &replaced).map_err(|e| ExportError::write(pack_dir, e))?;
+    }
+    if let Err(e) = rename(staging, pack_dir) {
+        if had_pack {
+            let _ = rename(&replaced, pack_dir);
+        }
+        return Err(ExportError::write(pack_dir, e));
+    }
+    if had_pack {
+        if let Err(e) = remove_dir_all(&replaced) {
+            eprintln!("[EXPORT] Failed to remove the replaced pack {}: {}", replaced, e);
+        }
+    }
+    Ok(())
+}
+
+fn finish_archive(archive: ZipWriter<BufWriter<File>>, archive_path: &str) -> Result<(), ExportError> {
+    let part_path = format!("{}.part", archive_path);
+    let mut file = archive.finish().map_err(|e| ExportError::write(&part_path, e))?;
+    file.flush().map_err(|e| ExportError::write(&part_path, e))?;
+    drop(file);
+    rename(&part_path, archive_path).map_err(|e| ExportError::write(archive_path, e))
+}
+
+/*
+Files already in the folders an export of `pack_name` would write to (the pack and, if one is
+configured, its Lite variant), i.e. everything the export may overwrite. For archive exports
+that is the archives themselves.
+*/
+pub fn existing_pack_files(export_path: &str, pack_name: &str, settings: &ExportSettings) -> Vec<String> {
+    let mut dirs = vec![pack_output_path(export_path, pack_name, settings)];
+    if let Some(lite) = &settings.lite_variant {
+        dirs.push(pack_output_path(export_path, &format!("{}{}", pack_name, lite.name_suffix), settings));
+    }
+
+    let mut files = Vec::new();
+    if settings.archive {
+        files.extend(dirs.into_iter().filter(|archive| Path::new(archive).is_file()));
+        return files;
+    }
+    for dir in dirs {
+        for folder in [dir.clone(), format!("{}/images", dir)] {
+            let Ok(entries) = read_dir(&folder) else { continue };
+            let mut found: Vec<String> = entries
+                .flatten()
+                .filter(|entry| entry.path().is_file())
+                .map(|entry| entry.path().to_string_lossy().to_string())
+                .collect();
+            found.sort();
+            files.extend(found);
+        }
+    }
+    files
+}
+
+/*
+Writes one complete pack folder (images, custompaintings.json and icon) under `export_path`, or
+one zip archive of the same files if `settings.archive` is on.
+`pixels_per_block` caps the texture density; `None` keeps the full resolution.
+Folders are written into a staging folder next to the pack, with progress in its export manifest,
+and replace the pack folder only once complete; archives go to a temporary file in the same way.
+If the staging folder holds the progress of an interrupted run of the same export, that run is
+picked up where it stopped: paintings already written are kept and the existing pack is not
+backed up again. Archives are always written from scratch. On any error the partial pack is
+removed again; if `cancel` is set mid-export that error is `ExportError::Cancelled`. While the
+pack is written it is locked (see `SessionLock`); if another export holds it, this one stops
+with `ExportError::InUse` before touching anything.
+*/
+#[allow(clippy::too_many_arguments)]
+fn write_pack(
+    pack_name: String,
+    version: String,
+    id: String,
+    description: String,
+    items_to_export: Vec<ExportItem>,
+    export_path: &str,
+    settings: &ExportSettings,
+    harmony_palette: Option<(&[palette::Colour], f32)>,
+    pixels_per_block: Option<u32>,
+    decode_cache: &DecodeCache,
+    spec: &ExportSpec,
+    on_stage: &dyn Fn(ExportStage),
+    cancel: &AtomicBool,
+) -> Result<ExportReport, ExportError> {
+    // --- NEW: Sanitize Pack Name and ID ---
+    let pack_dir = pack_output_path(export_path, &pack_name, settings);
+    // Held until the pack is finished or discarded, so no other export writes it meanwhile.
+    let lock = SessionLock::acquire(Path::new(&pack_dir))?;
+    let on_stage = &|stage: ExportStage| {
+        lock.refresh_if_due();
+        on_stage(stage)
+    };
+    on_stage(ExportStage::StartingPack { pack_name: pack_name.clone(), pack_dir: pack_dir.clone() });
+
+    let sanitized_pack_id = validator::sanitize_id(&id);
+    let badge = settings.icon_badge.as_ref().map(|badge| badge.text(&pack_name, &version)).filter(|text| !text.is_empty());
+
+    let mut painting_list = PackList::new(
+        pack_name,
+        version,
+        sanitized_pack_id,
+        description,
+    );
+    painting_list.set_schema(settings.schema_url.as_deref().unwrap_or(lint::rules_for(&settings.target_mod_version).schema_url));
+    painting_list.extra_fields = settings.extra_pack_fields.clone();
+
+    let (mut out, mut manifest) = if settings.archive {
+        (start_archive(&pack_dir, settings.backup_existing)?, ExportManifest::new(spec.clone()))
+    } else {
+        let staging = export_resume::staging_dir(&pack_dir);
+        let interrupted = export_resume::load(&pack_dir).filter(|manifest| manifest.spec == *spec);
+        if let Some(manifest) = &interrupted {
+            println!("[EXPORT] Resuming {} with {} paintings already written", pack_dir, manifest.completed.len());
+        } else {
+            // Whatever another export left behind doesn't belong in this pack.
+            export_resume::remove(&pack_dir);
+            if settings.backup_existing {
+                backup_existing_pack(&pack_dir, settings.backup_images)?;
+            }
+        }
+        let manifest = interrupted.unwrap_or_else(|| ExportManifest::new(spec.clone()));
+        create_dir_all(&staging).map_err(|e| ExportError::write(&staging, e))?;
+        if let Err(e) = manifest.save(&pack_dir) {
+            eprintln!("[EXPORT] {}", e);
+        }
+        (PackWriter::Folder { pack_dir: pack_dir.clone(), staging }, manifest)
+    };
+
+    // Enough paintings for the largest collage icon.
+    let icon_items = icon_generator::icon_sources(&items_to_export, 3);
+    // One entry per painting shape; the strip is only built from what this pack exports.
+    let strip_items = settings.preview_strip.then(|| items_to_export.clone());
+    let written = write_images(
+        &mut painting_list,
+        items_to_export,
+        &mut out,
+        settings,
+        harmony_palette,
+        pixels_per_block,
+        decode_cache,
+        &mut manifest,
+        on_stage,
+        cancel,
+    ).and_then(|report| {
+        match settings.painting_order {
+            PaintingOrder::Library => {}
+            PaintingOrder::Name => painting_list.sort_by_name(),
+            PaintingOrder::Filename => painting_list.sort_by_filename(),
+        }
+        on_stage(ExportStage::WritingJson);
+        write_json(&painting_list, &mut out, &settings.json_style)?;
+        on_stage(ExportStage::WritingIcon);
+        write_icon(&mut out, settings.icon_style, &icon_items, badge.as_deref())?;
+        if let Some(items) = strip_items {
+            on_stage(ExportStage::WritingPreviewStrip);
+            let strip = preview_strip::strip_for_items(&items);
+            out.write_file(preview_strip::PREVIEW_STRIP_FILE, |file| PngEncoder.write(&strip, ColorDepth::Rgba32, file))?;
+        }
+        Ok(report)
+    });
+    let mut report = match written {
+        Ok(report) => report,
+        Err(e) => {
+            println!("[EXPORT] {}, removing the partial pack {}", e, pack_dir);
+            discard_partial_pack(out, &pack_dir);
+            return Err(e);
+        }
+    };
+    let finished = match out {
+        PackWriter::Folder { staging, .. } => finish_staging(&staging, &pack_dir).inspect_err(|_| export_resume::remove(&pack_dir)),
+        PackWriter::Archive(archive) => finish_archive(*archive, &pack_dir).inspect_err(|_| {
+            let _ = remove_file(format!("{}.part", pack_dir));
+        }),
+    };
+    finished?;
+    report.outputs.push(pack_dir);
+    Ok(report)
+}
+
+/*
+This is the final export call. It now accepts the raw metadata components
+and is responsible for creating the PackList<Painting> internally.
+Missing painting metadata is filled from the pack-wide defaults before anything is written.
+If a Lite variant is configured, it is written next to the full pack with the same paintings.
+*/
+pub fn export(
+    pack_name: String,
+    version: String,
+    id: String,
+    description: String,
+    items_to_export: Vec<ExportItem>,
+    export_path: &str,
+    settings: &ExportSettings,
+) -> Result<ExportReport, ExportError> {
+    export_with_stages(pack_name, version, id, description, items_to_export, export_path, settings, &|_| {})
+}
+
+/*
+Same as `export`, calling `on_stage` as each step of the export starts.
+*/
+#[allow(clippy::too_many_arguments)]
+pub fn export_with_stages(
+    pack_name: String,
+    version: String,
+    id: String,
+    description: String,
+    items_to_export: Vec<ExportItem>,
+    export_path: &str,
+    settings: &ExportSettings,
+    on_stage: &dyn Fn(ExportStage),
+) -> Result<ExportReport, ExportError> {
+    let never_cancelled = AtomicBool::new(false);
+    export_cancellable(pack_name, version, id, description, items_to_export, export_path, settings, on_stage, &never_cancelled)
+}
+
+/*
+Same as `export_with_stages`, stopping before the next source image once `cancel` is set. The
+pack being written is removed again and `ExportError::Cancelled` returned; a Lite variant that
+was already finished is kept.
+*/
+#[allow(clippy::too_many_arguments)]
+pub fn export_cancellable(
+    pack_name: String,
+    version: String,
+    id: String,
+    description: String,
+    mut items_to_export: Vec<ExportItem>,
+    export_path: &str,
+    settings: &ExportSettings,
+    on_stage: &dyn Fn(ExportStage),
+    cancel: &AtomicBool,
+) -> Result<ExportReport, ExportError> {
+    // Every setting is checked before anything is written, so a bad one can't leave half an export behind.
+    settings.validate()?;
+    if settings.lite_variant.as_ref().is_some_and(|lite| lite.pixels_per_block == 0) {
+        return Err(String::from("The Lite variant needs at least 1 pixel per block").into());
+    }
+    let export_path = &resolve_export_path(export_path, &id, &version);
+    let spec = ExportSpec {
+        pack_name: pack_name.clone(),
+        version: version.clone(),
+        id: id.clone(),
+        description: description.clone(),
+        items: items_to_export.clone(),
+        export_path: export_path.to_string(),
+        settings: settings.clone(),
+    };
+    apply_defaults(&mut items_to_export, settings)?;
+    if let Some(issue) = lint::painting_cap_issue(&items_to_export, settings.painting_cap) {
+        return Err(issue.message.into());
+    }
+    for weight in items_to_export.iter().filter_map(|item| item.data.weight) {
+        painting::validate_weight(weight)?;
+    }
+    for frame in items_to_export.iter().filter_map(|item| item.data.frame.as_ref()) {
+        frame.validate()?;
+    }
+    for size_adjustment in items_to_export.iter().flat_map(|item| &item.data.size_adjustments) {
+        size_adjustment.adjustments.validate()?;
+    }
+    let rules = lint::rules_for(&settings.target_mod_version);
+    // Whether the target release plays them is only linted, as `lint::RULES` isn't verified yet.
+    for animation in items_to_export.iter().filter_map(|item| item.data.animation.as_ref()) {
+        animation.validate()?;
+    }
+    image_format::check_supported(settings.output_format, rules)?;
+    // custompaintings.json lists paintings in library order; untracked items (and ties) go in
+    // natural order of their source files, so the listing doesn't depend on how they were passed in.
+    items_to_export.sort_by(|a, b| a.data.order.cmp(&b.data.order).then_with(|| natural_sort::natural_cmp(&a.source_path, &b.source_path)));
+
+    // Resolve the shared palette once up front so a bad reference image fails before writing.
+    let harmony = match &settings.palette_harmonization {
+        Some(harmonization) => Some((palette::resolve_palette(&harmonization.source)?, harmonization.strength)),
+        None => None,
+    };
+    let harmony = harmony.as_ref().map(|(colours, strength)| (colours.as_slice(), *strength));
+
+    let mut report = ExportReport::default();
+    // Each item is cut once for the pack and once more for its Lite variant, from a single decode.
+    let packs = if settings.lite_variant.is_some() { 2 } else { 1 };
+    let decode_cache = DecodeCache::new(
+        items_to_export
+            .iter()
+            .flat_map(|item| std::iter::repeat((item.source_path.as_str(), item.data.crop_options().deskew)).take(packs)),
+    );
+
+    if let Some(lite) = &settings.lite_variant {
+        let lite_report = write_pack(
+            format!("{}{}", pack_name, lite.name_suffix),
+            version.clone(),
+            format!("{}{}", id, lite.id_suffix),
+            description.clone(),
+            items_to_export.clone(),
+            export_path,
+            settings,
+            harmony,
+            Some(lite.pixels_per_block),
+            &decode_cache,
+            &spec,
+            on_stage,
+            cancel,
+        )?;
+        report.absorb(lite_report);
+    }
+
+    report.absorb(write_pack(pack_name, version, id, description, items_to_export, export_path, settings, harmony, settings.pixels_per_block, &decode_cache, &spec, on_stage, cancel)?);
+    on_stage(ExportStage::Finished);
+    Ok(report)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::export_settings::LiteVariant;
+    use crate::core::session_lock;
+    use crate::models::painting::PaintingFrame;
+    use image::RgbaImage;
+    use std::{env, fs, path::PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    // --- Test Helper: TestImage ---
+    // Creates a dummy image file for testing
+    struct TestImage {
+        path: PathBuf,
+    }
+
+    impl TestImage {
+        fn new(path: &PathBuf) -> Self {
+            let img = RgbaImage::new(800, 600); // 4:3 ratio
+            img.save(path).expect("Failed to save test image");
+            Self { path: path.clone() }
+        }
+
+        fn path_str(&self) -> String {
+            self.path.to_str().expect("Path is not valid UTF-8").to_string()
+        }
+    }
+
+    // --- Test Helper: Temporary Directory ---
+    // Creates a unique temp directory for an integration test
+    // and cleans it up when it goes out of scope.
+    struct TempExportDir {
+        path: PathBuf,
+    }
+
+    impl TempExportDir {
+        fn new() -> Self {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("Time went backwards")
+                .as_nanos();
+            let rand_string = format!("{:x}", nanos);
+            let path = env::temp_dir().join(format!("test_export_{}", rand_string));
+            fs::create_dir_all(&path).expect("Failed to create temp dir");
+            Self { path }
+        }
+
+        fn path_str(&self) -> String {
+            self.path.to_str().expect("Path is not valid UTF-8").to_string()
+        }
+    }
+
+    impl Drop for TempExportDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path); // Ignore cleanup errors
+        }
+    }
+
+    #[test]
+    fn test_generate_base64_previews() {
+        let mut images: Vec<DynamicImage> = Vec::new();
+        images.push(DynamicImage::ImageRgba8(RgbaImage::new(10, 10)));
+        images.push(DynamicImage::ImageRgba8(RgbaImage::new(20, 20)));
+
+        let previews = generate_base64_previews(&images);
+
+        assert_eq!(previews.len(), 2);
+        assert!(previews[0].starts_with("data:image/png;base64,"));
+        assert!(previews[1].starts_with("data:image/png;base64,"));
+        assert_ne!(previews[0], previews[1]); // Different images should have different base64
+    }
+
+    // --- Integration Test for export() ---
+
+    #[test]
+    fn test_export_integration() {
+        // 1. Setup: Create temp directory and a test source image
+        let temp_dir = TempExportDir::new();
+        let test_img_path = temp_dir.path.join("source_image.png");
+        let test_img = TestImage::new(&test_img_path);
+
+        // 2. Setup: Create export metadata
+        let pack_name = "My Test Pack".to_string();
+        let version = "1.0.0".to_string();
+        let id = "My Test ID!".to_string(); // Will be sanitized
+        let description = "A pack for testing".to_string();
+        
+        let mut items_to_export: Vec<ExportItem> = Vec::new();
+
+        // Create metadata for one "Square" crop
+        let mut square_data = ImageData::new(ImageSize::Square);
+        square_data.id = Some("Test Square".to_string());
+        square_data.filename = Some("test_square_file".to_string());
+        square_data.name = Some("My Square Painting".to_string());
+        square_data.artist = Some("The Artist".to_string());
+        
+        items_to_export.push(ExportItem {
+            source_path: test_img.path_str(),
+            data: square_data,
+        });
+
+        // 3. Act: Call the export function
+        export(
+            pack_name.clone(),
+            version.clone(),
+            id.clone(),
+            description.clone(),
+            items_to_export,
+            &temp_dir.path_str(),
+            &ExportSettings::default(),
+        ).expect("Export should succeed");
+
+        // 4. Assert: Check if files and directories were created correctly
+        let pack_dir = temp_dir.path.join("My_Test_Pack"); // Sanitized pack name
+        assert!(pack_dir.exists() && pack_dir.is_dir());
+
+        // Check for icon
+        let icon_path = pack_dir.join("icon.png");
+        assert!(icon_path.exists() && icon_path.is_file());
+
+        // Check for images directory
+        let images_dir = pack_dir.join("images");
+        assert!(images_dir.exists() && images_dir.is_dir());
+
+        // Check for generated images (Square has 4 sizes: 1x1, 2x2, 3x3, 4x4)
+        // Example: test_square_file_1x1.png
+        assert!(images_dir.join("test_square_file_1x1.png").exists());
+        assert!(images_dir.join("test_square_file_2x2.png").exists());
+        assert!(images_dir.join("test_square_file_3x3.png").exists());
+        assert!(images_dir.join("test_square_file_4x4.png").exists());
+
+        // Check for JSON file
+        let json_path = pack_dir.join("custompaintings.json");
+        assert!(json_path.exists() && json_path.is_file());
+
+        // 5. Assert: Check JSON content
+        let json_content = fs::read_to_string(json_path).expect("Failed to read JSON");
+        
+        // Simple string contains checks for key metadata
+        assert!(json_content.contains(r#""name": "My Test Pack""#));
+        assert!(json_content.contains(r#""version": "1.0.0""#));
+        assert!(json_content.contains(r#""id": "my_test_id""#)); // Sanitized ID
+        assert!(json_content.contains(r#""description": "A pack for testing""#));
+
+        // Check for painting entries
+        assert!(json_content.contains(r#""id": "test_square_1x1""#)); // Sanitized painting ID
+        assert!(json_content.contains(r#""filename": "test_square_file_1x1.png""#));
+        assert!(json_content.contains(r#""name": "My Square Painting""#));
+        assert!(json_content.contains(r#""artist": "The Artist""#));
+        assert!(json_content.contains(r#""width": 1"#));
+        assert!(json_content.contains(r#""height": 1"#));
+
+        assert!(json_content.contains(r#""id": "test_square_4x4""#));
+        assert!(json_content.contains(r#""width": 4"#));
+        assert!(json_content.contains(r#""height": 4"#));
+
+        // 6. Cleanup is handled by TempExportDir's Drop impl
+    }
+
+    #[test]
+    fn test_export_preserves_source_filenames() {
+        let temp_dir = TempExportDir::new();
+        let test_img = TestImage::new(&temp_dir.path.join("Holiday Photo.png"));
+
+        let mut wide_data = ImageData::new(ImageSize::Wide);
+        wide_data.id = Some("holiday".to_string());
+        wide_data.filename = Some("holiday".to_string());
+        wide_data.name = Some("Holiday".to_string());
+        wide_data.artist = Some("Me".to_string());
+
+        let settings = ExportSettings {
+            preserve_source_filenames: true,
+            ..ExportSettings::default()
+        };
+        let report = export(
+            "Pack".to_string(),
+            "1.0.0".to_string(),
+            "pack".to_string(),
+            "desc".to_string(),
+            vec![ExportItem { source_path: test_img.path_str(), data: wide_data }],
+            &temp_dir.path_str(),
+            &settings,
+        ).expect("Export should succeed");
+
+        // Wide has two sizes: the first keeps the exact name, the second is suffixed
+        let images_dir = temp_dir.path.join("Pack").join("images");
+        assert!(images_dir.join("Holiday Photo.png").exists());
+        assert!(images_dir.join("Holiday Photo_2.png").exists());
+        // Its own sizes don't clash with each other.
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+    }
+
+    #[test]
+    fn test_export_writes_lite_variant() {
+        let temp_dir = TempExportDir::new();
+        let test_img = TestImage::new(&temp_dir.path.join("source.png"));
+
+        let mut wide_data = ImageData::new(ImageSize::Wide);
+        wide_data.name = Some("Valley".to_string());
+        wide_data.artist = Some("Me".to_string());
+
+        let settings = ExportSettings {
+            lite_variant: Some(LiteVariant { pixels_per_block: 16, ..LiteVariant::default() }),
+            ..ExportSettings::default()
+        };
+        export(
+            "Forest Pack".to_string(),
+            "1.0.0".to_string(),
+            "forest".to_string(),
+            "desc".to_string(),
+            vec![ExportItem { source_path: test_img.path_str(), data: wide_data }],
+            &temp_dir.path_str(),
+            &settings,
+        ).expect("Export should succeed");
+
+        // The HD pack keeps the full 800x400 crop, the Lite pack gets 16 pixels per block
+        let hd_image = image::open(temp_dir.path.join("Forest_Pack/images/Valley_4x2.png")).unwrap();
+        assert_eq!((hd_image.width(), hd_image.height()), (800, 400));
+        let lite_dir = temp_dir.path.join("Forest_Pack_Lite");
+        let lite_small = image::open(lite_dir.join("images/Valley_2x1.png")).unwrap();
+        let lite_large = image::open(lite_dir.join("images/Valley_4x2.png")).unwrap();
+        assert_eq!((lite_small.width(), lite_small.height()), (32, 16));
+        assert_eq!((lite_large.width(), lite_large.height()), (64, 32));
+
+        let lite_json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(lite_dir.join("custompaintings.json")).unwrap()).unwrap();
+        assert_eq!(lite_json["id"], "forest_lite");
+        assert_eq!(lite_json["name"], "Forest Pack Lite");
+    }
+
+    #[test]
+    fn test_invalid_settings_fail_before_the_lite_variant_is_written() {
+        let temp_dir = TempExportDir::new();
+        let test_img = TestImage::new(&temp_dir.path.join("source.png"));
+
+        let mut data = ImageData::new(ImageSize::Square);
+        data.name = Some("Valley".to_string());
+        data.artist = Some("Me".to_string());
+        let settings = ExportSettings {
+            lite_variant: Some(LiteVariant::default()),
+            pixels_per_block: Some(0),
+            ..ExportSettings::default()
+        };
+        let result = export(
+            "Forest Pack".to_string(),
+            "1.0.0".to_string(),
+            "forest".to_string(),
+            "desc".to_string(),
+            vec![ExportItem { source_path: test_img.path_str(), data }],
+            &temp_dir.path_str(),
+            &settings,
+        );
+        assert!(result.unwrap_err().to_string().contains("at least 1 pixel per block"));
+        assert!(!temp_dir.path.join("Forest_Pack_Lite").exists());
+        assert!(!temp_dir.path.join("Forest_Pack").exists());
+    }
+
+    #[test]
+    fn test_export_streams_pack_into_archive() {
+        let temp_dir = TempExportDir::new();
+        let test_img = TestImage::new(&temp_dir.path.join("source.png"));
+
+        let mut data = ImageData::new(ImageSize::Square);
+        data.name = Some("Valley".to_string());
+        data.artist = Some("Me".to_string());
+        let settings = ExportSettings { archive: true, ..ExportSettings::default() };
+        let export_once = || export(
+            "Forest Pack".to_string(),
+            "1.0.0".to_string(),
+            "forest".to_string(),
+            "desc".to_string(),
+            vec![ExportItem { source_path: test_img.path_str(), data: data.clone() }],
+            &temp_dir.path_str(),
+            &settings,
+        ).expect("Export should succeed");
+        export_once();
+
+        // Only the archive is written; no pack folder and no leftover partial file.
+        let archive_path = temp_dir.path.join("Forest_Pack.zip");
+        assert!(!temp_dir.path.join("Forest_Pack").exists());
+        assert!(!temp_dir.path.join("Forest_Pack.zip.part").exists());
+        let mut archive = zip::ZipArchive::new(fs::File::open(&archive_path).unwrap()).unwrap();
+        let mut names: Vec<String> = archive.file_names().map(str::to_string).collect();
+        names.sort();
+        assert_eq!(names, vec!["custompaintings.json", "icon.png", "images/Valley_1x1.png", "images/Valley_2x2.png", "images/Valley_3x3.png", "images/Valley_4x4.png"]);
+        let mut json = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("custompaintings.json").unwrap(), &mut json).unwrap();
+        assert!(json.contains("Valley_4x4"));
+        assert_eq!(existing_pack_files(&temp_dir.path_str(), "Forest Pack", &settings), vec![archive_path.to_string_lossy().to_string()]);
+
+        // Exporting again backs the previous archive up.
+        export_once();
+        assert!(temp_dir.path.join("Forest_Pack.zip.backups").is_dir());
+    }
+
+    #[test]
+    fn test_export_numbers_duplicate_ids_and_filenames() {
+        let temp_dir = TempExportDir::new();
+        let first = TestImage::new(&temp_dir.path.join("a.png"));
+        let second = TestImage::new(&temp_dir.path.join("b.png"));
+
+        // Two sources with the same file name in different folders
+        let items: Vec<ExportItem> = [&first, &second].iter().map(|image| {
+            let mut data = ImageData::new(ImageSize::Square);
+            data.id = Some("Sunset".to_string());
+            data.filename = Some("Sunset".to_string());
+            data.name = Some("Sunset".to_string());
+            data.artist = Some("Me".to_string());
+            ExportItem { source_path: image.path_str(), data }
+        }).collect();
+        let settings = ExportSettings { pixels_per_block: Some(16), ..ExportSettings::default() };
+        let stages = std::cell::RefCell::new(Vec::new());
+        let report = export_with_stages(
+            "Pack".to_string(), "1.0.0".to_string(), "pack".to_string(), "desc".to_string(),
+            items, &temp_dir.path_str(), &settings, &|stage| stages.borrow_mut().push(stage),
+        ).expect("Export should succeed");
+        assert_eq!(report.outputs, [pack_dir(&temp_dir.path_str(), "Pack")]);
+        assert!(report.warnings.contains(&"sunset was already used, renamed to sunset_2".to_string()));
+
+        let images_dir = temp_dir.path.join("Pack").join("images");
+        assert!(images_dir.join("Sunset_1x1.png").exists());
+        assert!(images_dir.join("Sunset_1x1_2.png").exists());
+        let json = fs::read_to_string(temp_dir.path.join("Pack/custompaintings.json")).unwrap();
+        assert!(json.contains("\"sunset_1x1\""));
+        assert!(json.contains("\"sunset_2_1x1\""));
+        let renamed: Vec<(String, String)> = stages.borrow().iter()
+            .filter_map(|stage| match stage {
+                ExportStage::RenamedDuplicate { original, renamed } => Some((original.clone(), renamed.clone())),
+                _ => None,
+            })
+            .collect();
+        assert!(renamed.contains(&("sunset".to_string(), "sunset_2".to_string())));
+        assert!(renamed.contains(&("Sunset_1x1".to_string(), "Sunset_1x1_2".to_string())));
+
+        // Kept source names are numbered and reported the same way.
+        fs::create_dir_all(temp_dir.path.join("other")).unwrap();
+        let same_name = TestImage::new(&temp_dir.path.join("other").join("a.png"));
+        let items: Vec<ExportItem> = [&first, &same_name].iter().enumerate().map(|(index, image)| {
+            let mut data = ImageData::new(ImageSize::Square);
+            data.name = Some(format!("Painting {}", index));
+            data.artist = Some("Me".to_string());
+            ExportItem { source_path: image.path_str(), data }
+        }).collect();
+        let settings = ExportSettings { preserve_source_filenames: true, ..settings };
+        let report = export(
+            "Kept".to_string(), "1.0.0".to_string(), "kept".to_string(), "desc".to_string(),
+            items, &temp_dir.path_str(), &settings,
+        ).expect("Export should succeed");
+        assert!(temp_dir.path.join("Kept/images/a_5.png").exists());
+        assert_eq!(report.warnings, ["a was already used, renamed to a_5"]);
+    }
+
+    #[test]
+    fn test_failed_export_leaves_the_previous_pack_in_place() {
+        let temp_dir = TempExportDir::new();
+        let test_img = TestImage::new(&temp_dir.path.join("source.png"));
+        let item = |source_path: String, name: &str| {
+            let mut data = ImageData::new(ImageSize::Wide);
+            data.name = Some(name.to_string());
+            data.artist = Some("Me".to_string());
+            ExportItem { source_path, data }
+        };
+        let export_items = |items: Vec<ExportItem>| export(
+            "Pack".to_string(), "1.0.0".to_string(), "pack".to_string(), "desc".to_string(),
+            items, &temp_dir.path_str(), &ExportSettings::default(),
+        );
+        export_items(vec![item(test_img.path_str(), "Valley"), item(test_img.path_str(), "Lake")]).expect("Export should succeed");
+        let before = existing_pack_files(&temp_dir.path_str(), "Pack", &ExportSettings::default());
+
+        // The second source is gone, so the export fails after the first painting was staged
+        let missing = temp_dir.path.join("missing.png").to_string_lossy().to_string();
+        let result = export_items(vec![item(test_img.path_str(), "Hill"), item(missing, "Gone")]);
+        assert!(matches!(result, Err(ExportError::Source { .. })), "{:?}", result);
+        assert_eq!(existing_pack_files(&temp_dir.path_str(), "Pack", &ExportSettings::default()), before);
+        assert!(!temp_dir.path.join("Pack.staging").exists());
+
+        // A finished export replaces the whole pack, leaving no textures of paintings it no longer has
+        export_items(vec![item(test_img.path_str(), "Hill")]).expect("Export should succeed");
+        assert!(temp_dir.path.join("Pack/images/Hill_2x1.png").exists());
+        assert!(!temp_dir.path.join("Pack/images/Valley_2x1.png").exists());
+        assert!(!temp_dir.path.join("Pack.replaced").exists());
+    }
+
+    #[test]
+    fn test_unwritable_export_folder_is_an_error_not_a_panic() {
+        let temp_dir = TempExportDir::new();
+        let test_img = TestImage::new(&temp_dir.path.join("source.png"));
+        // A file where the export folder should be
+        let blocker = temp_dir.path.join("blocker");
+        fs::write(&blocker, "not a folder").unwrap();
+
+        let mut data = ImageData::new(ImageSize::Square);
+        data.artist = Some("Me".to_string());
+        let result = export(
+            "Pack".to_string(), "1.0.0".to_string(), "pack".to_string(), "desc".to_string(),
+            vec![ExportItem { source_path: test_img.path_str(), data }], &blocker.to_string_lossy(), &ExportSettings::default(),
+        );
+        assert!(matches!(result, Err(ExportError::Write { .. })), "{:?}", result);
+        let message = String::from(result.unwrap_err());
+        assert!(message.starts_with("Couldn't write"), "{}", message);
+    }
+
+    #[test]
+    fn test_pack_locked_by_another_export_is_left_alone() {
+        let temp_dir = TempExportDir::new();
+        let test_img = TestImage::new(&temp_dir.path.join("source.png"));
+        let export_path = temp_dir.path.to_string_lossy().to_string();
+        let pack_dir = pack_dir(&export_path, "Pack");
+        let lock = SessionLock::acquire(Path::new(&pack_dir)).unwrap();
+
+        let mut data = ImageData::new(ImageSize::Square);
+        data.artist = Some("Me".to_string());
+        let export_once = || export(
+            "Pack".to_string(), "1.0.0".to_string(), "pack".to_string(), "desc".to_string(),
+            vec![ExportItem { source_path: test_img.path_str(), data: data.clone() }], &export_path, &ExportSettings::default(),
+        );
+        let result = export_once();
+        assert!(matches!(&result, Err(ExportError::InUse { path, .. }) if *path == pack_dir), "{:?}", result);
+        assert!(!Path::new(&pack_dir).exists());
+        assert!(!Path::new(&export_resume::staging_dir(&pack_dir)).exists());
+
+        drop(lock);
+        export_once().unwrap();
+        assert!(Path::new(&pack_dir).join("custompaintings.json").exists());
+        assert!(!session_lock::lock_path(Path::new(&pack_dir)).exists());
+    }
+
+    #[test]
+    fn test_export_skips_undersized_variants_unless_forced() {
+        let temp_dir = TempExportDir::new();
+        let test_img = TestImage::new(&temp_dir.path.join("source.png"));
+
+        let mut square_data = ImageData::new(ImageSize::Square);
+        square_data.name = Some("Tile".to_string());
+        square_data.artist = Some("Me".to_string());
+        let item = ExportItem { source_path: test_img.path_str(), data: square_data };
+
+        // The 600x600 crop fills up to 3x3 at 200 px per block, but not 4x4
+        let mut settings = ExportSettings { pixels_per_block: Some(200), ..ExportSettings::default() };
+        let stages = std::cell::RefCell::new(Vec::new());
+        export_with_stages(
+            "Pack".to_string(), "1.0.0".to_string(), "pack".to_string(), "desc".to_string(),
+            vec![item.clone()], &temp_dir.path_str(), &settings, &|stage| stages.borrow_mut().push(stage),
+        ).expect("Export should succeed");
+
+        let images_dir = temp_dir.path.join("Pack").join("images");
+        assert!(images_dir.join("Tile_3x3.png").exists());
+        assert!(!images_dir.join("Tile_4x4.png").exists());
+        let skipped: Vec<_> = stages.borrow().iter()
+            .filter_map(|stage| match stage {
+                ExportStage::SkippedVariant { id, .. } => Some(id.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(skipped, ["tile_4x4"]);
+        let json = fs::read_to_string(temp_dir.path.join("Pack/custompaintings.json")).unwrap();
+        assert!(!json.contains("tile_4x4"));
+
+        settings.force_undersized_variants = true;
+        export(
+            "Pack".to_string(), "1.0.0".to_string(), "pack".to_string(), "desc".to_string(),
+            vec![item], &temp_dir.path_str(), &settings,
+        ).expect("Export should succeed");
+        assert!(images_dir.join("Tile_4x4.png").exists());
+    }
+
+    #[test]
+    fn test_export_writes_descriptions_and_frames_only_when_supported() {
+        let temp_dir = TempExportDir::new();
+        let test_img = TestImage::new(&temp_dir.path.join("source.png"));
+
+        let mut tall_data = ImageData::new(ImageSize::Tall);
+        tall_data.name = Some("Tower".to_string());
+        tall_data.artist = Some("Me".to_string());
+        tall_data.description = Some("It leans a little.".to_string());
+        tall_data.frame = Some(PaintingFrame { color: Some("#8b5a2b".to_string()), texture: None });
+        tall_data.weight = Some(4);
+        let item = ExportItem { source_path: test_img.path_str(), data: tall_data };
+
+        let mut settings = ExportSettings { target_mod_version: String::from("2.0.0"), ..ExportSettings::default() };
+        export(
+            "Pack".to_string(), "1.0.0".to_string(), "pack".to_string(), "desc".to_string(),
+            vec![item.clone()], &temp_dir.path_str(), &settings,
+        ).expect("Export should succeed");
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(temp_dir.path.join("Pack/custompaintings.json")).unwrap()).unwrap();
+        assert_eq!(json["paintings"][0]["description"], "It leans a little.");
+        assert_eq!(json["paintings"][0]["frame"], serde_json::json!({ "color": "#8b5a2b" }));
+        assert_eq!(json["paintings"][0]["weight"], 4);
+        assert_eq!(json["$schema"], lint::rules_for("2.0.0").schema_url);
+
+        // Older mod releases reject unknown fields, so the description and frame are left out
+        settings.target_mod_version = String::from("1.0.0");
+        // A fork's own schema and fields are written as given.
+        settings.schema_url = Some(String::from("https://example.com/fork.schema.json"));
+        settings.extra_pack_fields.insert(String::from("fork_version"), serde_json::json!(3));
+        export(
+            "Pack".to_string(), "1.0.0".to_string(), "pack".to_string(), "desc".to_string(),
+            vec![item], &temp_dir.path_str(), &settings,
+        ).expect("Export should succeed");
+        let json = fs::read_to_string(temp_dir.path.join("Pack/custompaintings.json")).unwrap();
+        assert!(!json.contains("It leans"));
+        assert!(!json.contains("frame"));
+        assert!(!json.contains("weight"));
+        assert!(json.contains(r#""$schema": "https://example.com/fork.schema.json""#));
+        assert!(json.contains(r#""fork_version": 3"#));
+    }
+
+    #[test]
+    fn test_export_rejects_formats_the_mod_cant_load() {
+        let temp_dir = TempExportDir::new();
+        let test_img = TestImage::new(&temp_dir.path.join("source.png"));
+        let mut data = ImageData::new(ImageSize::Square);
+        data.artist = Some("Me".to_string());
+
+        let settings = ExportSettings { output_format: image_format::OutputFormat::WebpLossless, ..ExportSettings::default() };
+        let result = export(
+            "Pack".to_string(), "1.0.0".to_string(), "pack".to_string(), "desc".to_string(),
+            vec![ExportItem { source_path: test_img.path_str(), data }], &temp_dir.path_str(), &settings,
+        );
+        assert!(result.is_err());
+        assert!(!temp_dir.path.join("Pack").exists());
+    }
+
+    #[test]
+    fn test_export_resumes_interrupted_pack() {
+        let temp_dir = TempExportDir::new();
+        let test_img = TestImage::new(&temp_dir.path.join("source.png"));
+        let mut wide_data = ImageData::new(ImageSize::Wide);
+        wide_data.name = Some("Valley".to_string());
+        wide_data.artist = Some("Me".to_string());
+        let items = vec![ExportItem { source_path: test_img.path_str(), data: wide_data }];
+        let settings = ExportSettings::default();
+        let run = |stages: &std::cell::RefCell<Vec<ExportStage>>| {
+            export_with_stages(
+                "Pack".to_string(), "1.0.0".to_string(), "pack".to_string(), "desc".to_string(),
+                items.clone(), &temp_dir.path_str(), &settings, &|stage| stages.borrow_mut().push(stage),
+            ).expect("Export should succeed")
+        };
+        run(&Default::default());
+        let pack_dir = pack_dir(&temp_dir.path_str(), "Pack");
+        assert!(export_resume::load(&pack_dir).is_none());
+
+        // Pretend the first run stopped after its first painting, marked so we can tell it was kept
+        let written: PackList<Painting> =
+            serde_json::from_str(&fs::read_to_string(temp_dir.path.join("Pack/custompaintings.json")).unwrap()).unwrap();
+        let first = written.paintings()[0].clone();
+        let staged_images = temp_dir.path.join("Pack.staging/images");
+        fs::create_dir_all(&staged_images).unwrap();
+        RgbaImage::new(2, 1).save(staged_images.join(&first.filename)).unwrap();
+        let spec = ExportSpec {
+            pack_name: "Pack".to_string(), version: "1.0.0".to_string(), id: "pack".to_string(), description: "desc".to_string(),
+            items: items.clone(), export_path: temp_dir.path_str(), settings: settings.clone(),
+        };
+        let mut manifest = ExportManifest::new(spec);
+        manifest.completed.push(first.clone());
+        manifest.save(&pack_dir).unwrap();
+
+        let stages = std::cell::RefCell::new(Vec::new());
+        run(&stages);
+        assert!(stages.borrow().contains(&ExportStage::AlreadyWritten { id: first.id.clone() }));
+        assert_eq!(image::image_dimensions(temp_dir.path.join("Pack/images").join(&first.filename)).unwrap(), (2, 1));
+        let resumed = fs::read_to_string(temp_dir.path.join("Pack/custompaintings.json")).unwrap();
+        assert_eq!(resumed.matches("\"id\": \"valley_").count(), written.painting_count());
+        assert!(export_resume::load(&pack_dir).is_none());
+        assert!(!temp_dir.path.join("Pack.staging").exists());
+        assert!(!temp_dir.path.join("Pack/.export-progress.json").exists());
+    }
+
+    #[test]
+    fn test_export_reports_stages_in_order() {
+        let temp_dir = TempExportDir::new();
+        let test_img = TestImage::new(&temp_dir.path.join("source.png"));
+        let mut data = ImageData::new(ImageSize::LongRectangle);
+        data.name = Some("Hall".to_string());
+        data.artist = Some("Me".to_string());
+
+        let stages = std::cell::RefCell::new(Vec::new());
+        let settings = ExportSettings { pixels_per_block: Some(16), ..ExportSettings::default() };
+        export_with_stages(
+            "Pack".to_string(),
+            "1.0.0".to_string(),
+            "pack".to_string(),
+            "desc".to_string(),
+            vec![ExportItem { source_path: test_img.path_str(), data }],
+            &temp_dir.path_str(),
+            &settings,
+            &|stage| stages.borrow_mut().push(stage),
+        ).expect("Export should succeed");
+
+        let stages = stages.into_inner();
+        assert!(matches!(stages[0], ExportStage::StartingPack { .. }));
+        assert_eq!(stages[1..], [
+            ExportStage::Decoding { source: test_img.path_str() },
+            ExportStage::Resizing { file: "Hall_4x3.png".to_string(), width: 4, height: 3 },
+            ExportStage::Encoding { file: "Hall_4x3.png".to_string() },
+            ExportStage::Progress { done: 1, total: 1, file: "Hall_4x3.png".to_string(), percent: 100 },
+            ExportStage::WritingJson,
+            ExportStage::WritingIcon,
+            ExportStage::Finished,
+        ]);
+    }
+
+    #[test]
+    fn test_parallel_export_keeps_library_order() {
+        let temp_dir = TempExportDir::new();
+        let test_img = TestImage::new(&temp_dir.path.join("source.png"));
+        // More sources than one batch of threads, so several batches are written in turn.
+        let items: Vec<ExportItem> = (1..=MAX_EXPORT_THREADS as u32 * 2 + 1)
+            .map(|order| {
+                let mut data = ImageData::new(ImageSize::LongRectangle);
+                data.name = Some(format!("Painting {}", order));
+                data.artist = Some("Me".to_string());
+                data.order = order;
+                ExportItem { source_path: test_img.path_str(), data }
+            })
+            .collect();
+
+        let done = std::cell::RefCell::new(Vec::new());
+        export_with_stages(
+            "Pack".to_string(),
+            "1.0.0".to_string(),
+            "pack".to_string(),
+            "desc".to_string(),
+            items.clone(),
+            &temp_dir.path_str(),
+            &ExportSettings::default(),
+            &|stage| if let ExportStage::Progress { done: count, .. } = stage { done.borrow_mut().push(count) },
+        ).expect("Export should succeed");
+
+        let json = fs::read_to_string(temp_dir.path.join("Pack/custompaintings.json")).unwrap();
+        let list: PackList<Painting> = serde_json::from_str(&json).unwrap();
+        let names: Vec<String> = list.paintings().iter().map(|painting| painting.name.clone()).collect();
+        let expected: Vec<String> = items.iter().map(|item| item.data.name.clone().unwrap()).collect();
+        assert_eq!(names, expected);
+        assert_eq!(done.into_inner(), (1..=items.len()).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn test_cancelled_export_removes_the_partial_pack() {
+        let temp_dir = TempExportDir::new();
+        let test_img = TestImage::new(&temp_dir.path.join("source.png"));
+        let mut data = ImageData::new(ImageSize::Square);
+        data.artist = Some("Me".to_string());
+        let export = |cancel: &AtomicBool| {
+            export_cancellable(
+                "Pack".to_string(),
+                "1.0.0".to_string(),
+                "pack".to_string(),
+                "desc".to_string(),
+                vec![ExportItem { source_path: test_img.path_str(), data: data.clone() }],
+                &temp_dir.path_str(),
+                &ExportSettings::default(),
+                &|_| {},
+                cancel,
+            )
+        };
+
+        assert_eq!(export(&AtomicBool::new(true)), Err(ExportError::Cancelled));
+        assert!(!temp_dir.path.join("Pack").exists());
+
+        // Cancelling over an existing pack leaves it as it was.
+        export(&AtomicBool::new(false)).expect("Export should succeed");
+        let before = existing_pack_files(&temp_dir.path_str(), "Pack", &ExportSettings::default());
+        assert!(export(&AtomicBool::new(true)).is_err());
+        assert_eq!(existing_pack_files(&temp_dir.path_str(), "Pack", &ExportSettings::default()), before);
+        assert!(!temp_dir.path.join("Pack.staging").exists());
+        assert!(export_resume::load(&temp_dir.path.join("Pack").to_string_lossy()).is_none());
+    }
+
+    #[test]
+    fn test_apply_defaults_fills_missing_fields() {
+        let mut blank = ImageData::new(ImageSize::Square);
+        blank.name = Some("   ".to_string());
+        let mut items = vec![ExportItem { source_path: "/photos/Red Barn.png".to_string(), data: blank }];
+
+        let settings = ExportSettings {
+            default_artist: "Pack Curator".to_string(),
+            ..ExportSettings::default()
+        };
+        apply_defaults(&mut items, &settings).expect("Defaults should be derivable");
+
+        let data = &items[0].data;
+        assert_eq!(data.name.as_deref(), Some("Red Barn"));
+        assert_eq!(data.artist.as_deref(), Some("Pack Curator"));
+        assert_eq!(data.id.as_deref(), Some("Red Barn"));
+        assert_eq!(data.filename.as_deref(), Some("Red Barn"));
+    }
+
+    #[test]
+    fn test_apply_defaults_errors_without_artist() {
+        let mut data = ImageData::new(ImageSize::Square);
+        data.name = Some("Named".to_string());
+        let mut items = vec![ExportItem { source_path: "named.png".to_string(), data }];
+
+        let result = apply_defaults(&mut items, &ExportSettings::default());
+        assert!(result.unwrap_err().contains("no artist"));
+    }
+
+    #[test]
+    fn test_backup_existing_pack() {
+        let temp_dir = TempExportDir::new();
+        let pack_dir = temp_dir.path.join("Pack");
+        fs::create_dir_all(pack_dir.join("images")).unwrap();
+        fs::write(pack_dir.join("custompaintings.json"), "old json").unwrap();
+        fs::write(pack_dir.join("images").join("old_1x1.png"), "old image").unwrap();
+
+        let pack_dir_str = pack_dir.to_str().unwrap();
+
+        // JSON only by default
+        let first = backup_existing_pack(pack_dir_str, false).unwrap().expect("Backup should be created");
+        assert_eq!(fs::read_to_string(format!("{}/custompaintings.json", first)).unwrap(), "old json");
+        assert!(!Path::new(&format!("{}/images", first)).exists());
+
+        // A second backup in the same second must not overwrite the first
+        let second = backup_existing_pack(pack_dir_str, true).unwrap().expect("Backup should be created");
+        assert_ne!(first, second);
+        assert!(Path::new(&format!("{}/images/old_1x1.png", second)).exists());
+    }
+
+    #[test]
+    fn test_existing_pack_files() {
+        let temp_dir = TempExportDir::new();
+        let settings = ExportSettings::default();
+        assert!(existing_pack_files(&temp_dir.path_str(), "Forest Pack", &settings).is_empty());
+
+        let images = temp_dir.path.join("Forest_Pack").join("images");
+        fs::create_dir_all(&images).unwrap();
+        fs::write(temp_dir.path.join("Forest_Pack/custompaintings.json"), "{}").unwrap();
+        fs::write(images.join("tree_1x1.png"), "").unwrap();
+        let files = existing_pack_files(&temp_dir.path_str(), "Forest Pack", &settings);
+        assert_eq!(files.len(), 2);
+        assert!(files[0].ends_with("custompaintings.json"));
+        assert!(files[1].ends_with("tree_1x1.png"));
+    }
+
+    #[test]
+    fn test_backup_skipped_for_new_pack() {
+        let temp_dir = TempExportDir::new();
+        let pack_dir = temp_dir.path.join("Fresh");
+        assert!(backup_existing_pack(pack_dir.to_str().unwrap(), true).unwrap().is_none());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,114 @@
+use std::env::var_os;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::core::lint;
+
+// Folder inside the Minecraft directory that packs are exported to by default.
+pub const PACKS_FOLDER: &str = "paintings";
+
+/*
+What the user chose in the first-run wizard, kept in the app settings and applied to every new
+pack: who paintings are credited to when an image has no artist, the folder exports go to
+without asking, and the Custom Paintings release packs are checked against.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SetupChoices {
+    pub default_artist: String,
+    pub export_folder: Option<String>,
+    pub target_mod_version: String,
+}
+
+// What the wizard starts from: the detected Minecraft folder and defaults built on it.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SetupProposal {
+    // Whether setup was completed before; the wizard only opens on its own when it wasn't.
+    pub completed: bool,
+    pub minecraft_dir: Option<String>,
+    pub choices: SetupChoices,
+    // Mod releases the target version can be picked from, oldest first.
+    pub mod_versions: Vec<&'static str>,
+}
+
+/*
+Where the default Minecraft launcher keeps its game folder on this platform, plus the Flatpak
+location on Linux. `home` and `appdata` are the user's home and roaming app data folders.
+*/
+fn minecraft_candidates(home: Option<&Path>, appdata: Option<&Path>) -> Vec<PathBuf> {
+    if cfg!(target_os = "windows") {
+        appdata.map(|appdata| appdata.join(".minecraft")).into_iter().collect()
+    } else if cfg!(target_os = "macos") {
+        home.map(|home| home.join("Library/Application Support/minecraft")).into_iter().collect()
+    } else {
+        home.map(|home| vec![home.join(".minecraft"), home.join(".var/app/com.mojang.Minecraft/.minecraft")])
+            .unwrap_or_default()
+    }
+}
+
+// The first Minecraft folder among the candidates that exists.
+fn find_minecraft_dir(home: Option<&Path>, appdata: Option<&Path>) -> Option<PathBuf> {
+    minecraft_candidates(home, appdata).into_iter().find(|dir| dir.is_dir())
+}
+
+// The Minecraft game folder of the default launcher, if it is installed.
+pub fn detect_minecraft_dir() -> Option<PathBuf> {
+    let home = var_os("HOME").or_else(|| var_os("USERPROFILE")).map(PathBuf::from);
+    let appdata = var_os("APPDATA").map(PathBuf::from);
+    find_minecraft_dir(home.as_deref(), appdata.as_deref())
+}
+
+/*
+Defaults for the wizard: `saved` choices if setup ran before, otherwise exports into the packs
+folder of `minecraft_dir` (when Minecraft was found), no default artist and the newest release.
+*/
+pub fn propose(saved: Option<&SetupChoices>, minecraft_dir: Option<&Path>) -> SetupProposal {
+    let choices = saved.cloned().unwrap_or_else(|| SetupChoices {
+        default_artist: String::new(),
+        export_folder: minecraft_dir.map(|dir| dir.join(PACKS_FOLDER).to_string_lossy().to_string()),
+        target_mod_version: lint::latest_version().to_string(),
+    });
+    SetupProposal {
+        completed: saved.is_some(),
+        minecraft_dir: minecraft_dir.map(|dir| dir.to_string_lossy().to_string()),
+        choices,
+        mod_versions: lint::RULES.iter().map(|rules| rules.mod_version).collect(),
+    }
+}
+
+// Tidies the choices and refuses ones no export could use.
+pub fn validate(choices: &SetupChoices) -> Result<SetupChoices, String> {
+    let target_mod_version = choices.target_mod_version.trim().to_string();
+    if !lint::RULES.iter().any(|rules| rules.mod_version == target_mod_version) {
+        return Err(format!("{} is not a Custom Paintings release the app knows", choices.target_mod_version));
+    }
+    Ok(SetupChoices {
+        default_artist: choices.default_artist.trim().to_string(),
+        export_folder: choices.export_folder.as_deref().map(str::trim).filter(|folder| !folder.is_empty()).map(String::from),
+        target_mod_version,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proposal_exports_into_the_detected_minecraft_folder() {
+        let home = std::env::temp_dir().join(format!("first_run_test_{}", std::process::id()));
+        assert_eq!(find_minecraft_dir(Some(&home), Some(&home)), None);
+        let minecraft = minecraft_candidates(Some(&home), Some(&home)).remove(0);
+        std::fs::create_dir_all(&minecraft).unwrap();
+        assert_eq!(find_minecraft_dir(Some(&home), Some(&home)), Some(minecraft.clone()));
+
+        let proposal = propose(None, Some(&minecraft));
+        assert!(!proposal.completed);
+        assert_eq!(proposal.choices.export_folder, Some(minecraft.join(PACKS_FOLDER).to_string_lossy().to_string()));
+        assert_eq!(proposal.choices.target_mod_version, lint::latest_version());
+
+        let chosen = SetupChoices { default_artist: String::from(" Ana "), export_folder: Some(String::new()), target_mod_version: String::from("1.0.0") };
+        let chosen = validate(&chosen).unwrap();
+        assert_eq!((chosen.default_artist.as_str(), chosen.export_folder.clone()), ("Ana", None));
+        assert!(propose(Some(&chosen), None).completed);
+        assert!(validate(&SetupChoices { target_mod_version: String::from("9.9"), ..chosen }).is_err());
+        std::fs::remove_dir_all(&home).ok();
+    }
+}
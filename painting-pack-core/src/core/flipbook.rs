@@ -0,0 +1,136 @@
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+use image::{imageops, DynamicImage, GenericImageView, RgbaImage};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use crate::core::{hot_folder, resample};
+
+// The game plays frames at 20 ticks a second; 2 ticks a frame is 10 fps.
+pub const DEFAULT_FRAME_TIME: u32 = 2;
+// More frames than this make textures too tall for some graphics cards at large painting sizes.
+pub const MAX_FRAMES: u32 = 64;
+
+/*
+How a painting's texture is animated: `frames` frames of equal height stacked top to bottom,
+each shown for `frame_time` game ticks, optionally blended into the next one.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Animation {
+    pub frames: u32,
+    pub frame_time: u32,
+    pub interpolate: bool,
+}
+
+impl Animation {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(2..=MAX_FRAMES).contains(&self.frames) {
+            return Err(format!("An animation needs between 2 and {} frames, not {}", MAX_FRAMES, self.frames));
+        }
+        if self.frame_time == 0 {
+            return Err(String::from("Each frame has to be shown for at least 1 tick"));
+        }
+        Ok(())
+    }
+
+    // The `.mcmeta` file the game reads next to an animated texture.
+    pub fn mcmeta(&self) -> String {
+        let meta = json!({ "animation": { "frametime": self.frame_time, "interpolate": self.interpolate } });
+        serde_json::to_string_pretty(&meta).expect("mcmeta is always valid JSON")
+    }
+}
+
+// Splits a file stem into the text before its trailing number, and the number, e.g. "walk_07" into ("walk_", 7).
+fn split_number(stem: &str) -> Option<(&str, u64)> {
+    let prefix = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+    let number = stem[prefix.len()..].parse().ok()?;
+    Some((prefix, number))
+}
+
+/*
+Every frame of the numbered sequence `first_frame` belongs to: the images in its folder with the
+same name before the number and the same extension, in numeric order ("frame2" before "frame10").
+Errors if `first_frame` isn't numbered or the sequence has fewer than 2 frames.
+*/
+pub fn find_sequence(first_frame: &Path) -> Result<Vec<PathBuf>, String> {
+    let stem = first_frame.file_stem().unwrap_or_default().to_string_lossy();
+    let (prefix, _) = split_number(&stem)
+        .ok_or_else(|| format!("{} isn't numbered, so it can't start an image sequence", first_frame.display()))?;
+    let extension = first_frame.extension().map(|e| e.to_ascii_lowercase());
+    let folder = first_frame.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+
+    let entries = read_dir(folder).map_err(|e| format!("Failed to read {}: {}", folder.display(), e))?;
+    let mut frames: Vec<(u64, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| hot_folder::is_supported_image(path) && path.extension().map(|e| e.to_ascii_lowercase()) == extension)
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_string_lossy().to_string();
+            let (frame_prefix, number) = split_number(&stem)?;
+            (frame_prefix == prefix).then_some((number, path))
+        })
+        .collect();
+    frames.sort();
+
+    if frames.len() < 2 {
+        return Err(format!("Found no other frames numbered like {}", first_frame.display()));
+    }
+    if frames.len() as u32 > MAX_FRAMES {
+        return Err(format!("The sequence has {} frames; at most {} are supported", frames.len(), MAX_FRAMES));
+    }
+    Ok(frames.into_iter().map(|(_, path)| path).collect())
+}
+
+/*
+Stacks `frames` top to bottom into one flipbook texture. Every frame is scaled to the size of the
+first, so a sequence with a stray odd-sized frame still lines up.
+*/
+pub fn build_flipbook(frames: &[DynamicImage]) -> Result<DynamicImage, String> {
+    let first = frames.first().ok_or("An animation needs at least one frame")?;
+    let (width, height) = first.dimensions();
+    if width == 0 || height == 0 {
+        return Err(String::from("The first frame is empty"));
+    }
+    let mut strip = RgbaImage::new(width, height * frames.len() as u32);
+    for (index, frame) in frames.iter().enumerate() {
+        let frame = if frame.dimensions() == (width, height) { frame.clone() } else { resample::resize_exact(frame, width, height) };
+        imageops::replace(&mut strip, &frame.to_rgba8(), 0, index as i64 * height as i64);
+    }
+    Ok(DynamicImage::ImageRgba8(strip))
+}
+
+// Frame `index` of a flipbook with `frames` frames.
+pub fn frame(flipbook: &DynamicImage, frames: u32, index: u32) -> DynamicImage {
+    let height = flipbook.height() / frames.max(1);
+    flipbook.crop_imm(0, index * height, flipbook.width(), height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+    use std::fs;
+
+    #[test]
+    fn test_sequence_is_found_in_order_and_stacked() {
+        let dir = std::env::temp_dir().join(format!("flipbook_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (name, shade) in [("walk1.png", 10), ("walk2.png", 20), ("walk10.png", 30), ("run1.png", 0)] {
+            RgbaImage::from_pixel(4, 4, Rgba([shade, 0, 0, 255])).save(dir.join(name)).unwrap();
+        }
+
+        let sequence = find_sequence(&dir.join("walk1.png")).unwrap();
+        let names: Vec<String> = sequence.iter().map(|p| p.file_name().unwrap().to_string_lossy().to_string()).collect();
+        assert_eq!(names, vec!["walk1.png", "walk2.png", "walk10.png"]);
+        assert!(find_sequence(&dir.join("run1.png")).is_err());
+
+        let frames: Vec<DynamicImage> = sequence.iter().map(|p| image::open(p).unwrap()).collect();
+        let flipbook = build_flipbook(&frames).unwrap();
+        assert_eq!(flipbook.dimensions(), (4, 12));
+        assert_eq!(frame(&flipbook, 3, 2).to_rgba8().get_pixel(0, 0), &Rgba([30, 0, 0, 255]));
+
+        let animation = Animation { frames: 3, frame_time: DEFAULT_FRAME_TIME, interpolate: false };
+        assert!(animation.validate().is_ok());
+        assert!(animation.mcmeta().contains("\"frametime\": 2"));
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,124 @@
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+use crate::core::{hot_folder, natural_sort};
+
+// One level of a folder naming rule.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Level {
+    Name,
+    Artist,
+    // `*`: a folder that carries no metadata.
+    Skip,
+}
+
+fn parse_rule(rule: &str) -> Result<Vec<Level>, String> {
+    let levels = rule
+        .trim()
+        .trim_matches('/')
+        .split('/')
+        .map(|level| match level.trim() {
+            "{name}" => Ok(Level::Name),
+            "{artist}" => Ok(Level::Artist),
+            "*" => Ok(Level::Skip),
+            other => Err(format!("Unknown folder level \"{}\"; use {{name}}, {{artist}} or *", other)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for wanted in [Level::Name, Level::Artist] {
+        if levels.iter().filter(|&&level| level == wanted).count() > 1 {
+            return Err(format!("A folder naming rule can use {:?} only once", wanted));
+        }
+    }
+    if !levels.iter().any(|&level| level != Level::Skip) {
+        return Err(String::from("A folder naming rule needs {name} or {artist}"));
+    }
+    Ok(levels)
+}
+
+/*
+Checks a rule such as `{artist}/{name}` before it is saved. Levels are separated by `/`, the last
+one is the file itself, and `*` skips a folder level.
+*/
+pub fn validate_rule(rule: &str) -> Result<(), String> {
+    parse_rule(rule).map(|_| ())
+}
+
+// Metadata read off an image's place in the folder tree. `None` keeps the usual default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FolderMetadata {
+    pub name: Option<String>,
+    pub artist: Option<String>,
+}
+
+/*
+Applies a naming rule to an image's path relative to the imported folder, matching the rule's
+levels against the path's last components. `{artist}/{name}` turns `VanGogh/Starry Night.png`
+into artist "VanGogh" and name "Starry Night"; levels the path is too shallow for are left unset.
+*/
+pub fn apply_rule(rule: &str, relative_path: &Path) -> Result<FolderMetadata, String> {
+    let levels = parse_rule(rule)?;
+    let mut components: Vec<String> = relative_path
+        .parent()
+        .into_iter()
+        .flat_map(|parent| parent.components())
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect();
+    components.push(relative_path.file_stem().unwrap_or_default().to_string_lossy().to_string());
+
+    let mut metadata = FolderMetadata::default();
+    for (level, value) in levels.iter().rev().zip(components.into_iter().rev()) {
+        match level {
+            Level::Name => metadata.name = Some(value),
+            Level::Artist => metadata.artist = Some(value),
+            Level::Skip => {}
+        }
+    }
+    Ok(metadata)
+}
+
+/*
+Every supported image anywhere under `root`, naturally sorted by path (see `natural_sort`) so a
+tree imports in a stable order with "file2" before "file10". Hidden files and folders (starting with '.') are skipped.
+*/
+pub fn find_images(root: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut images = Vec::new();
+    let mut folders = vec![root.to_path_buf()];
+    while let Some(folder) = folders.pop() {
+        let entries = read_dir(&folder).map_err(|e| format!("Failed to read {}: {}", folder.display(), e))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            if path.is_dir() {
+                folders.push(path);
+            } else if hot_folder::is_supported_image(&path) {
+                images.push(path);
+            }
+        }
+    }
+    natural_sort::sort_paths(&mut images);
+    Ok(images)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_rule_reads_trailing_levels() {
+        let metadata = apply_rule("{artist}/{name}", Path::new("Collection/VanGogh/Starry Night.png")).unwrap();
+        assert_eq!(metadata, FolderMetadata { name: Some("Starry Night".into()), artist: Some("VanGogh".into()) });
+
+        // Artist two levels up, skipping a year folder
+        let metadata = apply_rule("{artist}/*/{name}", Path::new("Monet/1899/Water Lilies.jpg")).unwrap();
+        assert_eq!(metadata.artist.as_deref(), Some("Monet"));
+
+        // Too shallow for the artist level
+        let metadata = apply_rule("{artist}/{name}", Path::new("Loose.png")).unwrap();
+        assert_eq!(metadata, FolderMetadata { name: Some("Loose".into()), artist: None });
+
+        assert!(validate_rule("{artist}/{artist}").is_err());
+        assert!(validate_rule("{year}/{name}").is_err());
+        assert!(validate_rule("*").is_err());
+    }
+}
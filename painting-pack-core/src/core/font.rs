@@ -0,0 +1,240 @@
+use image::{Rgba, RgbaImage};
+
+/*
+A bundled 5x7 pixel font covering printable ASCII, so text can be drawn without shipping
+a font file or pulling in a font rasterizer. The blocky look suits Minecraft paintings.
+Each glyph is 5 columns; bit 0 of a column is the top row.
+*/
+pub const GLYPH_WIDTH: u32 = 5;
+pub const GLYPH_HEIGHT: u32 = 7;
+// One blank column between characters and one blank row between lines.
+pub const ADVANCE: u32 = GLYPH_WIDTH + 1;
+pub const LINE_HEIGHT: u32 = GLYPH_HEIGHT + 1;
+
+const FIRST_CHAR: u8 = b' ';
+const GLYPHS: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // #
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x56, 0x20, 0x50], // &
+    [0x00, 0x00, 0x07, 0x00, 0x00], // '
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // )
+    [0x14, 0x08, 0x3E, 0x08, 0x14], // *
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // +
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x60, 0x60, 0x00, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // 0
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x45, 0x4B, 0x31], // 3
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3C, 0x4A, 0x49, 0x49, 0x30], // 6
+    [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x06, 0x49, 0x49, 0x29, 0x1E], // 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], // :
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+    [0x32, 0x49, 0x79, 0x41, 0x3E], // @
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], // A
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], // D
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7F, 0x09, 0x09, 0x09, 0x01], // F
+    [0x3E, 0x41, 0x49, 0x49, 0x7A], // G
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // H
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // J
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7F, 0x02, 0x0C, 0x02, 0x7F], // M
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // N
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // O
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // Q
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // R
+    [0x46, 0x49, 0x49, 0x49, 0x31], // S
+    [0x01, 0x01, 0x7F, 0x01, 0x01], // T
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // U
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // V
+    [0x3F, 0x40, 0x38, 0x40, 0x3F], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x07, 0x08, 0x70, 0x08, 0x07], // Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+    [0x00, 0x7F, 0x41, 0x41, 0x00], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x00, 0x41, 0x41, 0x7F, 0x00], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x01, 0x02, 0x04, 0x00], // `
+    [0x20, 0x54, 0x54, 0x54, 0x78], // a
+    [0x7F, 0x48, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x20], // c
+    [0x38, 0x44, 0x44, 0x48, 0x7F], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x08, 0x7E, 0x09, 0x01, 0x02], // f
+    [0x0C, 0x52, 0x52, 0x52, 0x3E], // g
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // i
+    [0x20, 0x40, 0x44, 0x3D, 0x00], // j
+    [0x7F, 0x10, 0x28, 0x44, 0x00], // k
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // l
+    [0x7C, 0x04, 0x18, 0x04, 0x78], // m
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0x7C, 0x14, 0x14, 0x14, 0x08], // p
+    [0x08, 0x14, 0x14, 0x18, 0x7C], // q
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x20], // s
+    [0x04, 0x3F, 0x44, 0x40, 0x20], // t
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // u
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // v
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x0C, 0x50, 0x50, 0x50, 0x3C], // y
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x7F, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x08, 0x04, 0x08, 0x10, 0x08], // ~
+];
+
+// Characters outside printable ASCII are drawn as '?'.
+fn glyph(c: char) -> &'static [u8; 5] {
+    let index = match c {
+        ' '..='~' => c as u8 - FIRST_CHAR,
+        _ => b'?' - FIRST_CHAR,
+    };
+    &GLYPHS[index as usize]
+}
+
+// Printable characters other than the space whose glyph has no pixels set, i.e. would draw nothing.
+pub(crate) fn blank_glyphs() -> Vec<char> {
+    (FIRST_CHAR..=b'~')
+        .filter(|&c| c != b' ' && GLYPHS[(c - FIRST_CHAR) as usize].iter().all(|column| *column == 0))
+        .map(char::from)
+        .collect()
+}
+
+// Width in unscaled pixels of a single line of text.
+pub fn line_width(line: &str) -> u32 {
+    let chars = line.chars().count() as u32;
+    if chars == 0 {
+        0
+    } else {
+        chars * ADVANCE - 1
+    }
+}
+
+/*
+Greedy word wrap so that no line is wider than `max_width` as reported by `measure`. Explicit
+newlines are kept and words wider than a whole line are broken between characters.
+Shared by the pixel font and TrueType fonts, which only differ in how text is measured.
+*/
+pub fn wrap(text: &str, max_width: u32, measure: impl Fn(&str) -> u32) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+            if measure(&candidate) <= max_width {
+                line = candidate;
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+
+            // The word doesn't fit on a line of its own either, so split it.
+            for c in word.chars() {
+                line.push(c);
+                if measure(&line) > max_width && line.chars().count() > 1 {
+                    line.pop();
+                    lines.push(std::mem::replace(&mut line, c.to_string()));
+                }
+            }
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+/*
+Draws one line of text onto `canvas` with its top-left corner at (x, y). Every font pixel
+becomes a `scale` x `scale` square; `bold` thickens strokes by one font pixel.
+*/
+pub fn draw_text(canvas: &mut RgbaImage, text: &str, x: i64, y: i64, scale: u32, colour: Rgba<u8>, bold: bool) {
+    let scale = scale.max(1) as i64;
+    for (index, c) in text.chars().enumerate() {
+        let origin_x = x + index as i64 * ADVANCE as i64 * scale;
+        for (column, bits) in glyph(c).iter().enumerate() {
+            for row in 0..GLYPH_HEIGHT as i64 {
+                if bits & (1 << row) == 0 {
+                    continue;
+                }
+                let strokes: &[i64] = if bold { &[0, 1] } else { &[0] };
+                for offset in strokes {
+                    let px = origin_x + (column as i64 + offset) * scale;
+                    let py = y + row * scale;
+                    fill_square(canvas, px, py, scale, colour);
+                }
+            }
+        }
+    }
+}
+
+fn fill_square(canvas: &mut RgbaImage, x: i64, y: i64, size: i64, colour: Rgba<u8>) {
+    for dy in 0..size {
+        for dx in 0..size {
+            let (px, py) = (x + dx, y + dy);
+            if px >= 0 && py >= 0 && (px as u32) < canvas.width() && (py as u32) < canvas.height() {
+                canvas.put_pixel(px as u32, py as u32, colour);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_respects_width_and_newlines() {
+        let chars = |line: &str| line.chars().count() as u32;
+        assert_eq!(wrap("no running in the hall", 10, chars), vec!["no running", "in the", "hall"]);
+        assert_eq!(wrap("rule one\nrule two", 20, chars), vec!["rule one", "rule two"]);
+        assert_eq!(wrap("abcdefghij", 4, chars), vec!["abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn test_line_width() {
+        assert_eq!(line_width(""), 0);
+        assert_eq!(line_width("A"), 5);
+        assert_eq!(line_width("AB"), 11);
+    }
+
+    #[test]
+    fn test_draw_text_scales_pixels() {
+        let mut canvas = RgbaImage::new(20, 20);
+        let red = Rgba([255, 0, 0, 255]);
+        // '|' is a single vertical stroke in the middle column
+        draw_text(&mut canvas, "|", 0, 0, 2, red, false);
+        assert_eq!(*canvas.get_pixel(4, 0), red);
+        assert_eq!(*canvas.get_pixel(5, 13), red);
+        assert_eq!(canvas.get_pixel(0, 0).0[3], 0);
+        assert_eq!(canvas.get_pixel(4, 14).0[3], 0); // below the 7-row glyph
+    }
+}
@@ -0,0 +1,249 @@
+use std::sync::OnceLock;
+use image::RgbaImage;
+use wgpu::util::DeviceExt;
+
+/*
+Separable Lanczos3 resize in two compute passes (rows, then columns), the same filter the CPU
+path uses. Pixels travel as packed RGBA8 words; the intermediate pass is kept in f32.
+*/
+const SHADER: &str = r#"
+struct Params { src_w: u32, src_h: u32, dst_w: u32, dst_h: u32 }
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> source: array<u32>;
+@group(0) @binding(2) var<storage, read_write> middle: array<vec4<f32>>;
+@group(0) @binding(3) var<storage, read_write> destination: array<u32>;
+
+const PI: f32 = 3.14159265;
+
+fn sinc(x: f32) -> f32 {
+    if x == 0.0 { return 1.0; }
+    return sin(PI * x) / (PI * x);
+}
+
+fn lanczos3(x: f32) -> f32 {
+    if abs(x) >= 3.0 { return 0.0; }
+    return sinc(x) * sinc(x / 3.0);
+}
+
+// Source pixel range and kernel scale for one output coordinate along an axis.
+fn window(out: u32, src_len: u32, dst_len: u32) -> vec4<f32> {
+    let scale = f32(src_len) / f32(dst_len);
+    let support = max(scale, 1.0);
+    let center = (f32(out) + 0.5) * scale;
+    let first = max(floor(center - 3.0 * support), 0.0);
+    let last = min(ceil(center + 3.0 * support), f32(src_len) - 1.0);
+    return vec4<f32>(center, support, first, last);
+}
+
+@compute @workgroup_size(8, 8)
+fn horizontal(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.dst_w || id.y >= params.src_h { return; }
+    let w = window(id.x, params.src_w, params.dst_w);
+    var sum = vec4<f32>(0.0);
+    var total = 0.0;
+    for (var i = u32(w.z); i <= u32(w.w); i++) {
+        let weight = lanczos3((f32(i) + 0.5 - w.x) / w.y);
+        sum += unpack4x8unorm(source[id.y * params.src_w + i]) * weight;
+        total += weight;
+    }
+    middle[id.y * params.dst_w + id.x] = select(sum, sum / total, total != 0.0);
+}
+
+@compute @workgroup_size(8, 8)
+fn vertical(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.dst_w || id.y >= params.dst_h { return; }
+    let w = window(id.y, params.src_h, params.dst_h);
+    var sum = vec4<f32>(0.0);
+    var total = 0.0;
+    for (var i = u32(w.z); i <= u32(w.w); i++) {
+        let weight = lanczos3((f32(i) + 0.5 - w.x) / w.y);
+        sum += middle[i * params.dst_w + id.x] * weight;
+        total += weight;
+    }
+    destination[id.y * params.dst_w + id.x] = pack4x8unorm(clamp(select(sum, sum / total, total != 0.0), vec4<f32>(0.0), vec4<f32>(1.0)));
+}
+"#;
+
+const WORKGROUP_SIZE: u32 = 8;
+
+struct GpuResizer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    layout: wgpu::BindGroupLayout,
+    horizontal: wgpu::ComputePipeline,
+    vertical: wgpu::ComputePipeline,
+}
+
+// Initialised on first use; `None` if there's no usable adapter, in which case callers stay on the CPU.
+fn resizer() -> Option<&'static GpuResizer> {
+    static RESIZER: OnceLock<Option<GpuResizer>> = OnceLock::new();
+    RESIZER
+        .get_or_init(|| {
+            // wgpu reports driver and validation problems by panicking; treat those as "no GPU".
+            let resizer = std::panic::catch_unwind(|| pollster::block_on(GpuResizer::new()))
+                .unwrap_or_else(|_| Err(String::from("the GPU driver failed to initialise")));
+            match &resizer {
+                Ok(_) => println!("[GPU] using the GPU for image resizing"),
+                Err(e) => println!("[GPU] falling back to the CPU: {}", e),
+            }
+            resizer.ok()
+        })
+        .as_ref()
+}
+
+impl GpuResizer {
+    async fn new() -> Result<Self, String> {
+        let instance = wgpu::Instance::default();
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .map_err(|e| e.to_string())?;
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                label: Some("painting-pack-maker resize"),
+                // Ask for the adapter's real limits so large images fit in one buffer.
+                required_limits: adapter.limits(),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("resize"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let storage = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("resize"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage(1, true),
+                storage(2, false),
+                storage(3, false),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("resize"),
+            bind_group_layouts: &[Some(&layout)],
+            immediate_size: 0,
+        });
+        let pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+        let (horizontal, vertical) = (pipeline("horizontal"), pipeline("vertical"));
+
+        Ok(GpuResizer { device, queue, layout, horizontal, vertical })
+    }
+
+    fn resize(&self, image: &RgbaImage, width: u32, height: u32) -> Result<RgbaImage, String> {
+        let (src_w, src_h) = image.dimensions();
+        let limits = self.device.limits();
+        let largest = [src_w as u64 * src_h as u64 * 4, width as u64 * src_h as u64 * 16, width as u64 * height as u64 * 4]
+            .into_iter()
+            .max()
+            .unwrap_or(0);
+        if largest > limits.max_storage_buffer_binding_size.min(limits.max_buffer_size) {
+            return Err(String::from("image is too large for the GPU's buffers"));
+        }
+        let groups = |n: u32| n.div_ceil(WORKGROUP_SIZE);
+        if groups(width.max(src_w)).max(groups(height.max(src_h))) > limits.max_compute_workgroups_per_dimension {
+            return Err(String::from("image is too large to dispatch"));
+        }
+
+        let params: Vec<u8> = [src_w, src_h, width, height].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let params = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("params"),
+            contents: &params,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let source = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("source"),
+            contents: image.as_raw(),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let buffer = |label: &str, size: u64, usage: wgpu::BufferUsages| {
+            self.device.create_buffer(&wgpu::BufferDescriptor { label: Some(label), size, usage, mapped_at_creation: false })
+        };
+        let middle = buffer("middle", width as u64 * src_h as u64 * 16, wgpu::BufferUsages::STORAGE);
+        let output_size = width as u64 * height as u64 * 4;
+        let target = buffer("target", output_size, wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC);
+        let readback = buffer("readback", output_size, wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST);
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("resize"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: params.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: source.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: middle.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: target.as_entire_binding() },
+            ],
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("resize") });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: Some("resize"), timestamp_writes: None });
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.set_pipeline(&self.horizontal);
+            pass.dispatch_workgroups(groups(width), groups(src_h), 1);
+            pass.set_pipeline(&self.vertical);
+            pass.dispatch_workgroups(groups(width), groups(height), 1);
+        }
+        encoder.copy_buffer_to_buffer(&target, 0, &readback, 0, output_size);
+        self.queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        readback.map_async(wgpu::MapMode::Read, .., move |result| {
+            sender.send(result).ok();
+        });
+        self.device.poll(wgpu::PollType::wait_indefinitely()).map_err(|e| e.to_string())?;
+        receiver.recv().map_err(|e| e.to_string())?.map_err(|e| e.to_string())?;
+
+        let bytes = readback.get_mapped_range(..).map_err(|e| e.to_string())?.to_vec();
+        readback.unmap();
+        RgbaImage::from_raw(width, height, bytes).ok_or_else(|| String::from("GPU returned the wrong number of pixels"))
+    }
+}
+
+/*
+Resizes on the GPU, or returns `None` when there's no GPU or the job doesn't fit on it so the
+caller can fall back to the CPU.
+*/
+pub fn resize(image: &RgbaImage, width: u32, height: u32) -> Option<RgbaImage> {
+    let resizer = resizer()?;
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| resizer.resize(image, width, height)))
+        .unwrap_or_else(|_| Err(String::from("the GPU driver reported an error")));
+    match result {
+        Ok(resized) => Some(resized),
+        Err(e) => {
+            println!("[GPU] resize failed, using the CPU instead: {}", e);
+            None
+        }
+    }
+}
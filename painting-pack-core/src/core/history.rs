@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use crate::core::{adjustments::SizeAdjustment, cropper::{CropOffset, CropRect, FitMode}, flipbook::Animation};
+use crate::models::{export_settings::ExportSettings, image_size::ImageSize, painting::PaintingFrame};
+
+// Older snapshots are dropped once the history grows past this.
+pub const MAX_SNAPSHOTS: usize = 50;
+
+// The editable metadata of one imported image. Pixels are never stored; the source path is enough.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GroupSnapshot {
+    pub source_path: String,
+    pub name: String,
+    pub artist: String,
+    pub selected_sizes: Vec<ImageSize>,
+    #[serde(default)]
+    pub deskew: bool,
+    // Set when `source_path` is a normalized working copy.
+    #[serde(default)]
+    pub original_path: Option<String>,
+    // Set when `source_path` is a lossy working copy of this file.
+    #[serde(default)]
+    pub full_quality_path: Option<String>,
+    // The file a working copy was made from, so it can be rebuilt in a later session.
+    #[serde(default)]
+    pub working_copy_source: Option<String>,
+    #[serde(default)]
+    pub order: u32,
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub inset_percent: f32,
+    #[serde(default)]
+    pub bleed_percent: f32,
+    #[serde(default)]
+    pub frame: Option<PaintingFrame>,
+    #[serde(default)]
+    pub weight: Option<u32>,
+    #[serde(default)]
+    pub animation: Option<Animation>,
+    // Per-size adjustments of every crop; block sizes are unique across shapes.
+    #[serde(default)]
+    pub size_adjustments: Vec<SizeAdjustment>,
+    // Crops moved off centre, by shape.
+    #[serde(default)]
+    pub crop_offsets: Vec<(ImageSize, CropOffset)>,
+    // Crops picked by hand, by shape.
+    #[serde(default)]
+    pub crop_rects: Vec<(ImageSize, CropRect)>,
+    // Shapes that are letterboxed or stretched rather than cropped.
+    #[serde(default)]
+    pub fit_modes: Vec<(ImageSize, FitMode)>,
+}
+
+/*
+A lightweight copy of the project's metadata and settings at one point in time.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProjectSnapshot {
+    pub snapshot_id: u64,
+    // Unix seconds.
+    pub created_at: u64,
+    // What triggered the snapshot, e.g. "export" or "manual".
+    pub reason: String,
+    pub pack_name: String,
+    pub version: String,
+    pub pack_id: String,
+    pub description: String,
+    pub export_settings: ExportSettings,
+    pub groups: Vec<GroupSnapshot>,
+}
+
+// What the frontend lists for each snapshot.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SnapshotSummary {
+    pub snapshot_id: u64,
+    pub created_at: u64,
+    pub reason: String,
+    pub pack_name: String,
+    pub version: String,
+    pub image_count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct History {
+    snapshots: Vec<ProjectSnapshot>,
+    next_id: u64,
+}
+
+impl History {
+    // Stores a snapshot, assigning its ID, and returns that ID.
+    pub fn push(&mut self, mut snapshot: ProjectSnapshot) -> u64 {
+        self.next_id += 1;
+        snapshot.snapshot_id = self.next_id;
+        self.snapshots.push(snapshot);
+        if self.snapshots.len() > MAX_SNAPSHOTS {
+            let excess = self.snapshots.len() - MAX_SNAPSHOTS;
+            self.snapshots.drain(..excess);
+        }
+        self.next_id
+    }
+
+    // Newest first.
+    pub fn list(&self) -> Vec<SnapshotSummary> {
+        self.snapshots
+            .iter()
+            .rev()
+            .map(|snapshot| SnapshotSummary {
+                snapshot_id: snapshot.snapshot_id,
+                created_at: snapshot.created_at,
+                reason: snapshot.reason.clone(),
+                pack_name: snapshot.pack_name.clone(),
+                version: snapshot.version.clone(),
+                image_count: snapshot.groups.len(),
+            })
+            .collect()
+    }
+
+    pub fn get(&self, snapshot_id: u64) -> Option<&ProjectSnapshot> {
+        self.snapshots.iter().find(|snapshot| snapshot.snapshot_id == snapshot_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(version: &str) -> ProjectSnapshot {
+        ProjectSnapshot {
+            snapshot_id: 0,
+            created_at: 0,
+            reason: String::from("export"),
+            pack_name: String::from("Pack"),
+            version: version.to_string(),
+            pack_id: String::from("pack"),
+            description: String::new(),
+            export_settings: ExportSettings::default(),
+            groups: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_push_assigns_ids_and_lists_newest_first() {
+        let mut history = History::default();
+        let first = history.push(snapshot("1.0.0"));
+        let second = history.push(snapshot("1.1.0"));
+        assert_ne!(first, second);
+
+        let listed = history.list();
+        assert_eq!(listed[0].version, "1.1.0");
+        assert_eq!(listed[1].version, "1.0.0");
+        assert_eq!(history.get(first).unwrap().version, "1.0.0");
+    }
+
+    #[test]
+    fn test_history_is_capped() {
+        let mut history = History::default();
+        for i in 0..MAX_SNAPSHOTS + 5 {
+            history.push(snapshot(&i.to_string()));
+        }
+        assert_eq!(history.list().len(), MAX_SNAPSHOTS);
+        // The oldest snapshots were dropped
+        assert!(history.get(1).is_none());
+    }
+}
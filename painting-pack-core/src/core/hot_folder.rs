@@ -0,0 +1,100 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+use crate::core::natural_sort;
+
+// File types the importer accepts, matching the file picker's filter.
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg"];
+
+pub fn is_supported_image(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| SUPPORTED_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/*
+Watches a folder for new images by polling it. Files already there when the watch starts are
+ignored. A new file is only reported once its size has stayed the same across two polls, so
+images that an editor is still writing aren't imported half finished.
+*/
+#[derive(Debug)]
+pub struct HotFolder {
+    path: PathBuf,
+    // Files already reported, or present when the watch started.
+    seen: HashSet<PathBuf>,
+    // New files and their size at the previous poll.
+    pending: HashMap<PathBuf, u64>,
+}
+
+impl HotFolder {
+    pub fn new(path: &Path) -> Result<Self, String> {
+        let mut folder = HotFolder { path: path.to_path_buf(), seen: HashSet::new(), pending: HashMap::new() };
+        folder.seen = folder.images()?.into_keys().collect();
+        Ok(folder)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Supported images directly inside the folder and their current sizes.
+    fn images(&self) -> Result<HashMap<PathBuf, u64>, String> {
+        let entries = read_dir(&self.path).map_err(|e| format!("Failed to read {}: {}", self.path.display(), e))?;
+        Ok(entries
+            .flatten()
+            .filter(|entry| is_supported_image(&entry.path()))
+            .filter_map(|entry| {
+                let meta = entry.metadata().ok()?;
+                meta.is_file().then(|| (entry.path(), meta.len()))
+            })
+            .collect())
+    }
+
+    /// Returns the images that have finished arriving since the last poll, oldest name first.
+    pub fn poll(&mut self) -> Result<Vec<PathBuf>, String> {
+        let current = self.images()?;
+        let mut ready = Vec::new();
+        let mut still_pending = HashMap::new();
+        for (path, len) in current {
+            if self.seen.contains(&path) {
+                continue;
+            }
+            match self.pending.get(&path) {
+                Some(&previous) if previous == len && len > 0 => ready.push(path),
+                _ => { still_pending.insert(path, len); }
+            }
+        }
+        self.pending = still_pending;
+        natural_sort::sort_paths(&mut ready);
+        self.seen.extend(ready.iter().cloned());
+        Ok(ready)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn test_reports_new_images_once_stable() {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = env::temp_dir().join(format!("test_hot_folder_{:x}", nanos));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("old.png"), b"old").unwrap();
+
+        let mut folder = HotFolder::new(&dir).unwrap();
+        fs::write(dir.join("new.PNG"), b"partial").unwrap();
+        fs::write(dir.join("notes.txt"), b"ignored").unwrap();
+        // First sighting only records the size.
+        assert!(folder.poll().unwrap().is_empty());
+
+        fs::write(dir.join("new.PNG"), b"partial, now complete").unwrap();
+        assert!(folder.poll().unwrap().is_empty());
+        assert_eq!(folder.poll().unwrap(), vec![dir.join("new.PNG")]);
+        assert!(folder.poll().unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
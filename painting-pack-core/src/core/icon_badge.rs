@@ -0,0 +1,97 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use crate::core::font;
+
+// Longer badge text is cut off; anything more wouldn't be readable at icon size.
+pub const MAX_BADGE_CHARS: usize = 6;
+// Initials are taken from at most this many words of the pack name.
+const MAX_INITIALS: usize = 3;
+const BADGE_BACKGROUND: Rgba<u8> = Rgba([0, 0, 0, 190]);
+const BADGE_TEXT: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/*
+Short text stamped onto the pack icon so several packs sharing the same icon style can be told
+apart in the in-game pack list.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum IconBadge {
+    // First letters of the pack name's words, e.g. "FP" for "Forest Pack".
+    Initials,
+    // The pack version, e.g. "1.2".
+    Version,
+    Custom(String),
+}
+
+impl IconBadge {
+    // The text drawn for a pack, cut to `MAX_BADGE_CHARS`. Empty if there's nothing to show.
+    pub fn text(&self, pack_name: &str, version: &str) -> String {
+        let text = match self {
+            IconBadge::Initials => pack_name
+                .split_whitespace()
+                .filter_map(|word| word.chars().next())
+                .take(MAX_INITIALS)
+                .flat_map(char::to_uppercase)
+                .collect(),
+            IconBadge::Version => version.trim().to_string(),
+            IconBadge::Custom(text) => text.trim().to_string(),
+        };
+        text.chars().take(MAX_BADGE_CHARS).collect()
+    }
+}
+
+/*
+Draws `text` in the bundled pixel font on a dark strip along the bottom right of `icon`, as large
+as fits in a quarter of the icon's height.
+*/
+pub fn draw_badge(icon: &DynamicImage, text: &str) -> DynamicImage {
+    let mut canvas = icon.to_rgba8();
+    let (width, height) = canvas.dimensions();
+    let text_width = font::line_width(text);
+    if text_width == 0 || width == 0 || height == 0 {
+        return DynamicImage::ImageRgba8(canvas);
+    }
+
+    let padding = (height / 32).max(1);
+    let scale = ((width.saturating_sub(2 * padding)) / text_width)
+        .min((height / 4) / font::GLYPH_HEIGHT)
+        .max(1);
+    let badge_width = (text_width * scale + 2 * padding).min(width);
+    let badge_height = (font::GLYPH_HEIGHT * scale + 2 * padding).min(height);
+    let (left, top) = (width - badge_width, height - badge_height);
+
+    fill_blended(&mut canvas, left, top, badge_width, badge_height, BADGE_BACKGROUND);
+    font::draw_text(&mut canvas, text, (left + padding) as i64, (top + padding) as i64, scale, BADGE_TEXT, false);
+    DynamicImage::ImageRgba8(canvas)
+}
+
+fn fill_blended(canvas: &mut RgbaImage, left: u32, top: u32, width: u32, height: u32, colour: Rgba<u8>) {
+    let alpha = colour[3] as u32;
+    for y in top..top + height {
+        for x in left..left + width {
+            let pixel = canvas.get_pixel_mut(x, y);
+            for channel in 0..3 {
+                pixel[channel] = ((colour[channel] as u32 * alpha + pixel[channel] as u32 * (255 - alpha)) / 255) as u8;
+            }
+            pixel[3] = pixel[3].max(colour[3]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_badge_text_and_drawing() {
+        assert_eq!(IconBadge::Initials.text("forest pack of the north", "1.0.0"), "FPO");
+        assert_eq!(IconBadge::Version.text("Forest", " 1.2.10-beta "), "1.2.10");
+        assert_eq!(IconBadge::Custom(String::new()).text("Forest", "1.0"), "");
+
+        let icon = DynamicImage::ImageRgba8(RgbaImage::from_pixel(128, 128, Rgba([200, 200, 200, 255])));
+        let badged = draw_badge(&icon, "FP").to_rgba8();
+        // Top left untouched, bottom right darkened by the badge.
+        assert_eq!(badged.get_pixel(0, 0), &Rgba([200, 200, 200, 255]));
+        assert!(badged.get_pixel(127, 127)[0] < 100);
+        assert_eq!(draw_badge(&icon, "").to_rgba8(), icon.to_rgba8());
+    }
+}
@@ -0,0 +1,96 @@
+use image::{imageops, DynamicImage, RgbaImage};
+use crate::core::{cropper, exporter::ExportItem, flipbook, resample};
+use crate::models::image_size::ImageSize;
+
+/*
+The paintings a collage icon of `grid` x `grid` tiles could use: the first ones in export order,
+one item per painting even when several of its shapes are exported. Fewer come back if the pack
+doesn't have that many.
+*/
+pub fn icon_sources(items: &[ExportItem], grid: u32) -> Vec<ExportItem> {
+    let mut sources: Vec<ExportItem> = Vec::new();
+    for item in items {
+        if sources.len() as u32 >= grid * grid {
+            break;
+        }
+        if !sources.iter().any(|source| source.source_path == item.source_path && source.data.order == item.data.order) {
+            sources.push(item.clone());
+        }
+    }
+    sources
+}
+
+// Square tile of one painting: its square crop, or its first frame for a flipbook.
+fn tile(item: &ExportItem) -> Result<DynamicImage, String> {
+    let options = item.data.crop_options();
+    let square = cropper::crop_single_image(&item.source_path, &ImageSize::Square, &options)
+        .map_err(|e| format!("Couldn't crop {}: {}", item.source_path, e))?;
+    Ok(flipbook::frame(&square, options.frames, 0))
+}
+
+/*
+Builds a `size` pixel icon from a `grid` x `grid` collage (2 or 3 a side) of the paintings'
+square crops, left to right and top to bottom. A pack with too few paintings for the grid gets
+the next smaller one, down to a single painting; paintings that can't be read are left out.
+Returns `None` if none could be used.
+*/
+pub fn collage_icon(items: &[ExportItem], grid: u32, size: u32) -> Option<DynamicImage> {
+    let tiles: Vec<DynamicImage> = icon_sources(items, grid)
+        .iter()
+        .filter_map(|item| match tile(item) {
+            Ok(tile) => Some(tile),
+            Err(e) => {
+                eprintln!("[EXPORT] Leaving a painting out of the icon: {}", e);
+                None
+            }
+        })
+        .collect();
+    let grid = (1..=grid).rev().find(|grid| (grid * grid) as usize <= tiles.len())?;
+
+    let mut canvas = RgbaImage::new(size, size);
+    for (index, tile) in tiles.iter().take((grid * grid) as usize).enumerate() {
+        let (column, row) = (index as u32 % grid, index as u32 / grid);
+        // Edges are spread evenly so the tiles cover the icon exactly when `size` doesn't divide.
+        let (x0, x1) = (column * size / grid, (column + 1) * size / grid);
+        let (y0, y1) = (row * size / grid, (row + 1) * size / grid);
+        let scaled = resample::resize_exact(tile, x1 - x0, y1 - y0);
+        imageops::replace(&mut canvas, &scaled.to_rgba8(), x0 as i64, y0 as i64);
+    }
+    Some(DynamicImage::ImageRgba8(canvas))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::image_data::ImageData;
+    use image::{GenericImageView, Rgba};
+
+    #[test]
+    fn test_collage_icon_falls_back_to_a_smaller_grid() {
+        let dir = std::env::temp_dir().join(format!("icon_generator_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let colours = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255], [255, 255, 0, 255], [0, 0, 0, 255]];
+        let mut items = Vec::new();
+        for (order, colour) in colours.iter().enumerate() {
+            let path = dir.join(format!("{}.png", order));
+            RgbaImage::from_pixel(40, 40, Rgba(*colour)).save(&path).unwrap();
+            // Two shapes of the same painting only count once.
+            for size in [ImageSize::Square, ImageSize::Wide] {
+                let mut data = ImageData::new(size);
+                data.order = order as u32 + 1;
+                items.push(ExportItem { source_path: path.to_string_lossy().to_string(), data });
+            }
+        }
+        assert_eq!(icon_sources(&items, 3).len(), 5);
+
+        // Five paintings can't fill 3x3, so the first four make a 2x2.
+        let icon = collage_icon(&items, 3, 128).unwrap();
+        assert_eq!(icon.dimensions(), (128, 128));
+        assert_eq!(icon.get_pixel(10, 10), Rgba([255, 0, 0, 255]));
+        assert_eq!(icon.get_pixel(100, 10), Rgba([0, 255, 0, 255]));
+        assert_eq!(icon.get_pixel(10, 100), Rgba([0, 0, 255, 255]));
+        assert_eq!(icon.get_pixel(100, 100), Rgba([255, 255, 0, 255]));
+        assert!(collage_icon(&[], 2, 128).is_none());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
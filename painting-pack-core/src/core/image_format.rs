@@ -0,0 +1,211 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use color_quant::NeuQuant;
+use image::{DynamicImage, ExtendedColorType, ImageEncoder as _};
+use image::codecs::png::PngEncoder as ImagePngEncoder;
+use image::codecs::webp::WebPEncoder;
+use serde::{Deserialize, Serialize};
+use crate::core::lint::ModRules;
+
+/*
+Writes one painting texture. The exporter only talks to this trait, so adding an output format is
+a new encoder plus an `OutputFormat` variant; the mod must also list it in `lint::RULES`.
+Encoders are shared by the export threads, so they can't hold per-image state.
+*/
+pub trait ImageEncoder: Sync {
+    // File extension without the dot, used for the image file and its custompaintings.json entry.
+    fn extension(&self) -> &'static str;
+    // Streams the encoded image into `out`, which may be a file or an entry of a zip archive.
+    // `depth` has already been resolved for this image by `ColorDepth::resolve`.
+    fn write(&self, image: &DynamicImage, depth: ColorDepth, out: &mut dyn Write) -> Result<(), String>;
+
+    fn encode(&self, image: &DynamicImage, depth: ColorDepth, path: &Path) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        let mut out = BufWriter::new(file);
+        self.write(image, depth, &mut out)
+            .and_then(|()| out.flush().map_err(|e| e.to_string()))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+/*
+Colour depth of the painting textures, chosen per pack. Smaller depths give smaller files:
+24-bit drops the alpha channel and 8-bit indexed keeps a 256 colour palette.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorDepth {
+    Rgb24,
+    #[default]
+    Rgba32,
+    Indexed8,
+}
+
+impl ColorDepth {
+    /*
+    The depth one image is actually written at. Images with any transparent pixel always fall
+    back to 32-bit, since both smaller depths would turn their transparency opaque.
+    */
+    pub fn resolve(self, image: &DynamicImage) -> ColorDepth {
+        if self != ColorDepth::Rgba32 && needs_alpha(image) {
+            ColorDepth::Rgba32
+        } else {
+            self
+        }
+    }
+}
+
+// Whether any pixel of `image` is not fully opaque.
+pub fn needs_alpha(image: &DynamicImage) -> bool {
+    image.color().has_alpha() && image.to_rgba8().pixels().any(|pixel| pixel[3] != u8::MAX)
+}
+
+// Samples every 10th pixel when building the palette; NeuQuant's recommended speed/quality balance.
+const QUANTIZE_SAMPLE_FACTOR: i32 = 10;
+
+// A 256 colour palette (flat RGB triplets) for `image` and each pixel's index into it.
+fn quantize(image: &DynamicImage) -> (Vec<u8>, Vec<u8>) {
+    let rgba = image.to_rgba8();
+    let quantizer = NeuQuant::new(QUANTIZE_SAMPLE_FACTOR, 256, rgba.as_raw());
+    let indices = rgba.pixels().map(|pixel| quantizer.index_of(&pixel.0) as u8).collect();
+    (quantizer.color_map_rgb(), indices)
+}
+
+pub struct PngEncoder;
+
+impl ImageEncoder for PngEncoder {
+    fn extension(&self) -> &'static str {
+        "png"
+    }
+
+    fn write(&self, image: &DynamicImage, depth: ColorDepth, out: &mut dyn Write) -> Result<(), String> {
+        let (width, height) = (image.width(), image.height());
+        match depth {
+            ColorDepth::Rgb24 => ImagePngEncoder::new(out)
+                .write_image(image.to_rgb8().as_raw(), width, height, ExtendedColorType::Rgb8)
+                .map_err(|e| e.to_string()),
+            ColorDepth::Rgba32 => ImagePngEncoder::new(out)
+                .write_image(image.to_rgba8().as_raw(), width, height, ExtendedColorType::Rgba8)
+                .map_err(|e| e.to_string()),
+            ColorDepth::Indexed8 => {
+                let (palette, indices) = quantize(image);
+                let mut encoder = png::Encoder::new(out, width, height);
+                encoder.set_color(png::ColorType::Indexed);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder.set_palette(palette);
+                let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+                writer.write_image_data(&indices).map_err(|e| e.to_string())?;
+                writer.finish().map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+/*
+Lossless WebP: the same pixels as PNG in a noticeably smaller file. WebP has no indexed mode, so
+8-bit output is quantized to the palette and stored as 24-bit, which the encoder packs tightly.
+*/
+pub struct WebpLosslessEncoder;
+
+impl ImageEncoder for WebpLosslessEncoder {
+    fn extension(&self) -> &'static str {
+        "webp"
+    }
+
+    fn write(&self, image: &DynamicImage, depth: ColorDepth, out: &mut dyn Write) -> Result<(), String> {
+        let (pixels, color_type) = match depth {
+            ColorDepth::Rgb24 => (image.to_rgb8().into_raw(), ExtendedColorType::Rgb8),
+            ColorDepth::Rgba32 => (image.to_rgba8().into_raw(), ExtendedColorType::Rgba8),
+            ColorDepth::Indexed8 => {
+                let (palette, indices) = quantize(image);
+                let rgb = indices.iter().flat_map(|&i| palette[i as usize * 3..i as usize * 3 + 3].to_vec()).collect();
+                (rgb, ExtendedColorType::Rgb8)
+            }
+        };
+        WebPEncoder::new_lossless(out)
+            .write_image(&pixels, image.width(), image.height(), color_type)
+            .map_err(|e| e.to_string())
+    }
+}
+
+// The format painting textures are written in, chosen per export.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Png,
+    WebpLossless,
+}
+
+impl OutputFormat {
+    pub fn encoder(self) -> &'static dyn ImageEncoder {
+        match self {
+            OutputFormat::Png => &PngEncoder,
+            OutputFormat::WebpLossless => &WebpLosslessEncoder,
+        }
+    }
+}
+
+// Errors if the targeted mod release can't load textures in `format`.
+pub fn check_supported(format: OutputFormat, rules: &ModRules) -> Result<(), String> {
+    if rules.image_formats.contains(&format) {
+        return Ok(());
+    }
+    Err(format!(
+        "Custom Paintings {} can't load {:?} textures. Export as {:?} or target a newer mod release.",
+        rules.mod_version,
+        format,
+        rules.image_formats.first().copied().unwrap_or_default()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::lint::{self, RULES};
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn test_webp_encoder_is_lossless() {
+        let mut source = RgbaImage::from_pixel(8, 4, Rgba([10, 200, 30, 255]));
+        source.put_pixel(3, 2, Rgba([255, 0, 128, 64]));
+        let path = std::env::temp_dir().join(format!("image_format_test_{}.webp", std::process::id()));
+
+        let encoder = OutputFormat::WebpLossless.encoder();
+        assert_eq!(encoder.extension(), "webp");
+        encoder.encode(&DynamicImage::ImageRgba8(source.clone()), ColorDepth::Rgba32, &path).unwrap();
+        assert_eq!(image::open(&path).unwrap().to_rgba8(), source);
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_color_depth_falls_back_to_alpha_only_when_needed() {
+        let opaque = DynamicImage::ImageRgba8(RgbaImage::from_pixel(8, 4, Rgba([10, 200, 30, 255])));
+        let mut translucent = opaque.to_rgba8();
+        translucent.put_pixel(0, 0, Rgba([0, 0, 0, 0]));
+        let translucent = DynamicImage::ImageRgba8(translucent);
+        assert_eq!(ColorDepth::Indexed8.resolve(&opaque), ColorDepth::Indexed8);
+        assert_eq!(ColorDepth::Rgb24.resolve(&translucent), ColorDepth::Rgba32);
+
+        let path = std::env::temp_dir().join(format!("image_format_depth_test_{}.png", std::process::id()));
+        for (depth, expected) in [
+            (ColorDepth::Rgb24, png::ColorType::Rgb),
+            (ColorDepth::Rgba32, png::ColorType::Rgba),
+            (ColorDepth::Indexed8, png::ColorType::Indexed),
+        ] {
+            PngEncoder.encode(&opaque, depth, &path).unwrap();
+            let reader = png::Decoder::new(std::io::BufReader::new(File::open(&path).unwrap())).read_info().unwrap();
+            assert_eq!(reader.info().color_type, expected);
+            assert_eq!(image::open(&path).unwrap().to_rgb8(), opaque.to_rgb8());
+        }
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_check_supported_follows_the_mod_rules() {
+        assert!(check_supported(OutputFormat::Png, &RULES[0]).is_ok());
+        assert!(check_supported(OutputFormat::WebpLossless, lint::rules_for(lint::latest_version())).is_err());
+
+        let rules = ModRules { image_formats: &[OutputFormat::Png, OutputFormat::WebpLossless], ..RULES[RULES.len() - 1].clone() };
+        assert!(check_supported(OutputFormat::WebpLossless, &rules).is_ok());
+    }
+}
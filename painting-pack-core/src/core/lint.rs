@@ -0,0 +1,247 @@
+use serde::Serialize;
+use crate::core::{cropper, exporter::{self, ExportItem}, image_format::OutputFormat};
+use crate::models::pack_list::DEFAULT_SCHEMA_URL;
+
+/*
+Limits of one release of the Custom Paintings mod. A pack that breaks them either fails to load
+or loads with paintings missing, so they are checked before anything is written and the user is
+warned about each one.
+*/
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ModRules {
+    // First mod release these limits apply to.
+    pub mod_version: &'static str,
+    // Paintings per pack, counting every block size separately.
+    pub max_paintings: usize,
+    // Longest side of a painting texture, in pixels.
+    pub max_texture_size: u32,
+    // Pack IDs the mod or the game already use as a namespace.
+    pub reserved_ids: &'static [&'static str],
+    // Whether paintings can carry a description (lore) line.
+    pub painting_descriptions: bool,
+    // Whether paintings can set their own frame tint or frame texture.
+    pub painting_frames: bool,
+    // Whether paintings can carry a weight that makes them turn up more often when placed at random.
+    pub painting_weights: bool,
+    // Whether animated (flipbook) painting textures play, with their `.mcmeta` timing.
+    pub animated_paintings: bool,
+    // Texture formats the mod can load, preferred first.
+    pub image_formats: &'static [OutputFormat],
+    // Written as `$schema` in custompaintings.json unless the pack overrides it.
+    pub schema_url: &'static str,
+}
+
+/*
+Known limits, oldest release first. When a mod release changes a limit, append a new entry
+rather than editing an old one so packs targeting older releases are still checked correctly.
+None of the numbers or flags below has been checked against the mod's source or changelog yet,
+so lint issues are only warnings and never stop an export. Cite the release notes or source file
+an entry comes from once it is verified.
+*/
+pub const RULES: &[ModRules] = &[
+    // Custom Paintings 1.0.0. Unverified: no source cited yet.
+    ModRules {
+        mod_version: "1.0.0",
+        max_paintings: 256,
+        max_texture_size: 2048,
+        reserved_ids: &["minecraft", "custompaintings"],
+        painting_descriptions: false,
+        painting_frames: false,
+        painting_weights: false,
+        animated_paintings: false,
+        image_formats: &[OutputFormat::Png],
+        schema_url: DEFAULT_SCHEMA_URL,
+    },
+    // Custom Paintings 2.0.0. Unverified: no source cited yet.
+    ModRules {
+        mod_version: "2.0.0",
+        max_paintings: 1024,
+        max_texture_size: 4096,
+        reserved_ids: &["minecraft", "custompaintings", "realms"],
+        painting_descriptions: true,
+        painting_frames: true,
+        painting_weights: true,
+        animated_paintings: true,
+        image_formats: &[OutputFormat::Png],
+        schema_url: DEFAULT_SCHEMA_URL,
+    },
+];
+
+// The newest release the table knows about; used when no target version is set.
+pub fn latest_version() -> &'static str {
+    RULES[RULES.len() - 1].mod_version
+}
+
+// "2.1" -> [2, 1, 0]. Anything unparseable counts as 0 so odd input still picks a rule set.
+fn parse_version(version: &str) -> [u32; 3] {
+    let mut parts = [0; 3];
+    for (part, value) in parts.iter_mut().zip(version.trim().trim_start_matches('v').split('.')) {
+        *part = value.parse().unwrap_or(0);
+    }
+    parts
+}
+
+/*
+Picks the rules for a mod version: the newest entry released at or before it. Versions older
+than the whole table use the oldest entry.
+*/
+pub fn rules_for(mod_version: &str) -> &'static ModRules {
+    let target = parse_version(mod_version);
+    RULES
+        .iter()
+        .rev()
+        .find(|rules| parse_version(rules.mod_version) <= target)
+        .unwrap_or(&RULES[0])
+}
+
+// One broken limit, with an explanation the user can act on.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LintIssue {
+    pub rule: &'static str,
+    pub message: String,
+}
+
+impl std::fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.rule, self.message)
+    }
+}
+
+// Paintings a pack writes, counting every block size separately, as the mod does.
+pub fn painting_count(items: &[ExportItem]) -> usize {
+    items.iter().map(|item| item.data.get_sizes().len()).sum()
+}
+
+/*
+The issue raised when a pack goes over the curator's own painting cap (see
+`ExportSettings::painting_cap`), with how many packs of at most `cap` paintings it would split into.
+*/
+pub fn painting_cap_issue(items: &[ExportItem], cap: Option<usize>) -> Option<LintIssue> {
+    let cap = cap?;
+    let count = painting_count(items);
+    (count > cap).then(|| LintIssue {
+        rule: "painting_cap",
+        message: format!(
+            "The pack has {} paintings but is capped at {}. Deselect some sizes or split it into {} packs of at most {} paintings.",
+            count, cap, count.div_ceil(cap.max(1)), cap
+        ),
+    })
+}
+
+/*
+Checks a pack against the given rules, predicting texture sizes under the pack's
+`max_texture_width` cap. Source images are only probed for their dimensions, so this is cheap
+enough to run before every export.
+*/
+pub fn lint(pack_id: &str, items: &[ExportItem], rules: &ModRules, max_texture_width: Option<u32>) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+
+    let count = painting_count(items);
+    if count > rules.max_paintings {
+        issues.push(LintIssue {
+            rule: "max_paintings",
+            message: format!(
+                "The pack has {} paintings but Custom Paintings {} loads at most {} per pack. Deselect some sizes or split the pack.",
+                count, rules.mod_version, rules.max_paintings
+            ),
+        });
+    }
+
+    let sanitized_pack_id = pack_id.trim().to_lowercase();
+    if rules.reserved_ids.contains(&sanitized_pack_id.as_str()) {
+        issues.push(LintIssue {
+            rule: "reserved_id",
+            message: format!("The pack ID \"{}\" is reserved and would clash with existing paintings. Choose another pack ID.", pack_id),
+        });
+    }
+
+    for item in items {
+        if item.data.animation.is_some() && !rules.animated_paintings {
+            issues.push(LintIssue {
+                rule: "animated_paintings",
+                message: format!(
+                    "\"{}\" is animated, but Custom Paintings {} can't play animated paintings. Target a newer mod release.",
+                    item.data.name.as_deref().unwrap_or(&item.source_path),
+                    rules.mod_version
+                ),
+            });
+        }
+        let Ok(source_dimensions) = image::image_dimensions(&item.source_path) else {
+            issues.push(LintIssue {
+                rule: "unreadable_source",
+                message: format!("{} could not be read.", item.source_path),
+            });
+            continue;
+        };
+        let options = item.data.crop_options();
+        let (crop_width, crop_height) = cropper::output_dimensions(options.frame_dimensions(source_dimensions), item.data.image_size.get_size()[0], &options);
+        // A flipbook's frames are stacked into one texture.
+        let (width, height) = exporter::texture_dimensions(crop_width, crop_height * options.frames.max(1), max_texture_width);
+        if width.max(height) > rules.max_texture_size {
+            issues.push(LintIssue {
+                rule: "max_texture_size",
+                message: format!(
+                    "\"{}\" ({:?}) would be exported at {}x{}, above the {}px limit of Custom Paintings {}.",
+                    item.data.name.as_deref().unwrap_or(&item.source_path),
+                    item.data.image_size,
+                    width,
+                    height,
+                    rules.max_texture_size,
+                    rules.mod_version
+                ),
+            });
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{export_settings::DEFAULT_MAX_TEXTURE_WIDTH, image_data::ImageData, image_size::ImageSize};
+    use image::RgbaImage;
+
+    #[test]
+    fn test_rules_for_picks_newest_applicable_entry() {
+        assert_eq!(rules_for("1.0.0").mod_version, "1.0.0");
+        assert_eq!(rules_for("1.9.3").mod_version, "1.0.0");
+        assert_eq!(rules_for("v2.1").mod_version, "2.0.0");
+        assert_eq!(rules_for("0.5").mod_version, "1.0.0");
+        assert_eq!(rules_for(latest_version()), &RULES[RULES.len() - 1]);
+    }
+
+    #[test]
+    fn test_lint_reports_each_broken_limit() {
+        let path = std::env::temp_dir().join("lint_test_tall_source.png");
+        RgbaImage::new(1200, 3000).save(&path).unwrap();
+        let item = |size| ExportItem {
+            source_path: path.to_string_lossy().to_string(),
+            data: ImageData::new(size),
+        };
+
+        let rules = ModRules { mod_version: "test", max_paintings: 3, max_texture_size: 2000, reserved_ids: &["minecraft"], painting_descriptions: true, painting_frames: false, painting_weights: false, animated_paintings: false, image_formats: &[OutputFormat::Png], schema_url: DEFAULT_SCHEMA_URL };
+        // Square gives 4 paintings (over the limit); Tall is cropped to 1200x2400 and scaled to 1024x2048.
+        let issues = lint("Minecraft", &[item(ImageSize::Square), item(ImageSize::Tall)], &rules, Some(DEFAULT_MAX_TEXTURE_WIDTH));
+        let rules_hit: Vec<&str> = issues.iter().map(|issue| issue.rule).collect();
+        assert_eq!(rules_hit, vec!["max_paintings", "reserved_id", "max_texture_size"]);
+
+        assert!(lint("my_pack", &[item(ImageSize::Wide)], &rules, Some(DEFAULT_MAX_TEXTURE_WIDTH)).is_empty());
+        // A lower texture cap brings the Tall crop down to 512x1024, within the mod's limit.
+        assert!(lint("my_pack", &[item(ImageSize::Tall)], &rules, Some(512)).is_empty());
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_painting_cap_suggests_a_split() {
+        let items: Vec<ExportItem> = (0..6)
+            .map(|_| ExportItem { source_path: String::new(), data: ImageData::new(ImageSize::Square) })
+            .collect();
+        // 6 squares in 4 sizes each are 24 paintings.
+        let issue = painting_cap_issue(&items, Some(20)).unwrap();
+        assert_eq!(issue.rule, "painting_cap");
+        assert!(issue.message.contains("split it into 2 packs"));
+        assert!(painting_cap_issue(&items, Some(24)).is_none());
+        assert!(painting_cap_issue(&items, None).is_none());
+    }
+}
@@ -0,0 +1,110 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use image::{DynamicImage, Limits};
+use crate::core::resample;
+
+/*
+Longest side a source is kept at once decoded in low-memory mode. A 4096 px source is 64 MB as
+RGBA, against several hundred MB for a full-size camera or scanner image, and still leaves room for
+256 px per block on a 16-block-wide crop.
+*/
+pub const MAX_DECODE_DIMENSION: u32 = 4096;
+
+/*
+Largest source low-memory mode decodes at all, by side and by decoded size. A source past either
+is refused rather than decoded whole, since the decode itself is what runs out of memory; 256 MB
+still holds an 8000x8000 RGBA image.
+*/
+pub const MAX_SOURCE_DIMENSION: u32 = 4 * MAX_DECODE_DIMENSION;
+pub const MAX_DECODE_BYTES: u64 = 256 * 1024 * 1024;
+
+// Off unless the user turns it on in the app settings; applies to the whole process.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+// Held while a preview is made in low-memory mode, so only one source is decoded for previews at a time.
+static PREVIEW_GATE: Mutex<()> = Mutex::new(());
+
+/*
+Turns low-memory mode on or off for everything that decodes from now on: sources are scaled down
+to `MAX_DECODE_DIMENSION` as soon as they are decoded, sources past `MAX_SOURCE_DIMENSION` or
+`MAX_DECODE_BYTES` aren't decoded at all, exports run on a single thread and previews are made
+one at a time.
+*/
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+// The longest side decoded sources are capped to, if any.
+pub fn decode_limit() -> Option<u32> {
+    enabled().then_some(MAX_DECODE_DIMENSION)
+}
+
+// The factor a source `full_dims` big is scaled by when decoded under `limit` (see `decode_limit`).
+pub fn decode_scale(full_dims: (u32, u32), limit: Option<u32>) -> f64 {
+    let longest = full_dims.0.max(full_dims.1);
+    match limit {
+        Some(limit) if longest > limit => limit as f64 / longest as f64,
+        _ => 1.0,
+    }
+}
+
+// Decoder limits for a source in low-memory mode; `None` when the mode is off.
+pub fn decode_limits() -> Option<Limits> {
+    enabled().then(|| {
+        let mut limits = Limits::default();
+        limits.max_image_width = Some(MAX_SOURCE_DIMENSION);
+        limits.max_image_height = Some(MAX_SOURCE_DIMENSION);
+        limits.max_alloc = Some(MAX_DECODE_BYTES);
+        limits
+    })
+}
+
+// `image` scaled down to fit within `limit` on its longest side, if it doesn't already.
+pub fn cap_to(image: DynamicImage, limit: u32) -> DynamicImage {
+    if image.width() <= limit && image.height() <= limit {
+        return image;
+    }
+    resample::fit_within(&image, limit, limit)
+}
+
+// A freshly decoded source capped to the decode limit when low-memory mode is on.
+pub fn cap_decoded(image: DynamicImage) -> DynamicImage {
+    match decode_limit() {
+        Some(limit) => cap_to(image, limit),
+        None => image,
+    }
+}
+
+/*
+Waits for the previews being made elsewhere to finish when low-memory mode is on; keep the guard
+while decoding. Outside low-memory mode previews don't wait on each other and this returns `None`.
+*/
+pub fn preview_slot() -> Option<MutexGuard<'static, ()>> {
+    // A preview that panicked leaves nothing half-done behind the gate.
+    enabled().then(|| PREVIEW_GATE.lock().unwrap_or_else(|poisoned| poisoned.into_inner()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_cap_scales_only_oversized_images_keeping_their_shape() {
+        let small = DynamicImage::ImageRgba8(RgbaImage::new(64, 32));
+        assert_eq!(cap_to(small.clone(), 64), small);
+
+        let capped = cap_to(DynamicImage::ImageRgba8(RgbaImage::new(400, 100)), 100);
+        assert_eq!((capped.width(), capped.height()), (100, 25));
+        let capped = cap_to(DynamicImage::ImageRgba8(RgbaImage::new(60, 300)), 100);
+        assert_eq!((capped.width(), capped.height()), (20, 100));
+
+        assert_eq!(decode_scale((8192, 64), Some(4096)), 0.5);
+        assert_eq!(decode_scale((4096, 64), Some(4096)), 1.0);
+        assert_eq!(decode_scale((8192, 64), None), 1.0);
+    }
+}
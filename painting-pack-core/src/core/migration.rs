@@ -0,0 +1,155 @@
+use std::fs::{copy, read_to_string, write};
+use std::path::{Path, PathBuf};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+// Key every saved settings or project file records its format version under.
+pub const VERSION_KEY: &str = "format_version";
+
+// Extension of saved project files.
+pub const PROJECT_EXTENSION: &str = "ppmproj";
+
+// Upgrades a file's top-level object by exactly one version, in place.
+pub type Migration = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+/*
+One kind of saved file and how to bring it up to date. Files saved before versioning existed
+count as version 0, and `migrations[n]` upgrades version n to n + 1, so the current version is
+always `migrations.len()`. A format change adds a migration to the end; old ones never change.
+*/
+pub struct Schema {
+    pub name: &'static str,
+    pub migrations: &'static [Migration],
+}
+
+impl Schema {
+    pub fn current_version(&self) -> u32 {
+        self.migrations.len() as u32
+    }
+}
+
+// v0 -> v1: the version stamp itself; the fields are unchanged.
+fn add_version(_: &mut Map<String, Value>) -> Result<(), String> {
+    Ok(())
+}
+
+pub const SETTINGS_SCHEMA: Schema = Schema { name: "settings", migrations: &[add_version] };
+pub const PROJECT_SCHEMA: Schema = Schema { name: "project", migrations: &[add_version] };
+
+fn version_of(object: &Map<String, Value>) -> Result<u32, String> {
+    match object.get(VERSION_KEY) {
+        None => Ok(0),
+        Some(value) => value
+            .as_u64()
+            .map(|version| version as u32)
+            .ok_or_else(|| format!("{} is not a valid format version", value)),
+    }
+}
+
+/*
+Runs every migration the document still needs and stamps it with the current version. Returns
+the version it started at. Files from a newer version of the app are refused rather than
+silently losing whatever they added.
+*/
+pub fn upgrade(document: &mut Value, schema: &Schema) -> Result<u32, String> {
+    let object = document.as_object_mut().ok_or_else(|| format!("The {} file is not a JSON object", schema.name))?;
+    let from = version_of(object)?;
+    let current = schema.current_version();
+    if from > current {
+        return Err(format!(
+            "The {} file is format version {}, but this version of the app only reads up to {}; please update the app",
+            schema.name, from, current
+        ));
+    }
+    for migration in &schema.migrations[from as usize..] {
+        migration(object)?;
+    }
+    object.insert(VERSION_KEY.to_string(), Value::from(current));
+    Ok(from)
+}
+
+// `<file>.<label>.bak` next to the file, numbered if an earlier backup is already there.
+pub fn backup_path(path: &Path, label: &str) -> PathBuf {
+    let base = format!("{}.{}", path.display(), label);
+    let mut backup = PathBuf::from(format!("{}.bak", base));
+    let mut attempt = 2;
+    while backup.exists() {
+        backup = PathBuf::from(format!("{}_{}.bak", base, attempt));
+        attempt += 1;
+    }
+    backup
+}
+
+/*
+Reads a saved file, upgrading it to the current format first. An upgraded file is copied to a
+versioned backup and then rewritten in the new format, so the original can always be recovered.
+*/
+pub fn load<T: DeserializeOwned>(path: &Path, schema: &Schema) -> Result<T, String> {
+    let contents = read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut document: Value =
+        serde_json::from_str(&contents).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    let from = upgrade(&mut document, schema).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    if from < schema.current_version() {
+        let backup = backup_path(path, &format!("v{}", from));
+        copy(path, &backup).map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+        let upgraded = serde_json::to_string_pretty(&document).map_err(|e| format!("Failed to serialize {}: {}", schema.name, e))?;
+        write(path, upgraded).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        println!("[MIGRATION] upgraded {} from format {} to {}, original kept at {}", path.display(), from, schema.current_version(), backup.display());
+    }
+
+    serde_json::from_value(document).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+// Serializes `value` as pretty JSON stamped with the schema's current version.
+pub fn to_versioned_json<T: Serialize>(value: &T, schema: &Schema) -> Result<String, String> {
+    let mut document = serde_json::to_value(value).map_err(|e| format!("Failed to serialize {}: {}", schema.name, e))?;
+    let object = document.as_object_mut().ok_or_else(|| format!("The {} file must be a JSON object", schema.name))?;
+    object.insert(VERSION_KEY.to_string(), Value::from(schema.current_version()));
+    serde_json::to_string_pretty(&document).map_err(|e| format!("Failed to serialize {}: {}", schema.name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rename_title(object: &mut Map<String, Value>) -> Result<(), String> {
+        if let Some(title) = object.remove("title") {
+            object.insert(String::from("name"), title);
+        }
+        Ok(())
+    }
+
+    const TEST_SCHEMA: Schema = Schema { name: "test", migrations: &[add_version, rename_title] };
+
+    #[test]
+    fn test_upgrade_runs_pending_migrations() {
+        let mut document = json!({ "title": "Old" });
+        assert_eq!(upgrade(&mut document, &TEST_SCHEMA).unwrap(), 0);
+        assert_eq!(document, json!({ "name": "Old", "format_version": 2 }));
+
+        // Already current: nothing to do
+        assert_eq!(upgrade(&mut document, &TEST_SCHEMA).unwrap(), 2);
+
+        let mut newer = json!({ "format_version": 3 });
+        assert!(upgrade(&mut newer, &TEST_SCHEMA).unwrap_err().contains("update the app"));
+    }
+
+    #[test]
+    fn test_load_backs_up_and_rewrites_old_files() {
+        let dir = std::env::temp_dir().join(format!("migration_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("project.paintingpack");
+        write(&path, r#"{ "title": "Old" }"#).unwrap();
+
+        let loaded: Value = load(&path, &TEST_SCHEMA).unwrap();
+        assert_eq!(loaded["name"], "Old");
+        assert_eq!(read_to_string(dir.join("project.paintingpack.v0.bak")).unwrap(), r#"{ "title": "Old" }"#);
+        let rewritten: Value = serde_json::from_str(&read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(rewritten["format_version"], 2);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
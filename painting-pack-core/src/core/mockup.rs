@@ -0,0 +1,86 @@
+use image::{imageops, DynamicImage, Rgba, RgbaImage};
+use crate::core::{palette::{self, Framing}, resample};
+
+const MOCKUP_WIDTH: u32 = 512;
+const MOCKUP_HEIGHT: u32 = 384;
+// The framed painting takes up at most this share of the wall in each direction.
+const WALL_COVERAGE: f32 = 0.7;
+const WALL_COLOUR: Rgba<u8> = Rgba([214, 208, 198, 255]);
+const SHADOW_COLOUR: Rgba<u8> = Rgba([150, 144, 136, 255]);
+
+fn fill(canvas: &mut RgbaImage, x: i64, y: i64, width: u32, height: u32, colour: Rgba<u8>) {
+    imageops::overlay(canvas, &RgbaImage::from_pixel(width, height, colour), x, y);
+}
+
+/*
+Renders a painting hanging on a plain wall, surrounded by a matte and frame in colours derived
+from its dominant colour, so the user can judge how it will read in a room. A painting's own
+`frame_tint` replaces the derived frame colour. Returns the image together with the framing
+colours used.
+*/
+pub fn render_wall_mockup(painting: &DynamicImage, frame_tint: Option<palette::Colour>) -> (DynamicImage, Framing) {
+    let mut framing = palette::framing_for(palette::dominant_colour(painting));
+    if let Some(tint) = frame_tint {
+        framing.frame = tint;
+    }
+    let [fr, fg, fb] = framing.frame;
+    let [mr, mg, mb] = framing.matte;
+
+    // Matte and frame widths are a fixed share of the wall space, so every mockup looks alike.
+    let max_w = (MOCKUP_WIDTH as f32 * WALL_COVERAGE) as u32;
+    let max_h = (MOCKUP_HEIGHT as f32 * WALL_COVERAGE) as u32;
+    let border = |size: u32| (size as f32 * 0.06).round().max(2.0) as u32;
+    let (matte, frame) = (border(max_w.min(max_h)), border(max_w.min(max_h)) / 2 + 1);
+    let inset = 2 * (matte + frame);
+    let (box_w, box_h) = (max_w.saturating_sub(inset).max(1), max_h.saturating_sub(inset).max(1));
+    let scale = (box_w as f32 / painting.width() as f32).min(box_h as f32 / painting.height() as f32);
+    let width = ((painting.width() as f32 * scale).round() as u32).clamp(1, box_w);
+    let height = ((painting.height() as f32 * scale).round() as u32).clamp(1, box_h);
+    let art = if scale < 1.0 {
+        resample::resize_exact(painting, width, height)
+    } else {
+        // Small paintings are blown up with nearest neighbour so pixel art stays crisp.
+        painting.resize_exact(width, height, imageops::FilterType::Nearest)
+    };
+
+    let (outer_w, outer_h) = (art.width() + inset, art.height() + inset);
+    let x = (MOCKUP_WIDTH - outer_w) as i64 / 2;
+    let y = (MOCKUP_HEIGHT - outer_h) as i64 / 2;
+
+    let mut canvas = RgbaImage::from_pixel(MOCKUP_WIDTH, MOCKUP_HEIGHT, WALL_COLOUR);
+    fill(&mut canvas, x + 6, y + 8, outer_w, outer_h, SHADOW_COLOUR);
+    fill(&mut canvas, x, y, outer_w, outer_h, Rgba([fr, fg, fb, 255]));
+    let (matte_x, matte_y) = (x + frame as i64, y + frame as i64);
+    fill(&mut canvas, matte_x, matte_y, outer_w - 2 * frame, outer_h - 2 * frame, Rgba([mr, mg, mb, 255]));
+    imageops::overlay(&mut canvas, &art.to_rgba8(), matte_x + matte as i64, matte_y + matte as i64);
+
+    (DynamicImage::ImageRgba8(canvas), framing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mockup_frames_painting_in_derived_colours() {
+        let painting = DynamicImage::ImageRgba8(RgbaImage::from_pixel(64, 32, Rgba([40, 60, 180, 255])));
+        let (mockup, framing) = render_wall_mockup(&painting, None);
+        let mockup = mockup.to_rgba8();
+        assert_eq!(mockup.dimensions(), (MOCKUP_WIDTH, MOCKUP_HEIGHT));
+
+        // Wall in the corner, painting in the middle, and the frame and matte around it.
+        assert_eq!(*mockup.get_pixel(0, 0), WALL_COLOUR);
+        assert_eq!(mockup.get_pixel(MOCKUP_WIDTH / 2, MOCKUP_HEIGHT / 2).0, [40, 60, 180, 255]);
+        let row: Vec<[u8; 3]> = (0..MOCKUP_WIDTH).map(|x| {
+            let [r, g, b, _] = mockup.get_pixel(x, MOCKUP_HEIGHT / 2).0;
+            [r, g, b]
+        }).collect();
+        assert!(row.contains(&framing.frame));
+        assert!(row.contains(&framing.matte));
+
+        // A custom frame tint is used as it is.
+        let (mockup, framing) = render_wall_mockup(&painting, Some([139, 90, 43]));
+        assert_eq!(framing.frame, [139, 90, 43]);
+        assert!((0..MOCKUP_WIDTH).any(|x| mockup.to_rgba8().get_pixel(x, MOCKUP_HEIGHT / 2).0 == [139, 90, 43, 255]));
+    }
+}
@@ -0,0 +1,66 @@
+pub mod cropper;
+pub mod exporter;
+pub mod timestamp;
+pub mod naming;
+pub mod validator;
+pub mod suggest;
+pub mod palette;
+pub mod collage;
+pub mod font;
+pub mod text_painting;
+pub mod procedural;
+pub mod balance;
+pub mod lint;
+pub mod export_queue;
+pub mod pack_reader;
+pub mod pack_diff;
+pub mod history;
+pub mod deskew;
+pub mod working_copy;
+pub mod resample;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod mockup;
+pub mod templates;
+pub mod settings;
+pub mod hot_folder;
+pub mod splitter;
+pub mod pack_json;
+pub mod migration;
+pub mod preview_key;
+pub mod export_presets;
+pub mod confirmation;
+pub mod guides;
+pub mod vanilla;
+pub mod pack_registry;
+pub mod image_format;
+pub mod session_temp;
+pub mod capabilities;
+pub mod folder_naming;
+pub mod export_resume;
+pub mod search;
+pub mod crop_report;
+pub mod icon_badge;
+pub mod preview_cache;
+pub mod flipbook;
+pub mod preview_strip;
+pub mod pack_split;
+pub mod relink;
+pub mod pack_import;
+pub mod pack_inspect;
+pub mod project;
+pub mod path_scope;
+pub mod icon_generator;
+pub mod first_run;
+pub mod sprite_sheet;
+pub mod artists;
+pub mod decode_cache;
+pub mod adjustments;
+pub mod export_history;
+pub mod project_recovery;
+pub mod pack_budget;
+pub mod low_memory;
+pub mod natural_sort;
+pub mod contact_sheet;
+pub mod asset_check;
+pub mod session_lock;
\ No newline at end of file
@@ -0,0 +1,310 @@
+use std::collections::HashSet;
+use crate::models::export_settings::NameCase;
+
+/*
+Returns true for characters Minecraft's font can't draw: emoji and pictograph blocks,
+the joiners/selectors used to build emoji sequences, private-use glyphs and control characters.
+*/
+fn is_unsupported_glyph(c: char) -> bool {
+    let code = c as u32;
+    c.is_control()
+        || (0x1F000..=0x1FAFF).contains(&code) // Mahjong, cards, emoji, pictographs, symbols
+        || (0x2600..=0x27BF).contains(&code)   // Misc symbols and dingbats
+        || (0xE000..=0xF8FF).contains(&code)   // Private use area
+        || (0xFE00..=0xFE0F).contains(&code)   // Variation selectors
+        || (0xE0000..=0xE007F).contains(&code) // Tag characters (flag sequences)
+        || code == 0x200D                      // Zero width joiner
+        || code == 0x20E3                      // Combining keycap
+}
+
+// Uppercases the first character of a word and lowercases the rest.
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(|c| c.to_lowercase())).collect(),
+        None => String::new(),
+    }
+}
+
+/*
+Applies the export-time name normalization to a painting's display name.
+Stripping happens first so removed emojis don't leave doubled spaces behind.
+*/
+pub fn normalize_name(name: &str, case: NameCase, strip_unsupported: bool) -> String {
+    let stripped: String = if strip_unsupported {
+        name.chars().filter(|c| !is_unsupported_glyph(*c)).collect()
+    } else {
+        name.to_string()
+    };
+
+    match case {
+        NameCase::Unchanged if !strip_unsupported => stripped,
+        NameCase::Unchanged => stripped.split_whitespace().collect::<Vec<_>>().join(" "),
+        NameCase::TitleCase => stripped
+            .split_whitespace()
+            .map(capitalize)
+            .collect::<Vec<_>>()
+            .join(" "),
+        NameCase::SentenceCase => {
+            capitalize(&stripped.split_whitespace().collect::<Vec<_>>().join(" "))
+        }
+    }
+}
+
+/*
+Replaces path separators, characters reserved on Windows and control characters with `_`,
+leaving everything else (including spaces) untouched.
+*/
+pub fn strip_unsafe_filename_chars(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/*
+Makes a string safe to use as a file name on every platform. Spaces also become underscores,
+matching the exporter's historical behaviour.
+*/
+pub fn sanitize_filename(name: &str) -> String {
+    strip_unsafe_filename_chars(name).replace(' ', "_")
+}
+
+/*
+Hands out file names that are unique within one export. Comparison ignores case because
+Windows and macOS file systems do, so `Cat` and `cat` would otherwise overwrite each other.
+The first claim of a name gets it unchanged; later claims get `_2`, `_3`, ...
+*/
+#[derive(Default)]
+pub struct UniqueNames {
+    used: HashSet<String>,
+}
+
+impl UniqueNames {
+    pub fn claim(&mut self, name: &str) -> String {
+        if self.used.insert(name.to_lowercase()) {
+            return name.to_string();
+        }
+
+        let mut suffix = 2;
+        loop {
+            let candidate = format!("{}_{}", name, suffix);
+            if self.used.insert(candidate.to_lowercase()) {
+                return candidate;
+            }
+            suffix += 1;
+        }
+    }
+}
+
+// The values available to a filename template for one exported image.
+pub struct FilenameTokens<'a> {
+    pub pack_id: &'a str,
+    // 1-based position of the painting in the library (or in the export if untracked).
+    pub index: usize,
+    pub id: &'a str,
+    pub filename: &'a str,
+    pub name: &'a str,
+    pub width: u32,
+    pub height: u32,
+}
+
+const TEMPLATE_TOKENS: [&str; 7] = ["pack_id", "index", "id", "filename", "name", "w", "h"];
+
+enum TemplatePart {
+    Literal(String),
+    Token { name: String, spec: String },
+}
+
+// Splits a template into literal text and `{token:spec}` parts, rejecting unknown tokens.
+fn parse_template(template: &str) -> Result<Vec<TemplatePart>, String> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        let close = rest[open..]
+            .find('}')
+            .map(|i| open + i)
+            .ok_or_else(|| format!("Unclosed '{{' in filename template \"{}\"", template))?;
+
+        let inner = &rest[open + 1..close];
+        let (token, spec) = match inner.split_once(':') {
+            Some((token, spec)) => (token, spec),
+            None => (inner, ""),
+        };
+        if !TEMPLATE_TOKENS.contains(&token) {
+            return Err(format!(
+                "Unknown token {{{}}} in filename template, expected one of: {}",
+                token,
+                TEMPLATE_TOKENS.map(|t| format!("{{{}}}", t)).join(", ")
+            ));
+        }
+        if !spec.is_empty() && (token != "index" || spec.parse::<usize>().is_err()) {
+            return Err(format!("Invalid format \"{}\" for token {{{}}}, only {{index:03}} style padding is supported", spec, token));
+        }
+
+        parts.push(TemplatePart::Literal(rest[..open].to_string()));
+        parts.push(TemplatePart::Token { name: token.to_string(), spec: spec.to_string() });
+        rest = &rest[close + 1..];
+    }
+    parts.push(TemplatePart::Literal(rest.to_string()));
+
+    Ok(parts)
+}
+
+/*
+Checks a filename template before it is stored. Every size of a painting is written from the same
+template, so it must contain both {w} and {h} or the sizes would overwrite each other.
+*/
+pub fn validate_filename_template(template: &str) -> Result<(), String> {
+    let parts = parse_template(template)?;
+    let has_token = |wanted: &str| parts.iter().any(|part| matches!(part, TemplatePart::Token { name, .. } if name == wanted));
+    if !has_token("w") || !has_token("h") {
+        return Err(String::from("The filename template must contain both {w} and {h}"));
+    }
+    Ok(())
+}
+
+/*
+Renders an export filename (without extension) from a template such as
+`{pack_id}_{index:03}_{id}_{w}x{h}`. A trailing `.png` in the template is ignored
+since the exporter always adds the extension itself.
+*/
+pub fn render_filename(template: &str, tokens: &FilenameTokens) -> Result<String, String> {
+    let template = template.strip_suffix(".png").unwrap_or(template);
+    let mut rendered = String::new();
+
+    for part in parse_template(template)? {
+        match part {
+            TemplatePart::Literal(literal) => rendered.push_str(&literal),
+            TemplatePart::Token { name, spec } => {
+                let value = match name.as_str() {
+                    "pack_id" => tokens.pack_id.to_string(),
+                    "index" => match spec.parse::<usize>() {
+                        Ok(width) => format!("{:0width$}", tokens.index, width = width),
+                        Err(_) => tokens.index.to_string(),
+                    },
+                    "id" => tokens.id.to_string(),
+                    "filename" => tokens.filename.to_string(),
+                    "name" => tokens.name.to_string(),
+                    "w" => tokens.width.to_string(),
+                    _ => tokens.height.to_string(),
+                };
+                rendered.push_str(&value);
+            }
+        }
+    }
+
+    Ok(sanitize_filename(&rendered))
+}
+
+/*
+Fills the `{pack_id}`, `{version}` and `{date}` tokens of an export folder, so repeated exports
+land in versioned folders like `exports/{pack_id}/{version}`. Values are made safe as folder
+names; any other braces are left alone since they may be part of a real folder name.
+*/
+pub fn render_export_path(path: &str, pack_id: &str, version: &str, date: &str) -> String {
+    path.replace("{pack_id}", &sanitize_filename(pack_id))
+        .replace("{version}", &sanitize_filename(version))
+        .replace("{date}", &sanitize_filename(date))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_tokens() -> FilenameTokens<'static> {
+        FilenameTokens {
+            pack_id: "forest_pack",
+            index: 7,
+            id: "old_oak",
+            filename: "Old Oak",
+            name: "The Old Oak",
+            width: 2,
+            height: 1,
+        }
+    }
+
+    #[test]
+    fn test_unchanged_keeps_name_verbatim() {
+        assert_eq!(normalize_name("  the Starry NIGHT ", NameCase::Unchanged, false), "  the Starry NIGHT ");
+    }
+
+    #[test]
+    fn test_title_case() {
+        assert_eq!(normalize_name("the starry   NIGHT", NameCase::TitleCase, false), "The Starry Night");
+    }
+
+    #[test]
+    fn test_sentence_case() {
+        assert_eq!(normalize_name("THE STARRY night", NameCase::SentenceCase, false), "The starry night");
+    }
+
+    #[test]
+    fn test_strip_unsupported_glyphs() {
+        assert_eq!(normalize_name("Sunset 🌅 Over ❤️ Hills", NameCase::Unchanged, true), "Sunset Over Hills");
+        // Accented letters and CJK are rendered by the game and must survive
+        assert_eq!(normalize_name("Café 夜", NameCase::Unchanged, true), "Café 夜");
+    }
+
+    #[test]
+    fn test_strip_and_title_case_combined() {
+        assert_eq!(normalize_name("🐱 cat nap", NameCase::TitleCase, true), "Cat Nap");
+    }
+
+    #[test]
+    fn test_render_default_template_matches_legacy_names() {
+        let rendered = render_filename("{filename}_{w}x{h}", &sample_tokens()).unwrap();
+        assert_eq!(rendered, "Old_Oak_2x1");
+    }
+
+    #[test]
+    fn test_render_template_with_padding_and_extension() {
+        let rendered = render_filename("{pack_id}_{index:03}_{id}_{w}x{h}.png", &sample_tokens()).unwrap();
+        assert_eq!(rendered, "forest_pack_007_old_oak_2x1");
+    }
+
+    #[test]
+    fn test_render_sanitizes_unsafe_characters() {
+        let mut tokens = sample_tokens();
+        tokens.name = "a/b: c?";
+        assert_eq!(render_filename("{name}_{w}x{h}", &tokens).unwrap(), "a_b__c__2x1");
+    }
+
+    #[test]
+    fn test_validate_template_errors() {
+        assert!(validate_filename_template("{filename}_{w}x{h}").is_ok());
+        assert!(validate_filename_template("{filename}").is_err()); // sizes would collide
+        assert!(validate_filename_template("{nope}_{w}x{h}").is_err());
+        assert!(validate_filename_template("{id}_{w}x{h").is_err());
+        assert!(validate_filename_template("{id:03}_{w}x{h}").is_err());
+    }
+
+    #[test]
+    fn test_strip_unsafe_keeps_spaces() {
+        assert_eq!(strip_unsafe_filename_chars("My Photo: v2?"), "My Photo_ v2_");
+    }
+
+    #[test]
+    fn test_unique_names_suffixes_collisions() {
+        let mut names = UniqueNames::default();
+        assert_eq!(names.claim("sunset"), "sunset");
+        assert_eq!(names.claim("Sunset"), "Sunset_2"); // case-insensitive collision
+        assert_eq!(names.claim("sunset"), "sunset_3");
+        assert_eq!(names.claim("sunset_2"), "sunset_2_2");
+    }
+
+    #[test]
+    fn test_render_export_path_fills_tokens() {
+        assert_eq!(
+            render_export_path("exports/{pack_id}/{version}", "forest_pack", "1.2.0", "2026-10-16"),
+            "exports/forest_pack/1.2.0"
+        );
+        // Values can't add folder levels; unknown braces are kept.
+        assert_eq!(render_export_path("out/{date} {misc}", "x", "1/2", "2026-10-16"), "out/2026-10-16 {misc}");
+        assert_eq!(render_export_path("out/v{version}", "x", "1/2", "d"), "out/v1_2");
+    }
+}
@@ -0,0 +1,66 @@
+use std::cmp::Ordering;
+use std::path::PathBuf;
+
+// The run of ASCII digits at the start of `s`.
+fn leading_digits(s: &str) -> &str {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    &s[..end]
+}
+
+// Numbers of any length by value: fewer significant digits is smaller, then digit by digit.
+fn compare_numbers(a: &str, b: &str) -> Ordering {
+    let (a, b) = (a.trim_start_matches('0'), b.trim_start_matches('0'));
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/*
+Orders names the way people count: runs of digits compare by value, so "file2" comes before
+"file10", and letters ignore case. Names that only differ in leading zeros or case are then
+ordered by their exact text, so the result never depends on the order the names came in.
+*/
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a_rest, mut b_rest) = (a, b);
+    while let (Some(a_char), Some(b_char)) = (a_rest.chars().next(), b_rest.chars().next()) {
+        let ordering = if a_char.is_ascii_digit() && b_char.is_ascii_digit() {
+            let (a_number, b_number) = (leading_digits(a_rest), leading_digits(b_rest));
+            a_rest = &a_rest[a_number.len()..];
+            b_rest = &b_rest[b_number.len()..];
+            compare_numbers(a_number, b_number)
+        } else {
+            a_rest = &a_rest[a_char.len_utf8()..];
+            b_rest = &b_rest[b_char.len_utf8()..];
+            a_char.to_lowercase().cmp(b_char.to_lowercase())
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a_rest.len().cmp(&b_rest.len()).then_with(|| a.cmp(b))
+}
+
+// Sorts paths naturally by their full text, so a folder's files stay together in counting order.
+pub fn sort_paths(paths: &mut [PathBuf]) {
+    paths.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numbers_sort_by_value_and_ties_are_deterministic() {
+        let mut names = vec!["file10.png", "File2.png", "file1.png", "file02.png", "file.png", "file2b.png"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(names, ["file.png", "file1.png", "File2.png", "file02.png", "file2b.png", "file10.png"]);
+
+        // The same names in any order come out the same.
+        let mut reversed: Vec<&str> = names.iter().rev().copied().collect();
+        reversed.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(reversed, names);
+
+        let mut paths = vec![PathBuf::from("art/img12.jpg"), PathBuf::from("art/img9.jpg")];
+        sort_paths(&mut paths);
+        assert_eq!(paths[0], PathBuf::from("art/img9.jpg"));
+        assert_eq!(natural_cmp("99999999999999999999999", "100000000000000000000000"), Ordering::Less);
+    }
+}
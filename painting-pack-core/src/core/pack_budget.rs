@@ -0,0 +1,161 @@
+use std::collections::HashSet;
+use serde::Serialize;
+use crate::core::{cropper, exporter::{self, ExportItem}, lint::{self, LintIssue}};
+use crate::models::export_settings::ExportSettings;
+
+// Bytes in a megabyte, as budgets are given.
+pub const MEGABYTE: u64 = 1024 * 1024;
+
+/*
+How big a pack can get before it slows the game down. Every painting texture is held in memory
+uncompressed for as long as the pack is loaded, and more than about 20 distinct images or 200 MB
+is where clients start to noticeably stutter.
+*/
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct BudgetLimits {
+    pub max_images: usize,
+    pub max_bytes: u64,
+}
+
+pub const DEFAULT_LIMITS: BudgetLimits = BudgetLimits { max_images: 20, max_bytes: 200 * MEGABYTE };
+
+/*
+What a pack will cost once exported, worked out before anything is written, with a warning for
+each limit it goes over. Sizes are estimates: texture memory assumes 4 bytes per pixel, and the
+size on disk assumes each texture compresses as well as its source file does.
+*/
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PackBudget {
+    // Distinct source images.
+    pub image_count: usize,
+    // Paintings written, counting every block size separately.
+    pub painting_count: usize,
+    pub texture_memory_bytes: u64,
+    pub disk_bytes: u64,
+    pub warnings: Vec<LintIssue>,
+}
+
+// Width and height of every texture `item` exports under `settings`, or `None` if its source can't be read.
+fn texture_sizes(item: &ExportItem, settings: &ExportSettings) -> Option<Vec<(u32, u32)>> {
+    let source_dimensions = image::image_dimensions(&item.source_path).ok()?;
+    let options = item.data.crop_options();
+    let frames = options.frames.max(1);
+    let (crop_width, crop_height) = cropper::output_dimensions(options.frame_dimensions(source_dimensions), item.data.image_size.get_size()[0], &options);
+    let (width, height) = exporter::texture_dimensions(crop_width, crop_height * frames, settings.max_texture_width);
+
+    Some(item
+        .data
+        .get_sizes()
+        .iter()
+        .filter(|(blocks_wide, blocks_high)| {
+            // Variants the exporter leaves out cost nothing.
+            settings.force_undersized_variants
+                || settings
+                    .pixels_per_block
+                    .and_then(|ppb| exporter::undersized_reason(crop_width, crop_height, *blocks_wide, *blocks_high, ppb))
+                    .is_none()
+        })
+        .map(|(blocks_wide, blocks_high)| match settings.pixels_per_block {
+            Some(ppb) if width > blocks_wide * ppb => (blocks_wide * ppb, blocks_high * ppb * frames),
+            _ => (width, height),
+        })
+        .collect())
+}
+
+// Compressed bytes per pixel of the source file, used to guess how large its textures will be on disk.
+fn source_bytes_per_pixel(path: &str) -> f64 {
+    let file_size = std::fs::metadata(path).map_or(0, |metadata| metadata.len());
+    match image::image_dimensions(path) {
+        Ok((width, height)) if width > 0 && height > 0 => file_size as f64 / (width as f64 * height as f64),
+        _ => 0.0,
+    }
+}
+
+// Estimated texture memory and size on disk of everything `item` exports. Unreadable sources cost nothing; the lint reports them.
+fn item_cost(item: &ExportItem, settings: &ExportSettings) -> (u64, f64) {
+    let Some(sizes) = texture_sizes(item, settings) else {
+        return (0, 0.0);
+    };
+    let bytes_per_pixel = source_bytes_per_pixel(&item.source_path);
+    sizes.into_iter().fold((0, 0.0), |(memory, disk), (width, height)| {
+        let pixels = width as u64 * height as u64;
+        (memory + pixels * 4, disk + pixels as f64 * bytes_per_pixel)
+    })
+}
+
+// Estimated memory the textures of `items` take once loaded (see `PackBudget`).
+pub fn texture_memory_bytes(items: &[ExportItem], settings: &ExportSettings) -> u64 {
+    items.iter().map(|item| item_cost(item, settings).0).sum()
+}
+
+/*
+Estimates the image count, texture memory and size on disk of the main pack (a Lite variant is
+left out) and warns where they go over `limits`. Only image headers and file sizes are read, so
+this is cheap enough to run before every export.
+*/
+pub fn estimate(items: &[ExportItem], settings: &ExportSettings, limits: &BudgetLimits) -> PackBudget {
+    let image_count = items.iter().map(|item| item.source_path.as_str()).collect::<HashSet<&str>>().len();
+    let (texture_memory_bytes, disk_bytes) = items
+        .iter()
+        .map(|item| item_cost(item, settings))
+        .fold((0, 0.0), |(memory, disk), (item_memory, item_disk)| (memory + item_memory, disk + item_disk));
+    let disk_bytes = disk_bytes.round() as u64;
+
+    let mut warnings = Vec::new();
+    if image_count > limits.max_images {
+        warnings.push(LintIssue {
+            rule: "max_images",
+            message: format!(
+                "The pack uses {} distinct images; more than {} can slow the game down. Consider splitting it.",
+                image_count, limits.max_images
+            ),
+        });
+    }
+    if texture_memory_bytes > limits.max_bytes {
+        warnings.push(LintIssue {
+            rule: "texture_memory",
+            message: format!(
+                "The textures take about {} MB of memory once loaded, over the {} MB budget. Lower the pixels per block or the texture size, or deselect some sizes.",
+                texture_memory_bytes.div_ceil(MEGABYTE), limits.max_bytes / MEGABYTE
+            ),
+        });
+    }
+    if disk_bytes > limits.max_bytes {
+        warnings.push(LintIssue {
+            rule: "disk_size",
+            message: format!(
+                "The pack will be about {} MB on disk, over the {} MB budget. Lower the pixels per block or the texture size, or deselect some sizes.",
+                disk_bytes.div_ceil(MEGABYTE), limits.max_bytes / MEGABYTE
+            ),
+        });
+    }
+
+    PackBudget { image_count, painting_count: lint::painting_count(items), texture_memory_bytes, disk_bytes, warnings }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{image_data::ImageData, image_size::ImageSize};
+    use image::RgbaImage;
+
+    #[test]
+    fn test_budget_counts_every_texture_and_warns_over_the_limits() {
+        let path = std::env::temp_dir().join(format!("pack_budget_test_{}.png", std::process::id()));
+        RgbaImage::new(64, 64).save(&path).unwrap();
+        let item = ExportItem { source_path: path.to_string_lossy().to_string(), data: ImageData::new(ImageSize::Square) };
+        let settings = ExportSettings { pixels_per_block: Some(16), ..ExportSettings::default() };
+
+        // 1x1 to 4x4 blocks at 16 px per block: 16², 32², 48² and 64² pixels.
+        let budget = estimate(std::slice::from_ref(&item), &settings, &DEFAULT_LIMITS);
+        assert_eq!((budget.image_count, budget.painting_count), (1, 4));
+        assert_eq!(budget.texture_memory_bytes, (256 + 1024 + 2304 + 4096) * 4);
+        assert!(budget.disk_bytes > 0);
+        assert!(budget.warnings.is_empty());
+
+        let tight = BudgetLimits { max_images: 0, max_bytes: 1024 };
+        let rules: Vec<&str> = estimate(&[item.clone(), item], &settings, &tight).warnings.iter().map(|warning| warning.rule).collect();
+        assert_eq!(rules[..2], ["max_images", "texture_memory"]);
+        std::fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use std::fs::{copy, create_dir_all, read, write};
+use std::path::Path;
+use serde::Serialize;
+use crate::core::pack_reader;
+use crate::models::painting::Painting;
+
+// A painting present in both versions whose metadata or image differs.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PaintingChange {
+    pub id: String,
+    // Which parts differ: "name", "artist", "size", "filename" and/or "image".
+    pub fields: Vec<&'static str>,
+}
+
+/*
+Differences between two versions of a pack, keyed by painting ID.
+*/
+#[derive(Serialize, Debug, Clone, PartialEq, Default)]
+pub struct PackDiff {
+    pub old_version: String,
+    pub new_version: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<PaintingChange>,
+    pub unchanged: usize,
+}
+
+impl PackDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+// Compares two image files byte for byte; a missing file counts as different.
+fn same_image(old_dir: &Path, old: &Painting, new_dir: &Path, new: &Painting) -> bool {
+    match (read(old_dir.join("images").join(&old.filename)), read(new_dir.join("images").join(&new.filename))) {
+        (Ok(old_bytes), Ok(new_bytes)) => old_bytes == new_bytes,
+        _ => false,
+    }
+}
+
+/*
+Compares two exported pack folders. Paintings are matched by ID; metadata is compared field by
+field and images byte for byte, so re-exports of untouched paintings count as unchanged.
+*/
+pub fn diff_packs(old_dir: &Path, new_dir: &Path) -> Result<PackDiff, String> {
+    let old_pack = pack_reader::read_pack(old_dir)?;
+    let new_pack = pack_reader::read_pack(new_dir)?;
+
+    let old_paintings: HashMap<&str, &Painting> = old_pack.paintings().iter().map(|p| (p.id.as_str(), p)).collect();
+    let new_ids: Vec<&str> = new_pack.paintings().iter().map(|p| p.id.as_str()).collect();
+
+    let mut diff = PackDiff {
+        old_version: old_pack.version.clone(),
+        new_version: new_pack.version.clone(),
+        ..PackDiff::default()
+    };
+
+    for new in new_pack.paintings() {
+        let Some(old) = old_paintings.get(new.id.as_str()) else {
+            diff.added.push(new.id.clone());
+            continue;
+        };
+
+        let mut fields = Vec::new();
+        if old.name != new.name {
+            fields.push("name");
+        }
+        if old.artist != new.artist {
+            fields.push("artist");
+        }
+        if (old.width, old.height) != (new.width, new.height) {
+            fields.push("size");
+        }
+        if old.filename != new.filename {
+            fields.push("filename");
+        }
+        if !same_image(old_dir, old, new_dir, new) {
+            fields.push("image");
+        }
+
+        if fields.is_empty() {
+            diff.unchanged += 1;
+        } else {
+            diff.changed.push(PaintingChange { id: new.id.clone(), fields });
+        }
+    }
+
+    diff.removed = old_pack
+        .paintings()
+        .iter()
+        .filter(|old| !new_ids.contains(&old.id.as_str()))
+        .map(|old| old.id.clone())
+        .collect();
+
+    Ok(diff)
+}
+
+/*
+Writes a patch pack into `patch_dir`: the new custompaintings.json and icon, only the images of
+added or changed paintings, and a patch.json listing the diff (including removed IDs) so a server
+can push just the difference to players who already have the old version.
+*/
+pub fn write_patch_pack(new_dir: &Path, diff: &PackDiff, patch_dir: &Path) -> Result<(), String> {
+    let new_pack = pack_reader::read_pack(new_dir)?;
+    let images_dir = patch_dir.join("images");
+    create_dir_all(&images_dir).map_err(|e| format!("Failed to create {}: {}", images_dir.display(), e))?;
+
+    let changed_ids: Vec<&str> = diff.added.iter().map(String::as_str).chain(diff.changed.iter().map(|c| c.id.as_str())).collect();
+    for painting in new_pack.paintings().iter().filter(|p| changed_ids.contains(&p.id.as_str())) {
+        let source = new_dir.join("images").join(&painting.filename);
+        copy(&source, images_dir.join(&painting.filename)).map_err(|e| format!("Failed to copy {}: {}", source.display(), e))?;
+    }
+
+    copy(new_dir.join("custompaintings.json"), patch_dir.join("custompaintings.json"))
+        .map_err(|e| format!("Failed to copy custompaintings.json: {}", e))?;
+    if new_dir.join("icon.png").exists() {
+        copy(new_dir.join("icon.png"), patch_dir.join("icon.png")).map_err(|e| format!("Failed to copy icon.png: {}", e))?;
+    }
+
+    let patch_json = serde_json::to_string_pretty(diff).map_err(|e| format!("Failed to serialize patch summary: {}", e))?;
+    write(patch_dir.join("patch.json"), patch_json).map_err(|e| format!("Failed to write patch.json: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::pack_list::PackList;
+    use std::path::PathBuf;
+
+    fn painting(id: &str, name: &str) -> Painting {
+        Painting {
+            id: id.to_string(),
+            filename: format!("{}.png", id),
+            name: name.to_string(),
+            artist: String::from("Artist"),
+            width: 1,
+            height: 1,
+            description: None,
+            frame: None,
+            weight: None,
+        }
+    }
+
+    // Writes a minimal pack with one image file per painting whose contents are given.
+    fn write_pack(dir: &Path, version: &str, paintings: &[(Painting, &[u8])]) {
+        create_dir_all(dir.join("images")).unwrap();
+        let mut list: PackList<Painting> = PackList::new("Pack".into(), version.into(), "pack".into(), String::new());
+        for (painting, bytes) in paintings {
+            write(dir.join("images").join(&painting.filename), bytes).unwrap();
+            list.add_painting(painting.clone());
+        }
+        write(dir.join("custompaintings.json"), serde_json::to_string(&list).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_diff_and_patch_pack() {
+        let root: PathBuf = std::env::temp_dir().join(format!("pack_diff_test_{}", std::process::id()));
+        let (old_dir, new_dir, patch_dir) = (root.join("old"), root.join("new"), root.join("patch"));
+
+        write_pack(&old_dir, "1.0.0", &[
+            (painting("kept", "Kept"), b"same"),
+            (painting("renamed", "Old Name"), b"same"),
+            (painting("repainted", "Repainted"), b"before"),
+            (painting("dropped", "Dropped"), b"gone"),
+        ]);
+        write_pack(&new_dir, "1.1.0", &[
+            (painting("kept", "Kept"), b"same"),
+            (painting("renamed", "New Name"), b"same"),
+            (painting("repainted", "Repainted"), b"after"),
+            (painting("fresh", "Fresh"), b"new"),
+        ]);
+
+        let diff = diff_packs(&old_dir, &new_dir).unwrap();
+        assert_eq!(diff.added, vec!["fresh"]);
+        assert_eq!(diff.removed, vec!["dropped"]);
+        assert_eq!(diff.changed, vec![
+            PaintingChange { id: "renamed".into(), fields: vec!["name"] },
+            PaintingChange { id: "repainted".into(), fields: vec!["image"] },
+        ]);
+        assert_eq!(diff.unchanged, 1);
+
+        write_patch_pack(&new_dir, &diff, &patch_dir).unwrap();
+        assert!(patch_dir.join("images/fresh.png").exists());
+        assert!(patch_dir.join("images/renamed.png").exists());
+        assert!(patch_dir.join("images/repainted.png").exists());
+        assert!(!patch_dir.join("images/kept.png").exists());
+        assert!(patch_dir.join("custompaintings.json").exists());
+        assert!(patch_dir.join("patch.json").exists());
+
+        std::fs::remove_dir_all(root).ok();
+    }
+}
@@ -0,0 +1,187 @@
+use std::fs::{self, create_dir_all, File};
+use std::io::{ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use serde_json::Value;
+use zip::{result::ZipError, ZipArchive};
+use crate::core::{flipbook::Animation, vanilla};
+use crate::models::{image_size::ImageSize, pack_list::PackList, painting::Painting};
+
+// An exported pack, unpacked or zipped. Paths inside are relative to the pack root.
+pub(crate) enum PackSource {
+    Folder(PathBuf),
+    Archive(ZipArchive<File>),
+}
+
+impl PackSource {
+    // A pack folder, the custompaintings.json inside one, or a zip archive of a pack.
+    pub(crate) fn open(source: &Path) -> Result<Self, String> {
+        if source.is_dir() {
+            Ok(PackSource::Folder(source.to_path_buf()))
+        } else if source.file_name().is_some_and(|name| name == "custompaintings.json") {
+            Ok(PackSource::Folder(source.parent().unwrap_or(Path::new(".")).to_path_buf()))
+        } else {
+            let file = File::open(source).map_err(|e| format!("Failed to open {}: {}", source.display(), e))?;
+            let archive = ZipArchive::new(file).map_err(|e| format!("{} is not a pack folder or zip file: {}", source.display(), e))?;
+            Ok(PackSource::Archive(archive))
+        }
+    }
+
+    // The pack's painting list. `source` is only used in errors.
+    pub(crate) fn read_list(&mut self, source: &Path) -> Result<PackList<Painting>, String> {
+        let json = self.read("custompaintings.json")?.ok_or_else(|| format!("{} has no custompaintings.json", source.display()))?;
+        serde_json::from_slice(&json)
+            .map_err(|e| format!("The custompaintings.json of {} is not a valid painting list: {}", source.display(), e))
+    }
+
+    // The file at `relative_path`, or `None` if the pack doesn't have it.
+    pub(crate) fn read(&mut self, relative_path: &str) -> Result<Option<Vec<u8>>, String> {
+        match self {
+            PackSource::Folder(dir) => match fs::read(dir.join(relative_path)) {
+                Ok(bytes) => Ok(Some(bytes)),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(format!("Failed to read {}: {}", dir.join(relative_path).display(), e)),
+            },
+            PackSource::Archive(archive) => {
+                let mut entry = match archive.by_name(relative_path) {
+                    Ok(entry) => entry,
+                    Err(ZipError::FileNotFound) => return Ok(None),
+                    Err(e) => return Err(format!("Failed to read {} from the archive: {}", relative_path, e)),
+                };
+                let mut bytes = Vec::new();
+                entry.read_to_end(&mut bytes).map_err(|e| format!("Failed to extract {}: {}", relative_path, e))?;
+                Ok(Some(bytes))
+            }
+        }
+    }
+}
+
+// One painting of an imported pack, ready to become a row of the library.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedPainting {
+    // The largest texture of the painting's shape, copied out of the pack.
+    pub texture: PathBuf,
+    pub size: ImageSize,
+    // The pack's entry for that texture; its name, artist, description and frame carry over.
+    pub painting: Painting,
+    pub animation: Option<Animation>,
+}
+
+// An exported pack read back for editing: its metadata and paintings in pack order.
+#[derive(Debug)]
+pub struct ImportedPack {
+    pub metadata: PackList<()>,
+    pub paintings: Vec<ImportedPainting>,
+    // IDs of entries that couldn't be brought back, and why.
+    pub skipped: Vec<String>,
+}
+
+// The ID the exporter started from, before it appended the block size (e.g. "sunset" for "sunset_2x2").
+fn base_id(painting: &Painting) -> &str {
+    painting.id.strip_suffix(&format!("_{}x{}", painting.width, painting.height)).unwrap_or(&painting.id)
+}
+
+// The animation an `.mcmeta` file describes for the texture of `painting`, `texture` pixels in size.
+pub(crate) fn parse_animation(mcmeta: &[u8], painting: &Painting, texture: (u32, u32)) -> Result<Animation, String> {
+    let meta: Value = serde_json::from_slice(mcmeta).map_err(|e| format!("{}.mcmeta is not valid JSON: {}", painting.filename, e))?;
+    let animation = &meta["animation"];
+    let frame_height = (texture.0 * painting.height / painting.width.max(1)).max(1);
+    Ok(Animation {
+        frames: texture.1 / frame_height,
+        frame_time: animation["frametime"].as_u64().unwrap_or(1) as u32,
+        interpolate: animation["interpolate"].as_bool().unwrap_or(false),
+    })
+}
+
+/*
+Reads a pack the app exported earlier (a pack folder, its custompaintings.json, or a zip archive)
+so it can be edited and exported again. Every block size of a painting was written from the same
+crop, so the sizes of one shape become one painting, sourced from its largest texture; textures
+are copied into `dest_dir` so exporting over the original pack can't overwrite them while they
+are read. Entries whose shape the app doesn't have or whose texture is missing are skipped.
+*/
+pub fn import_pack(source: &Path, dest_dir: &Path) -> Result<ImportedPack, String> {
+    let mut pack = PackSource::open(source)?;
+    let (metadata, entries) = pack.read_list(source)?.separate_paintings::<()>();
+
+    // Largest entry of each painting and shape, in the order the pack lists them.
+    let mut skipped = Vec::new();
+    let mut largest: Vec<(String, ImageSize, Painting)> = Vec::new();
+    for painting in entries {
+        let Some(size) = vanilla::shape_for((painting.width, painting.height)) else {
+            skipped.push(format!("{}: {}x{} blocks doesn't match any painting shape", painting.id, painting.width, painting.height));
+            continue;
+        };
+        let base = base_id(&painting).to_string();
+        match largest.iter_mut().find(|(kept_base, kept_size, _)| *kept_base == base && *kept_size == size) {
+            Some((_, _, kept)) if kept.width < painting.width => *kept = painting,
+            Some(_) => {}
+            None => largest.push((base, size, painting)),
+        }
+    }
+
+    create_dir_all(dest_dir).map_err(|e| format!("Failed to create {}: {}", dest_dir.display(), e))?;
+    let mut paintings = Vec::new();
+    for (_, size, painting) in largest {
+        let relative_path = format!("images/{}", painting.filename);
+        let Some(bytes) = pack.read(&relative_path)? else {
+            skipped.push(format!("{}: {} is missing from the pack", painting.id, relative_path));
+            continue;
+        };
+        // Filenames come from the pack, so never let one point outside `dest_dir`.
+        let texture = dest_dir.join(Path::new(&painting.filename).file_name().unwrap_or_default());
+        fs::write(&texture, &bytes).map_err(|e| format!("Failed to write {}: {}", texture.display(), e))?;
+
+        let animation = match pack.read(&format!("{}.mcmeta", relative_path))? {
+            Some(mcmeta) => {
+                let dimensions = image::image_dimensions(&texture).map_err(|e| format!("Failed to read {}: {}", texture.display(), e))?;
+                Some(parse_animation(&mcmeta, &painting, dimensions)?).filter(|animation| animation.frames > 1)
+            }
+            None => None,
+        };
+        paintings.push(ImportedPainting { texture, size, painting, animation });
+    }
+    Ok(ImportedPack { metadata, paintings, skipped })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::exporter::{self, ExportItem};
+    use crate::models::{export_settings::ExportSettings, image_data::ImageData};
+    use image::RgbaImage;
+
+    #[test]
+    fn test_exported_pack_comes_back_one_painting_per_shape() {
+        let dir = std::env::temp_dir().join(format!("pack_import_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("sunset.png");
+        RgbaImage::new(64, 64).save(&source).unwrap();
+        let mut data = ImageData::new(ImageSize::Square);
+        data.name = Some(String::from("Sunset"));
+        data.artist = Some(String::from("Me"));
+        data.description = Some(String::from("Warm"));
+        let settings = ExportSettings { archive: true, ..ExportSettings::default() };
+        exporter::export(
+            String::from("Gallery"),
+            String::from("2.1.0"),
+            String::from("gallery"),
+            String::from("Old paintings"),
+            vec![ExportItem { source_path: source.to_string_lossy().to_string(), data }],
+            &dir.to_string_lossy(),
+            &settings,
+        )
+        .unwrap();
+
+        let archive = exporter::pack_output_path(&dir.to_string_lossy(), "Gallery", &settings);
+        let imported = import_pack(Path::new(&archive), &dir.join("imported")).unwrap();
+        assert_eq!(imported.metadata.pack_name, "Gallery");
+        assert_eq!(imported.metadata.version, "2.1.0");
+        assert_eq!(imported.paintings.len(), 1);
+        let painting = &imported.paintings[0];
+        assert_eq!(painting.size, ImageSize::Square);
+        assert_eq!((painting.painting.width, painting.painting.name.as_str()), (4, "Sunset"));
+        assert_eq!(painting.painting.description.as_deref(), Some("Warm"));
+        assert!(painting.texture.starts_with(dir.join("imported")) && painting.texture.is_file());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
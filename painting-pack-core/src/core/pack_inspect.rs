@@ -0,0 +1,203 @@
+use std::collections::HashSet;
+use std::path::Path;
+use image::GenericImageView;
+use serde::Serialize;
+use crate::core::{exporter, lint::{LintIssue, ModRules}, pack_import::{self, PackSource}, resample, validator};
+use crate::models::painting::Painting;
+
+// Longest side of the gallery thumbnails; an inspection can cover hundreds of paintings.
+const THUMBNAIL_SIZE: u32 = 128;
+
+// One entry of an inspected pack, with what was found of its texture.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct InspectedPainting {
+    pub painting: Painting,
+    // Texture size in pixels, `None` if it is missing or can't be decoded.
+    pub texture_size: Option<(u32, u32)>,
+    pub texture_bytes: u64,
+    // Frames of an animated texture; 1 for a still one.
+    pub frames: u32,
+    // Data URI of a small thumbnail (the first frame of animations).
+    pub thumbnail: Option<String>,
+}
+
+/*
+Everything a downloaded or older pack holds, for looking it over without opening it as a project:
+its metadata, counts per artist and block size, the paintings with thumbnails, and whatever would
+keep it from loading in the targeted mod release.
+*/
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PackInspection {
+    pub pack_name: String,
+    pub version: String,
+    pub id: String,
+    pub description: String,
+    pub painting_count: usize,
+    // Paintings per artist and per block size, most common first.
+    pub artists: Vec<(String, usize)>,
+    pub sizes: Vec<((u32, u32), usize)>,
+    pub texture_bytes: u64,
+    pub paintings: Vec<InspectedPainting>,
+    pub issues: Vec<LintIssue>,
+}
+
+// How often each value occurs, most common first and then in order of first appearance.
+fn tally<T: PartialEq + Clone>(values: impl Iterator<Item = T>) -> Vec<(T, usize)> {
+    let mut counts: Vec<(T, usize)> = Vec::new();
+    for value in values {
+        match counts.iter_mut().find(|(existing, _)| *existing == value) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((value, 1)),
+        }
+    }
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    counts
+}
+
+fn issue(issues: &mut Vec<LintIssue>, rule: &'static str, message: String) {
+    issues.push(LintIssue { rule, message });
+}
+
+// Reads one painting's texture and checks it against its entry and `rules`.
+fn inspect_painting(pack: &mut PackSource, painting: Painting, rules: &ModRules, issues: &mut Vec<LintIssue>) -> Result<InspectedPainting, String> {
+    let mut inspected = InspectedPainting { painting, texture_size: None, texture_bytes: 0, frames: 1, thumbnail: None };
+    let painting = &inspected.painting;
+    let relative_path = format!("images/{}", painting.filename);
+    let Some(bytes) = pack.read(&relative_path)? else {
+        issue(issues, "missing_texture", format!("\"{}\" uses {}, which isn't in the pack.", painting.name, relative_path));
+        return Ok(inspected);
+    };
+    inspected.texture_bytes = bytes.len() as u64;
+    let texture = match image::load_from_memory(&bytes) {
+        Ok(texture) => texture,
+        Err(e) => {
+            issue(issues, "unreadable_texture", format!("{} could not be decoded: {}", relative_path, e));
+            return Ok(inspected);
+        }
+    };
+    let (width, height) = texture.dimensions();
+    inspected.texture_size = Some((width, height));
+
+    if let Some(mcmeta) = pack.read(&format!("{}.mcmeta", relative_path))? {
+        match pack_import::parse_animation(&mcmeta, painting, (width, height)) {
+            Ok(animation) => inspected.frames = animation.frames.max(1),
+            Err(e) => issue(issues, "invalid_animation", e),
+        }
+        if !rules.animated_paintings {
+            issue(issues, "unsupported_animation", format!("\"{}\" is animated, which Custom Paintings {} can't play.", painting.name, rules.mod_version));
+        }
+    }
+    let frame_height = height / inspected.frames;
+    if width * painting.height != frame_height * painting.width {
+        issue(issues, "aspect_mismatch", format!(
+            "\"{}\" is {}x{} blocks but its texture is {}x{} px, so it will be stretched.",
+            painting.name, painting.width, painting.height, width, frame_height
+        ));
+    }
+    if width.max(height) > rules.max_texture_size {
+        issue(issues, "max_texture_size", format!(
+            "{} is {}x{}, above the {}px limit of Custom Paintings {}.",
+            relative_path, width, height, rules.max_texture_size, rules.mod_version
+        ));
+    }
+
+    let first_frame = texture.crop_imm(0, 0, width, frame_height.max(1));
+    let thumbnail = resample::fit_within(&first_frame, THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+    inspected.thumbnail = Some(exporter::preview_data_uri(&exporter::encode_preview_png(&thumbnail)));
+    Ok(inspected)
+}
+
+/*
+Reads a pack (a folder, its custompaintings.json or a zip archive) for viewing only. Nothing is
+copied or imported; problems with the pack itself are reported as issues against `rules` rather
+than errors, so a broken pack can still be looked at.
+*/
+pub fn inspect_pack(source: &Path, rules: &ModRules) -> Result<PackInspection, String> {
+    let mut pack = PackSource::open(source)?;
+    let (metadata, entries) = pack.read_list(source)?.separate_paintings::<()>();
+    let mut issues = Vec::new();
+
+    if entries.len() > rules.max_paintings {
+        issue(&mut issues, "max_paintings", format!(
+            "The pack has {} paintings but Custom Paintings {} loads at most {} per pack.",
+            entries.len(), rules.mod_version, rules.max_paintings
+        ));
+    }
+    if rules.reserved_ids.contains(&metadata.id.as_str()) {
+        issue(&mut issues, "reserved_id", format!("The pack ID \"{}\" is reserved and clashes with existing paintings.", metadata.id));
+    }
+    let mut seen_ids = HashSet::new();
+    for id in std::iter::once(&metadata.id).chain(entries.iter().map(|painting| &painting.id)) {
+        if validator::sanitize_id(id) != *id {
+            issue(&mut issues, "invalid_id", format!("\"{}\" has characters outside a-z, 0-9, _, -, . and won't load.", id));
+        }
+    }
+    for painting in &entries {
+        if !seen_ids.insert(painting.id.as_str()) {
+            issue(&mut issues, "duplicate_id", format!("More than one painting has the ID \"{}\"; only one of them will load.", painting.id));
+        }
+        if painting.description.is_some() && !rules.painting_descriptions {
+            issue(&mut issues, "unsupported_description", format!("\"{}\" has a description, which Custom Paintings {} ignores.", painting.name, rules.mod_version));
+        }
+        if painting.frame.is_some() && !rules.painting_frames {
+            issue(&mut issues, "unsupported_frame", format!("\"{}\" has a custom frame, which Custom Paintings {} ignores.", painting.name, rules.mod_version));
+        }
+        if painting.weight.is_some() && !rules.painting_weights {
+            issue(&mut issues, "unsupported_weight", format!("\"{}\" has a weight, which Custom Paintings {} ignores.", painting.name, rules.mod_version));
+        }
+    }
+
+    let artists = tally(entries.iter().map(|painting| painting.artist.clone()));
+    let sizes = tally(entries.iter().map(|painting| (painting.width, painting.height)));
+    let paintings = entries
+        .into_iter()
+        .map(|painting| inspect_painting(&mut pack, painting, rules, &mut issues))
+        .collect::<Result<Vec<InspectedPainting>, String>>()?;
+
+    Ok(PackInspection {
+        pack_name: metadata.pack_name,
+        version: metadata.version,
+        id: metadata.id,
+        description: metadata.description,
+        painting_count: paintings.len(),
+        artists,
+        sizes,
+        texture_bytes: paintings.iter().map(|painting| painting.texture_bytes).sum(),
+        paintings,
+        issues,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::lint;
+    use image::RgbaImage;
+    use std::fs;
+
+    #[test]
+    fn test_inspection_reports_stats_and_problems() {
+        let dir = std::env::temp_dir().join(format!("pack_inspect_test_{}", std::process::id()));
+        fs::create_dir_all(dir.join("images")).unwrap();
+        RgbaImage::new(32, 32).save(dir.join("images/sun.png")).unwrap();
+        RgbaImage::new(32, 32).save(dir.join("images/wide.png")).unwrap();
+        let json = r#"{
+            "name": "Downloaded", "version": "1.0", "id": "downloaded", "description": "",
+            "paintings": [
+                {"id": "sun_2x2", "filename": "sun.png", "name": "Sun", "artist": "Ana", "width": 2, "height": 2},
+                {"id": "wide_2x1", "filename": "wide.png", "name": "Wide", "artist": "Ana", "width": 2, "height": 1},
+                {"id": "sun_2x2", "filename": "gone.png", "name": "Gone", "artist": "Ben", "width": 2, "height": 2}
+            ]
+        }"#;
+        fs::write(dir.join("custompaintings.json"), json).unwrap();
+
+        let inspection = inspect_pack(&dir, lint::rules_for("2.0.0")).unwrap();
+        assert_eq!(inspection.painting_count, 3);
+        assert_eq!(inspection.artists, vec![(String::from("Ana"), 2), (String::from("Ben"), 1)]);
+        assert_eq!(inspection.sizes[0], ((2, 2), 2));
+        assert!(inspection.paintings[0].thumbnail.is_some());
+        let rules_hit: Vec<&str> = inspection.issues.iter().map(|issue| issue.rule).collect();
+        assert_eq!(rules_hit, vec!["duplicate_id", "aspect_mismatch", "missing_texture"]);
+        fs::remove_dir_all(&dir).ok();
+    }
+}
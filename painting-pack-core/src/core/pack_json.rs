@@ -0,0 +1,78 @@
+use serde::Serialize;
+use serde_json::{ser::PrettyFormatter, Serializer, Value};
+use crate::models::export_settings::JsonStyle;
+
+// Top-level keys the Custom Paintings mod doesn't require.
+const OPTIONAL_PACK_FIELDS: &[&str] = &["$schema", "description"];
+// Painting keys the mod doesn't require.
+const OPTIONAL_PAINTING_FIELDS: &[&str] = &["name", "artist", "description"];
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(text) => text.trim().is_empty(),
+        _ => false,
+    }
+}
+
+fn strip_empty(object: &mut Value, optional: &[&str]) {
+    if let Value::Object(map) = object {
+        map.retain(|key, value| !(optional.contains(&key.as_str()) && is_empty(value)));
+    }
+}
+
+/*
+Renders a custompaintings.json in the given style: pretty printed with the chosen indent or
+minified, optionally leaving out optional fields that are empty. Required fields are always kept.
+*/
+pub fn render<T: Serialize>(pack: &T, style: &JsonStyle) -> Result<String, String> {
+    let mut value = serde_json::to_value(pack).map_err(|e| format!("Failed to serialize painting list: {}", e))?;
+    if style.omit_empty_fields {
+        strip_empty(&mut value, OPTIONAL_PACK_FIELDS);
+        if let Some(Value::Array(paintings)) = value.get_mut("paintings") {
+            for painting in paintings {
+                strip_empty(painting, OPTIONAL_PAINTING_FIELDS);
+            }
+        }
+    }
+
+    if !style.pretty {
+        return serde_json::to_string(&value).map_err(|e| format!("Failed to serialize painting list: {}", e));
+    }
+    let indent = " ".repeat(style.indent as usize);
+    let mut output = Vec::new();
+    let mut serializer = Serializer::with_formatter(&mut output, PrettyFormatter::with_indent(indent.as_bytes()));
+    value.serialize(&mut serializer).map_err(|e| format!("Failed to serialize painting list: {}", e))?;
+    String::from_utf8(output).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_styles() {
+        let pack = json!({
+            "name": "Pack",
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "description": "",
+            "paintings": [{ "id": "a", "name": "", "artist": "Me", "width": 1 }],
+        });
+
+        let pretty = render(&pack, &JsonStyle::default()).unwrap();
+        assert!(pretty.contains("\n  \"name\": \"Pack\""));
+        assert!(pretty.contains("\"description\": \"\""));
+
+        let style = JsonStyle { pretty: false, indent: 2, omit_empty_fields: true };
+        let minified = render(&pack, &style).unwrap();
+        assert!(!minified.contains('\n'));
+        assert!(!minified.contains("description"));
+        // Key order is kept
+        assert!(minified.starts_with(r#"{"name":"Pack","$schema":"#));
+        assert!(minified.contains(r#"[{"id":"a","artist":"Me","width":1}]"#));
+
+        let style = JsonStyle { pretty: true, indent: 4, omit_empty_fields: false };
+        assert!(render(&pack, &style).unwrap().contains("\n    \"name\": \"Pack\""));
+    }
+}
@@ -0,0 +1,12 @@
+use std::fs::read_to_string;
+use std::path::Path;
+use crate::models::{pack_list::PackList, painting::Painting};
+
+/*
+Reads the custompaintings.json of an exported pack folder back into a PackList.
+*/
+pub fn read_pack(pack_dir: &Path) -> Result<PackList<Painting>, String> {
+    let json_path = pack_dir.join("custompaintings.json");
+    let json = read_to_string(&json_path).map_err(|e| format!("Failed to read {}: {}", json_path.display(), e))?;
+    serde_json::from_str(&json).map_err(|e| format!("{} is not a valid painting list: {}", json_path.display(), e))
+}
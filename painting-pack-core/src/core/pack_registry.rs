@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+// A pack the user exported before, as last exported.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PackRecord {
+    pub id: String,
+    pub pack_name: String,
+    // Sanitized painting IDs, without the size suffix.
+    pub painting_ids: Vec<String>,
+    // Unix seconds.
+    pub last_exported: u64,
+}
+
+// Another pack already exported under the ID the current pack is about to use.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PackCollision {
+    pub id: String,
+    pub previous_pack_name: String,
+    pub last_exported: u64,
+}
+
+/*
+Every pack ID the user has exported, so a new pack that reuses one can be caught before players
+end up with two packs fighting over the same namespace. Updating a pack is not a collision: a
+record counts as the same pack if it has the same name or shares at least one painting.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct PackRegistry {
+    pub packs: Vec<PackRecord>,
+}
+
+impl PackRegistry {
+    // The earlier, different pack exported under `id`, if there is one.
+    pub fn collision(&self, id: &str, pack_name: &str, painting_ids: &[String]) -> Option<PackCollision> {
+        let record = self.packs.iter().find(|record| record.id == id)?;
+        let same_pack = record.pack_name.trim().eq_ignore_ascii_case(pack_name.trim())
+            || record.painting_ids.iter().any(|painting| painting_ids.contains(painting));
+        (!same_pack).then(|| PackCollision {
+            id: record.id.clone(),
+            previous_pack_name: record.pack_name.clone(),
+            last_exported: record.last_exported,
+        })
+    }
+
+    // Remembers an export, replacing whatever was last exported under the same ID.
+    pub fn record(&mut self, record: PackRecord) {
+        match self.packs.iter_mut().find(|existing| existing.id == record.id) {
+            Some(existing) => *existing = record,
+            None => self.packs.push(record),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_collision_ignores_updates_of_the_same_pack() {
+        let mut registry = PackRegistry::default();
+        registry.record(PackRecord {
+            id: String::from("forest"),
+            pack_name: String::from("Forest Pack"),
+            painting_ids: ids(&["oak", "birch"]),
+            last_exported: 100,
+        });
+
+        // Renamed but still sharing paintings, or same name with new paintings: an update
+        assert!(registry.collision("forest", "Forest Pack v2", &ids(&["oak"])).is_none());
+        assert!(registry.collision("forest", "forest pack", &ids(&["pine"])).is_none());
+        assert!(registry.collision("desert", "Desert Pack", &ids(&["cactus"])).is_none());
+
+        let collision = registry.collision("forest", "Desert Pack", &ids(&["cactus"])).unwrap();
+        assert_eq!(collision.previous_pack_name, "Forest Pack");
+        assert_eq!(collision.last_exported, 100);
+    }
+}
@@ -0,0 +1,236 @@
+use serde::Deserialize;
+use crate::core::{exporter::ExportItem, lint, naming::UniqueNames, pack_budget::{self, MEGABYTE}, validator};
+use crate::models::export_settings::ExportSettings;
+
+/*
+How a pack is split into sub-packs: by artist, for compilations of many contributors, or into
+numbered parts that each stay within a size budget, for libraries too big for one pack. Artists
+are never split across packs; the painting budget counts paintings like the mod does, every block
+size apart.
+*/
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SplitMode {
+    // One pack per artist.
+    PerArtist,
+    // Artists grouped into as few packs as keep each within `max_paintings`.
+    ArtistsWithinBudget { max_paintings: usize },
+    // The library in order, cut into parts of at most `max_images` source images and
+    // `max_megabytes` of texture memory (see `pack_budget`). `None` leaves that limit off.
+    SizeBudget { max_images: Option<usize>, max_megabytes: Option<u64> },
+}
+
+// One pack of a split: its own name and ID, and the paintings it gets in library order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubPack {
+    pub pack_name: String,
+    pub id: String,
+    pub items: Vec<ExportItem>,
+}
+
+// Items grouped by artist (blank and differently cased names count as the same artist), in order of
+// each artist's first painting.
+fn group_by_artist(items: Vec<ExportItem>) -> Vec<(String, Vec<ExportItem>)> {
+    let mut groups: Vec<(String, Vec<ExportItem>)> = Vec::new();
+    for item in items {
+        let artist = item.data.artist.as_deref().unwrap_or_default().trim().to_string();
+        match groups.iter_mut().find(|(existing, _)| existing.to_lowercase() == artist.to_lowercase()) {
+            Some((_, group)) => group.push(item),
+            None => groups.push((artist, vec![item])),
+        }
+    }
+    groups
+}
+
+// Items grouped by source image, in library order, so the crops of one image stay in one pack.
+fn group_by_source(items: Vec<ExportItem>) -> Vec<Vec<ExportItem>> {
+    let mut groups: Vec<Vec<ExportItem>> = Vec::new();
+    for item in items {
+        match groups.iter_mut().find(|group| group[0].source_path == item.source_path) {
+            Some(group) => group.push(item),
+            None => groups.push(vec![item]),
+        }
+    }
+    groups
+}
+
+/*
+Fills parts in library order, starting a new one whenever the next image would take the current
+part over either limit. Errors if one image alone is over the megabyte budget.
+*/
+fn split_by_size(items: Vec<ExportItem>, max_images: Option<usize>, max_megabytes: Option<u64>, settings: &ExportSettings) -> Result<Vec<Vec<ExportItem>>, String> {
+    if max_images.is_none() && max_megabytes.is_none() {
+        return Err(String::from("Set an image or megabyte budget to split the pack by size"));
+    }
+    if max_images == Some(0) || max_megabytes == Some(0) {
+        return Err(String::from("The budget needs to allow at least 1 image and 1 MB per pack"));
+    }
+    let max_bytes = max_megabytes.map(|megabytes| megabytes * MEGABYTE);
+
+    let mut parts: Vec<(Vec<ExportItem>, usize, u64)> = Vec::new();
+    for source in group_by_source(items) {
+        let bytes = pack_budget::texture_memory_bytes(&source, settings);
+        if max_bytes.is_some_and(|max_bytes| bytes > max_bytes) {
+            return Err(format!(
+                "{} alone takes about {} MB, more than the budget of {} MB. Lower the pixels per block or the texture size, or raise the budget.",
+                source[0].data.name.as_deref().unwrap_or(&source[0].source_path),
+                bytes.div_ceil(MEGABYTE),
+                max_megabytes.unwrap_or_default()
+            ));
+        }
+        let fits = parts.last().is_some_and(|(_, images, part_bytes)| {
+            max_images.map_or(true, |max_images| *images < max_images)
+                && max_bytes.map_or(true, |max_bytes| part_bytes + bytes <= max_bytes)
+        });
+        if !fits {
+            parts.push((Vec::new(), 0, 0));
+        }
+        let (part, images, part_bytes) = parts.last_mut().expect("a part was just added");
+        part.extend(source);
+        *images += 1;
+        *part_bytes += bytes;
+    }
+    Ok(parts.into_iter().map(|(part, _, _)| part).collect())
+}
+
+/*
+Splits the paintings of `pack_name` into sub-packs. Per-artist packs are named "<pack> - <artist>"
+with the artist appended to the ID. Packs budgeted by paintings are filled biggest artist first
+and numbered in order; packs budgeted by size are named "<pack>_part1", "<pack>_part2" and so on.
+Items are expected to have had their defaults applied, so every one has an artist. Errors if an
+artist (or, by size, an image) alone is over the budget.
+*/
+pub fn split_pack(pack_name: &str, pack_id: &str, items: Vec<ExportItem>, mode: SplitMode, settings: &ExportSettings) -> Result<Vec<SubPack>, String> {
+    let mut ids = UniqueNames::default();
+
+    match mode {
+        SplitMode::PerArtist => Ok(group_by_artist(items)
+            .into_iter()
+            .map(|(artist, items)| {
+                let artist_label = if artist.is_empty() { String::from("Unknown Artist") } else { artist };
+                SubPack {
+                    pack_name: format!("{} - {}", pack_name, artist_label),
+                    id: ids.claim(&format!("{}_{}", pack_id, validator::sanitize_id(&artist_label))),
+                    items,
+                }
+            })
+            .collect()),
+        SplitMode::ArtistsWithinBudget { max_paintings } => {
+            let groups = group_by_artist(items);
+            if max_paintings == 0 {
+                return Err(String::from("The budget needs to allow at least 1 painting per pack"));
+            }
+            if let Some((artist, items)) = groups.iter().find(|(_, items)| lint::painting_count(items) > max_paintings) {
+                return Err(format!(
+                    "{} alone has {} paintings, more than the budget of {}. Split one pack per artist or deselect some sizes.",
+                    artist, lint::painting_count(items), max_paintings
+                ));
+            }
+
+            // First fit, biggest artists first, keeps the number of packs low.
+            let mut by_size = groups;
+            by_size.sort_by_key(|(_, items)| std::cmp::Reverse(lint::painting_count(items)));
+            let mut bins: Vec<Vec<ExportItem>> = Vec::new();
+            for (_, items) in by_size {
+                let count = lint::painting_count(&items);
+                match bins.iter_mut().find(|bin| lint::painting_count(bin) + count <= max_paintings) {
+                    Some(bin) => bin.extend(items),
+                    None => bins.push(items),
+                }
+            }
+
+            Ok(bins
+                .into_iter()
+                .enumerate()
+                .map(|(index, mut items)| {
+                    items.sort_by_key(|item| item.data.order);
+                    SubPack { pack_name: format!("{} {}", pack_name, index + 1), id: format!("{}_{}", pack_id, index + 1), items }
+                })
+                .collect())
+        }
+        SplitMode::SizeBudget { max_images, max_megabytes } => Ok(split_by_size(items, max_images, max_megabytes, settings)?
+            .into_iter()
+            .enumerate()
+            .map(|(index, items)| SubPack {
+                pack_name: format!("{}_part{}", pack_name, index + 1),
+                id: format!("{}_part{}", pack_id, index + 1),
+                items,
+            })
+            .collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{image_data::ImageData, image_size::ImageSize};
+
+    fn item(artist: &str, size: ImageSize, order: u32) -> ExportItem {
+        let mut data = ImageData::new(size);
+        data.artist = Some(artist.to_string());
+        data.order = order;
+        ExportItem { source_path: String::new(), data }
+    }
+
+    #[test]
+    fn test_split_per_artist_and_within_budget() {
+        // Square and Tall variants come in 4 and 2 block sizes.
+        let items = vec![
+            item("Ana", ImageSize::Square, 1),
+            item("Ben", ImageSize::Square, 2),
+            item("ana ", ImageSize::Tall, 3),
+            item("Cy", ImageSize::Tall, 4),
+        ];
+        let settings = ExportSettings::default();
+
+        let per_artist = split_pack("Gallery", "gallery", items.clone(), SplitMode::PerArtist, &settings).unwrap();
+        let names: Vec<&str> = per_artist.iter().map(|pack| pack.pack_name.as_str()).collect();
+        assert_eq!(names, vec!["Gallery - Ana", "Gallery - Ben", "Gallery - Cy"]);
+        assert_eq!(per_artist[0].id, "gallery_ana");
+        assert_eq!(per_artist[0].items.len(), 2);
+
+        // Ana (6) fills a pack of 6 alone; Ben (4) and Cy (2) share the second.
+        let budgeted = split_pack("Gallery", "gallery", items.clone(), SplitMode::ArtistsWithinBudget { max_paintings: 6 }, &settings).unwrap();
+        assert_eq!(budgeted.len(), 2);
+        assert_eq!(budgeted[1].id, "gallery_2");
+        let orders: Vec<u32> = budgeted[1].items.iter().map(|item| item.data.order).collect();
+        assert_eq!(orders, vec![2, 4]);
+
+        assert!(split_pack("Gallery", "gallery", items, SplitMode::ArtistsWithinBudget { max_paintings: 5 }, &settings).is_err());
+    }
+
+    #[test]
+    fn test_split_by_size_keeps_each_part_within_budget() {
+        let dir = std::env::temp_dir().join(format!("pack_split_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut items = Vec::new();
+        for order in 1..=5 {
+            let path = dir.join(format!("image{}.png", order));
+            image::RgbaImage::new(512, 512).save(&path).unwrap();
+            for size in [ImageSize::Square, ImageSize::Wide] {
+                let mut source = item("Ana", size, order);
+                source.source_path = path.to_string_lossy().to_string();
+                items.push(source);
+            }
+        }
+        let settings = ExportSettings { pixels_per_block: Some(128), ..ExportSettings::default() };
+
+        // At 128 px per block the Square crop makes textures of 128² to 512² and the Wide crop 256x128
+        // and 512x256: 2.5 MB of texture memory per image.
+        let by_images = split_pack("Gallery", "gallery", items.clone(), SplitMode::SizeBudget { max_images: Some(2), max_megabytes: None }, &settings).unwrap();
+        let names: Vec<&str> = by_images.iter().map(|pack| pack.pack_name.as_str()).collect();
+        assert_eq!(names, ["Gallery_part1", "Gallery_part2", "Gallery_part3"]);
+        assert_eq!(by_images[2].id, "gallery_part3");
+        // Both crops of an image stay together.
+        assert_eq!(by_images[0].items.len(), 4);
+
+        let by_megabytes = split_pack("Gallery", "gallery", items.clone(), SplitMode::SizeBudget { max_images: None, max_megabytes: Some(6) }, &settings).unwrap();
+        let orders: Vec<Vec<u32>> = by_megabytes.iter().map(|pack| pack.items.iter().step_by(2).map(|item| item.data.order).collect()).collect();
+        assert_eq!(orders, [vec![1, 2], vec![3, 4], vec![5]]);
+
+        assert!(split_pack("Gallery", "gallery", items.clone(), SplitMode::SizeBudget { max_images: None, max_megabytes: None }, &settings).is_err());
+        let full_resolution = ExportSettings::default();
+        assert!(split_pack("Gallery", "gallery", items, SplitMode::SizeBudget { max_images: None, max_megabytes: Some(1) }, &full_resolution).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,210 @@
+use image::{DynamicImage, Rgba};
+use crate::models::export_settings::{PaletteSource, PaletteTheme};
+
+pub type Colour = [u8; 3];
+
+// Hand-picked palettes for the built-in themes.
+pub fn theme_palette(theme: PaletteTheme) -> Vec<Colour> {
+    match theme {
+        PaletteTheme::Warm => vec![[74, 35, 22], [150, 72, 38], [214, 128, 62], [240, 196, 120], [252, 236, 200]],
+        PaletteTheme::Cool => vec![[18, 30, 56], [40, 78, 120], [80, 140, 170], [160, 200, 210], [230, 240, 244]],
+        PaletteTheme::Sepia => vec![[44, 30, 18], [102, 74, 48], [160, 126, 88], [212, 186, 146], [244, 232, 208]],
+        PaletteTheme::Pastel => vec![[120, 110, 140], [244, 172, 183], [255, 214, 165], [203, 232, 186], [190, 214, 240]],
+        PaletteTheme::Forest => vec![[24, 36, 24], [52, 84, 44], [104, 130, 66], [176, 162, 104], [226, 218, 180]],
+    }
+}
+
+fn distance_squared(a: Colour, b: Colour) -> u32 {
+    // Weighted towards green like the eye, which keeps matches perceptually sensible.
+    let dr = a[0] as i32 - b[0] as i32;
+    let dg = a[1] as i32 - b[1] as i32;
+    let db = a[2] as i32 - b[2] as i32;
+    (2 * dr * dr + 4 * dg * dg + 3 * db * db) as u32
+}
+
+fn nearest(palette: &[Colour], colour: Colour) -> Colour {
+    *palette
+        .iter()
+        .min_by_key(|candidate| distance_squared(**candidate, colour))
+        .unwrap_or(&colour)
+}
+
+fn luminance(c: Colour) -> u32 {
+    299 * c[0] as u32 + 587 * c[1] as u32 + 114 * c[2] as u32
+}
+
+/*
+Clusters the colours of an image with a few rounds of k-means on a small thumbnail and returns
+each centre with the number of thumbnail pixels it covers. Seeds are spread across the brightness
+range so the result is deterministic.
+*/
+fn clusters(image: &DynamicImage, count: usize) -> Vec<(Colour, u64)> {
+    // Only shrink large images; resampling small ones would invent blended in-between colours.
+    let thumb = if image.width() > 64 || image.height() > 64 {
+        image.thumbnail(64, 64).to_rgb8()
+    } else {
+        image.to_rgb8()
+    };
+    let mut pixels: Vec<Colour> = thumb.pixels().map(|p| p.0).collect();
+    if pixels.is_empty() || count == 0 {
+        return Vec::new();
+    }
+    pixels.sort_by_key(|c| luminance(*c));
+
+    let count = count.min(pixels.len());
+    let spacing = (count - 1).max(1);
+    let mut centres: Vec<Colour> = (0..count)
+        .map(|i| pixels[i * (pixels.len() - 1) / spacing])
+        .collect();
+    let mut members = vec![0u64; count];
+
+    for _ in 0..8 {
+        let mut sums = vec![[0u64; 4]; count];
+        for pixel in &pixels {
+            let (index, _) = centres
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, centre)| distance_squared(**centre, *pixel))
+                .unwrap();
+            sums[index][0] += pixel[0] as u64;
+            sums[index][1] += pixel[1] as u64;
+            sums[index][2] += pixel[2] as u64;
+            sums[index][3] += 1;
+        }
+        for (centre, sum) in centres.iter_mut().zip(&sums) {
+            // Centres that attracted no pixels keep their previous position.
+            let members = sum[3].max(1);
+            if sum[3] > 0 {
+                *centre = [(sum[0] / members) as u8, (sum[1] / members) as u8, (sum[2] / members) as u8];
+            }
+        }
+        members = sums.iter().map(|sum| sum[3]).collect();
+    }
+
+    centres.into_iter().zip(members).collect()
+}
+
+/*
+Extracts the `count` most representative colours of an image.
+*/
+pub fn extract_palette(image: &DynamicImage, count: usize) -> Vec<Colour> {
+    let mut centres: Vec<Colour> = clusters(image, count).into_iter().map(|(centre, _)| centre).collect();
+    centres.dedup();
+    centres
+}
+
+// The colour covering most of the image. Falls back to mid grey for an empty image.
+pub fn dominant_colour(image: &DynamicImage) -> Colour {
+    clusters(image, 5)
+        .into_iter()
+        .max_by_key(|(_, members)| *members)
+        .map(|(centre, _)| centre)
+        .unwrap_or([128, 128, 128])
+}
+
+// Colours for framing a painting in a preview, derived from its dominant colour.
+#[derive(serde::Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Framing {
+    // The outer moulding: a deep, muted shade of the dominant colour.
+    pub frame: Colour,
+    // The mat board between frame and painting: a pale tint of it.
+    pub matte: Colour,
+}
+
+/*
+Derives a frame and matte that suit a painting, the way a framer would pick them: both are
+desaturated towards grey so they support the artwork instead of competing with it.
+*/
+pub fn framing_for(dominant: Colour) -> Framing {
+    let grey = (luminance(dominant) / 1000) as f32;
+    let muted = dominant.map(|c| c as f32 * 0.5 + grey * 0.5);
+    Framing {
+        frame: muted.map(|c| (c * 0.35).round() as u8),
+        matte: muted.map(|c| (c * 0.2 + 255.0 * 0.8).round() as u8),
+    }
+}
+
+/*
+Resolves the palette to harmonize towards, opening the reference image if one was chosen.
+*/
+pub fn resolve_palette(source: &PaletteSource) -> Result<Vec<Colour>, String> {
+    match source {
+        PaletteSource::Theme(theme) => Ok(theme_palette(*theme)),
+        PaletteSource::Reference(path) => {
+            let reference = image::open(path).map_err(|e| format!("Failed to open palette reference {}: {}", path, e))?;
+            Ok(extract_palette(&reference, 6))
+        }
+    }
+}
+
+/*
+Gently pulls every pixel towards its nearest palette colour. `strength` is clamped to 0..=1,
+where 0 leaves the image untouched and 1 posterizes it to the palette. Alpha is preserved.
+*/
+pub fn harmonize(image: &DynamicImage, palette: &[Colour], strength: f32) -> DynamicImage {
+    let strength = strength.clamp(0.0, 1.0);
+    if palette.is_empty() || strength == 0.0 {
+        return image.clone();
+    }
+
+    let mut rgba = image.to_rgba8();
+    for pixel in rgba.pixels_mut() {
+        let Rgba([r, g, b, a]) = *pixel;
+        let target = nearest(palette, [r, g, b]);
+        let blend = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * strength).round() as u8;
+        *pixel = Rgba([blend(r, target[0]), blend(g, target[1]), blend(b, target[2]), a]);
+    }
+    DynamicImage::ImageRgba8(rgba)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{RgbImage, Rgb};
+
+    #[test]
+    fn test_extract_palette_finds_dominant_colours() {
+        // Left half red, right half blue
+        let img = RgbImage::from_fn(40, 20, |x, _| if x < 20 { Rgb([200, 20, 20]) } else { Rgb([20, 20, 200]) });
+        let palette = extract_palette(&DynamicImage::ImageRgb8(img), 2);
+        assert_eq!(palette.len(), 2);
+        assert!(palette.contains(&[200, 20, 20]));
+        assert!(palette.contains(&[20, 20, 200]));
+    }
+
+    #[test]
+    fn test_dominant_colour_and_framing() {
+        // Mostly green with a red stripe
+        let img = RgbImage::from_fn(40, 20, |x, _| if x < 8 { Rgb([200, 20, 20]) } else { Rgb([30, 160, 60]) });
+        let dominant = dominant_colour(&DynamicImage::ImageRgb8(img));
+        assert_eq!(dominant, [30, 160, 60]);
+
+        let framing = framing_for(dominant);
+        // The frame is darker and the matte lighter than the painting, both still leaning green.
+        assert!(luminance(framing.frame) < luminance(dominant));
+        assert!(luminance(framing.matte) > luminance(dominant));
+        assert!(framing.frame[1] > framing.frame[0] && framing.matte[1] > framing.matte[0]);
+    }
+
+    #[test]
+    fn test_harmonize_strength_bounds() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(4, 4, Rgb([100, 100, 100])));
+        let palette = vec![[200, 100, 0]];
+
+        let untouched = harmonize(&img, &palette, 0.0).to_rgb8();
+        assert_eq!(untouched.get_pixel(0, 0).0, [100, 100, 100]);
+
+        let half = harmonize(&img, &palette, 0.5).to_rgb8();
+        assert_eq!(half.get_pixel(0, 0).0, [150, 100, 50]);
+
+        let full = harmonize(&img, &palette, 5.0).to_rgb8(); // clamped to 1.0
+        assert_eq!(full.get_pixel(0, 0).0, [200, 100, 0]);
+    }
+
+    #[test]
+    fn test_resolve_missing_reference_errors() {
+        let source = PaletteSource::Reference("does_not_exist.png".to_string());
+        assert!(resolve_palette(&source).is_err());
+        assert_eq!(resolve_palette(&PaletteSource::Theme(PaletteTheme::Warm)).unwrap().len(), 5);
+    }
+}
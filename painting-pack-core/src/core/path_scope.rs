@@ -0,0 +1,98 @@
+use std::env::current_dir;
+use std::path::{Component, Path, PathBuf};
+
+/*
+The files and folders the frontend may name in commands. Paths only get in through the import
+pipeline, the app's own native pickers and project files the user opened; anything else the
+webview sends is refused, so a compromised page can't have the backend read or overwrite
+arbitrary files. A folder covers everything under it.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct PathScope {
+    files: Vec<PathBuf>,
+    folders: Vec<PathBuf>,
+}
+
+/*
+`path` made absolute with symlinks resolved as far as it exists, so a link or a not-yet-created
+export folder can't be used to step outside the scope. Paths that climb with `..` are refused
+outright rather than second-guessed.
+*/
+fn resolve(path: &Path) -> Result<PathBuf, String> {
+    if path.components().any(|component| component == Component::ParentDir) {
+        return Err(format!("{} may not contain \"..\"", path.display()));
+    }
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        current_dir().map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))?.join(path)
+    };
+    let mut existing = absolute.as_path();
+    let mut missing = Vec::new();
+    while !existing.exists() {
+        let Some(parent) = existing.parent() else { break };
+        missing.push(existing.file_name().unwrap_or_default().to_os_string());
+        existing = parent;
+    }
+    let mut resolved = existing.canonicalize().unwrap_or_else(|_| existing.to_path_buf());
+    resolved.extend(missing.into_iter().rev());
+    Ok(resolved)
+}
+
+impl PathScope {
+    // Lets the frontend name this file from now on, e.g. a source that was imported.
+    pub fn allow_file(&mut self, path: &Path) {
+        if let Ok(path) = resolve(path) {
+            if !self.files.contains(&path) {
+                self.files.push(path);
+            }
+        }
+    }
+
+    // Lets the frontend name this folder and anything in it, e.g. one the user picked.
+    pub fn allow_folder(&mut self, path: &Path) {
+        if let Ok(path) = resolve(path) {
+            if !self.folders.contains(&path) {
+                self.folders.push(path);
+            }
+        }
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        resolve(path).is_ok_and(|path| self.files.contains(&path) || self.folders.iter().any(|folder| path.starts_with(folder)))
+    }
+
+    // `path` unchanged if the frontend may use it, otherwise an error saying how to get it in scope.
+    pub fn check(&self, path: &str) -> Result<String, String> {
+        if self.contains(Path::new(path)) {
+            Ok(path.to_string())
+        } else {
+            Err(format!("{} hasn't been imported or chosen in a file dialog, so the app won't touch it", path))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_only_registered_paths_are_in_scope() {
+        let dir = std::env::temp_dir().join(format!("path_scope_test_{}", std::process::id()));
+        fs::create_dir_all(dir.join("picked")).unwrap();
+        fs::write(dir.join("imported.png"), b"png").unwrap();
+        fs::write(dir.join("other.png"), b"png").unwrap();
+
+        let mut scope = PathScope::default();
+        scope.allow_file(&dir.join("imported.png"));
+        scope.allow_folder(&dir.join("picked"));
+        assert!(scope.contains(&dir.join("imported.png")));
+        assert!(!scope.contains(&dir.join("other.png")));
+        // Folders cover what doesn't exist yet, like a pack about to be exported into them.
+        assert!(scope.contains(&dir.join("picked/My Pack/images")));
+        assert!(!scope.contains(&dir.join("picked/../other.png")));
+        assert!(scope.check(&dir.join("other.png").to_string_lossy()).is_err());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
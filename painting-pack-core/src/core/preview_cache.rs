@@ -0,0 +1,87 @@
+use std::fs::{read, remove_file, rename, write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use image::DynamicImage;
+use crate::core::{exporter, preview_key::PreviewKey};
+use crate::models::image_size::ImageSize;
+
+// Numbers the temp files of this process, so concurrent writers never share one.
+static NEXT_TEMP_FILE: AtomicU64 = AtomicU64::new(0);
+
+// One cached preview, named after its key so different crop settings get different files.
+fn preview_path(cache_dir: &Path, key: &PreviewKey, crop_index: usize) -> PathBuf {
+    cache_dir.join(format!("preview-{}-{}.png", key.file_tag(), crop_index))
+}
+
+/*
+Writes `bytes` to `path` through a temp file unique to this write, then renames it into place.
+Readers only ever see a missing or a complete file, even while another job writes the same
+preview; the rename of whichever finishes last wins, and both wrote the same pixels.
+*/
+fn write_atomically(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let temp_path = path.with_extension(format!(
+        "png.{}-{}.tmp",
+        std::process::id(),
+        NEXT_TEMP_FILE.fetch_add(1, Ordering::Relaxed)
+    ));
+    write(&temp_path, bytes).map_err(|e| format!("Failed to write {}: {}", temp_path.display(), e))?;
+    rename(&temp_path, path).map_err(|e| {
+        let _ = remove_file(&temp_path);
+        format!("Failed to move {} into place: {}", path.display(), e)
+    })
+}
+
+// The previews cached under `key` as Data URIs, one per shape, or `None` if any is missing.
+pub fn load(cache_dir: &Path, key: &PreviewKey) -> Option<Vec<String>> {
+    (0..ImageSize::iter().len())
+        .map(|crop_index| read(preview_path(cache_dir, key, crop_index)).ok().map(|png| exporter::preview_data_uri(&png)))
+        .collect()
+}
+
+/*
+Encodes the crops of one entry as previews, caches them under `key` and returns them as Data
+URIs. A preview that can't be cached is still returned; it is just made again next time.
+*/
+pub fn store(cache_dir: &Path, key: &PreviewKey, crops: &[DynamicImage]) -> Vec<String> {
+    crops
+        .iter()
+        .enumerate()
+        .map(|(crop_index, crop)| {
+            let png = exporter::encode_preview_png(crop);
+            if let Err(e) = write_atomically(&preview_path(cache_dir, key, crop_index), &png) {
+                eprintln!("[PREVIEW] {}", e);
+            }
+            exporter::preview_data_uri(&png)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+    use std::fs;
+
+    #[test]
+    fn test_previews_are_cached_per_crop_settings() {
+        let dir = std::env::temp_dir().join(format!("preview_cache_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("source.png");
+        fs::write(&source, b"source").unwrap();
+        let plain = PreviewKey::new(&source, &false);
+        let deskewed = PreviewKey::new(&source, &true);
+
+        let crops = |colour: u8| -> Vec<DynamicImage> {
+            ImageSize::iter().map(|_| DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, Rgba([colour, 0, 0, 255])))).collect()
+        };
+        assert!(load(&dir, &plain).is_none());
+        let stored = store(&dir, &plain, &crops(10));
+        store(&dir, &deskewed, &crops(200));
+
+        assert_eq!(load(&dir, &plain), Some(stored));
+        assert_ne!(load(&dir, &plain), load(&dir, &deskewed));
+        // No temp files are left behind.
+        assert!(fs::read_dir(&dir).unwrap().flatten().all(|entry| !entry.file_name().to_string_lossy().ends_with(".tmp")));
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs::metadata;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::time::SystemTime;
+
+/*
+Everything a library entry's crop previews depend on: the source file's path, modification time
+and size, and a hash of the settings that change how it is cropped. Previews made under one key
+are stale as soon as the current key differs, whether the file was edited outside the app or a
+crop setting changed inside it.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PreviewKey {
+    source: u64,
+    // `None` if the file couldn't be read, e.g. it was moved.
+    modified: Option<SystemTime>,
+    len: u64,
+    crop_settings: u64,
+}
+
+impl PreviewKey {
+    pub fn new(source: &Path, crop_settings: &impl Hash) -> Self {
+        let meta = metadata(source).ok();
+        let mut hasher = DefaultHasher::new();
+        crop_settings.hash(&mut hasher);
+        let mut source_hasher = DefaultHasher::new();
+        source.hash(&mut source_hasher);
+        PreviewKey {
+            source: source_hasher.finish(),
+            modified: meta.as_ref().and_then(|meta| meta.modified().ok()),
+            len: meta.map(|meta| meta.len()).unwrap_or(0),
+            crop_settings: hasher.finish(),
+        }
+    }
+
+    /*
+    The key as a short hex string for naming cached preview files. Two jobs previewing the same
+    source with different crop settings always get different tags, so their files never mix.
+    */
+    pub fn file_tag(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+
+    #[test]
+    fn test_key_changes_with_file_and_settings() {
+        let path = env::temp_dir().join(format!("preview_key_test_{}.png", std::process::id()));
+        fs::write(&path, b"before").unwrap();
+        let key = PreviewKey::new(&path, &false);
+        assert_eq!(PreviewKey::new(&path, &false), key);
+        assert_ne!(PreviewKey::new(&path, &true), key);
+        assert_ne!(PreviewKey::new(&path, &true).file_tag(), key.file_tag());
+
+        fs::write(&path, b"after the edit").unwrap();
+        assert_ne!(PreviewKey::new(&path, &false), key);
+
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,117 @@
+use std::io::Cursor;
+use image::{imageops, DynamicImage, ImageFormat, Rgba, RgbaImage};
+use crate::core::{cropper, exporter::ExportItem, flipbook, font, resample};
+
+// Each block of a painting is drawn this many pixels wide, so paintings keep their relative size.
+pub const BLOCK_PIXELS: u32 = 48;
+// Written into the pack folder when `ExportSettings::preview_strip` is on.
+pub const PREVIEW_STRIP_FILE: &str = "preview.png";
+const GAP: u32 = 16;
+const LABEL_SCALE: u32 = 2;
+const BACKGROUND: Rgba<u8> = Rgba([32, 32, 36, 255]);
+const LABEL_COLOUR: Rgba<u8> = Rgba([230, 230, 230, 255]);
+
+// One painting on the strip: its crop, its size in blocks and the label printed under it.
+#[derive(Debug, Clone)]
+pub struct StripEntry {
+    pub image: DynamicImage,
+    pub blocks: (u32, u32),
+    pub label: String,
+}
+
+// The longest start of `label` that fits in `max_width` pixels at `LABEL_SCALE`.
+fn fit_label(label: &str, max_width: u32) -> String {
+    let mut fitted = String::new();
+    for c in label.chars() {
+        fitted.push(c);
+        if font::line_width(&fitted) * LABEL_SCALE > max_width {
+            fitted.pop();
+            break;
+        }
+    }
+    fitted
+}
+
+/*
+Draws every entry left to right on one long image, each scaled to its size in blocks and standing
+on a shared baseline with its label underneath, so a whole pack can be judged at a glance. Labels
+too long for their painting are cut off.
+*/
+pub fn render_strip(entries: &[StripEntry]) -> DynamicImage {
+    let label_height = font::LINE_HEIGHT * LABEL_SCALE;
+    let tallest = entries.iter().map(|entry| entry.blocks.1 * BLOCK_PIXELS).max().unwrap_or(0);
+    let width = GAP + entries.iter().map(|entry| entry.blocks.0 * BLOCK_PIXELS + GAP).sum::<u32>();
+    let height = GAP + tallest + GAP / 2 + label_height + GAP;
+    let mut canvas = RgbaImage::from_pixel(width, height, BACKGROUND);
+
+    let mut x = GAP;
+    for entry in entries {
+        let (w, h) = (entry.blocks.0 * BLOCK_PIXELS, entry.blocks.1 * BLOCK_PIXELS);
+        let art = resample::resize_exact(&entry.image, w, h);
+        imageops::overlay(&mut canvas, &art.to_rgba8(), x as i64, (GAP + tallest - h) as i64);
+        let label = fit_label(&entry.label, w);
+        font::draw_text(&mut canvas, &label, x as i64, (GAP + tallest + GAP / 2) as i64, LABEL_SCALE, LABEL_COLOUR, false);
+        x += w + GAP;
+    }
+    DynamicImage::ImageRgba8(canvas)
+}
+
+/*
+The strip for a list of export items, each at its smallest block size and labelled with its name.
+Flipbooks show their first frame. Items whose source can't be read are left out.
+*/
+pub fn strip_for_items(items: &[ExportItem]) -> DynamicImage {
+    let entries: Vec<StripEntry> = items
+        .iter()
+        .filter_map(|item| {
+            let options = item.data.crop_options();
+            let crop = match cropper::crop_single_image(&item.source_path, &item.data.image_size, &options) {
+                Ok(crop) => crop,
+                Err(e) => {
+                    eprintln!("[PREVIEW] Leaving {} off the preview strip: {}", item.source_path, e);
+                    return None;
+                }
+            };
+            Some(StripEntry {
+                image: flipbook::frame(&crop, options.frames, 0),
+                blocks: item.data.get_sizes()[0],
+                label: item.data.name.clone().unwrap_or_default(),
+            })
+        })
+        .collect();
+    render_strip(&entries)
+}
+
+// Encodes the strip at full size; unlike crop previews it is never scaled down.
+pub fn encode_png(strip: &DynamicImage) -> Result<Vec<u8>, String> {
+    let mut png = Vec::new();
+    strip
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode the preview strip: {}", e))?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::GenericImageView;
+
+    #[test]
+    fn test_strip_keeps_relative_scale_on_a_shared_baseline() {
+        let red = DynamicImage::ImageRgba8(RgbaImage::from_pixel(10, 10, Rgba([255, 0, 0, 255])));
+        let blue = DynamicImage::ImageRgba8(RgbaImage::from_pixel(10, 20, Rgba([0, 0, 255, 255])));
+        let strip = render_strip(&[
+            StripEntry { image: red, blocks: (1, 1), label: String::from("A very long painting name") },
+            StripEntry { image: blue, blocks: (1, 2), label: String::from("Tall") },
+        ]);
+
+        let tallest = 2 * BLOCK_PIXELS;
+        assert_eq!(strip.dimensions(), (3 * GAP + 2 * BLOCK_PIXELS, GAP + tallest + GAP / 2 + font::LINE_HEIGHT * LABEL_SCALE + GAP));
+        // Both stand on the baseline; the square's top half is background.
+        let baseline = GAP + tallest - 1;
+        assert_eq!(strip.get_pixel(GAP, baseline), Rgba([255, 0, 0, 255]));
+        assert_eq!(strip.get_pixel(GAP, GAP), BACKGROUND);
+        assert_eq!(strip.get_pixel(2 * GAP + BLOCK_PIXELS, GAP), Rgba([0, 0, 255, 255]));
+        assert!(font::line_width(&fit_label("A very long painting name", BLOCK_PIXELS)) * LABEL_SCALE <= BLOCK_PIXELS);
+    }
+}
@@ -0,0 +1,140 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Deserialize;
+use crate::models::image_size::ImageSize;
+
+// Longest side of a generated pattern painting, matching the exporter's texture cap.
+const PATTERN_LONG_SIDE: u32 = 1024;
+
+// A procedural source for minimalist decorative paintings. Colours are RGBA.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub enum Pattern {
+    Solid { colour: [u8; 4] },
+    // `angle` is in degrees; 0 runs left to right, 90 top to bottom.
+    LinearGradient { from: [u8; 4], to: [u8; 4], angle: f32 },
+    // Runs from `inner` at the centre to `outer` at the corners.
+    RadialGradient { inner: [u8; 4], outer: [u8; 4] },
+    // Value noise blending between two colours. `cells` is how many noise cells span the long
+    // side; `smooth` interpolates between cells instead of leaving them as hard-edged blocks.
+    Noise { low: [u8; 4], high: [u8; 4], cells: u32, seed: u64, smooth: bool },
+}
+
+impl Pattern {
+    // Short description used to name the generated files.
+    pub fn file_stem(&self) -> &'static str {
+        match self {
+            Pattern::Solid { .. } => "solid",
+            Pattern::LinearGradient { .. } => "linear_gradient",
+            Pattern::RadialGradient { .. } => "radial_gradient",
+            Pattern::Noise { .. } => "noise",
+        }
+    }
+}
+
+fn lerp(from: [u8; 4], to: [u8; 4], t: f32) -> Rgba<u8> {
+    let t = t.clamp(0.0, 1.0);
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Rgba([mix(from[0], to[0]), mix(from[1], to[1]), mix(from[2], to[2]), mix(from[3], to[3])])
+}
+
+// Smoothstep easing so interpolated noise has no visible grid lines.
+fn ease(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn noise(width: u32, height: u32, low: [u8; 4], high: [u8; 4], cells: u32, seed: u64, smooth: bool) -> RgbaImage {
+    let cell_size = (width.max(height) as f32 / cells.max(1) as f32).max(1.0);
+    let columns = (width as f32 / cell_size).ceil() as usize + 1;
+    let rows = (height as f32 / cell_size).ceil() as usize + 1;
+
+    // Random lattice values; the same seed always produces the same painting.
+    let mut rng = StdRng::seed_from_u64(seed);
+    let lattice: Vec<f32> = (0..columns * rows).map(|_| rng.random::<f32>()).collect();
+    let value = |column: usize, row: usize| lattice[row.min(rows - 1) * columns + column.min(columns - 1)];
+
+    RgbaImage::from_fn(width, height, |x, y| {
+        let (fx, fy) = (x as f32 / cell_size, y as f32 / cell_size);
+        let (column, row) = (fx as usize, fy as usize);
+        let t = if smooth {
+            let (tx, ty) = (ease(fx.fract()), ease(fy.fract()));
+            let top = value(column, row) + (value(column + 1, row) - value(column, row)) * tx;
+            let bottom = value(column, row + 1) + (value(column + 1, row + 1) - value(column, row + 1)) * tx;
+            top + (bottom - top) * ty
+        } else {
+            value(column, row)
+        };
+        lerp(low, high, t)
+    })
+}
+
+/*
+Renders the pattern at the shape of one painting size.
+*/
+pub fn render_pattern(pattern: &Pattern, size: ImageSize) -> DynamicImage {
+    let (width, height) = size.dimensions_for_long_side(PATTERN_LONG_SIDE);
+
+    let canvas = match pattern {
+        Pattern::Solid { colour } => RgbaImage::from_pixel(width, height, Rgba(*colour)),
+        Pattern::LinearGradient { from, to, angle } => {
+            let (dx, dy) = (angle.to_radians().cos(), angle.to_radians().sin());
+            // Project every corner onto the gradient direction so the colours span the whole image.
+            let corners = [(0.0, 0.0), (width as f32, 0.0), (0.0, height as f32), (width as f32, height as f32)];
+            let projections: Vec<f32> = corners.iter().map(|(x, y)| x * dx + y * dy).collect();
+            let min = projections.iter().cloned().fold(f32::MAX, f32::min);
+            let max = projections.iter().cloned().fold(f32::MIN, f32::max);
+            let span = (max - min).max(1.0);
+            RgbaImage::from_fn(width, height, |x, y| lerp(*from, *to, ((x as f32 + 0.5) * dx + (y as f32 + 0.5) * dy - min) / span))
+        }
+        Pattern::RadialGradient { inner, outer } => {
+            let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+            let radius = (cx * cx + cy * cy).sqrt();
+            RgbaImage::from_fn(width, height, |x, y| {
+                let (px, py) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+                lerp(*inner, *outer, (px * px + py * py).sqrt() / radius)
+            })
+        }
+        Pattern::Noise { low, high, cells, seed, smooth } => noise(width, height, *low, *high, *cells, *seed, *smooth),
+    };
+
+    DynamicImage::ImageRgba8(canvas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solid_and_linear_gradient() {
+        let solid = render_pattern(&Pattern::Solid { colour: [10, 20, 30, 255] }, ImageSize::Wide).to_rgba8();
+        assert_eq!(solid.dimensions(), (1024, 512));
+        assert_eq!(solid.get_pixel(700, 300).0, [10, 20, 30, 255]);
+
+        let gradient = Pattern::LinearGradient { from: [0, 0, 0, 255], to: [255, 255, 255, 255], angle: 0.0 };
+        let image = render_pattern(&gradient, ImageSize::Square).to_rgba8();
+        assert!(image.get_pixel(0, 500).0[0] < 5);
+        assert!(image.get_pixel(1023, 500).0[0] > 250);
+        // A horizontal gradient doesn't change down a column
+        assert_eq!(image.get_pixel(300, 0), image.get_pixel(300, 1023));
+    }
+
+    #[test]
+    fn test_radial_gradient_centre_to_corner() {
+        let pattern = Pattern::RadialGradient { inner: [255, 0, 0, 255], outer: [0, 0, 255, 255] };
+        let image = render_pattern(&pattern, ImageSize::Tall).to_rgba8();
+        assert_eq!(image.dimensions(), (512, 1024));
+        assert!(image.get_pixel(256, 512).0[0] > 250);
+        assert!(image.get_pixel(0, 0).0[2] > 250);
+    }
+
+    #[test]
+    fn test_noise_is_deterministic_per_seed() {
+        let pattern = |seed| Pattern::Noise { low: [0, 0, 0, 255], high: [255, 255, 255, 255], cells: 8, seed, smooth: false };
+        let a = render_pattern(&pattern(7), ImageSize::Square).to_rgba8();
+        let b = render_pattern(&pattern(7), ImageSize::Square).to_rgba8();
+        let c = render_pattern(&pattern(8), ImageSize::Square).to_rgba8();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        // Blocky noise is constant within a cell (1024 / 8 = 128px)
+        assert_eq!(a.get_pixel(1, 1), a.get_pixel(126, 126));
+    }
+}
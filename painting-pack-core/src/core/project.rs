@@ -0,0 +1,158 @@
+use std::fs::{create_dir_all, write};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::core::export_history::ExportHistory;
+use crate::core::history::{GroupSnapshot, History};
+use crate::core::migration::{self, PROJECT_EXTENSION, PROJECT_SCHEMA};
+use crate::models::{export_settings::ExportSettings, import_settings::ImportSettings};
+
+/*
+A whole working session saved to a `.ppmproj` file: the pack's metadata, its export and import
+settings, every image in the library with its metadata and selected sizes, and the edit history
+so rollbacks still work after reopening. Like snapshots it
+only stores source paths, never pixels, so the sources have to stay where they are (or be
+relinked) for the project to open with previews. Older files are upgraded through
+`migration::PROJECT_SCHEMA` when they are loaded.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Project {
+    pub pack_name: String,
+    pub version: String,
+    pub pack_id: String,
+    pub description: String,
+    #[serde(default)]
+    pub export_settings: ExportSettings,
+    #[serde(default)]
+    pub import_settings: ImportSettings,
+    // Library rows in order.
+    pub groups: Vec<GroupSnapshot>,
+    // Every finished export of the project, for re-exporting with the same settings.
+    #[serde(default)]
+    pub export_history: ExportHistory,
+    // Snapshots to roll back to, including the one taken when the project was saved.
+    #[serde(default)]
+    pub history: History,
+}
+
+impl Project {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        migration::load(path, &PROJECT_SCHEMA)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let contents = migration::to_versioned_json(self, &PROJECT_SCHEMA)?;
+        write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+// `path` with the project extension, added if the save dialog left it off.
+pub fn project_path(path: &Path) -> PathBuf {
+    if path.extension().is_some_and(|extension| extension == PROJECT_EXTENSION) {
+        path.to_path_buf()
+    } else {
+        let mut with_extension = path.as_os_str().to_owned();
+        with_extension.push(format!(".{}", PROJECT_EXTENSION));
+        PathBuf::from(with_extension)
+    }
+}
+
+// Where the session is autosaved while `path` is its project file, e.g. `gallery.ppmproj.autosave`.
+pub fn autosave_path(path: &Path) -> PathBuf {
+    let mut autosave = path.as_os_str().to_owned();
+    autosave.push(".autosave");
+    PathBuf::from(autosave)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::history::ProjectSnapshot;
+    use crate::models::image_size::ImageSize;
+
+    #[test]
+    fn test_project_round_trips_through_its_file() {
+        let dir = std::env::temp_dir().join(format!("project_test_{}", std::process::id()));
+        let path = project_path(&dir.join("gallery"));
+        assert_eq!(path, dir.join("gallery.ppmproj"));
+        assert_eq!(project_path(&path), path);
+
+        let project = Project {
+            pack_name: String::from("Gallery"),
+            version: String::from("1.2.0"),
+            pack_id: String::from("gallery"),
+            description: String::from("Autumn drop"),
+            export_settings: ExportSettings::default(),
+            import_settings: ImportSettings { normalize: true, ..ImportSettings::default() },
+            groups: vec![GroupSnapshot {
+                source_path: String::from("/photos/sunset.jpg"),
+                name: String::from("Sunset"),
+                artist: String::from("Ana"),
+                selected_sizes: vec![ImageSize::Square],
+                deskew: true,
+                original_path: None,
+                full_quality_path: None,
+                working_copy_source: None,
+                order: 1,
+                content_hash: None,
+                description: String::from("Warm"),
+                inset_percent: 2.0,
+                bleed_percent: 0.0,
+                frame: None,
+                weight: Some(3),
+                animation: None,
+                size_adjustments: Vec::new(),
+                crop_offsets: Vec::new(),
+                crop_rects: Vec::new(),
+                fit_modes: Vec::new(),
+            }],
+            export_history: ExportHistory::default(),
+            history: History::default(),
+        };
+        project.save(&path).unwrap();
+        assert_eq!(Project::load(&path).unwrap(), project);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_history_round_trips_and_defaults_when_missing() {
+        let dir = std::env::temp_dir().join(format!("project_history_test_{}", std::process::id()));
+        let path = dir.join("gallery.ppmproj");
+        let mut history = History::default();
+        let saved = history.push(ProjectSnapshot {
+            snapshot_id: 0,
+            created_at: 1_700_000_000,
+            reason: String::from("save"),
+            pack_name: String::from("Gallery"),
+            version: String::from("1.0.0"),
+            pack_id: String::from("gallery"),
+            description: String::new(),
+            export_settings: ExportSettings::default(),
+            groups: Vec::new(),
+        });
+        let project = Project {
+            pack_name: String::from("Gallery"),
+            version: String::from("1.0.0"),
+            pack_id: String::from("gallery"),
+            description: String::new(),
+            export_settings: ExportSettings::default(),
+            import_settings: ImportSettings::default(),
+            groups: Vec::new(),
+            export_history: ExportHistory::default(),
+            history,
+        };
+        project.save(&path).unwrap();
+        let loaded = Project::load(&path).unwrap();
+        assert_eq!(loaded.history.get(saved).unwrap().reason, "save");
+        assert_eq!(loaded, project);
+
+        // Projects saved before history was kept open with an empty one.
+        let mut json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        json.as_object_mut().unwrap().remove("history");
+        std::fs::write(&path, json.to_string()).unwrap();
+        assert_eq!(Project::load(&path).unwrap().history, History::default());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
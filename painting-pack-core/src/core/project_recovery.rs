@@ -0,0 +1,373 @@
+use std::fs::{copy, read_to_string};
+use std::path::Path;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use crate::core::history::GroupSnapshot;
+use crate::core::migration::{self, PROJECT_SCHEMA};
+use crate::core::project::{self, Project};
+
+/*
+What opening a damaged project file kept and what it couldn't. Parts that couldn't be read from
+the file were taken from the project's autosave when there was one, otherwise they are lost and
+fall back to their defaults (or, for images, are left out).
+*/
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RecoveryReport {
+    // Where the damaged file was copied before anything else happened to it.
+    pub backup_path: String,
+    pub autosave_used: bool,
+    // Images read from the project file itself.
+    pub images_salvaged: usize,
+    // What came from the autosave instead, e.g. "description" or "image \"Sunset\"".
+    pub from_autosave: Vec<String>,
+    // What neither the file nor the autosave could supply.
+    pub lost: Vec<String>,
+}
+
+// One member of a JSON object or element of an array, as far as the text goes.
+struct Entry<'a> {
+    // `None` for array elements.
+    key: Option<String>,
+    raw: &'a str,
+    // The text ended before the value did.
+    cut_off: bool,
+}
+
+// Where the JSON value starting at `start` ends (exclusive), or `None` if the text ends first.
+fn value_end(text: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, &byte) in text.iter().enumerate().skip(start) {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if byte == b'\\' {
+                escaped = true;
+            } else if byte == b'"' {
+                in_string = false;
+                if depth == 0 {
+                    return Some(index + 1);
+                }
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'{' | b'[' => depth += 1,
+            // A closing bracket at depth 0 belongs to the enclosing value and ends a bare scalar.
+            b'}' | b']' if depth == 0 => return Some(index),
+            b'}' | b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index + 1);
+                }
+            }
+            b',' if depth == 0 => return Some(index),
+            _ if depth == 0 && byte.is_ascii_whitespace() => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn skip_whitespace(text: &[u8], mut index: usize) -> usize {
+    while text.get(index).is_some_and(|byte| byte.is_ascii_whitespace()) {
+        index += 1;
+    }
+    index
+}
+
+/*
+The members (or elements) of the object (or array) whose opening bracket is at `open`, read one at
+a time so everything before the first damaged spot survives. Stops at the end of the text, the
+closing bracket, or the first thing that isn't valid JSON structure.
+*/
+fn scan(text: &str, open: usize) -> Vec<Entry<'_>> {
+    let bytes = text.as_bytes();
+    let is_object = bytes.get(open) == Some(&b'{');
+    let close = if is_object { b'}' } else { b']' };
+    let mut entries = Vec::new();
+    let mut index = open + 1;
+    loop {
+        index = skip_whitespace(bytes, index);
+        if index >= bytes.len() || bytes[index] == close {
+            break;
+        }
+        let key = if is_object {
+            let Some(end) = value_end(bytes, index) else { break };
+            let Ok(key) = serde_json::from_str::<String>(&text[index..end]) else { break };
+            index = skip_whitespace(bytes, end);
+            if bytes.get(index) != Some(&b':') {
+                break;
+            }
+            index = skip_whitespace(bytes, index + 1);
+            Some(key)
+        } else {
+            None
+        };
+        if index >= bytes.len() {
+            break;
+        }
+        let Some(end) = value_end(bytes, index) else {
+            entries.push(Entry { key, raw: &text[index..], cut_off: true });
+            break;
+        };
+        entries.push(Entry { key, raw: &text[index..end], cut_off: false });
+        index = skip_whitespace(bytes, end);
+        if bytes.get(index) != Some(&b',') {
+            break;
+        }
+        index += 1;
+    }
+    entries
+}
+
+// A string member of a (possibly damaged) object, e.g. an image's name.
+fn string_member(raw: &str, key: &str) -> Option<String> {
+    let open = raw.find('{')?;
+    scan(raw, open)
+        .into_iter()
+        .find(|entry| entry.key.as_deref() == Some(key) && !entry.cut_off)
+        .and_then(|entry| serde_json::from_str(entry.raw).ok())
+}
+
+// How an image is named in the report.
+fn describe_image(name: Option<String>, position: usize) -> String {
+    match name {
+        Some(name) => format!("image \"{}\"", name),
+        None => format!("image {}", position + 1),
+    }
+}
+
+// Collects what the salvage kept, borrowed or lost.
+struct Salvage {
+    object: Map<String, Value>,
+    autosave: Option<Project>,
+    from_autosave: Vec<String>,
+    lost: Vec<String>,
+}
+
+impl Salvage {
+    /*
+    The field from the file if it reads, else from the autosave, else its default. `label` is how
+    the field is named in the report.
+    */
+    fn field<T: DeserializeOwned + Default>(&mut self, key: &str, label: &str, from_autosave: impl Fn(&Project) -> T) -> T {
+        if let Some(value) = self.object.get(key).and_then(|value| serde_json::from_value(value.clone()).ok()) {
+            return value;
+        }
+        match &self.autosave {
+            Some(autosave) => {
+                self.from_autosave.push(label.to_string());
+                from_autosave(autosave)
+            }
+            None => {
+                self.lost.push(label.to_string());
+                T::default()
+            }
+        }
+    }
+}
+
+/*
+Reads as much of a damaged project file as it can: every top-level field and every image that
+still parses is kept, and what doesn't is taken from the project's autosave if there is one. Images
+are matched to the autosave by source path; when the image list was cut short, the autosave's
+images that come after it are added back. The damaged file is backed up first. Fails if the file
+can't be read at all, is from a newer version of the app, or nothing could be recovered.
+*/
+pub fn recover(path: &Path) -> Result<(Project, RecoveryReport), String> {
+    let text = read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let autosave = Project::load(&project::autosave_path(path)).ok();
+    let members = text.find('{').map(|open| scan(&text, open)).unwrap_or_default();
+    if members.is_empty() && autosave.is_none() {
+        return Err(format!("Nothing in {} could be recovered", path.display()));
+    }
+
+    let backup = migration::backup_path(path, "corrupt");
+    copy(path, &backup).map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+
+    // Images stay raw text until the rest of the document has been upgraded.
+    let mut object = Map::new();
+    let mut image_entries: Option<(Vec<Entry>, bool)> = None;
+    for member in &members {
+        match member.key.as_deref() {
+            Some("groups") if member.raw.starts_with('[') => {
+                let elements = scan(member.raw, 0);
+                let list_complete = !member.cut_off;
+                image_entries = Some((elements, list_complete));
+            }
+            Some(key) if !member.cut_off => {
+                if let Ok(value) = serde_json::from_str::<Value>(member.raw) {
+                    object.insert(key.to_string(), value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Images that parse go through the same upgrade as the rest; the others are looked up below.
+    let (elements, list_complete) = image_entries.unwrap_or((Vec::new(), false));
+    let mut parsed_positions = Vec::new();
+    let mut parsed = Vec::new();
+    for (position, element) in elements.iter().enumerate() {
+        if element.cut_off {
+            continue;
+        }
+        if let Ok(value) = serde_json::from_str::<Value>(element.raw) {
+            parsed_positions.push(position);
+            parsed.push(value);
+        }
+    }
+    object.insert(String::from("groups"), Value::Array(parsed));
+    let mut document = Value::Object(object);
+    migration::upgrade(&mut document, &PROJECT_SCHEMA).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let Value::Object(mut object) = document else { unreachable!("upgrade keeps the document an object") };
+    let upgraded = match object.remove("groups") {
+        Some(Value::Array(upgraded)) => upgraded,
+        _ => Vec::new(),
+    };
+
+    let mut salvage = Salvage { object, autosave, from_autosave: Vec::new(), lost: Vec::new() };
+    let mut groups: Vec<GroupSnapshot> = Vec::new();
+    let mut images_salvaged = 0;
+    for (position, element) in elements.iter().enumerate() {
+        let read = parsed_positions
+            .iter()
+            .position(|parsed_position| *parsed_position == position)
+            .and_then(|index| serde_json::from_value::<GroupSnapshot>(upgraded[index].clone()).ok());
+        if let Some(group) = read {
+            groups.push(group);
+            images_salvaged += 1;
+            continue;
+        }
+        let label = describe_image(string_member(element.raw, "name"), position);
+        let source_path = string_member(element.raw, "source_path");
+        let from_autosave = salvage.autosave.as_ref().and_then(|autosave| {
+            autosave.groups.iter().find(|group| source_path.as_deref() == Some(group.source_path.as_str())).cloned()
+        });
+        match from_autosave {
+            Some(group) => {
+                salvage.from_autosave.push(label);
+                groups.push(group);
+            }
+            None => salvage.lost.push(label),
+        }
+    }
+    // The rest of a list that was cut short (or is missing) can only come from the autosave.
+    if !list_complete {
+        match &salvage.autosave {
+            Some(autosave) => {
+                for group in &autosave.groups {
+                    if !groups.iter().any(|kept| kept.source_path == group.source_path) {
+                        salvage.from_autosave.push(describe_image(Some(group.name.clone()), groups.len()));
+                        groups.push(group.clone());
+                    }
+                }
+            }
+            None => salvage.lost.push(String::from("any images after the last one recovered")),
+        }
+    }
+
+    let project = Project {
+        pack_name: salvage.field("pack_name", "pack name", |autosave| autosave.pack_name.clone()),
+        version: salvage.field("version", "version", |autosave| autosave.version.clone()),
+        pack_id: salvage.field("pack_id", "pack ID", |autosave| autosave.pack_id.clone()),
+        description: salvage.field("description", "description", |autosave| autosave.description.clone()),
+        export_settings: salvage.field("export_settings", "export settings", |autosave| autosave.export_settings.clone()),
+        import_settings: salvage.field("import_settings", "import settings", |autosave| autosave.import_settings.clone()),
+        export_history: salvage.field("export_history", "export history", |autosave| autosave.export_history.clone()),
+        history: salvage.field("history", "edit history", |autosave| autosave.history.clone()),
+        groups,
+    };
+    let report = RecoveryReport {
+        backup_path: backup.to_string_lossy().to_string(),
+        autosave_used: !salvage.from_autosave.is_empty(),
+        images_salvaged,
+        from_autosave: salvage.from_autosave,
+        lost: salvage.lost,
+    };
+    Ok((project, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::image_size::ImageSize;
+
+    fn group(name: &str) -> GroupSnapshot {
+        GroupSnapshot {
+            source_path: format!("/photos/{}.png", name),
+            name: name.to_string(),
+            artist: String::from("Ana"),
+            selected_sizes: vec![ImageSize::Square],
+            deskew: false,
+            original_path: None,
+            full_quality_path: None,
+            working_copy_source: None,
+            order: 0,
+            content_hash: None,
+            description: String::new(),
+            inset_percent: 0.0,
+            bleed_percent: 0.0,
+            frame: None,
+            weight: None,
+            animation: None,
+            size_adjustments: Vec::new(),
+            crop_offsets: Vec::new(),
+            crop_rects: Vec::new(),
+            fit_modes: Vec::new(),
+        }
+    }
+
+    fn project(description: &str, names: &[&str]) -> Project {
+        Project {
+            pack_name: String::from("Gallery"),
+            version: String::from("1.0.0"),
+            pack_id: String::from("gallery"),
+            description: description.to_string(),
+            export_settings: Default::default(),
+            import_settings: Default::default(),
+            groups: names.iter().map(|name| group(name)).collect(),
+            export_history: Default::default(),
+            history: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_truncated_project_is_completed_from_its_autosave() {
+        let dir = std::env::temp_dir().join(format!("project_recovery_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("gallery.ppmproj");
+
+        // The saved file has a third image the autosave hasn't seen yet, and is cut off inside it.
+        let saved = migration::to_versioned_json(&project("Autumn", &["Sunset", "Harbour", "Dunes"]), &PROJECT_SCHEMA).unwrap();
+        let cut = saved.rfind("\"artist\"").unwrap();
+        std::fs::write(&path, &saved[..cut]).unwrap();
+        assert!(Project::load(&path).is_err());
+
+        // Without an autosave, whatever came after the damage is lost.
+        let (recovered, report) = recover(&path).unwrap();
+        assert_eq!(recovered.pack_name, "Gallery");
+        let names: Vec<&str> = recovered.groups.iter().map(|group| group.name.as_str()).collect();
+        assert_eq!(names, ["Sunset", "Harbour"]);
+        assert_eq!(report.images_salvaged, 2);
+        assert!(!report.autosave_used);
+        assert!(report.lost.contains(&String::from("image \"Dunes\"")));
+        assert!(Path::new(&report.backup_path).exists());
+
+        project("Older", &["Sunset", "Harbour", "Dunes", "Meadow"]).save(&project::autosave_path(&path)).unwrap();
+        let (recovered, report) = recover(&path).unwrap();
+        let names: Vec<&str> = recovered.groups.iter().map(|group| group.name.as_str()).collect();
+        assert_eq!(names, ["Sunset", "Harbour", "Dunes", "Meadow"]);
+        assert_eq!(report.from_autosave, ["image \"Dunes\"", "image \"Meadow\"", "export history", "edit history"]);
+        assert!(report.lost.is_empty());
+
+        std::fs::write(&path, "not a project").unwrap();
+        std::fs::remove_file(project::autosave_path(&path)).unwrap();
+        assert!(recover(&path).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
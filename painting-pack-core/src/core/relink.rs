@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use crate::core::{hot_folder, working_copy};
+
+// How a moved source was recognised.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RelinkMatch {
+    // Same bytes as the file that was imported, whatever it is called now.
+    Hash,
+    // Only file name matched, either because the import wasn't hashed or the file has been edited since.
+    Name,
+}
+
+// A source that can no longer be found: where it was, and the hash of the file that was imported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LostSource {
+    pub path: String,
+    pub content_hash: Option<String>,
+}
+
+// One source pointed at its new location.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RelinkedSource {
+    pub name: String,
+    pub from: String,
+    pub to: String,
+    pub matched_by: RelinkMatch,
+}
+
+// What a relink repaired, and the names of the paintings whose sources are still missing.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct RelinkReport {
+    pub relinked: Vec<RelinkedSource>,
+    pub still_missing: Vec<String>,
+}
+
+// Every supported image under `root`, in subfolders too.
+fn images_under(root: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut images = Vec::new();
+    let mut folders = vec![root.to_path_buf()];
+    while let Some(folder) = folders.pop() {
+        let entries = read_dir(&folder).map_err(|e| format!("Failed to read {}: {}", folder.display(), e))?;
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                folders.push(path);
+            } else if hot_folder::is_supported_image(&path) {
+                images.push(path);
+            }
+        }
+    }
+    images.sort();
+    Ok(images)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().to_lowercase()
+}
+
+/*
+Looks for each lost source under `root`. A file with the same hash wins, first among the files
+of the same name and then among all of them, so renamed files are found too; only sources whose
+hash matches nothing fall back to a unique file of the same name. Files are hashed at most once
+and only when needed. Returns one entry per lost source, `None` where nothing matched.
+*/
+pub fn find_replacements(lost: &[LostSource], root: &Path) -> Result<Vec<Option<(PathBuf, RelinkMatch)>>, String> {
+    let images = images_under(root)?;
+    let mut hashes: HashMap<PathBuf, Option<String>> = HashMap::new();
+    let mut hash_of = |path: &Path| -> Option<String> {
+        hashes.entry(path.to_path_buf()).or_insert_with(|| working_copy::content_hash(path).ok()).clone()
+    };
+
+    Ok(lost
+        .iter()
+        .map(|source| {
+            let name = file_name(Path::new(&source.path));
+            let same_name: Vec<&PathBuf> = images.iter().filter(|image| file_name(image) == name).collect();
+            if let Some(hash) = &source.content_hash {
+                let by_hash = same_name
+                    .iter()
+                    .copied()
+                    .chain(images.iter())
+                    .find(|image| hash_of(image).as_ref() == Some(hash));
+                if let Some(image) = by_hash {
+                    return Some((image.clone(), RelinkMatch::Hash));
+                }
+            }
+            match same_name.as_slice() {
+                [only] => Some(((*only).clone(), RelinkMatch::Name)),
+                _ => None,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_lost_sources_are_found_by_hash_then_name() {
+        let root = std::env::temp_dir().join(format!("relink_test_{}", std::process::id()));
+        fs::create_dir_all(root.join("nested")).unwrap();
+        fs::write(root.join("nested/renamed.png"), b"sunset").unwrap();
+        fs::write(root.join("forest.png"), b"forest, edited since").unwrap();
+        fs::write(root.join("notes.txt"), b"sunset").unwrap();
+
+        let hash = working_copy::content_hash(&root.join("nested/renamed.png")).unwrap();
+        let lost = vec![
+            LostSource { path: String::from("/old/sunset.png"), content_hash: Some(hash) },
+            LostSource { path: String::from("/old/Forest.PNG"), content_hash: Some(String::from("0000")) },
+            LostSource { path: String::from("/old/gone.png"), content_hash: None },
+        ];
+        let found = find_replacements(&lost, &root).unwrap();
+        assert_eq!(found, vec![
+            Some((root.join("nested/renamed.png"), RelinkMatch::Hash)),
+            Some((root.join("forest.png"), RelinkMatch::Name)),
+            None,
+        ]);
+        fs::remove_dir_all(&root).ok();
+    }
+}
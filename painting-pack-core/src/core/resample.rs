@@ -0,0 +1,123 @@
+use image::{imageops::FilterType, DynamicImage};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "gpu")]
+use crate::core::gpu;
+
+// Below this many source pixels the GPU round trip costs more than it saves.
+#[cfg(feature = "gpu")]
+const GPU_MIN_PIXELS: u64 = 1_000_000;
+
+/*
+The filter textures are scaled down with on export. `Lanczos3` keeps photos sharpest; `Nearest`
+keeps every pixel of pixel art crisp instead of blending neighbours; `Triangle` and `CatmullRom`
+sit in between.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResampleFilter {
+    Nearest,
+    Triangle,
+    CatmullRom,
+    #[default]
+    Lanczos3,
+}
+
+impl ResampleFilter {
+    pub fn filter_type(self) -> FilterType {
+        match self {
+            ResampleFilter::Nearest => FilterType::Nearest,
+            ResampleFilter::Triangle => FilterType::Triangle,
+            ResampleFilter::CatmullRom => FilterType::CatmullRom,
+            ResampleFilter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+/*
+Resizes to exactly `width` x `height` with a Lanczos3 filter. Built with the `gpu` feature,
+large 8-bit images go through the GPU; everything else, and any GPU failure, uses the CPU.
+*/
+pub fn resize_exact(image: &DynamicImage, width: u32, height: u32) -> DynamicImage {
+    resize_with(image, width, height, ResampleFilter::Lanczos3)
+}
+
+// Same as `resize_exact` with the given filter. Only Lanczos3 resizes are done on the GPU.
+pub fn resize_with(image: &DynamicImage, width: u32, height: u32, filter: ResampleFilter) -> DynamicImage {
+    #[cfg(feature = "gpu")]
+    if filter == ResampleFilter::Lanczos3
+        && image.width() as u64 * image.height() as u64 >= GPU_MIN_PIXELS
+        && matches!(image, DynamicImage::ImageRgba8(_) | DynamicImage::ImageRgb8(_))
+    {
+        if let Some(resized) = gpu::resize(&image.to_rgba8(), width, height) {
+            return DynamicImage::ImageRgba8(resized);
+        }
+    }
+    image.resize_exact(width, height, filter.filter_type())
+}
+
+// Scales to `width` wide, keeping the aspect ratio, with the given filter.
+pub fn scale_to_width(image: &DynamicImage, width: u32, filter: ResampleFilter) -> DynamicImage {
+    let height = ((image.height() as f64 * width as f64 / image.width().max(1) as f64).round() as u32).max(1);
+    resize_with(image, width, height, filter)
+}
+
+// Scales down (never up) to fit within `max_width` x `max_height`, keeping the aspect ratio.
+pub fn fit_within(image: &DynamicImage, max_width: u32, max_height: u32) -> DynamicImage {
+    let (width, height) = (image.width(), image.height());
+    if width <= max_width && height <= max_height {
+        return image.clone();
+    }
+    let scale = (max_width as f64 / width as f64).min(max_height as f64 / height as f64);
+    let target_width = ((width as f64 * scale).round() as u32).max(1);
+    let target_height = ((height as f64 * scale).round() as u32).max(1);
+    resize_exact(image, target_width, target_height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_fit_within_keeps_aspect_and_never_upscales() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(400, 100));
+        let fitted = fit_within(&image, 200, 200);
+        assert_eq!((fitted.width(), fitted.height()), (200, 50));
+
+        let small = fit_within(&image, 1000, 1000);
+        assert_eq!((small.width(), small.height()), (400, 100));
+    }
+
+    #[test]
+    fn test_nearest_filter_keeps_pixel_art_colours() {
+        // 2x2 blocks of black and white, halved: Nearest picks whole pixels, Lanczos3 blends them.
+        let art = RgbaImage::from_fn(8, 8, |x, y| if (x / 2 + y / 2) % 2 == 0 { image::Rgba([0, 0, 0, 255]) } else { image::Rgba([255, 255, 255, 255]) });
+        let art = DynamicImage::ImageRgba8(art);
+        let crisp = scale_to_width(&art, 4, ResampleFilter::Nearest).to_rgba8();
+        assert_eq!(crisp.dimensions(), (4, 4));
+        assert!(crisp.pixels().all(|p| p.0[0] == 0 || p.0[0] == 255));
+        let smooth = resize_with(&art, 4, 4, ResampleFilter::Lanczos3).to_rgba8();
+        assert!(smooth.pixels().any(|p| p.0[0] != 0 && p.0[0] != 255));
+        assert_eq!(ResampleFilter::default(), ResampleFilter::Lanczos3);
+    }
+
+    #[test]
+    fn test_resize_exact_matches_the_cpu_filter() {
+        // Large enough to take the GPU path when the feature is on and a GPU is present.
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_fn(1200, 900, |x, y| {
+            image::Rgba([(x % 256) as u8, (y % 256) as u8, ((x + y) % 256) as u8, 255])
+        }));
+        let resized = resize_exact(&image, 300, 225).to_rgba8();
+        let expected = image.resize_exact(300, 225, FilterType::Lanczos3).to_rgba8();
+        assert_eq!(resized.dimensions(), expected.dimensions());
+
+        // The GPU rounds differently, so allow a small per-channel difference.
+        let worst = resized
+            .as_raw()
+            .iter()
+            .zip(expected.as_raw())
+            .map(|(a, b)| a.abs_diff(*b))
+            .max()
+            .unwrap_or(0);
+        assert!(worst <= 8, "largest channel difference {}", worst);
+    }
+}
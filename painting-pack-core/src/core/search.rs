@@ -0,0 +1,121 @@
+use serde::Serialize;
+
+/*
+The searchable text of one library entry. Paintings have no tags yet, so the description is
+searched as the free-text field instead.
+*/
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchEntry<'a> {
+    pub id: &'a str,
+    pub name: &'a str,
+    pub artist: &'a str,
+    pub filename: &'a str,
+    pub description: &'a str,
+}
+
+// One match, best first. `index` is the entry's position in the searched list.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub index: usize,
+    pub id: String,
+    pub score: u32,
+}
+
+// A hit in the name counts for more than the same hit in the artist, and so on down.
+const NAME_WEIGHT: u32 = 4;
+const ARTIST_WEIGHT: u32 = 3;
+const FILENAME_WEIGHT: u32 = 2;
+const DESCRIPTION_WEIGHT: u32 = 1;
+
+/*
+How well one query word matches one field, or `None` if it doesn't. Exact and prefix matches
+beat substrings, and a fuzzy match (the word's letters in order, e.g. "strnt" in "Starry Night")
+scores lower the more spread out its letters are.
+*/
+fn word_score(word: &str, field: &str) -> Option<u32> {
+    let field = field.to_lowercase();
+    if field == word {
+        return Some(100);
+    }
+    if field.split(|c: char| !c.is_alphanumeric()).any(|part| part.starts_with(word)) {
+        return Some(if field.starts_with(word) { 90 } else { 80 });
+    }
+    if field.contains(word) {
+        return Some(60);
+    }
+
+    // Fuzzy: every letter of the word in order, scored by how tightly they are packed.
+    let field: Vec<char> = field.chars().collect();
+    let mut letters = word.chars().peekable();
+    let (mut first, mut last) = (None, 0);
+    for (position, &c) in field.iter().enumerate() {
+        if letters.peek() == Some(&c) {
+            letters.next();
+            first.get_or_insert(position);
+            last = position;
+        }
+    }
+    if letters.peek().is_some() {
+        return None;
+    }
+    let span = (last - first.unwrap_or(0) + 1) as u32;
+    Some((40 * word.chars().count() as u32 / span).max(1))
+}
+
+/*
+Ranks `entries` against `query`. Every word of the query has to match some field; an entry's
+score adds up each word's best weighted match. Ties keep library order. A blank query matches
+nothing.
+*/
+pub fn search(query: &str, entries: &[SearchEntry]) -> Vec<SearchHit> {
+    let query = query.to_lowercase();
+    let words: Vec<&str> = query.split_whitespace().collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let mut hits: Vec<SearchHit> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| {
+            let fields = [
+                (entry.name, NAME_WEIGHT),
+                (entry.artist, ARTIST_WEIGHT),
+                (entry.filename, FILENAME_WEIGHT),
+                (entry.description, DESCRIPTION_WEIGHT),
+            ];
+            let score = words
+                .iter()
+                .map(|word| fields.iter().filter_map(|(field, weight)| word_score(word, field).map(|s| s * weight)).max())
+                .sum::<Option<u32>>()?;
+            Some(SearchHit { index, id: entry.id.to_string(), score })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.cmp(&a.score).then(a.index.cmp(&b.index)));
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_ranks_and_matches_fuzzily() {
+        let entries = [
+            SearchEntry { id: "sunflowers", name: "Sunflowers", artist: "Van Gogh", filename: "IMG_0042.jpg", ..Default::default() },
+            SearchEntry { id: "starry_night", name: "Starry Night", artist: "Van Gogh", filename: "starry.png", ..Default::default() },
+            SearchEntry { id: "the_night_watch", name: "The Night Watch", artist: "Rembrandt", filename: "watch.png", ..Default::default() },
+        ];
+
+        let ids = |query: &str| search(query, &entries).into_iter().map(|hit| hit.id).collect::<Vec<_>>();
+        // A name hit outranks the same word in another field
+        assert_eq!(ids("night"), vec!["starry_night", "the_night_watch"]);
+        // Every word has to match
+        assert_eq!(ids("gogh night"), vec!["starry_night"]);
+        // Letters in order, with some missing
+        assert_eq!(ids("strynt"), vec!["starry_night"]);
+        assert_eq!(ids("img_0042"), vec!["sunflowers"]);
+        assert!(ids("   ").is_empty());
+        assert!(ids("monet").is_empty());
+    }
+}
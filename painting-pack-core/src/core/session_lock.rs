@@ -0,0 +1,201 @@
+use std::cell::Cell;
+use std::fs::{self, create_dir_all, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use crate::core::timestamp;
+
+// A lock nobody has refreshed for this long is taken to belong to an instance that is gone.
+const STALE_LOCK_AGE: Duration = Duration::from_secs(10 * 60);
+// How often a long-running holder refreshes its lock through `refresh_if_due`.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+// Which instance of the app holds a lock. Written into the lock file as JSON.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LockOwner {
+    pub pid: u32,
+    // Machine name, so a lock on a shared drive names the computer it is held from.
+    pub host: String,
+    // Unix seconds the lock was taken at.
+    pub since: u64,
+}
+
+impl LockOwner {
+    fn current() -> Self {
+        LockOwner { pid: std::process::id(), host: host_name(), since: timestamp::unix_seconds() }
+    }
+
+    // E.g. "process 4121 on studio-pc, since 2026-10-16".
+    pub fn describe(&self) -> String {
+        let host = if self.host.is_empty() { "this computer" } else { &self.host };
+        format!("process {} on {}, since {}", self.pid, host, timestamp::format_date(self.since))
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum LockError {
+    // Another instance (or another export in this one) is using the file or folder.
+    #[error("{path} is open elsewhere: another Painting Pack Maker window or export ({}) is using it. Close it there or wait for it to finish.", .owner.describe())]
+    Held { path: String, owner: LockOwner },
+    #[error("Couldn't lock {path}: {message}")]
+    Io { path: String, message: String },
+}
+
+impl From<LockError> for String {
+    fn from(error: LockError) -> Self {
+        error.to_string()
+    }
+}
+
+/*
+Claims a project file or export folder for this instance of the app, so two windows, or a window
+and the headless server, never write it at the same time. The claim is a `<target>.lock` file next
+to the target, removed again when the lock is dropped. A lock left behind by a crashed instance
+doesn't block forever: on Linux it is taken over as soon as its process is gone, elsewhere once it
+hasn't been refreshed for `STALE_LOCK_AGE`, so holders call `refresh` or `refresh_if_due`.
+*/
+#[derive(Debug)]
+pub struct SessionLock {
+    target: PathBuf,
+    owner: LockOwner,
+    refreshed: Cell<Instant>,
+}
+
+// The lock file for `target`, e.g. `gallery.ppmproj.lock`.
+pub fn lock_path(target: &Path) -> PathBuf {
+    let mut lock = target.as_os_str().to_owned();
+    lock.push(".lock");
+    PathBuf::from(lock)
+}
+
+impl SessionLock {
+    pub fn acquire(target: &Path) -> Result<Self, LockError> {
+        let path = lock_path(target);
+        let io_error = |e: std::io::Error| LockError::Io { path: target.display().to_string(), message: e.to_string() };
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            create_dir_all(dir).map_err(io_error)?;
+        }
+        let owner = LockOwner::current();
+        let contents = serde_json::to_vec(&owner).map_err(|e| LockError::Io { path: target.display().to_string(), message: e.to_string() })?;
+        // Two tries: the second after clearing a stale lock out of the way.
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(&contents).map_err(io_error)?;
+                    return Ok(SessionLock { target: target.to_path_buf(), owner, refreshed: Cell::new(Instant::now()) });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let held_by = fs::read(&path).ok().and_then(|bytes| serde_json::from_slice::<LockOwner>(&bytes).ok());
+                    let age = fs::metadata(&path)
+                        .and_then(|meta| meta.modified())
+                        .ok()
+                        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                        .unwrap_or_default();
+                    match held_by {
+                        Some(held_by) if !is_stale(&held_by, age) => {
+                            return Err(LockError::Held { path: target.display().to_string(), owner: held_by });
+                        }
+                        // Stale, or not a lock file this app could have written.
+                        _ => {
+                            println!("[LOCK] Taking over the stale lock {}", path.display());
+                            fs::remove_file(&path).or_else(|e| if e.kind() == ErrorKind::NotFound { Ok(()) } else { Err(e) }).map_err(io_error)?;
+                        }
+                    }
+                }
+                Err(e) => return Err(io_error(e)),
+            }
+        }
+        Err(LockError::Io { path: target.display().to_string(), message: "another instance claimed it at the same time".to_string() })
+    }
+
+    pub fn target(&self) -> &Path {
+        &self.target
+    }
+
+    // Marks the lock as still in use, so other instances don't take it over as stale.
+    pub fn refresh(&self) -> Result<(), String> {
+        let path = lock_path(&self.target);
+        let contents = serde_json::to_vec(&self.owner).map_err(|e| e.to_string())?;
+        fs::write(&path, contents).map_err(|e| format!("Failed to refresh {}: {}", path.display(), e))?;
+        self.refreshed.set(Instant::now());
+        Ok(())
+    }
+
+    // `refresh`, at most once per `REFRESH_INTERVAL`. Cheap enough to call for every export stage.
+    pub fn refresh_if_due(&self) {
+        if self.refreshed.get().elapsed() >= REFRESH_INTERVAL {
+            if let Err(e) = self.refresh() {
+                eprintln!("[LOCK] {}", e);
+            }
+        }
+    }
+}
+
+impl Drop for SessionLock {
+    // Only removes the lock file if it is still ours, not one another instance took over.
+    fn drop(&mut self) {
+        let path = lock_path(&self.target);
+        let ours = fs::read(&path).ok().and_then(|bytes| serde_json::from_slice::<LockOwner>(&bytes).ok()) == Some(self.owner.clone());
+        if ours {
+            if let Err(e) = fs::remove_file(&path) {
+                eprintln!("[LOCK] Failed to remove {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+fn host_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| fs::read_to_string("/proc/sys/kernel/hostname").map(|name| name.trim().to_string()))
+        .unwrap_or_default()
+}
+
+// Whether process `pid` on this machine is still running, where that can be told without asking the OS.
+pub(crate) fn process_running(pid: u32) -> Option<bool> {
+    if cfg!(target_os = "linux") {
+        Some(Path::new("/proc").join(pid.to_string()).exists())
+    } else {
+        None
+    }
+}
+
+fn is_stale(owner: &LockOwner, age: Duration) -> bool {
+    if owner.host == host_name() {
+        if let Some(running) = process_running(owner.pid) {
+            return !running;
+        }
+    }
+    age > STALE_LOCK_AGE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_lock_is_refused_until_the_first_is_dropped() {
+        let dir = std::env::temp_dir().join(format!("session_lock_test_{}", std::process::id()));
+        let project = dir.join("gallery.ppmproj");
+
+        let lock = SessionLock::acquire(&project).unwrap();
+        assert!(lock_path(&project).exists());
+        let Err(LockError::Held { owner, .. }) = SessionLock::acquire(&project) else { panic!("lock was not refused") };
+        assert_eq!(owner.pid, std::process::id());
+        assert!(SessionLock::acquire(&project).unwrap_err().to_string().contains("is open elsewhere"));
+        lock.refresh().unwrap();
+        drop(lock);
+        assert!(!lock_path(&project).exists());
+
+        // A lock its owner stopped refreshing long ago is taken over.
+        let crashed = LockOwner { pid: u32::MAX, host: String::from("elsewhere"), since: 0 };
+        fs::write(lock_path(&project), serde_json::to_vec(&crashed).unwrap()).unwrap();
+        let hour_ago = SystemTime::now() - Duration::from_secs(60 * 60);
+        fs::File::options().write(true).open(lock_path(&project)).unwrap().set_modified(hour_ago).unwrap();
+        let taken_over = SessionLock::acquire(&project).unwrap();
+        assert_eq!(taken_over.target(), project.as_path());
+        drop(taken_over);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
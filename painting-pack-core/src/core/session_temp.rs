@@ -0,0 +1,188 @@
+use std::fs::{self, create_dir_all};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use serde::Serialize;
+use crate::core::session_lock;
+
+// Sessions left behind by a crash are cleaned up by the next run once they are this old.
+const STALE_SESSION_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+const SESSION_PREFIX: &str = "session-";
+// Touched by `SessionTemp::keep_alive` so a long-running session never looks abandoned.
+const ALIVE_MARKER: &str = ".alive";
+
+// The app's folder under the system temp directory; each run gets its own session inside it.
+pub fn temp_root() -> PathBuf {
+    std::env::temp_dir().join("painting-pack-maker")
+}
+
+// What a purge removed.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct PurgeReport {
+    pub removed_files: usize,
+    pub freed_bytes: u64,
+    // Files left alone because the library still uses them.
+    pub kept_files: usize,
+}
+
+/*
+The one folder this run writes its temporary files to: working copies, generated paintings,
+uploads and downloads each get a subfolder. Nothing is ever written next to the user's sources,
+and the whole folder is removed when the app exits.
+*/
+#[derive(Debug, Clone)]
+pub struct SessionTemp {
+    dir: PathBuf,
+}
+
+impl Default for SessionTemp {
+    fn default() -> Self {
+        SessionTemp::in_root(&temp_root())
+    }
+}
+
+impl SessionTemp {
+    // A session for this process under `root`. The folder is only created once something needs it.
+    pub fn in_root(root: &Path) -> Self {
+        SessionTemp { dir: root.join(format!("{}{}", SESSION_PREFIX, std::process::id())) }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    // A subfolder of the session, e.g. "cache" or "uploads", created if needed.
+    pub fn subdir(&self, name: &str) -> Result<PathBuf, String> {
+        let dir = self.dir.join(name);
+        create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        Ok(dir)
+    }
+
+    /*
+    Deletes the session's temp files, except those in `keep` (sources the library still points
+    at), so a long session can give back disk space without breaking open paintings.
+    */
+    pub fn purge(&self, keep: &[PathBuf]) -> Result<PurgeReport, String> {
+        let mut report = PurgeReport::default();
+        if self.dir.exists() {
+            purge_folder(&self.dir, keep, &mut report)?;
+        }
+        Ok(report)
+    }
+
+    // Marks the session as still in use, for platforms where `remove_stale_sessions` can't ask whether its process is running.
+    pub fn keep_alive(&self) {
+        if let Err(e) = create_dir_all(&self.dir).and_then(|_| fs::write(self.dir.join(ALIVE_MARKER), b"")) {
+            eprintln!("[TEMP] Failed to mark {} as in use: {}", self.dir.display(), e);
+        }
+    }
+
+    // Removes the whole session folder. Called on exit; failures are only logged.
+    pub fn cleanup(&self) {
+        if let Err(e) = fs::remove_dir_all(&self.dir) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                eprintln!("[TEMP] Failed to remove {}: {}", self.dir.display(), e);
+            }
+        }
+    }
+}
+
+fn purge_folder(folder: &Path, keep: &[PathBuf], report: &mut PurgeReport) -> Result<(), String> {
+    let entries = fs::read_dir(folder).map_err(|e| format!("Failed to read {}: {}", folder.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            purge_folder(&path, keep, report)?;
+            // Only succeeds once the folder is empty, which is what we want.
+            let _ = fs::remove_dir(&path);
+        } else if keep.contains(&path) {
+            report.kept_files += 1;
+        } else {
+            let size = entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+            fs::remove_file(&path).map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+            report.removed_files += 1;
+            report.freed_bytes += size;
+        }
+    }
+    Ok(())
+}
+
+// When a session folder was last written to or marked alive.
+fn last_active(session_dir: &Path) -> Option<SystemTime> {
+    let modified = |path: &Path| fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    modified(session_dir).into_iter().chain(modified(&session_dir.join(ALIVE_MARKER))).max()
+}
+
+/*
+Removes session folders under `root` that earlier runs left behind (e.g. after a crash). A folder
+goes once the process it is named after has exited; where that can't be told, once it hasn't been
+touched for a day, which a running session prevents through `SessionTemp::keep_alive`.
+*/
+pub fn remove_stale_sessions(root: &Path, current: &SessionTemp) {
+    let Ok(entries) = fs::read_dir(root) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(pid) = name.strip_prefix(SESSION_PREFIX).and_then(|pid| pid.parse::<u32>().ok()) else { continue };
+        if path == current.dir || !path.is_dir() {
+            continue;
+        }
+        let stale = match session_lock::process_running(pid) {
+            Some(running) => !running,
+            None => last_active(&path)
+                .and_then(|active| SystemTime::now().duration_since(active).ok())
+                .is_some_and(|age| age > STALE_SESSION_AGE),
+        };
+        if stale {
+            if let Err(e) = fs::remove_dir_all(&path) {
+                eprintln!("[TEMP] Failed to remove stale session {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_purge_keeps_files_in_use_and_cleanup_removes_everything() {
+        let root = std::env::temp_dir().join(format!("session_temp_test_{}", std::process::id()));
+        let session = SessionTemp::in_root(&root);
+        let cache = session.subdir("cache").unwrap();
+        let uploads = session.subdir("uploads").unwrap();
+        fs::write(cache.join("in_use.png"), b"1234").unwrap();
+        fs::write(cache.join("old.png"), b"12").unwrap();
+        fs::write(uploads.join("upload.png"), b"123").unwrap();
+
+        let report = session.purge(&[cache.join("in_use.png")]).unwrap();
+        assert_eq!(report, PurgeReport { removed_files: 2, freed_bytes: 5, kept_files: 1 });
+        assert!(cache.join("in_use.png").exists());
+        assert!(!uploads.exists());
+
+        session.cleanup();
+        assert!(!session.dir().exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_stale_sessions_are_only_removed_once_their_process_is_gone() {
+        let root = std::env::temp_dir().join(format!("stale_sessions_test_{}", std::process::id()));
+        let current = SessionTemp::in_root(&root);
+        current.keep_alive();
+        // Process 1 is always running; the other PID is past the kernel's limit so never is.
+        let running = root.join(format!("{}1", SESSION_PREFIX));
+        let crashed = root.join(format!("{}{}", SESSION_PREFIX, u32::MAX));
+        for dir in [&running, &crashed] {
+            create_dir_all(dir).unwrap();
+        }
+        let long_ago = SystemTime::now() - 2 * STALE_SESSION_AGE;
+        fs::File::open(&running).unwrap().set_modified(long_ago).unwrap();
+
+        remove_stale_sessions(&root, &current);
+        assert!(running.exists());
+        assert!(!crashed.exists());
+        assert!(current.dir().join(ALIVE_MARKER).exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
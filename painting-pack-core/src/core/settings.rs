@@ -0,0 +1,70 @@
+use std::fs::{create_dir_all, write};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::core::export_presets::ExportPreset;
+use crate::core::first_run::SetupChoices;
+use crate::core::migration::{self, SETTINGS_SCHEMA};
+use crate::core::pack_registry::PackRegistry;
+use crate::core::templates::PackTemplate;
+
+/*
+App-wide preferences that outlive a single pack, stored as JSON in the app's config folder.
+Missing fields fall back to their defaults, and files from older versions of the app are upgraded
+through `migration::SETTINGS_SCHEMA` when they are loaded.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct AppSettings {
+    // Templates the user saved; the built-in ones aren't stored.
+    pub pack_templates: Vec<PackTemplate>,
+    // Saved export options, applied by name.
+    pub export_presets: Vec<ExportPreset>,
+    // Pack IDs exported before, to warn about reusing one for a different pack.
+    pub pack_registry: PackRegistry,
+    // Pack folders of exports that were started and may not have finished. Checked for export
+    // progress on launch so an interrupted export can be resumed.
+    pub unfinished_exports: Vec<String>,
+    // What the first-run wizard set up; `None` until it has been completed.
+    pub setup: Option<SetupChoices>,
+    // Low-memory mode (see `low_memory`), for machines that struggle with large libraries.
+    pub low_memory: bool,
+}
+
+impl AppSettings {
+    // Reads the settings file, or returns the defaults if there isn't one yet.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(AppSettings::default());
+        }
+        migration::load(path, &SETTINGS_SCHEMA)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let contents = migration::to_versioned_json(self, &SETTINGS_SCHEMA)?;
+        write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::templates;
+
+    #[test]
+    fn test_settings_round_trip_and_missing_file() {
+        let path = std::env::temp_dir().join(format!("settings_test_{}", std::process::id())).join("settings.json");
+        assert_eq!(AppSettings::load(&path).unwrap(), AppSettings::default());
+
+        let mut template = templates::built_in_templates().remove(1);
+        template.name = String::from("Weekly drop");
+        template.built_in = false;
+        let settings = AppSettings { pack_templates: vec![template], ..AppSettings::default() };
+        settings.save(&path).unwrap();
+        assert_eq!(AppSettings::load(&path).unwrap(), settings);
+
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+}
@@ -0,0 +1,91 @@
+use image::DynamicImage;
+use serde::Deserialize;
+use crate::models::image_size::ImageSize;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct VerticalSplitOptions {
+    // How much of each slice is repeated at the top of the next one, as a fraction of the image
+    // width (one block). Gives some slack when lining the paintings up in game. 0.0-0.5.
+    pub overlap: f32,
+}
+
+impl Default for VerticalSplitOptions {
+    fn default() -> Self {
+        VerticalSplitOptions { overlap: 0.0 }
+    }
+}
+
+// Top edge, height and painting shape of each slice, top to bottom.
+fn plan_vertical(width: u32, height: u32, overlap: f32) -> Vec<(u32, u32, ImageSize)> {
+    let square_h = width;
+    let tall_h = width * 2;
+    let overlap_px = (width as f32 * overlap.clamp(0.0, 0.5)).round() as u32;
+
+    let mut slices = Vec::new();
+    let mut y = 0;
+    loop {
+        let remaining = height - y;
+        let (h, size) = if remaining > square_h { (tall_h, ImageSize::Tall) } else { (square_h, ImageSize::Square) };
+        // The last slice is pushed up to sit on the bottom edge instead of running past it.
+        let top = y.min(height - h);
+        slices.push((top, h, size));
+        if top + h >= height {
+            break;
+        }
+        y = top + h - overlap_px;
+    }
+    slices
+}
+
+/*
+Slices a very tall image (a waterfall, a tower) into a column of full-width 1x2 Tall paintings,
+finishing with a Tall or Square one, so the pieces can be hung on top of each other in game.
+Returns each slice with its shape, top to bottom.
+*/
+pub fn split_vertical(image: &DynamicImage, options: &VerticalSplitOptions) -> Result<Vec<(ImageSize, DynamicImage)>, String> {
+    let (width, height) = (image.width(), image.height());
+    if width == 0 || height <= width * 2 {
+        return Err(format!(
+            "A {}x{} image fits in a single Tall painting; only images more than twice as tall as they are wide can be split",
+            width, height
+        ));
+    }
+
+    Ok(plan_vertical(width, height, options.overlap)
+        .into_iter()
+        .map(|(top, h, size)| (size, image.crop_imm(0, top, width, h)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_plan_vertical_slices() {
+        // Two Tall slices and a Square to finish
+        assert_eq!(
+            plan_vertical(100, 500, 0.0),
+            vec![(0, 200, ImageSize::Tall), (200, 200, ImageSize::Tall), (400, 100, ImageSize::Square)]
+        );
+        // Overlap repeats 20px; the last Tall slice is pushed up onto the bottom edge
+        assert_eq!(
+            plan_vertical(100, 500, 0.2),
+            vec![(0, 200, ImageSize::Tall), (180, 200, ImageSize::Tall), (300, 200, ImageSize::Tall)]
+        );
+    }
+
+    #[test]
+    fn test_split_vertical_rejects_short_images() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(100, 200));
+        assert!(split_vertical(&image, &VerticalSplitOptions::default()).is_err());
+
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(100, 300));
+        let slices = split_vertical(&image, &VerticalSplitOptions::default()).unwrap();
+        let shapes: Vec<ImageSize> = slices.iter().map(|(size, _)| *size).collect();
+        assert_eq!(shapes, [ImageSize::Tall, ImageSize::Square]);
+        assert_eq!((slices[1].1.width(), slices[1].1.height()), (100, 100));
+    }
+}
@@ -0,0 +1,116 @@
+use image::{DynamicImage, GenericImageView};
+use serde::Deserialize;
+
+/*
+How a sprite sheet (or contact sheet) is laid out: a grid of equally sized cells, with an optional
+border around the whole sheet and gutter between neighbouring cells. Distances are in pixels.
+*/
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct SheetGrid {
+    pub rows: u32,
+    pub columns: u32,
+    // Border around the whole sheet.
+    pub margin: u32,
+    // Gap between neighbouring cells, both across and down.
+    pub spacing: u32,
+    // Leave out cells that are one flat colour, like the unused slots at the end of a sheet.
+    pub skip_blank: bool,
+}
+
+impl Default for SheetGrid {
+    fn default() -> Self {
+        SheetGrid { rows: 1, columns: 1, margin: 0, spacing: 0, skip_blank: true }
+    }
+}
+
+// One cell cut out of a sheet; rows and columns count from 1, top left first.
+#[derive(Debug, Clone)]
+pub struct SheetCell {
+    pub row: u32,
+    pub column: u32,
+    pub image: DynamicImage,
+}
+
+// Size of one cell along an edge of `length` pixels split into `count` cells.
+fn cell_length(length: u32, count: u32, margin: u32, spacing: u32) -> Option<u32> {
+    let gaps = margin.checked_mul(2)?.checked_add(spacing.checked_mul(count.checked_sub(1)?)?)?;
+    length.checked_sub(gaps).map(|usable| usable / count).filter(|cell| *cell > 0)
+}
+
+// Left, top, width and height of every cell, row by row. Leftover pixels at the right and bottom are dropped.
+fn plan_cells(width: u32, height: u32, grid: &SheetGrid) -> Result<Vec<(u32, u32, u32, u32)>, String> {
+    if grid.rows == 0 || grid.columns == 0 {
+        return Err(String::from("A sheet needs at least 1 row and 1 column"));
+    }
+    let (Some(cell_width), Some(cell_height)) = (
+        cell_length(width, grid.columns, grid.margin, grid.spacing),
+        cell_length(height, grid.rows, grid.margin, grid.spacing),
+    ) else {
+        return Err(format!(
+            "A {}x{} sheet is too small for {} rows and {} columns with a {} px margin and {} px spacing",
+            width, height, grid.rows, grid.columns, grid.margin, grid.spacing
+        ));
+    };
+
+    Ok((0..grid.rows)
+        .flat_map(|row| (0..grid.columns).map(move |column| (row, column)))
+        .map(|(row, column)| {
+            let left = grid.margin + column * (cell_width + grid.spacing);
+            let top = grid.margin + row * (cell_height + grid.spacing);
+            (left, top, cell_width, cell_height)
+        })
+        .collect())
+}
+
+fn is_blank(image: &DynamicImage) -> bool {
+    let first = image.get_pixel(0, 0);
+    image.pixels().all(|(_, _, pixel)| pixel == first)
+}
+
+/*
+Cuts a sheet into the cells of `grid`, in reading order, so each can be imported as its own
+painting source. Blank cells are left out when `grid.skip_blank` is on.
+*/
+pub fn slice_sheet(sheet: &DynamicImage, grid: &SheetGrid) -> Result<Vec<SheetCell>, String> {
+    let cells: Vec<SheetCell> = plan_cells(sheet.width(), sheet.height(), grid)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, (left, top, width, height))| SheetCell {
+            row: index as u32 / grid.columns + 1,
+            column: index as u32 % grid.columns + 1,
+            image: sheet.crop_imm(left, top, width, height),
+        })
+        .filter(|cell| !(grid.skip_blank && is_blank(&cell.image)))
+        .collect();
+    if cells.is_empty() {
+        return Err(String::from("Every cell of the sheet is blank"));
+    }
+    Ok(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn test_sheet_is_sliced_inside_margins_and_spacing() {
+        // 2x3 cells of 10x8 px, a 2 px border and 1 px gutters; the last cell is left empty.
+        let mut sheet = RgbaImage::from_pixel(2 * 2 + 3 * 10 + 2, 2 * 2 + 2 * 8 + 1, Rgba([0, 0, 0, 0]));
+        for (index, (left, top)) in [(2, 2), (13, 2), (24, 2), (2, 11), (13, 11)].into_iter().enumerate() {
+            sheet.put_pixel(left, top, Rgba([index as u8 + 1, 0, 0, 255]));
+        }
+        let grid = SheetGrid { rows: 2, columns: 3, margin: 2, spacing: 1, skip_blank: true };
+
+        let cells = slice_sheet(&DynamicImage::ImageRgba8(sheet), &grid).unwrap();
+        let positions: Vec<(u32, u32)> = cells.iter().map(|cell| (cell.row, cell.column)).collect();
+        assert_eq!(positions, vec![(1, 1), (1, 2), (1, 3), (2, 1), (2, 2)]);
+        assert_eq!(cells[4].image.dimensions(), (10, 8));
+        // Each cell starts exactly at its marked corner.
+        assert_eq!(cells[4].image.get_pixel(0, 0), Rgba([5, 0, 0, 255]));
+
+        let too_many = SheetGrid { columns: 40, ..grid };
+        assert!(slice_sheet(&DynamicImage::ImageRgba8(RgbaImage::new(36, 21)), &too_many).is_err());
+    }
+}
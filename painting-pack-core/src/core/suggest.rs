@@ -0,0 +1,273 @@
+use image::{DynamicImage, GenericImageView};
+use serde::Serialize;
+
+// A proposed title and description for one painting. The user decides whether to apply it.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub name: String,
+    pub description: String,
+}
+
+/*
+Anything that can propose metadata for an image. The offline implementation below always works;
+network-backed implementations are compiled in behind the `suggest-api` feature.
+*/
+pub trait Suggester: Send + Sync {
+    fn suggest(&self, source_path: &str, image: &DynamicImage) -> Result<Suggestion, String>;
+}
+
+/*
+Works without any network access: the title is cleaned up from the file name and the
+description is built from the image's overall colour, brightness and shape.
+*/
+pub struct OfflineSuggester;
+
+// Camera and export prefixes that carry no meaning as a title.
+const NOISE_WORDS: [&str; 6] = ["img", "dsc", "dscn", "pxl", "photo", "image"];
+
+/*
+Turns `IMG_2041_sunsetOverLake-final` into `Sunset Over Lake Final`: splits on separators and
+camelCase boundaries, drops camera prefixes and bare numbers, and title-cases the rest.
+*/
+fn title_from_file_name(source_path: &str) -> String {
+    let stem = std::path::Path::new(source_path).file_stem().unwrap_or_default().to_string_lossy().to_string();
+
+    let mut words: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut previous_lowercase = false;
+    for c in stem.chars() {
+        if !c.is_alphanumeric() {
+            words.push(std::mem::take(&mut current));
+            previous_lowercase = false;
+            continue;
+        }
+        if c.is_uppercase() && previous_lowercase {
+            words.push(std::mem::take(&mut current));
+        }
+        previous_lowercase = c.is_lowercase();
+        current.push(c);
+    }
+    words.push(current);
+
+    let title: Vec<String> = words
+        .into_iter()
+        .filter(|w| !w.is_empty())
+        .filter(|w| !w.chars().all(|c| c.is_ascii_digit()))
+        .filter(|w| !NOISE_WORDS.contains(&w.to_lowercase().as_str()))
+        .map(|w| {
+            let mut chars = w.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars.flat_map(|c| c.to_lowercase())).collect(),
+                None => String::new(),
+            }
+        })
+        .collect();
+
+    if title.is_empty() {
+        String::from("Untitled")
+    } else {
+        title.join(" ")
+    }
+}
+
+// Names the hue of an average colour in plain words.
+fn colour_word(r: f32, g: f32, b: f32) -> &'static str {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    if max - min < 0.08 {
+        return "muted";
+    }
+
+    let delta = max - min;
+    let hue = if max == r {
+        60.0 * (((g - b) / delta).rem_euclid(6.0))
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+
+    match hue as u32 {
+        0..=20 | 340..=360 => "red",
+        21..=45 => "orange",
+        46..=70 => "golden",
+        71..=160 => "green",
+        161..=200 => "teal",
+        201..=260 => "blue",
+        _ => "violet",
+    }
+}
+
+fn describe_image(image: &DynamicImage) -> String {
+    // A tiny thumbnail is plenty to judge the overall tone.
+    let thumb = image.thumbnail(32, 32).to_rgb8();
+    let pixel_count = (thumb.width() * thumb.height()).max(1) as f32;
+    let (mut r, mut g, mut b) = (0.0, 0.0, 0.0);
+    for pixel in thumb.pixels() {
+        r += pixel[0] as f32 / 255.0;
+        g += pixel[1] as f32 / 255.0;
+        b += pixel[2] as f32 / 255.0;
+    }
+    let (r, g, b) = (r / pixel_count, g / pixel_count, b / pixel_count);
+    let brightness = (r + g + b) / 3.0;
+
+    let tone = match brightness {
+        x if x < 0.3 => "dark",
+        x if x > 0.7 => "bright",
+        _ => "softly lit",
+    };
+    let (width, height) = image.dimensions();
+    let shape = if width as f32 > height as f32 * 1.2 {
+        "wide scene"
+    } else if height as f32 > width as f32 * 1.2 {
+        "tall composition"
+    } else {
+        "balanced composition"
+    };
+
+    format!("A {}, {}-toned {}.", tone, colour_word(r, g, b), shape)
+}
+
+impl Suggester for OfflineSuggester {
+    fn suggest(&self, source_path: &str, image: &DynamicImage) -> Result<Suggestion, String> {
+        Ok(Suggestion {
+            name: title_from_file_name(source_path),
+            description: describe_image(image),
+        })
+    }
+}
+
+/*
+Asks an OpenAI-compatible chat completions endpoint to look at the image.
+Configured through `PPM_SUGGEST_API_URL`, `PPM_SUGGEST_API_KEY` and optionally `PPM_SUGGEST_MODEL`.
+*/
+// How long the suggestion endpoint gets to accept the connection, and then to answer, before the
+// request gives up instead of leaving the suggestion spinner running forever.
+#[cfg(feature = "suggest-api")]
+const CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+#[cfg(feature = "suggest-api")]
+const READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+#[cfg(feature = "suggest-api")]
+pub struct ApiSuggester {
+    pub endpoint: String,
+    pub api_key: String,
+    pub model: String,
+}
+
+#[cfg(feature = "suggest-api")]
+impl ApiSuggester {
+    pub fn from_env() -> Option<Self> {
+        Some(ApiSuggester {
+            endpoint: std::env::var("PPM_SUGGEST_API_URL").ok().filter(|v| !v.is_empty())?,
+            api_key: std::env::var("PPM_SUGGEST_API_KEY").ok().filter(|v| !v.is_empty())?,
+            model: std::env::var("PPM_SUGGEST_MODEL").unwrap_or_else(|_| String::from("gpt-4o-mini")),
+        })
+    }
+}
+
+#[cfg(feature = "suggest-api")]
+fn request_error(e: ureq::Error) -> String {
+    match e {
+        ureq::Error::Timeout(ureq::Timeout::Connect | ureq::Timeout::Resolve) => {
+            format!("Suggestion request failed: no connection within {} seconds", CONNECT_TIMEOUT.as_secs())
+        }
+        ureq::Error::Timeout(_) => format!("Suggestion request failed: no answer within {} seconds", READ_TIMEOUT.as_secs()),
+        e => format!("Suggestion request failed: {}", e),
+    }
+}
+
+#[cfg(feature = "suggest-api")]
+impl Suggester for ApiSuggester {
+    fn suggest(&self, _source_path: &str, image: &DynamicImage) -> Result<Suggestion, String> {
+        use base64::{Engine as _, engine::general_purpose};
+        use serde_json::{json, Value};
+
+        // Send a small JPEG; the model doesn't need full resolution to name a painting.
+        let mut jpeg: Vec<u8> = Vec::new();
+        image
+            .thumbnail(512, 512)
+            .to_rgb8()
+            .write_to(&mut std::io::Cursor::new(&mut jpeg), image::ImageFormat::Jpeg)
+            .map_err(|e| format!("Failed to encode image for suggestion: {}", e))?;
+        let data_url = format!("data:image/jpeg;base64,{}", general_purpose::STANDARD.encode(&jpeg));
+
+        let request = json!({
+            "model": self.model,
+            "response_format": { "type": "json_object" },
+            "messages": [{
+                "role": "user",
+                "content": [
+                    { "type": "text", "text": "Suggest a short gallery title and a one sentence description for this painting. Reply as JSON with the keys \"name\" and \"description\"." },
+                    { "type": "image_url", "image_url": { "url": data_url } }
+                ]
+            }]
+        });
+
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .timeout_connect(Some(CONNECT_TIMEOUT))
+            .timeout_recv_response(Some(READ_TIMEOUT))
+            .timeout_recv_body(Some(READ_TIMEOUT))
+            .build()
+            .into();
+        let mut response = agent
+            .post(&self.endpoint)
+            .header("Authorization", &format!("Bearer {}", self.api_key))
+            .send_json(&request)
+            .map_err(request_error)?;
+        let body: Value = response.body_mut().read_json().map_err(|e| match e {
+            ureq::Error::Timeout(_) => request_error(e),
+            e => format!("Suggestion response was not JSON: {}", e),
+        })?;
+
+        let content = body["choices"][0]["message"]["content"]
+            .as_str()
+            .ok_or("Suggestion response had no message content")?;
+        let suggestion: Value = serde_json::from_str(content).map_err(|e| format!("Suggestion was not valid JSON: {}", e))?;
+
+        Ok(Suggestion {
+            name: suggestion["name"].as_str().unwrap_or_default().trim().to_string(),
+            description: suggestion["description"].as_str().unwrap_or_default().trim().to_string(),
+        })
+    }
+}
+
+/*
+Picks the best available suggester: the API-backed one when it is compiled in and configured,
+otherwise the offline stub.
+*/
+pub fn default_suggester() -> Box<dyn Suggester> {
+    #[cfg(feature = "suggest-api")]
+    if let Some(api) = ApiSuggester::from_env() {
+        return Box::new(api);
+    }
+    Box::new(OfflineSuggester)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    #[test]
+    fn test_title_from_file_name() {
+        assert_eq!(title_from_file_name("/photos/IMG_2041_sunsetOverLake-final.jpg"), "Sunset Over Lake Final");
+        assert_eq!(title_from_file_name("starry night.png"), "Starry Night");
+        assert_eq!(title_from_file_name("DSC_0001.jpg"), "Untitled");
+    }
+
+    #[test]
+    fn test_offline_description_reflects_colour_and_shape() {
+        let blue_wide = DynamicImage::ImageRgb8(RgbImage::from_pixel(200, 100, Rgb([30, 60, 200])));
+        let suggestion = OfflineSuggester.suggest("lake.png", &blue_wide).unwrap();
+        assert_eq!(suggestion.name, "Lake");
+        assert!(suggestion.description.contains("blue"));
+        assert!(suggestion.description.contains("wide"));
+    }
+
+    #[test]
+    fn test_colour_word_greys_are_muted() {
+        assert_eq!(colour_word(0.5, 0.5, 0.52), "muted");
+        assert_eq!(colour_word(0.9, 0.1, 0.1), "red");
+    }
+}
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use crate::core::naming;
+use crate::models::{export_settings::IconStyle, image_size::ImageSize};
+
+/*
+A starting point for a new pack: which shapes new images get, how dense the textures are, how
+files are named, who the paintings are credited to and what the icon shows.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PackTemplate {
+    pub name: String,
+    pub description: String,
+    pub selected_sizes: Vec<ImageSize>,
+    // `None` keeps full resolution.
+    pub pixels_per_block: Option<u32>,
+    pub filename_template: String,
+    pub default_artist: String,
+    pub icon_style: IconStyle,
+    // Built-in templates ship with the app and can't be overwritten or deleted.
+    #[serde(default)]
+    pub built_in: bool,
+}
+
+// The templates that ship with the app.
+pub fn built_in_templates() -> Vec<PackTemplate> {
+    vec![
+        PackTemplate {
+            name: String::from("Museum pack"),
+            description: String::from("Framed artworks in every shape, numbered in gallery order and credited to their artists."),
+            selected_sizes: ImageSize::iter().copied().collect(),
+            pixels_per_block: Some(64),
+            filename_template: String::from("{pack_id}_{index:03}_{w}x{h}"),
+            default_artist: String::from("Unknown artist"),
+            icon_style: IconStyle::FirstPainting,
+            built_in: true,
+        },
+        PackTemplate {
+            name: String::from("Meme pack"),
+            description: String::from("Small, square-ish paintings at a light texture density."),
+            selected_sizes: vec![ImageSize::Square, ImageSize::Wide],
+            pixels_per_block: Some(32),
+            filename_template: String::from("{filename}_{w}x{h}"),
+            default_artist: String::from("Anonymous"),
+            icon_style: IconStyle::Default,
+            built_in: true,
+        },
+        PackTemplate {
+            name: String::from("Photo wall"),
+            description: String::from("Landscape and portrait photos at full resolution."),
+            selected_sizes: vec![ImageSize::Wide, ImageSize::Tall],
+            pixels_per_block: None,
+            filename_template: String::from("{filename}_{w}x{h}"),
+            default_artist: String::new(),
+            icon_style: IconStyle::FirstPainting,
+            built_in: true,
+        },
+    ]
+}
+
+// Built-in templates first, then the user's own.
+pub fn all_templates(user_templates: &[PackTemplate]) -> Vec<PackTemplate> {
+    built_in_templates().into_iter().chain(user_templates.iter().cloned()).collect()
+}
+
+/*
+Checks a user-defined template before it is saved. Its name can't clash with a built-in one.
+*/
+pub fn validate_template(template: &PackTemplate) -> Result<(), String> {
+    if template.name.trim().is_empty() {
+        return Err(String::from("A template needs a name"));
+    }
+    if built_in_templates().iter().any(|t| t.name.eq_ignore_ascii_case(template.name.trim())) {
+        return Err(format!("\"{}\" is a built-in template", template.name));
+    }
+    if template.selected_sizes.is_empty() {
+        return Err(String::from("A template needs at least one painting shape"));
+    }
+    if template.pixels_per_block == Some(0) {
+        return Err(String::from("A template needs at least 1 pixel per block"));
+    }
+    naming::validate_filename_template(&template.filename_template)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_built_in_templates_are_valid_apart_from_their_names() {
+        for template in built_in_templates() {
+            let renamed = PackTemplate { name: format!("My {}", template.name), ..template };
+            assert_eq!(validate_template(&renamed), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_validate_template_rejects_clashes_and_empty_sizes() {
+        let mut template = built_in_templates().remove(0);
+        assert!(validate_template(&template).is_err());
+
+        template.name = String::from("Weekly drop");
+        template.selected_sizes.clear();
+        assert!(validate_template(&template).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+use ab_glyph::{point, Font, FontVec, PxScale, ScaleFont};
+use image::{DynamicImage, Rgba, RgbaImage};
+use serde::Deserialize;
+use crate::core::font;
+use crate::models::image_size::ImageSize;
+
+// Longest side of a generated text painting, matching the exporter's texture cap.
+const TEXT_PAINTING_LONG_SIDE: u32 = 1024;
+// Smallest TrueType size tried when fitting text automatically.
+const MIN_FONT_SIZE: u32 = 8;
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub enum TextFont {
+    // The bundled blocky pixel font; always available.
+    Pixel { bold: bool },
+    // Path to a TrueType/OpenType font file.
+    File(String),
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum TextAlign {
+    Left,
+    #[default]
+    Center,
+    Right,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct TextPaintingOptions {
+    pub text: String,
+    pub font: TextFont,
+    // Font size in output pixels (the painting is 1024px on its long side).
+    // `None` picks the largest size at which all of the text fits.
+    pub font_size: Option<u32>,
+    pub text_colour: [u8; 4],
+    pub background: [u8; 4],
+    #[serde(default)]
+    pub align: TextAlign,
+}
+
+enum LoadedFont {
+    Pixel { bold: bool },
+    TrueType(FontVec),
+}
+
+impl LoadedFont {
+    fn load(choice: &TextFont) -> Result<Self, String> {
+        match choice {
+            TextFont::Pixel { bold } => Ok(LoadedFont::Pixel { bold: *bold }),
+            TextFont::File(path) => {
+                let bytes = std::fs::read(path).map_err(|e| format!("Failed to read font {}: {}", path, e))?;
+                let font = FontVec::try_from_vec(bytes).map_err(|e| format!("{} is not a usable font: {}", path, e))?;
+                Ok(LoadedFont::TrueType(font))
+            }
+        }
+    }
+
+    // The pixel font only scales by whole multiples so its pixels stay square.
+    fn pixel_scale(size: u32) -> u32 {
+        (size / font::GLYPH_HEIGHT).max(1)
+    }
+
+    // Sizes worth trying, largest first, when fitting text into `max_height`.
+    fn candidate_sizes(&self, max_height: u32) -> Vec<u32> {
+        match self {
+            LoadedFont::Pixel { .. } => (1..=(max_height / font::GLYPH_HEIGHT).max(1)).rev().map(|scale| scale * font::GLYPH_HEIGHT).collect(),
+            LoadedFont::TrueType(_) => {
+                let mut sizes = Vec::new();
+                let mut size = max_height.max(MIN_FONT_SIZE);
+                while size > MIN_FONT_SIZE {
+                    sizes.push(size);
+                    size = (size * 19 / 20).min(size - 1);
+                }
+                sizes.push(MIN_FONT_SIZE);
+                sizes
+            }
+        }
+    }
+
+    fn line_width(&self, text: &str, size: u32) -> u32 {
+        match self {
+            LoadedFont::Pixel { bold } => {
+                let scale = Self::pixel_scale(size);
+                let width = font::line_width(text) * scale;
+                if *bold && width > 0 { width + scale } else { width }
+            }
+            LoadedFont::TrueType(ttf) => {
+                let scaled = ttf.as_scaled(PxScale::from(size as f32));
+                let mut width = 0.0;
+                let mut previous = None;
+                for c in text.chars() {
+                    let id = scaled.glyph_id(c);
+                    if let Some(previous) = previous {
+                        width += scaled.kern(previous, id);
+                    }
+                    width += scaled.h_advance(id);
+                    previous = Some(id);
+                }
+                width.ceil() as u32
+            }
+        }
+    }
+
+    fn line_height(&self, size: u32) -> u32 {
+        match self {
+            LoadedFont::Pixel { .. } => font::LINE_HEIGHT * Self::pixel_scale(size),
+            LoadedFont::TrueType(ttf) => {
+                let scaled = ttf.as_scaled(PxScale::from(size as f32));
+                (scaled.height() + scaled.line_gap()).ceil() as u32
+            }
+        }
+    }
+
+    fn draw(&self, canvas: &mut RgbaImage, text: &str, x: i64, y: i64, size: u32, colour: Rgba<u8>) {
+        match self {
+            LoadedFont::Pixel { bold } => font::draw_text(canvas, text, x, y, Self::pixel_scale(size), colour, *bold),
+            LoadedFont::TrueType(ttf) => {
+                let scale = PxScale::from(size as f32);
+                let scaled = ttf.as_scaled(scale);
+                let mut caret = point(x as f32, y as f32 + scaled.ascent());
+                let mut previous = None;
+                for c in text.chars() {
+                    let id = scaled.glyph_id(c);
+                    if let Some(previous) = previous {
+                        caret.x += scaled.kern(previous, id);
+                    }
+                    let glyph = id.with_scale_and_position(scale, caret);
+                    caret.x += scaled.h_advance(id);
+                    previous = Some(id);
+
+                    let Some(outlined) = ttf.outline_glyph(glyph) else {
+                        continue;
+                    };
+                    let bounds = outlined.px_bounds();
+                    outlined.draw(|gx, gy, coverage| {
+                        let px = bounds.min.x as i64 + gx as i64;
+                        let py = bounds.min.y as i64 + gy as i64;
+                        if px >= 0 && py >= 0 && (px as u32) < canvas.width() && (py as u32) < canvas.height() {
+                            let pixel = canvas.get_pixel_mut(px as u32, py as u32);
+                            *pixel = blend(*pixel, colour, coverage);
+                        }
+                    });
+                }
+            }
+        }
+    }
+}
+
+// Draws `colour` over `base` with the given glyph coverage (0.0-1.0).
+fn blend(base: Rgba<u8>, colour: Rgba<u8>, coverage: f32) -> Rgba<u8> {
+    let alpha = coverage.clamp(0.0, 1.0) * colour.0[3] as f32 / 255.0;
+    let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * alpha).round() as u8;
+    Rgba([
+        mix(base.0[0], colour.0[0]),
+        mix(base.0[1], colour.0[1]),
+        mix(base.0[2], colour.0[2]),
+        mix(base.0[3], 255),
+    ])
+}
+
+// Wraps the text for one font size and reports whether it fits in the given area.
+fn layout(font: &LoadedFont, text: &str, size: u32, width: u32, height: u32) -> Option<Vec<String>> {
+    let lines = font::wrap(text, width, |line| font.line_width(line, size));
+    let fits_width = lines.iter().all(|line| font.line_width(line, size) <= width);
+    let fits_height = lines.len() as u32 * font.line_height(size) <= height;
+    (fits_width && fits_height).then_some(lines)
+}
+
+fn render(font: &LoadedFont, options: &TextPaintingOptions, size: ImageSize) -> Result<DynamicImage, String> {
+    let (width, height) = size.dimensions_for_long_side(TEXT_PAINTING_LONG_SIDE);
+    let padding = TEXT_PAINTING_LONG_SIDE / 16;
+    let (inner_w, inner_h) = (width - 2 * padding, height - 2 * padding);
+
+    let sizes = match options.font_size {
+        Some(font_size) => vec![font_size],
+        None => font.candidate_sizes(inner_h),
+    };
+    let (font_size, lines) = sizes
+        .into_iter()
+        .find_map(|font_size| layout(font, &options.text, font_size, inner_w, inner_h).map(|lines| (font_size, lines)))
+        .ok_or_else(|| format!("The text doesn't fit on a {:?} painting at this font size", size))?;
+
+    let mut canvas = RgbaImage::from_pixel(width, height, Rgba(options.background));
+    let line_height = font.line_height(font_size);
+    // Centre the block of lines vertically.
+    let mut y = padding + (inner_h - lines.len() as u32 * line_height) / 2;
+    for line in &lines {
+        let line_width = font.line_width(line, font_size);
+        let x = match options.align {
+            TextAlign::Left => padding,
+            TextAlign::Center => padding + (inner_w - line_width) / 2,
+            TextAlign::Right => padding + inner_w - line_width,
+        };
+        font.draw(&mut canvas, line, x as i64, y as i64, font_size, Rgba(options.text_colour));
+        y += line_height;
+    }
+
+    Ok(DynamicImage::ImageRgba8(canvas))
+}
+
+/*
+Renders the text as a painting for each requested size, so every shape gets its own layout
+instead of a crop of one big image.
+*/
+pub fn render_text_paintings(options: &TextPaintingOptions, sizes: &[ImageSize]) -> Result<Vec<(ImageSize, DynamicImage)>, String> {
+    if options.text.trim().is_empty() {
+        return Err(String::from("Enter some text for the painting"));
+    }
+    if sizes.is_empty() {
+        return Err(String::from("Choose at least one painting size"));
+    }
+
+    let font = LoadedFont::load(&options.font)?;
+    sizes.iter().map(|size| render(&font, options, *size).map(|image| (*size, image))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel_options(text: &str) -> TextPaintingOptions {
+        TextPaintingOptions {
+            text: text.to_string(),
+            font: TextFont::Pixel { bold: false },
+            font_size: None,
+            text_colour: [255, 255, 255, 255],
+            background: [0, 0, 0, 255],
+            align: TextAlign::Center,
+        }
+    }
+
+    fn text_pixels(image: &DynamicImage) -> usize {
+        image.to_rgba8().pixels().filter(|p| p.0 == [255, 255, 255, 255]).count()
+    }
+
+    #[test]
+    fn test_renders_each_size_with_its_own_shape() {
+        let paintings = render_text_paintings(&pixel_options("Welcome to spawn"), &[ImageSize::Wide, ImageSize::Tall]).unwrap();
+        assert_eq!(paintings.len(), 2);
+        assert_eq!((paintings[0].1.width(), paintings[0].1.height()), (1024, 512));
+        assert_eq!((paintings[1].1.width(), paintings[1].1.height()), (512, 1024));
+        assert!(text_pixels(&paintings[0].1) > 0);
+    }
+
+    #[test]
+    fn test_auto_size_shrinks_for_longer_text() {
+        let short = render_text_paintings(&pixel_options("Hi"), &[ImageSize::Square]).unwrap();
+        let long = render_text_paintings(&pixel_options("Rule 1: be kind. Rule 2: no griefing. Rule 3: have fun!"), &[ImageSize::Square]).unwrap();
+        // Fewer characters at a larger scale still cover more area per glyph.
+        let short_glyph_area = text_pixels(&short[0].1) / 2;
+        let long_glyph_area = text_pixels(&long[0].1) / 40;
+        assert!(short_glyph_area > long_glyph_area);
+    }
+
+    #[test]
+    fn test_rejects_text_that_cannot_fit() {
+        let mut options = pixel_options("This sign has far too much to say");
+        options.font_size = Some(700);
+        assert!(render_text_paintings(&options, &[ImageSize::Square]).is_err());
+        assert!(render_text_paintings(&pixel_options("   "), &[ImageSize::Square]).is_err());
+        assert!(render_text_paintings(&pixel_options("Shop"), &[]).is_err());
+
+        let mut missing_font = pixel_options("Shop");
+        missing_font.font = TextFont::File(String::from("does_not_exist.ttf"));
+        assert!(render_text_paintings(&missing_font, &[ImageSize::Square]).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/*
+Returns the current time as whole seconds since the Unix epoch.
+*/
+pub fn unix_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/*
+Converts a day count since 1970-01-01 into a (year, month, day) civil date.
+Based on Howard Hinnant's `civil_from_days` algorithm, so we don't need a date crate.
+*/
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let day_of_era = (z - era * 146_097) as u64;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era as i64 + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/*
+Formats a Unix timestamp as a compact, filesystem-safe UTC string, e.g. `20261016-142530`.
+Used for naming backup folders so they sort chronologically.
+*/
+pub fn format_compact(secs: u64) -> String {
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let seconds_of_day = secs % 86_400;
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        (seconds_of_day % 3600) / 60,
+        seconds_of_day % 60
+    )
+}
+
+// Formats a Unix timestamp as its UTC date, e.g. `2026-10-16`.
+pub fn format_date(secs: u64) -> String {
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_compact_epoch() {
+        assert_eq!(format_compact(0), "19700101-000000");
+    }
+
+    #[test]
+    fn test_format_compact_known_date() {
+        // 1700000000 is 2023-11-14 22:13:20 UTC
+        assert_eq!(format_compact(1_700_000_000), "20231114-221320");
+        assert_eq!(format_date(1_700_000_000), "2023-11-14");
+    }
+
+    #[test]
+    fn test_format_compact_leap_day() {
+        // 2024-02-29 12:00:00 UTC
+        assert_eq!(format_compact(1_709_208_000), "20240229-120000");
+    }
+}
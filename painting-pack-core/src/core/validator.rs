@@ -0,0 +1,215 @@
+use rand::Rng;
+use serde::Serialize;
+use crate::core::exporter::ExportItem;
+
+/*
+The Custom Paintings mod turns the pack ID into a resource namespace and each painting ID
+into a resource path (`pack_id:painting_id`). Minecraft rejects identifiers containing anything
+other than lowercase ASCII letters, digits, `_`, `-` and `.`, so both are held to that set.
+*/
+/*
+Longest painting ID the exporter may write, size suffix included. Each texture is saved as
+`<id>_<W>x<H>.png`, and ext4, NTFS and APFS all cap a file name at 255 bytes (IDs are ASCII).
+*/
+pub const MAX_ID_LENGTH: usize = 255 - ".png".len();
+
+pub fn is_valid_id_char(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-' || c == '.'
+}
+
+/*
+Converts user input into the identifier character set: lowercases it, turns spaces into
+underscores and drops everything else the game would reject.
+*/
+pub fn sanitize_id(raw: &str) -> String {
+    raw.to_lowercase()
+        .replace(' ', "_")
+        .chars()
+        .filter(|c| is_valid_id_char(*c))
+        .collect()
+}
+
+// Word lists for generated pack IDs. Kept short, lowercase and plain ASCII so every ID is valid.
+const ID_ADJECTIVES: &[&str] = &[
+    "amber", "ancient", "bold", "brave", "bright", "calm", "clever", "cosy", "crimson", "dusky",
+    "eager", "fabled", "gentle", "gilded", "golden", "hidden", "hollow", "jolly", "lively", "lucky",
+    "mellow", "misty", "mossy", "quiet", "rustic", "silver", "sleepy", "snowy", "stormy", "sunny",
+    "velvet", "wild",
+];
+const ID_NOUNS: &[&str] = &[
+    "badger", "beacon", "birch", "brook", "canyon", "cedar", "comet", "coral", "creek", "dune",
+    "ember", "falcon", "fern", "fjord", "fox", "glade", "harbor", "heron", "island", "lantern",
+    "meadow", "otter", "pebble", "pine", "quarry", "raven", "reef", "ridge", "spruce", "thistle",
+    "willow", "wren",
+];
+
+/*
+A fresh pack ID such as `mossy_heron_3f9a`: an adjective, a noun and four hex digits. Readable,
+always valid, and with about 67 million combinations two users are unlikely to pick the same one.
+*/
+pub fn generate_pack_id() -> String {
+    let mut rng = rand::rng();
+    format!(
+        "{}_{}_{:04x}",
+        ID_ADJECTIVES[rng.random_range(0..ID_ADJECTIVES.len())],
+        ID_NOUNS[rng.random_range(0..ID_NOUNS.len())],
+        rng.random_range(0..=0xffffu32)
+    )
+}
+
+// A single ID that would be rejected by the mod, with a human readable reason.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct IdViolation {
+    // What the ID belongs to, e.g. "pack" or the painting's name.
+    pub owner: String,
+    pub id: String,
+    pub reason: String,
+}
+
+impl std::fmt::Display for IdViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ID \"{}\": {}", self.owner, self.id, self.reason)
+    }
+}
+
+// Characters of `raw` that `sanitize_id` drops rather than converts, each listed once.
+fn rejected_chars(raw: &str) -> Vec<char> {
+    let mut rejected = Vec::new();
+    for c in raw.chars() {
+        if c != ' ' && !c.to_lowercase().all(is_valid_id_char) && !rejected.contains(&c) {
+            rejected.push(c);
+        }
+    }
+    rejected
+}
+
+fn violation(owner: &str, id: &str, reason: String) -> IdViolation {
+    IdViolation {
+        owner: owner.to_string(),
+        id: id.to_string(),
+        reason,
+    }
+}
+
+/*
+Checks the pack ID as the user typed it, so a violation names the characters that were actually
+rejected rather than silently dropping them from the namespace. Uppercase letters and spaces are
+fine, since sanitizing converts them.
+*/
+fn check_pack_id(raw: &str) -> Option<IdViolation> {
+    let rejected = rejected_chars(raw);
+    let reason = if !rejected.is_empty() {
+        let listed: Vec<String> = rejected.iter().map(|c| format!("\"{}\"", c)).collect();
+        format!("contains characters outside a-z, 0-9, _, -, .: {}", listed.join(", "))
+    } else if sanitize_id(raw).is_empty() {
+        String::from("contains no characters allowed in an ID (a-z, 0-9, _, -, .)")
+    } else {
+        return None;
+    };
+    Some(violation("Pack", raw, reason))
+}
+
+/*
+Checks a painting's IDs as the exporter writes them. Painting IDs come from display names, which
+may hold any characters, so only the sanitized ID is held to the rules: it must keep at least one
+character, and with each size suffix appended stay within `MAX_ID_LENGTH`.
+*/
+fn check_painting_id(owner: &str, raw: &str, sizes: &[(u32, u32)]) -> Option<IdViolation> {
+    let base_id = sanitize_id(raw);
+    if base_id.is_empty() {
+        return Some(violation(owner, raw, String::from("contains no characters allowed in an ID (a-z, 0-9, _, -, .)")));
+    }
+    sizes
+        .iter()
+        .map(|(width, height)| format!("{}_{}x{}", base_id, width, height))
+        .find(|final_id| final_id.len() > MAX_ID_LENGTH)
+        .map(|final_id| {
+            let reason = format!("is {} characters long, the limit is {}", final_id.len(), MAX_ID_LENGTH);
+            violation(owner, &final_id, reason)
+        })
+}
+
+/*
+Validates the pack ID and every painting ID the exporter would produce for these items,
+so problems are reported before anything is written instead of producing a pack the mod rejects.
+*/
+pub fn validate_ids(pack_id: &str, items: &[ExportItem]) -> Vec<IdViolation> {
+    let mut violations = Vec::new();
+    violations.extend(check_pack_id(pack_id));
+
+    for item in items {
+        let raw_id = item.data.id.clone().unwrap_or_default();
+        let owner = item.data.name.clone().unwrap_or_else(|| raw_id.clone());
+        violations.extend(check_painting_id(&owner, &raw_id, item.data.get_sizes()));
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{image_data::ImageData, image_size::ImageSize};
+
+    #[test]
+    fn test_generated_pack_ids_are_valid() {
+        for _ in 0..50 {
+            let id = generate_pack_id();
+            assert!(validate_ids(&id, &[]).is_empty(), "{} should be valid", id);
+            assert_eq!(id.split('_').count(), 3);
+        }
+    }
+
+    fn item_with_id(id: &str) -> ExportItem {
+        let mut data = ImageData::new(ImageSize::Square);
+        data.id = Some(id.to_string());
+        data.name = Some(id.to_string());
+        ExportItem {
+            source_path: String::from("unused.png"),
+            data,
+        }
+    }
+
+    #[test]
+    fn test_sanitize_id() {
+        assert_eq!(sanitize_id("My Test ID!"), "my_test_id");
+        assert_eq!(sanitize_id("forest-pack.v2"), "forest-pack.v2");
+        assert_eq!(sanitize_id("Ünïcode"), "ncode");
+    }
+
+    #[test]
+    fn test_valid_ids_pass() {
+        let items = vec![item_with_id("Starry Night"), item_with_id("sunflowers")];
+        assert!(validate_ids("my_pack", &items).is_empty());
+    }
+
+    #[test]
+    fn test_empty_after_sanitizing_is_reported() {
+        let items = vec![item_with_id("🌅🌅")];
+        let violations = validate_ids("!!!", &items);
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].owner, "Pack");
+        assert_eq!(violations[1].owner, "🌅🌅");
+    }
+
+    #[test]
+    fn test_painting_names_are_sanitized_but_the_pack_id_is_not() {
+        let items = vec![item_with_id("Bob's Cat"), item_with_id("Sunset (1)"), item_with_id("Café")];
+        assert!(validate_ids("forest_pack", &items).is_empty());
+
+        let violations = validate_ids("Forest Pack! (2)", &items);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].id, "Forest Pack! (2)");
+        assert!(violations[0].reason.ends_with("\"!\", \"(\", \")\""), "{}", violations[0].reason);
+    }
+
+    #[test]
+    fn test_length_limit_includes_size_suffix() {
+        // Exactly at the limit before the `_4x4` suffix is appended
+        let long_id = "a".repeat(MAX_ID_LENGTH);
+        let violations = validate_ids("pack", &[item_with_id(&long_id)]);
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].reason.contains("limit"));
+        assert!(violations[0].id.ends_with("_1x1"));
+    }
+}
@@ -0,0 +1,149 @@
+use std::fs::{self, create_dir_all, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use crate::models::image_size::ImageSize;
+
+// Where painting textures live inside a Minecraft jar or resource pack.
+const PAINTING_TEXTURE_DIR: &str = "assets/minecraft/textures/painting/";
+// The shared back face of every painting, not a painting itself.
+const BACK_TEXTURE: &str = "back";
+// Vanilla painting textures are drawn at 16 pixels per block.
+const VANILLA_PIXELS_PER_BLOCK: u32 = 16;
+
+// One vanilla painting texture copied out of the game files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VanillaPainting {
+    // Texture name, e.g. "kebab" or "skull_and_roses".
+    pub name: String,
+    pub path: PathBuf,
+    // Size in blocks.
+    pub blocks: (u32, u32),
+    // The app's shape with the same aspect ratio, if there is one.
+    pub size: Option<ImageSize>,
+}
+
+// Turns "skull_and_roses" into "Skull And Roses".
+pub fn display_name(texture_name: &str) -> String {
+    texture_name
+        .split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map(|first| first.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// The shape whose block sizes have the same aspect ratio as `blocks`.
+pub(crate) fn shape_for(blocks: (u32, u32)) -> Option<ImageSize> {
+    ImageSize::iter()
+        .find(|size| size.get_size().iter().any(|&(w, h)| w * blocks.1 == h * blocks.0))
+        .copied()
+}
+
+// Texture name for an archive or folder entry inside the painting folder, if it is a painting.
+fn texture_name(entry: &str) -> Option<&str> {
+    let file = entry.strip_prefix(PAINTING_TEXTURE_DIR)?;
+    let name = file.strip_suffix(".png")?;
+    (!name.is_empty() && !name.contains('/') && name != BACK_TEXTURE).then_some(name)
+}
+
+fn describe(name: &str, path: PathBuf) -> Result<VanillaPainting, String> {
+    let (width, height) = image::image_dimensions(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let blocks = ((width / VANILLA_PIXELS_PER_BLOCK).max(1), (height / VANILLA_PIXELS_PER_BLOCK).max(1));
+    Ok(VanillaPainting { name: name.to_string(), path, blocks, size: shape_for(blocks) })
+}
+
+/*
+Copies every painting texture out of a Minecraft client jar or a resource pack (zipped or
+unpacked) into `dest_dir`, so they can be imported and restyled like any other source. Returns
+the paintings in name order. Errors if the source has no painting textures, e.g. versions
+before 1.14 kept them all in a single atlas.
+*/
+pub fn extract_paintings(source: &Path, dest_dir: &Path) -> Result<Vec<VanillaPainting>, String> {
+    create_dir_all(dest_dir).map_err(|e| format!("Failed to create {}: {}", dest_dir.display(), e))?;
+    let mut paintings = if source.is_dir() {
+        extract_from_folder(source, dest_dir)?
+    } else {
+        extract_from_archive(source, dest_dir)?
+    };
+    if paintings.is_empty() {
+        return Err(format!("{} doesn't contain any painting textures", source.display()));
+    }
+    paintings.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(paintings)
+}
+
+fn extract_from_folder(source: &Path, dest_dir: &Path) -> Result<Vec<VanillaPainting>, String> {
+    let folder = source.join(PAINTING_TEXTURE_DIR);
+    let Ok(entries) = fs::read_dir(&folder) else { return Ok(Vec::new()) };
+    let mut paintings = Vec::new();
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(name) = texture_name(&format!("{}{}", PAINTING_TEXTURE_DIR, file_name)).map(str::to_string) else { continue };
+        let dest = dest_dir.join(&file_name);
+        fs::copy(entry.path(), &dest).map_err(|e| format!("Failed to copy {}: {}", entry.path().display(), e))?;
+        paintings.push(describe(&name, dest)?);
+    }
+    Ok(paintings)
+}
+
+fn extract_from_archive(source: &Path, dest_dir: &Path) -> Result<Vec<VanillaPainting>, String> {
+    let file = File::open(source).map_err(|e| format!("Failed to open {}: {}", source.display(), e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("{} is not a jar or zip file: {}", source.display(), e))?;
+    let mut paintings = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+        let Some(name) = texture_name(entry.name()).map(str::to_string) else { continue };
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes).map_err(|e| format!("Failed to extract {}: {}", entry.name(), e))?;
+        let dest = dest_dir.join(format!("{}.png", name));
+        fs::write(&dest, bytes).map_err(|e| format!("Failed to write {}: {}", dest.display(), e))?;
+        paintings.push(describe(&name, dest)?);
+    }
+    Ok(paintings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn png_bytes(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        RgbaImage::new(width, height)
+            .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_extract_paintings_from_jar() {
+        let dir = std::env::temp_dir().join(format!("vanilla_test_{}", std::process::id()));
+        create_dir_all(&dir).unwrap();
+        let jar = dir.join("client.jar");
+        let mut writer = zip::ZipWriter::new(File::create(&jar).unwrap());
+        for (entry, width, height) in [
+            ("assets/minecraft/textures/painting/skull_and_roses.png", 32, 32),
+            ("assets/minecraft/textures/painting/pool.png", 32, 16),
+            ("assets/minecraft/textures/painting/back.png", 16, 16),
+            ("assets/minecraft/textures/block/stone.png", 16, 16),
+        ] {
+            writer.start_file(entry, SimpleFileOptions::default()).unwrap();
+            writer.write_all(&png_bytes(width, height)).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let paintings = extract_paintings(&jar, &dir.join("out")).unwrap();
+        let summary: Vec<(&str, (u32, u32), Option<ImageSize>)> =
+            paintings.iter().map(|p| (p.name.as_str(), p.blocks, p.size)).collect();
+        assert_eq!(summary, [("pool", (2, 1), Some(ImageSize::Wide)), ("skull_and_roses", (2, 2), Some(ImageSize::Square))]);
+        assert!(paintings[0].path.is_file());
+        assert_eq!(display_name("skull_and_roses"), "Skull And Roses");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
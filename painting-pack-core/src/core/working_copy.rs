@@ -0,0 +1,166 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{copy, create_dir_all, metadata, File};
+use std::io::{copy as copy_stream, BufWriter};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use image::{codecs::jpeg::JpegEncoder, DynamicImage, ImageDecoder, ImageReader};
+use sha2::{Digest, Sha256};
+use crate::core::resample;
+use crate::models::import_settings::ImportSettings;
+
+/*
+Decodes an image with its EXIF orientation applied, so photos taken in portrait come in upright.
+*/
+pub fn open_oriented(path: &Path) -> Result<DynamicImage, String> {
+    let fail = |e: &dyn std::fmt::Display| format!("Failed to open {}: {}", path.display(), e);
+    let reader = ImageReader::open(path).and_then(|r| r.with_guessed_format()).map_err(|e| fail(&e))?;
+    let mut decoder = reader.into_decoder().map_err(|e| fail(&e))?;
+    let orientation = decoder.orientation().map_err(|e| fail(&e))?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(|e| fail(&e))?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+// The cache key changes whenever the original is moved, resized or modified.
+fn cache_key(source: &Path) -> Result<u64, String> {
+    let meta = metadata(source).map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    meta.len().hash(&mut hasher);
+    meta.modified().ok().hash(&mut hasher);
+    Ok(hasher.finish())
+}
+
+/*
+Returns the working copy of `source` inside `cache_dir`, creating it if needed: orientation
+applied, 8-bit RGBA (embedded colour profiles are dropped and pixels are treated as sRGB) and
+scaled down to at most `settings.max_dimension` on the long side. Saved as PNG so it stays lossless
+and quick to decode, which matters when the originals live on a slow network drive. Opaque sources
+of at least `settings.lossy_cache_min_bytes` are saved as JPEG instead when a lossy quality is set,
+so huge photos don't balloon the cache; see `is_lossy`.
+*/
+pub fn normalize(source: &Path, cache_dir: &Path, settings: &ImportSettings) -> Result<PathBuf, String> {
+    let source_bytes = metadata(source).map_err(|e| format!("Failed to read {}: {}", source.display(), e))?.len();
+    let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+    let base_name = format!("{}-{:016x}-{}", stem, cache_key(source)?, settings.max_dimension);
+    let lossless_path = cache_dir.join(format!("{}.png", base_name));
+    let quality = settings.lossy_cache_quality.filter(|_| source_bytes >= settings.lossy_cache_min_bytes);
+    let lossy_path = quality.map(|quality| cache_dir.join(format!("{}-q{}.jpg", base_name, quality)));
+    if let Some(cached) = lossy_path.iter().chain([&lossless_path]).find(|path| path.exists()) {
+        return Ok(cached.clone());
+    }
+
+    let image = resample::fit_within(&open_oriented(source)?, settings.max_dimension, settings.max_dimension);
+    // JPEG has no alpha channel, so sources with transparency always stay lossless.
+    let working_path = match (quality, lossy_path) {
+        (Some(quality), Some(lossy_path)) if !image.color().has_alpha() => {
+            let file = File::create(&lossy_path).map_err(|e| format!("Failed to write {}: {}", lossy_path.display(), e))?;
+            DynamicImage::ImageRgb8(image.to_rgb8())
+                .write_with_encoder(JpegEncoder::new_with_quality(BufWriter::new(file), quality))
+                .map_err(|e| format!("Failed to write {}: {}", lossy_path.display(), e))?;
+            lossy_path
+        }
+        _ => {
+            DynamicImage::ImageRgba8(image.to_rgba8())
+                .save(&lossless_path)
+                .map_err(|e| format!("Failed to write {}: {}", lossless_path.display(), e))?;
+            lossless_path
+        }
+    };
+    println!("[IMPORT] normalized {} into {}", source.display(), working_path.display());
+    Ok(working_path)
+}
+
+// Whether a working copy made by `normalize` was re-encoded lossily, so exports should read the original.
+pub fn is_lossy(working_path: &Path) -> bool {
+    working_path.extension().is_some_and(|extension| extension == "jpg")
+}
+
+/*
+SHA-256 of a file's bytes as lowercase hex. Two files with the same hash are exact duplicates,
+whatever they are named.
+*/
+pub fn content_hash(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    copy_stream(&mut file, &mut hasher).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/*
+Copies `source` byte for byte into `store_dir`, so the project keeps working once the original is
+gone. The copy is named after the original plus its cache key, and is reused if already stored.
+*/
+pub fn store_copy(source: &Path, store_dir: &Path) -> Result<PathBuf, String> {
+    create_dir_all(store_dir).map_err(|e| format!("Failed to create {}: {}", store_dir.display(), e))?;
+    let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = source.extension().unwrap_or_default().to_string_lossy();
+    let stored_path = store_dir.join(format!("{}-{:016x}.{}", stem, cache_key(source)?, extension));
+    if !stored_path.exists() {
+        copy(source, &stored_path).map_err(|e| format!("Failed to copy {} into the project store: {}", source.display(), e))?;
+    }
+    Ok(stored_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, RgbImage};
+
+    #[test]
+    fn test_normalize_caps_size_and_reuses_copy() {
+        let dir = std::env::temp_dir().join(format!("working_copy_test_{}", std::process::id()));
+        create_dir_all(&dir).unwrap();
+        let source = dir.join("scan.jpg");
+        RgbImage::new(300, 150).save(&source).unwrap();
+
+        let settings = ImportSettings { max_dimension: 100, ..ImportSettings::default() };
+        let working = normalize(&source, &dir, &settings).unwrap();
+        assert_eq!(working.extension().unwrap(), "png");
+        let image = image::open(&working).unwrap();
+        assert_eq!(image.dimensions(), (100, 50));
+        assert!(matches!(image, DynamicImage::ImageRgba8(_)));
+
+        // A second import of the unchanged original reuses the cached copy
+        assert_eq!(normalize(&source, &dir, &settings).unwrap(), working);
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_normalize_reencodes_large_opaque_sources_lossily() {
+        let dir = std::env::temp_dir().join(format!("lossy_cache_test_{}", std::process::id()));
+        create_dir_all(&dir).unwrap();
+        let photo = dir.join("photo.png");
+        RgbImage::new(300, 150).save(&photo).unwrap();
+        let cutout = dir.join("cutout.png");
+        image::RgbaImage::new(300, 150).save(&cutout).unwrap();
+
+        let settings = ImportSettings { max_dimension: 100, lossy_cache_quality: Some(80), lossy_cache_min_bytes: 0, ..ImportSettings::default() };
+        let working = normalize(&photo, &dir, &settings).unwrap();
+        assert!(is_lossy(&working));
+        assert_eq!(image::open(&working).unwrap().dimensions(), (100, 50));
+        // Transparency can't survive JPEG, and small sources aren't worth the loss.
+        assert!(!is_lossy(&normalize(&cutout, &dir, &settings).unwrap()));
+        let small_only = ImportSettings { lossy_cache_min_bytes: u64::MAX, ..settings };
+        assert!(!is_lossy(&normalize(&photo, &dir, &small_only).unwrap()));
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_store_copy_keeps_bytes() {
+        let dir = std::env::temp_dir().join(format!("store_copy_test_{}", std::process::id()));
+        create_dir_all(&dir).unwrap();
+        let source = dir.join("download.jpg");
+        std::fs::write(&source, b"not really a jpeg").unwrap();
+
+        let stored = store_copy(&source, &dir.join("store")).unwrap();
+        assert!(stored.starts_with(dir.join("store")));
+        assert_eq!(stored.extension().unwrap(), "jpg");
+        std::fs::remove_file(&source).unwrap();
+        assert_eq!(std::fs::read(&stored).unwrap(), b"not really a jpeg");
+        // The stored copy is recognised as the same file
+        std::fs::write(&source, b"not really a jpeg").unwrap();
+        assert_eq!(content_hash(&stored).unwrap(), content_hash(&source).unwrap());
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,61 @@
+use serde::Serialize;
+use thiserror::Error;
+use crate::core::session_lock::LockError;
+
+/*
+Why an export stopped. Serialized with a `kind` tag so the app's frontend can tell a rejected pack
+from a file it couldn't write; `Display` gives the message shown to the user.
+*/
+#[derive(Error, Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExportError {
+    // The pack or its settings were refused before anything was written.
+    #[error("{message}")]
+    Invalid { message: String },
+    // A source image couldn't be opened or cropped.
+    #[error("Couldn't read {path}: {message}")]
+    Source { path: String, message: String },
+    #[error("Couldn't encode {file}: {message}")]
+    Encode { file: String, message: String },
+    // A file or folder of the pack, or of its backup, couldn't be written.
+    #[error("Couldn't write {path}: {message}")]
+    Write { path: String, message: String },
+    // Another window or export is writing the same pack; nothing was touched.
+    #[error("{message}")]
+    InUse { path: String, message: String },
+    // Stopped through the cancel flag; the partial pack was removed again.
+    #[error("The export was cancelled")]
+    Cancelled,
+    // The export stopped on a bug rather than on anything about the pack.
+    #[error("{message}")]
+    Internal { message: String },
+}
+
+impl ExportError {
+    pub fn write(path: impl AsRef<std::path::Path>, error: impl ToString) -> Self {
+        ExportError::Write { path: path.as_ref().display().to_string(), message: error.to_string() }
+    }
+}
+
+// Validation throughout the crate reports plain messages; those refuse the pack as it is.
+impl From<String> for ExportError {
+    fn from(message: String) -> Self {
+        ExportError::Invalid { message }
+    }
+}
+
+impl From<LockError> for ExportError {
+    fn from(error: LockError) -> Self {
+        match &error {
+            LockError::Held { path, .. } => ExportError::InUse { path: path.clone(), message: error.to_string() },
+            LockError::Io { path, message } => ExportError::Write { path: path.clone(), message: message.clone() },
+        }
+    }
+}
+
+// For callers that report every error as a message, like the rest of the crate.
+impl From<ExportError> for String {
+    fn from(error: ExportError) -> Self {
+        error.to_string()
+    }
+}
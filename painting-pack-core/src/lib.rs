@@ -0,0 +1,33 @@
+//! Cropping, naming and export pipeline for [Custom Paintings](https://modrinth.com/mod/custom-paintings)
+//! packs for Minecraft, with no UI dependency. Shared by the Painting Pack Maker app and any other
+//! tool that builds packs.
+//!
+//! Most callers only need the builders re-exported here:
+//!
+//! ```no_run
+//! use painting_pack_core::{Exporter, ImageSize, Pack, Painting};
+//!
+//! let pack = Pack::builder("Forest Pack")
+//!     .description("Paintings of the woods")
+//!     .painting(Painting::builder("photos/oak.jpg", ImageSize::Tall).name("Oak").build())
+//!     .build()?;
+//! let pack_dir = Exporter::new("exports").default_artist("Me").pixels_per_block(32).export(&pack)?;
+//! println!("Wrote {}", pack_dir.display());
+//! # Ok::<(), String>(())
+//! ```
+//!
+//! The `core` and `models` modules expose the lower level pipeline the app itself uses.
+
+pub mod models;
+pub mod core;
+pub mod error;
+mod api;
+
+pub use api::{Cropper, Exporter, Pack, PackBuilder, Painting, PaintingBuilder};
+pub use models::export_settings::{ExportSettings, IconStyle};
+pub use models::image_size::ImageSize;
+pub use models::painting::PaintingFrame;
+pub use core::flipbook::Animation;
+pub use core::icon_badge::IconBadge;
+pub use error::ExportError;
+pub use core::image_format::{ColorDepth, OutputFormat};
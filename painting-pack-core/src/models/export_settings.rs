@@ -0,0 +1,280 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use crate::core::{icon_badge::IconBadge, image_format::{ColorDepth, OutputFormat}, lint, naming, resample::ResampleFilter};
+use crate::models::pack_list::PACK_KEYS;
+
+/*
+How painting display names are re-cased at export. IDs are never affected.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum NameCase {
+    #[default]
+    Unchanged,
+    TitleCase,
+    SentenceCase,
+}
+
+// Built-in colour themes for palette harmonization.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum PaletteTheme {
+    Warm,
+    Cool,
+    Sepia,
+    Pastel,
+    Forest,
+}
+
+// Where the shared palette comes from: a built-in theme or colours extracted from a reference image.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum PaletteSource {
+    Theme(PaletteTheme),
+    Reference(String),
+}
+
+/*
+Optional pass that nudges every painting towards a shared palette so packs assembled from
+many artists look cohesive. `strength` runs from 0 (off) to 1 (fully mapped to the palette).
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PaletteHarmonization {
+    pub source: PaletteSource,
+    pub strength: f32,
+}
+
+/*
+A second, lighter copy of the pack written alongside the full resolution one. It shares all
+painting metadata but gets its own pack name and ID so both can be installed side by side.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct LiteVariant {
+    // Texture density of the Lite pack; vanilla paintings use 16.
+    pub pixels_per_block: u32,
+    // Appended to the pack name, e.g. "Forest Pack Lite".
+    pub name_suffix: String,
+    // Appended to the pack ID, e.g. "forest_pack_lite".
+    pub id_suffix: String,
+}
+
+impl Default for LiteVariant {
+    fn default() -> Self {
+        LiteVariant {
+            pixels_per_block: 32,
+            name_suffix: String::from(" Lite"),
+            id_suffix: String::from("_lite"),
+        }
+    }
+}
+
+/*
+The order paintings are listed in custompaintings.json, which is the order the mod shows them in.
+Sorting leaves the sizes of one painting in their usual order.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum PaintingOrder {
+    // As arranged in the library.
+    #[default]
+    Library,
+    // By display name.
+    Name,
+    // By image file name.
+    Filename,
+}
+
+// A change to the order of custompaintings.json, as sent by the frontend.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum PaintingReorder {
+    // Moves one library entry, by index, and lists the pack in library order from then on.
+    Move { from: usize, to: usize },
+    SortBy { order: PaintingOrder },
+}
+
+// What the pack's icon.png shows.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum IconStyle {
+    // The bundled app icon.
+    #[default]
+    Default,
+    // A square crop of the first painting in the pack.
+    FirstPainting,
+    // A 2x2 or 3x3 collage of the first paintings in the pack. See `icon_generator::collage_icon`.
+    Collage2x2,
+    Collage3x3,
+}
+
+/*
+How custompaintings.json is laid out. Pretty printing with a 2 space indent matches earlier
+versions; minified output is smallest, and omitting empty optional fields keeps hand edits tidy.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct JsonStyle {
+    pub pretty: bool,
+    // Spaces per level when pretty printing.
+    pub indent: u32,
+    // Leave out optional fields (description, painting names and artists) that are empty.
+    pub omit_empty_fields: bool,
+}
+
+impl Default for JsonStyle {
+    fn default() -> Self {
+        JsonStyle { pretty: true, indent: 2, omit_empty_fields: false }
+    }
+}
+
+// Matches the historical `{filename}_{w}x{h}.png` naming.
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "{filename}_{w}x{h}";
+
+// Textures wider than this are scaled down unless the pack sets its own limit.
+pub const DEFAULT_MAX_TEXTURE_WIDTH: u32 = 1024;
+
+/*
+User-configurable options that control how a pack is written to disk.
+The frontend sends these as a single object; any missing field falls back to its default.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ExportSettings {
+    // Save a timestamped copy of an existing custompaintings.json before it is overwritten.
+    pub backup_existing: bool,
+    // Also copy the existing images directory into the backup. Off by default as it can be large.
+    pub backup_images: bool,
+    // Re-casing applied to each painting's display name.
+    pub name_case: NameCase,
+    // Remove emojis and other glyphs Minecraft's font can't render from display names.
+    pub strip_unsupported_glyphs: bool,
+    // Template for exported image names, without the extension. See `naming::render_filename`.
+    pub filename_template: String,
+    // Name exported images after their source file instead of using the template.
+    pub preserve_source_filenames: bool,
+    // Artist used for paintings whose artist field was left blank.
+    pub default_artist: String,
+    // Shift all paintings towards a shared palette. Off when `None`.
+    pub palette_harmonization: Option<PaletteHarmonization>,
+    // Custom Paintings release the pack is checked against. See `lint::RULES`.
+    pub target_mod_version: String,
+    // Also export a reduced-resolution "Lite" pack in the same run. Off when `None`.
+    pub lite_variant: Option<LiteVariant>,
+    // Texture density of the main pack; `None` keeps the full resolution (up to the texture cap).
+    pub pixels_per_block: Option<u32>,
+    // Texture cap: wider textures are scaled down to this width, keeping their aspect ratio.
+    // Lower it (e.g. 512) for lighter packs on weaker machines; `None` exports at source resolution.
+    pub max_texture_width: Option<u32>,
+    // Write block sizes the source is too small to fill at the chosen density anyway, upscaled by
+    // the game. Off by default: those variants are left out and reported instead.
+    pub force_undersized_variants: bool,
+    // Filter used when textures are scaled down to the texture cap or the pixels per block.
+    // Pixel-art packs want `Nearest` so their pixels stay crisp.
+    pub resample_filter: ResampleFilter,
+    pub icon_style: IconStyle,
+    // Text stamped onto the icon, so packs with the same icon can be told apart. Off when `None`.
+    pub icon_badge: Option<IconBadge>,
+    pub json_style: JsonStyle,
+    pub painting_order: PaintingOrder,
+    // Format of the painting textures; must be one the target mod release can load. The icon is always PNG.
+    pub output_format: OutputFormat,
+    // Colour depth of the painting textures. Images that need transparency are always written with alpha.
+    pub color_depth: ColorDepth,
+    // Also write preview.png, every painting side by side at relative scale, for sharing the pack.
+    pub preview_strip: bool,
+    // Write each pack as a single .zip archive next to where its folder would go, instead of the folder.
+    pub archive: bool,
+    // The curator's own limit on paintings per pack (every block size counts), e.g. 20 to keep
+    // packs light on servers. Enforced on top of the mod's limit; `None` for no cap.
+    pub painting_cap: Option<usize>,
+    // `$schema` written to custompaintings.json; `None` uses the target release's (see `lint::RULES`).
+    pub schema_url: Option<String>,
+    // Extra top-level fields of custompaintings.json, for forks of the mod with an extended format.
+    pub extra_pack_fields: Map<String, Value>,
+}
+
+impl ExportSettings {
+    // Rejects settings no export could honour, before they are stored.
+    pub fn validate(&self) -> Result<(), String> {
+        naming::validate_filename_template(&self.filename_template)?;
+        if self.pixels_per_block == Some(0) {
+            return Err(String::from("The pack needs at least 1 pixel per block"));
+        }
+        if self.max_texture_width == Some(0) {
+            return Err(String::from("The maximum texture size needs to be at least 1 pixel"));
+        }
+        if self.schema_url.as_deref().is_some_and(|url| url.trim().is_empty()) {
+            return Err(String::from("The schema URL can't be blank; clear it to use the default"));
+        }
+        for key in self.extra_pack_fields.keys() {
+            if key.trim().is_empty() {
+                return Err(String::from("Custom pack fields need a name"));
+            }
+            if PACK_KEYS.contains(&key.as_str()) {
+                return Err(format!("\"{}\" is a standard pack field and can't be set as a custom field", key));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        ExportSettings {
+            backup_existing: true,
+            backup_images: false,
+            name_case: NameCase::Unchanged,
+            strip_unsupported_glyphs: false,
+            filename_template: String::from(DEFAULT_FILENAME_TEMPLATE),
+            preserve_source_filenames: false,
+            default_artist: String::new(),
+            palette_harmonization: None,
+            target_mod_version: String::from(lint::latest_version()),
+            lite_variant: None,
+            pixels_per_block: None,
+            max_texture_width: Some(DEFAULT_MAX_TEXTURE_WIDTH),
+            force_undersized_variants: false,
+            resample_filter: ResampleFilter::default(),
+            icon_style: IconStyle::Default,
+            icon_badge: None,
+            json_style: JsonStyle::default(),
+            painting_order: PaintingOrder::default(),
+            output_format: OutputFormat::default(),
+            color_depth: ColorDepth::default(),
+            preview_strip: false,
+            archive: false,
+            painting_cap: None,
+            schema_url: None,
+            extra_pack_fields: Map::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_backs_up_json_only() {
+        let settings = ExportSettings::default();
+        assert!(settings.backup_existing);
+        assert!(!settings.backup_images);
+        assert_eq!(settings.name_case, NameCase::Unchanged);
+        assert!(!settings.strip_unsupported_glyphs);
+    }
+
+    #[test]
+    fn test_deserialize_partial_uses_defaults() {
+        let settings: ExportSettings = serde_json::from_str(r#"{ "backup_images": true, "name_case": "TitleCase" }"#).unwrap();
+        assert!(settings.backup_existing);
+        assert!(settings.backup_images);
+        assert_eq!(settings.name_case, NameCase::TitleCase);
+    }
+
+    #[test]
+    fn test_validate_rejects_custom_fields_named_like_standard_ones() {
+        let mut settings = ExportSettings::default();
+        settings.extra_pack_fields.insert(String::from("license"), Value::from("CC-BY"));
+        assert!(settings.validate().is_ok());
+        settings.extra_pack_fields.insert(String::from("paintings"), Value::Array(Vec::new()));
+        assert!(settings.validate().is_err());
+        let blank_schema = ExportSettings { schema_url: Some(String::from(" ")), ..ExportSettings::default() };
+        assert!(blank_schema.validate().is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use crate::core::adjustments::SizeAdjustment;
+use crate::core::cropper::{CropOffset, CropOptions, CropRect, FitMode};
+use crate::core::flipbook::Animation;
+use serde::{Deserialize, Serialize};
+use crate::models::image_size::ImageSize;
+use crate::models::painting::PaintingFrame;
+
+// The `DynamicImage` field has been removed to reduce memory usage.
+// This struct now only holds metadata about a potential crop.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ImageData {
+    pub id:         Option<String>,
+    pub filename:   Option<String>,
+    pub name:       Option<String>,
+    pub artist:     Option<String>,
+    // Optional flavor text (lore) for the painting.
+    pub description: Option<String>,
+    // Custom frame tint or texture, for mod releases that support it.
+    pub frame: Option<PaintingFrame>,
+    // Relative chance of being picked for a randomly placed painting, for mod releases that support it.
+    #[serde(default)]
+    pub weight: Option<u32>,
+    // Set when the source is a flipbook of stacked frames.
+    #[serde(default)]
+    pub animation: Option<Animation>,
+    pub image_size: ImageSize,
+    pub selected:   bool,
+    // Straighten the source before cropping. Copied from the image's group on export.
+    pub deskew:     bool,
+    // Share of each edge trimmed off the source, then over-cropped off the ratio crop. See `CropOptions`.
+    pub inset_percent: f32,
+    pub bleed_percent: f32,
+    // Where this crop's window sits instead of the centre of the source; `None` keeps it centred.
+    #[serde(default)]
+    pub crop_offset: Option<CropOffset>,
+    // A crop window the user picked by hand, used instead of the automatic one.
+    #[serde(default)]
+    pub crop_rect: Option<CropRect>,
+    // Whether the image is cropped, letterboxed or stretched to this shape.
+    #[serde(default)]
+    pub fit_mode: FitMode,
+    // The group's library position, copied on export. 0 means unordered.
+    pub order:      u32,
+    // Adjustments for single block sizes of this crop, applied to those textures only.
+    #[serde(default)]
+    pub size_adjustments: Vec<SizeAdjustment>,
+}
+
+impl ImageData {
+    // The constructor no longer takes an image, only the size metadata.
+    pub fn new(image_size: ImageSize) -> Self {
+        ImageData {
+            id:         None,
+            filename:   None,
+            name:       None,
+            artist:     None,
+            description: None,
+            frame:      None,
+            weight:     None,
+            animation:  None,
+            image_size,
+            selected:   true,
+            deskew:     false,
+            inset_percent: 0.0,
+            bleed_percent: 0.0,
+            crop_offset: None,
+            crop_rect: None,
+            fit_mode:   FitMode::Crop,
+            order:      0,
+            size_adjustments: Vec::new(),
+        }
+    }
+
+    // `get_image()` has been removed as the image data is no longer stored here.
+
+    pub fn crop_options(&self) -> CropOptions {
+        CropOptions {
+            deskew: self.deskew,
+            inset_percent: self.inset_percent,
+            bleed_percent: self.bleed_percent,
+            frames: self.animation.map_or(0, |animation| animation.frames),
+            offset: self.crop_offset.unwrap_or_default(),
+            rect: self.crop_rect,
+            fit: self.fit_mode,
+        }
+    }
+
+    pub fn get_sizes(&self) -> &[(u32, u32)] {
+        self.image_size.get_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::image_size::ImageSize;
+
+    #[test]
+    fn test_new_constructor_sets_defaults() {
+        let image_size = ImageSize::Square;
+        let image_data = ImageData::new(image_size);
+
+        // Check that all optional fields are None
+        assert!(image_data.id.is_none());
+        assert!(image_data.filename.is_none());
+        assert!(image_data.name.is_none());
+        assert!(image_data.artist.is_none());
+
+        // Check that 'selected' defaults to true
+        assert_eq!(image_data.selected, true);
+
+        // Check that image_size is set correctly
+        assert!(matches!(image_data.image_size, ImageSize::Square));
+        assert_eq!(image_data.get_sizes(), &[(1, 1), (2, 2), (3, 3), (4, 4)]);
+    }
+}
@@ -1,7 +1,7 @@
-use serde::{Serialize};
+use serde::{Deserialize, Serialize};
 use std::slice::Iter;
 
-#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub enum ImageSize {
     Square,
     Wide, 
@@ -32,6 +32,16 @@ impl ImageSize {
         ImageSize::TallRectangle => &[(3, 4)],
         }
     }
+
+    // Pixel dimensions with this shape's aspect ratio whose longer side is `long_side`.
+    pub fn dimensions_for_long_side(&self, long_side: u32) -> (u32, u32) {
+        let (blocks_w, blocks_h) = self.get_size()[0];
+        if blocks_w >= blocks_h {
+            (long_side, long_side * blocks_h / blocks_w)
+        } else {
+            (long_side * blocks_w / blocks_h, long_side)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -57,4 +67,11 @@ mod tests {
         assert_eq!(ImageSize::Tall.get_size(), &[(1, 2), (2, 4)]);
         assert_eq!(ImageSize::TallRectangle.get_size(), &[(3, 4)]);
     }
+
+    #[test]
+    fn test_dimensions_for_long_side() {
+        assert_eq!(ImageSize::Square.dimensions_for_long_side(1024), (1024, 1024));
+        assert_eq!(ImageSize::Wide.dimensions_for_long_side(1024), (1024, 512));
+        assert_eq!(ImageSize::TallRectangle.dimensions_for_long_side(1024), (768, 1024));
+    }
 }
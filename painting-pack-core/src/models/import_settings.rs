@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use crate::models::image_size::ImageSize;
+
+/*
+Options applied to images as they are imported. The frontend sends these as a single object;
+any missing field falls back to its default.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ImportSettings {
+    // Transcode each source into a normalized working copy in the cache and use that from then on.
+    pub normalize: bool,
+    // Working copies are scaled down so their long side is at most this many pixels.
+    pub max_dimension: u32,
+    // Working copies of sources at least `lossy_cache_min_bytes` big are re-encoded as JPEG at this
+    // quality (1-100) to keep the cache small; exports still read the full-quality file. `None`
+    // keeps every working copy lossless.
+    pub lossy_cache_quality: Option<u8>,
+    pub lossy_cache_min_bytes: u64,
+    // Painting shapes that start out selected for a new image; the rest start deselected.
+    pub selected_sizes: Vec<ImageSize>,
+    // Copy each source into the project store and move the original to the system trash.
+    pub consume_originals: bool,
+    // When importing a folder tree, read names and artists off the folders with a rule such as
+    // "{artist}/{name}". See `folder_naming::apply_rule`. `None` names paintings after their files.
+    pub folder_naming_rule: Option<String>,
+}
+
+impl ImportSettings {
+    // Rejects settings no import could honour.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_dimension == 0 {
+            return Err(String::from("Maximum working resolution must be at least 1"));
+        }
+        if self.lossy_cache_quality.is_some_and(|quality| !(1..=100).contains(&quality)) {
+            return Err(String::from("Working copy JPEG quality must be between 1 and 100"));
+        }
+        Ok(())
+    }
+}
+
+impl Default for ImportSettings {
+    fn default() -> Self {
+        ImportSettings {
+            normalize: false,
+            max_dimension: 4096,
+            lossy_cache_quality: None,
+            lossy_cache_min_bytes: 16 * 1024 * 1024,
+            selected_sizes: ImageSize::iter().copied().collect(),
+            consume_originals: false,
+            folder_naming_rule: None,
+        }
+    }
+}
@@ -0,0 +1,6 @@
+pub mod image_data;
+pub mod pack_list;
+pub mod image_size;
+pub mod export_settings;
+pub mod painting;
+pub mod import_settings;
\ No newline at end of file
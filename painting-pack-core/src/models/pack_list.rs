@@ -1,7 +1,17 @@
-use serde::Serialize;
-use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use crate::core::{natural_sort, validator};
+use crate::models::painting::Painting;
 
-#[derive(Serialize, Debug)]
+// The JSON Schema custompaintings.json declares unless the target release or the pack says otherwise.
+pub const DEFAULT_SCHEMA_URL: &str = "http://json-schema.org/draft-07/schema#";
+
+// Top-level keys of custompaintings.json; extra fields can't use them.
+pub const PACK_KEYS: &[&str] = &["name", "$schema", "version", "id", "description", "paintings"];
+
+// Missing fields fall back to the defaults when an existing pack is read back in.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
 pub struct PackList<T> {
     #[serde(rename = "name")]
     pub pack_name: String,
@@ -10,22 +20,22 @@ pub struct PackList<T> {
     pub version: String, 
     pub id: String, 
     pub description: String,
+    // Extra top-level fields for forks of the mod with an extended format, written as they are.
+    #[serde(flatten)]
+    pub extra_fields: Map<String, Value>,
     paintings: Vec<T>,
 }
 
 impl<T> Default for PackList<T> {
     fn default() -> Self {
 
-        let mut rng = rand::rng();
-        let random_int: i32 = rng.random_range(56000..=128000);
-        let random_id = format!("{}", random_int);
-        
         PackList {
             pack_name: String::from("Default"),
-            schema: String::from("http://json-schema.org/draft-07/schema#"),
+            schema: String::from(DEFAULT_SCHEMA_URL),
             version: String::from("1.0.0"),
-            id: random_id, 
+            id: validator::generate_pack_id(), 
             description: String::from("A list of paintings in the gallery"),
+            extra_fields: Map::new(),
             paintings: Vec::new(),
         }
     }
@@ -44,10 +54,11 @@ impl<T> PackList<T> {
     pub fn new(pack_name: String, version: String, id: String, description: String) -> Self {
         PackList {
             pack_name,
-            schema: String::from("http://json-schema.org/draft-07/schema#"),
+            schema: String::from(DEFAULT_SCHEMA_URL),
             version,
             id,
             description,
+            extra_fields: Map::new(),
             paintings: Vec::new(),
         }
     }
@@ -59,8 +70,7 @@ impl<T> PackList<T> {
         }
     }
 
-    // unused, nobody is setting a schema round these parts
-    pub fn _set_schema(&mut self, schema: &str) {
+    pub fn set_schema(&mut self, schema: &str) {
         match check_no_input(schema) {
             Some(valid_schema) => self.schema = valid_schema,
             None => {},
@@ -96,6 +106,43 @@ impl<T> PackList<T> {
         self.paintings.len()
     }
 
+    pub fn paintings(&self) -> &[T] {
+        &self.paintings
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.paintings.get(index)
+    }
+
+    // Takes the painting at `index` out of the list, shifting the ones after it up.
+    pub fn remove_painting(&mut self, index: usize) -> Result<T, String> {
+        if index >= self.paintings.len() {
+            return Err(format!("No painting {}: the pack has {} paintings", index, self.paintings.len()));
+        }
+        Ok(self.paintings.remove(index))
+    }
+
+    // Replaces the painting at `index`, keeping its place, and returns the old one.
+    pub fn update_painting(&mut self, index: usize, painting: T) -> Result<T, String> {
+        let count = self.paintings.len();
+        let slot = self
+            .paintings
+            .get_mut(index)
+            .ok_or_else(|| format!("No painting {}: the pack has {} paintings", index, count))?;
+        Ok(std::mem::replace(slot, painting))
+    }
+
+    // Moves the painting at `from` to position `to`, shifting the ones in between along.
+    pub fn move_painting(&mut self, from: usize, to: usize) -> Result<(), String> {
+        let count = self.paintings.len();
+        if from >= count || to >= count {
+            return Err(format!("Can't move painting {} to {}: the pack has {} paintings", from, to, count));
+        }
+        let painting = self.paintings.remove(from);
+        self.paintings.insert(to, painting);
+        Ok(())
+    }
+
     pub fn separate_paintings<U>(self) -> (PackList<U>, Vec<T>) {
         
         // 1. Create the new struct with a new, empty `paintings` vector.
@@ -106,6 +153,7 @@ impl<T> PackList<T> {
             version: self.version,
             id: self.id,
             description: self.description,
+            extra_fields: self.extra_fields,
             paintings: Vec::new(),
         };
 
@@ -118,6 +166,18 @@ impl<T> PackList<T> {
     }
 }
 
+impl PackList<Painting> {
+    // Orders paintings by display name, naturally ("Dusk 2" before "Dusk 10"); a painting's sizes keep their order.
+    pub fn sort_by_name(&mut self) {
+        self.paintings.sort_by(|a, b| natural_sort::natural_cmp(&a.name, &b.name));
+    }
+
+    // Orders paintings by image file name, naturally.
+    pub fn sort_by_filename(&mut self) {
+        self.paintings.sort_by(|a, b| natural_sort::natural_cmp(&a.filename, &b.filename));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -165,7 +225,7 @@ mod tests {
         assert_eq!(list.pack_name, "Default");
         assert_eq!(list.version, "1.0.0");
         assert_eq!(list.description, "A list of paintings in the gallery");
-        assert!(!list.id.is_empty()); // ID should be a random number string
+        assert!(!list.id.is_empty()); // ID should be a generated adjective_noun_hex slug
         assert_eq!(list.painting_count(), 0);
     }
 
@@ -225,6 +285,21 @@ mod tests {
         assert_eq!(list.description, "A new description."); // Should not change
     }
 
+    #[test]
+    fn test_schema_and_extra_fields_are_written_at_the_top_level() {
+        let mut list: PackList<i32> = PackList::new("Pack".to_string(), "1.0.0".to_string(), "pack".to_string(), String::new());
+        list.set_schema("  ");
+        assert_eq!(list.schema, DEFAULT_SCHEMA_URL);
+        list.set_schema("https://example.com/fork.schema.json");
+        list.extra_fields.insert("animated".to_string(), Value::Bool(true));
+
+        let json = serde_json::to_value(&list).unwrap();
+        assert_eq!(json["$schema"], "https://example.com/fork.schema.json");
+        assert_eq!(json["animated"], true);
+        let read: PackList<i32> = serde_json::from_value(json).unwrap();
+        assert_eq!(read.extra_fields.get("animated"), Some(&Value::Bool(true)));
+    }
+
     #[test]
     fn test_add_painting() {
         let mut list: PackList<i32> = PackList::default();
@@ -237,6 +312,50 @@ mod tests {
         assert_eq!(list.painting_count(), 2);
     }
 
+    #[test]
+    fn test_paintings_can_be_read_updated_and_removed() {
+        let mut list: PackList<i32> = PackList::default();
+        list.add_painting(1);
+        list.add_painting(2);
+        list.add_painting(3);
+
+        assert_eq!(list.get(1), Some(&2));
+        assert_eq!(list.get(3), None);
+        assert_eq!(list.update_painting(1, 20), Ok(2));
+        assert_eq!(list.remove_painting(0), Ok(1));
+        assert_eq!(list.paintings(), [20, 3]);
+        assert!(list.remove_painting(2).is_err());
+        assert!(list.update_painting(2, 4).is_err());
+    }
+
+    #[test]
+    fn test_paintings_can_be_moved_and_sorted() {
+        let painting = |name: &str, filename: &str| Painting {
+            id: filename.to_string(),
+            filename: filename.to_string(),
+            name: name.to_string(),
+            artist: String::new(),
+            width: 1,
+            height: 1,
+            description: None,
+            frame: None,
+            weight: None,
+        };
+        let mut list: PackList<Painting> = PackList::default();
+        list.add_painting(painting("Dusk 10", "a"));
+        list.add_painting(painting("Dusk 2", "c"));
+        list.add_painting(painting("Astra", "b"));
+        let names = |list: &PackList<Painting>| list.paintings().iter().map(|p| p.name.clone()).collect::<Vec<_>>();
+
+        list.sort_by_name();
+        assert_eq!(names(&list), ["Astra", "Dusk 2", "Dusk 10"]);
+        list.sort_by_filename();
+        assert_eq!(names(&list), ["Dusk 10", "Astra", "Dusk 2"]);
+        list.move_painting(2, 0).unwrap();
+        assert_eq!(names(&list), ["Dusk 2", "Dusk 10", "Astra"]);
+        assert!(list.move_painting(0, 3).is_err());
+    }
+
     #[test]
     fn test_separate_paintings() {
         let mut list: PackList<i32> = PackList::new(
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+
+// One entry of a pack's custompaintings.json, as written by the exporter.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Painting {
+    pub id:         String,
+    pub filename:   String,
+    pub name:       String,
+    pub artist:     String,
+    pub width:      u32,
+    pub height:     u32,
+    // Flavor text shown with the painting. Only written for mod releases that support it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    // Custom frame look. Only written for mod releases that support frames.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame: Option<PaintingFrame>,
+    // How often the painting is picked when one is placed at random, relative to the default of 1.
+    // Only written for mod releases that support weights.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<u32>,
+}
+
+// Highest weight a painting can have; a painting at this weight turns up 100 times as often as one left at 1.
+pub const MAX_PAINTING_WEIGHT: u32 = 100;
+
+// Errors on a weight outside 1 to `MAX_PAINTING_WEIGHT`.
+pub fn validate_weight(weight: u32) -> Result<(), String> {
+    if !(1..=MAX_PAINTING_WEIGHT).contains(&weight) {
+        return Err(format!("A painting's weight must be between 1 and {}", MAX_PAINTING_WEIGHT));
+    }
+    Ok(())
+}
+
+/*
+A painting's own frame: a tint for the default frame, a frame texture from a resource pack, or
+both. Field names follow the mod's JSON.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct PaintingFrame {
+    // Tint as "#rrggbb".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    // Resource location of the frame texture, e.g. "minecraft:block/oak_planks".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub texture: Option<String>,
+}
+
+impl PaintingFrame {
+    pub fn is_empty(&self) -> bool {
+        self.color.is_none() && self.texture.is_none()
+    }
+
+    // The tint as RGB, if one is set and well formed.
+    pub fn tint(&self) -> Option<[u8; 3]> {
+        let hex = self.color.as_deref()?.strip_prefix('#')?;
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        Some([channel(0)?, channel(2)?, channel(4)?])
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(color) = &self.color {
+            if self.tint().is_none() {
+                return Err(format!("Frame color \"{}\" must look like #8b5a2b", color));
+            }
+        }
+        if let Some(texture) = &self.texture {
+            let valid = texture.split_once(':').is_some_and(|(namespace, path)| {
+                let allowed = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || "_-.".contains(c);
+                !namespace.is_empty() && !path.is_empty() && namespace.chars().all(allowed) && path.chars().all(|c| allowed(c) || c == '/')
+            });
+            if !valid {
+                return Err(format!("Frame texture \"{}\" must be a resource location like minecraft:block/oak_planks", texture));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_validation() {
+        let frame = PaintingFrame { color: Some("#8B5a2b".into()), texture: Some("minecraft:block/oak_planks".into()) };
+        assert!(frame.validate().is_ok());
+        assert_eq!(frame.tint(), Some([0x8b, 0x5a, 0x2b]));
+
+        assert!(PaintingFrame { color: Some("brown".into()), texture: None }.validate().is_err());
+        assert!(PaintingFrame { color: None, texture: Some("Oak Planks".into()) }.validate().is_err());
+        assert!(PaintingFrame::default().is_empty());
+        assert!(validate_weight(5).is_ok());
+        assert!(validate_weight(0).is_err());
+        assert!(validate_weight(MAX_PAINTING_WEIGHT + 1).is_err());
+    }
+}
@@ -1,4 +1,40 @@
-use crate::models::{image_data::ImageData, pack_list::PackList};
+use std::path::{Path, PathBuf};
+use crate::core::{
+    adjustments::{Adjustments, SizeAdjustment},
+    artists::{self, ArtistGroup},
+    balance::{self, OrientationWarning, SizeWeight},
+    confirmation::{Confirmation, Confirmations},
+    cropper::{self, CropOffset, CropOptions, CropRect, FitMode},
+    export_history::ExportHistory,
+    export_presets::{self, ExportPreset},
+    export_queue::ExportJob,
+    export_resume::{self, InterruptedExport},
+    first_run::{self, SetupChoices},
+    flipbook::Animation,
+    exporter::{self, ExportItem, ExportReport},
+    hot_folder::HotFolder,
+    history::{GroupSnapshot, History, ProjectSnapshot},
+    lint::{self, LintIssue},
+    low_memory,
+    pack_budget::{self, PackBudget},
+    pack_split::{self, SplitMode},
+    pack_registry::{PackCollision, PackRecord},
+    path_scope::PathScope,
+    preview_key::PreviewKey,
+    project::{self, Project},
+    project_recovery::{self, RecoveryReport},
+    relink::{self, LostSource, RelinkMatch, RelinkReport, RelinkedSource},
+    session_lock::SessionLock,
+    session_temp::{PurgeReport, SessionTemp},
+    settings::AppSettings,
+    templates::{self, PackTemplate},
+    timestamp, validator, working_copy,
+};
+use crate::error::ExportError;
+use crate::models::{
+    export_settings::{ExportSettings, PaletteHarmonization, PaintingOrder, PaintingReorder, PaletteSource}, image_data::ImageData, image_size::ImageSize, import_settings::ImportSettings,
+    pack_list::PackList, painting::PaintingFrame,
+};
 
 /// Represents one row in your UI. It contains the data
 /// for one source image and all its generated crops.
@@ -6,11 +42,142 @@ use crate::models::{image_data::ImageData, pack_list::PackList};
 pub struct SourceImageGroup {
     // Added the path to the original source image.
     // This allows us to reload and re-crop it on-demand during export.
+    // When the source was normalized on import this is the working copy in the cache.
     pub source_path: String,
+    // The file the user imported, if `source_path` is a normalized working copy of it.
+    pub original_path: Option<String>,
+    // The full-quality file exports read when `source_path` is a lossy working copy. See `working_copy::is_lossy`.
+    pub full_quality_path: Option<String>,
+    // The file the working copy at `source_path` was made from (the stored copy of a consumed
+    // original, or the original itself), so it can be rebuilt once the session's cache is gone.
+    pub working_copy_source: Option<String>,
     pub name: String,
     pub artist: String,
+    // Flavor text for the painting; blank for none.
+    pub description: String,
+    // Custom frame tint or texture; `None` keeps the mod's default frame.
+    pub frame: Option<PaintingFrame>,
+    // How often the painting turns up when placed at random, relative to 1; `None` for the default.
+    pub weight: Option<u32>,
+    // Frame count and timing if the source is a flipbook; `None` for a still image.
+    pub animation: Option<Animation>,
     // This Vec now holds the metadata-only ImageData structs.
     pub crops: Vec<ImageData>,
+    // Whether the source is auto-straightened before cropping (for tilted scans and photos).
+    pub deskew: bool,
+    // Share of each edge trimmed off the source before cropping, and over-cropped after. See `CropOptions`.
+    pub inset_percent: f32,
+    pub bleed_percent: f32,
+    // 1-based position in the library. Drives `{index}` in filenames and the order of
+    // custompaintings.json; set when the group is added and kept through reorders and rollbacks.
+    pub order: u32,
+    // SHA-256 of the imported file, used to skip exact duplicates. `None` if it wasn't hashed.
+    pub content_hash: Option<String>,
+    // Key the frontend's current previews were made under. `None` until they have been sent.
+    pub preview_key: Option<PreviewKey>,
+}
+
+impl SourceImageGroup {
+    /// Creates the group for a freshly imported source: named after the file,
+    /// with a placeholder artist and every crop variant selected.
+    pub fn new(source_path: &str) -> Self {
+        let name = std::path::Path::new(source_path).file_stem().unwrap_or_default().to_string_lossy().to_string();
+        SourceImageGroup {
+            source_path: source_path.to_string(),
+            original_path: None,
+            full_quality_path: None,
+            working_copy_source: None,
+            name,
+            artist: String::from("Artist Name"),
+            description: String::new(),
+            frame: None,
+            weight: None,
+            animation: None,
+            crops: ImageSize::iter().map(|size_variant| ImageData::new(*size_variant)).collect(),
+            deskew: false,
+            inset_percent: 0.0,
+            bleed_percent: 0.0,
+            order: 0,
+            content_hash: None,
+            preview_key: None,
+        }
+    }
+
+    /// Every file this row reads from: its source, and the files behind a working copy.
+    pub fn files(&self) -> impl Iterator<Item = &String> {
+        [Some(&self.source_path), self.original_path.as_ref(), self.full_quality_path.as_ref(), self.working_copy_source.as_ref()]
+            .into_iter()
+            .flatten()
+    }
+
+    /// Everything that shapes this group's crops besides the source itself.
+    pub fn crop_options(&self) -> CropOptions {
+        CropOptions {
+            deskew: self.deskew,
+            inset_percent: self.inset_percent,
+            bleed_percent: self.bleed_percent,
+            frames: self.animation.map_or(0, |animation| animation.frames),
+            offset: CropOffset::default(),
+            rect: None,
+            fit: FitMode::Crop,
+        }
+    }
+
+    /// `crop_options` for each crop in turn, with the crop's own offset, manual rect and fit mode.
+    pub fn shape_crop_options(&self) -> Vec<CropOptions> {
+        self.crops
+            .iter()
+            .map(|crop| CropOptions {
+                offset: crop.crop_offset.unwrap_or_default(),
+                rect: crop.crop_rect,
+                fit: crop.fit_mode,
+                ..self.crop_options()
+            })
+            .collect()
+    }
+
+    /// The key previews made right now would have. Covers every setting that changes the crops.
+    pub fn current_preview_key(&self) -> PreviewKey {
+        let offsets: Vec<Option<(u32, u32)>> = self.crops.iter().map(|crop| crop.crop_offset.map(|offset| (offset.x.to_bits(), offset.y.to_bits()))).collect();
+        let framing: Vec<(Option<CropRect>, FitMode)> = self.crops.iter().map(|crop| (crop.crop_rect, crop.fit_mode)).collect();
+        let settings = (self.deskew, self.inset_percent.to_bits(), self.bleed_percent.to_bits(), self.animation, offsets, framing);
+        PreviewKey::new(Path::new(&self.source_path), &settings)
+    }
+
+    /// Whether the previews the frontend has are out of date (or were never sent).
+    pub fn preview_is_stale(&self) -> bool {
+        self.preview_key.as_ref() != Some(&self.current_preview_key())
+    }
+
+    /// Applies a pack template's metadata: its shapes are selected and, if the
+    /// template has a default artist, the painting is credited to them.
+    pub fn apply_template(&mut self, template: &PackTemplate) {
+        for crop in &mut self.crops {
+            crop.selected = template.selected_sizes.contains(&crop.image_size);
+        }
+        if !template.default_artist.trim().is_empty() {
+            self.artist = template.default_artist.clone();
+        }
+    }
+}
+
+/// What importing a file produced.
+#[derive(Debug)]
+pub enum Imported {
+    /// A new group, ready to be added to the library.
+    Group(Box<SourceImageGroup>),
+    /// The file's bytes match an image already in the library, named here. Nothing was imported.
+    Duplicate(String),
+}
+
+/// A folder the project auto-imports new images from.
+#[derive(Debug)]
+pub struct HotFolderWatch {
+    // Distinguishes this watch from earlier ones, so a replaced watch's poller stops.
+    pub id: u64,
+    pub folder: HotFolder,
+    // Metadata given to every image imported from the folder.
+    pub template: Option<PackTemplate>,
 }
 
 /// The single, central state for the entire application.
@@ -23,4 +190,1423 @@ pub struct AppState {
     // This vector holds all the source images and their groups of crops.
     // Each element corresponds to one row in the UI.
     pub image_groups: Vec<SourceImageGroup>,
+
+    // Options controlling how the pack is written out, edited from the frontend.
+    pub export_settings: ExportSettings,
+    // Folder exports go to without asking, set by applying a preset that has one.
+    pub export_path: Option<String>,
+
+    // Options applied to newly imported images.
+    pub import_settings: ImportSettings,
+
+    // Metadata-only snapshots taken on each export, for rolling back.
+    pub history: History,
+    // Every finished export of the project, saved with it.
+    pub export_history: ExportHistory,
+
+    // App-wide preferences such as saved pack templates.
+    pub settings: AppSettings,
+    // Where `settings` is persisted. `None` (tests, headless mode) keeps them in memory only.
+    pub settings_path: Option<PathBuf>,
+
+    // Where imported originals are copied to when they are consumed, and where generated
+    // sources are written. `None` (tests, headless mode) disables consuming originals.
+    pub store_dir: Option<PathBuf>,
+
+    // The folder currently being watched for new images, if any.
+    pub hot_folder: Option<HotFolderWatch>,
+
+    // Outstanding confirmation tokens for destructive commands.
+    pub confirmations: Confirmations,
+
+    // This run's temp folder: working copies, generated paintings and uploads. Removed on exit.
+    pub session_temp: SessionTemp,
+
+    // The project file the session was last saved to or opened from, if any.
+    pub project_path: Option<PathBuf>,
+
+    // Keeps other instances of the app from opening or saving `project_path` while it is open here.
+    pub project_lock: Option<SessionLock>,
+
+    // Files and folders the frontend may name in commands. See `PathScope`.
+    pub path_scope: PathScope,
+}
+
+// The fixed folder an export path template starts in, e.g. "/packs" for "/packs/{pack_id}/{version}".
+fn template_root(template: &str) -> &Path {
+    match template.find('{') {
+        Some(token) if template[..token].ends_with(['/', '\\']) => Path::new(&template[..token]),
+        Some(token) => Path::new(&template[..token]).parent().unwrap_or(Path::new("")),
+        None => Path::new(template),
+    }
+}
+
+/*
+A manual crop rect picked on the working copy at `working_path` (previews and the crop editor
+measure it there), moved into the pixels of the full-quality file the working copy was shrunk from.
+*/
+fn full_quality_rect(rect: CropRect, working_path: &str, full_quality_path: &str) -> CropRect {
+    match (cropper::full_resolution_dimensions(working_path), cropper::full_resolution_dimensions(full_quality_path)) {
+        (Ok((working_width, _)), Ok((full_width, _))) if working_width > 0 && working_width != full_width => {
+            rect.scaled(full_width as f64 / working_width as f64)
+        }
+        _ => rect,
+    }
+}
+
+impl AppState {
+    /// Creates the group for a newly imported file, consuming the original and transcoding it
+    /// into a normalized working copy first when the import settings ask for it. Exact
+    /// duplicates of an image already in the library are skipped before anything else
+    /// happens to the file. The group is not added.
+    pub fn new_group(&self, path: &str) -> Result<Imported, String> {
+        let content_hash = working_copy::content_hash(Path::new(path))?;
+        if let Some(existing) = self.image_groups.iter().find(|group| group.content_hash.as_ref() == Some(&content_hash)) {
+            return Ok(Imported::Duplicate(existing.name.clone()));
+        }
+
+        let source = if self.import_settings.consume_originals {
+            self.consume_original(path)?.to_string_lossy().to_string()
+        } else {
+            path.to_string()
+        };
+        let (working_path, full_quality_path, working_copy_source) = if self.import_settings.normalize {
+            let working_path = working_copy::normalize(Path::new(&source), &self.session_temp.subdir("cache")?, &self.import_settings)?;
+            let full_quality_path = working_copy::is_lossy(&working_path).then(|| source.clone());
+            (working_path.to_string_lossy().to_string(), full_quality_path, Some(source))
+        } else {
+            (source, None, None)
+        };
+
+        let mut group = SourceImageGroup::new(&working_path);
+        group.full_quality_path = full_quality_path;
+        group.working_copy_source = working_copy_source;
+        if working_path != path {
+            // Name the painting after the original, not the stored or cached copy.
+            group.name = Path::new(path).file_stem().unwrap_or_default().to_string_lossy().to_string();
+            group.original_path = Some(path.to_string());
+        }
+        group.content_hash = Some(content_hash);
+        for crop in &mut group.crops {
+            crop.selected = self.import_settings.selected_sizes.contains(&crop.image_size);
+        }
+        Ok(Imported::Group(Box::new(group)))
+    }
+
+    /// Copies an original into the project store, then moves it to the system trash so the
+    /// import folder is cleaned up as the user goes. Originals are never deleted outright: if
+    /// the trash is unavailable the file is left where it was.
+    fn consume_original(&self, path: &str) -> Result<PathBuf, String> {
+        let store_dir = self.store_dir.as_ref().ok_or("Consuming originals needs a project store folder")?;
+        let stored = working_copy::store_copy(Path::new(path), store_dir)?;
+        if let Err(e) = trash::delete(path) {
+            eprintln!("[IMPORT] kept {}, it could not be moved to the trash: {}", path, e);
+        }
+        Ok(stored)
+    }
+
+    /// Points the library entries whose source file no longer exists at their files under
+    /// `root`, matched by content hash or file name. Entries that were normalized get a fresh
+    /// working copy of the file that was found; a file matched only by name is hashed anew so
+    /// duplicate detection keeps working.
+    pub fn relink_sources(&mut self, root: &Path) -> Result<RelinkReport, String> {
+        let missing: Vec<usize> = (0..self.image_groups.len())
+            .filter(|&index| !Path::new(&self.image_groups[index].source_path).is_file())
+            .collect();
+        let lost: Vec<LostSource> = missing
+            .iter()
+            .map(|&index| {
+                let group = &self.image_groups[index];
+                LostSource {
+                    path: group.original_path.clone().unwrap_or_else(|| group.source_path.clone()),
+                    content_hash: group.content_hash.clone(),
+                }
+            })
+            .collect();
+        let found = relink::find_replacements(&lost, root)?;
+
+        let mut report = RelinkReport::default();
+        for (index, replacement) in missing.into_iter().zip(found) {
+            let group = &self.image_groups[index];
+            let Some((path, matched_by)) = replacement else {
+                report.still_missing.push(group.name.clone());
+                continue;
+            };
+            let from = group.original_path.clone().unwrap_or_else(|| group.source_path.clone());
+            let found_path = path.to_string_lossy().to_string();
+            let (source_path, full_quality_path, working_copy_source) = if group.original_path.is_some() && self.import_settings.normalize {
+                let working_path = working_copy::normalize(&path, &self.session_temp.subdir("cache")?, &self.import_settings)?;
+                let full_quality_path = working_copy::is_lossy(&working_path).then(|| found_path.clone());
+                (working_path.to_string_lossy().to_string(), full_quality_path, Some(found_path.clone()))
+            } else {
+                (found_path.clone(), None, None)
+            };
+
+            let group = &mut self.image_groups[index];
+            group.full_quality_path = full_quality_path;
+            group.working_copy_source = working_copy_source;
+            group.original_path = (source_path != found_path).then(|| found_path.clone());
+            group.source_path = source_path;
+            if matched_by == RelinkMatch::Name {
+                group.content_hash = working_copy::content_hash(&path).ok();
+            }
+            report.relinked.push(RelinkedSource { name: group.name.clone(), from, to: found_path, matched_by });
+            let relinked = group.clone();
+            self.allow_group_files(&relinked);
+        }
+        Ok(report)
+    }
+
+    /// Deletes this session's temp files that no painting in the library is using any more.
+    pub fn purge_temp(&self) -> Result<PurgeReport, String> {
+        let in_use: Vec<PathBuf> = self.image_groups.iter().flat_map(|group| group.files()).map(PathBuf::from).collect();
+        self.session_temp.purge(&in_use)
+    }
+
+    /// Where crop previews are cached, by preview key, for this session.
+    pub fn preview_cache_dir(&self) -> Result<PathBuf, String> {
+        self.session_temp.subdir("previews")
+    }
+
+    /// Writes the app settings to disk, if they have a home.
+    pub fn save_settings(&self) -> Result<(), String> {
+        match &self.settings_path {
+            Some(path) => self.settings.save(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Turns low-memory mode on or off for the whole app and remembers the choice.
+    pub fn set_low_memory(&mut self, enabled: bool) -> Result<(), String> {
+        self.settings.low_memory = enabled;
+        low_memory::set_enabled(enabled);
+        self.save_settings()
+    }
+
+    /// Looks up a built-in or user template by name.
+    pub fn find_template(&self, name: &str) -> Result<PackTemplate, String> {
+        templates::all_templates(&self.settings.pack_templates)
+            .into_iter()
+            .find(|template| template.name == name)
+            .ok_or_else(|| format!("No pack template named \"{}\"", name))
+    }
+
+    /// Saves a user template, replacing any earlier one with the same name.
+    pub fn save_template(&mut self, mut template: PackTemplate) -> Result<(), String> {
+        templates::validate_template(&template)?;
+        template.name = template.name.trim().to_string();
+        template.built_in = false;
+        let user_templates = &mut self.settings.pack_templates;
+        match user_templates.iter_mut().find(|t| t.name == template.name) {
+            Some(existing) => *existing = template,
+            None => user_templates.push(template),
+        }
+        self.save_settings()
+    }
+
+    pub fn delete_template(&mut self, name: &str) -> Result<(), String> {
+        let before = self.settings.pack_templates.len();
+        self.settings.pack_templates.retain(|template| template.name != name);
+        if self.settings.pack_templates.len() == before {
+            return Err(format!("No saved pack template named \"{}\"", name));
+        }
+        self.save_settings()
+    }
+
+    /// Saves the current export settings as a preset, replacing any earlier one with the same name.
+    pub fn save_export_preset(&mut self, name: &str, export_path: Option<String>) -> Result<(), String> {
+        let preset = ExportPreset {
+            name: name.trim().to_string(),
+            export_path,
+            settings: self.export_settings.clone(),
+        };
+        export_presets::validate_preset(&preset)?;
+        if let Some(export_path) = &preset.export_path {
+            self.path_scope
+                .check(export_path)
+                .map_err(|_| format!("Export to {} once before saving it in a preset", export_path))?;
+        }
+        let presets = &mut self.settings.export_presets;
+        match presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => presets.push(preset),
+        }
+        self.save_settings()
+    }
+
+    /// Makes a saved preset's settings and folder the current ones.
+    pub fn apply_export_preset(&mut self, name: &str) -> Result<ExportPreset, String> {
+        let preset = self
+            .settings
+            .export_presets
+            .iter()
+            .find(|preset| preset.name == name)
+            .cloned()
+            .ok_or_else(|| format!("No export preset named \"{}\"", name))?;
+        self.export_settings = preset.settings.clone();
+        self.export_path = preset.export_path.clone();
+        // Saved presets are the user's own; their folder can be exported to without asking.
+        if let Some(export_path) = &preset.export_path {
+            self.path_scope.allow_folder(template_root(export_path));
+        }
+        Ok(preset)
+    }
+
+    pub fn delete_export_preset(&mut self, name: &str) -> Result<(), String> {
+        let before = self.settings.export_presets.len();
+        self.settings.export_presets.retain(|preset| preset.name != name);
+        if self.settings.export_presets.len() == before {
+            return Err(format!("No export preset named \"{}\"", name));
+        }
+        self.save_settings()
+    }
+
+    /// Starts a new, empty pack. Pack metadata and images are cleared, any folder watch is
+    /// stopped and settings are reset to the template's if one is given. History and app
+    /// settings are kept.
+    pub fn new_pack(&mut self, template: Option<&PackTemplate>) {
+        self.pack_metadata = PackList::default();
+        self.image_groups.clear();
+        self.hot_folder = None;
+        self.export_settings = ExportSettings::default();
+        self.export_path = None;
+        self.project_path = None;
+        self.project_lock = None;
+        self.export_history = ExportHistory::default();
+        self.import_settings.selected_sizes = ImageSize::iter().copied().collect();
+        self.apply_setup_defaults();
+        if let Some(template) = template {
+            self.import_settings.selected_sizes = template.selected_sizes.clone();
+            self.export_settings.pixels_per_block = template.pixels_per_block;
+            self.export_settings.filename_template = template.filename_template.clone();
+            self.export_settings.default_artist = template.default_artist.clone();
+            self.export_settings.icon_style = template.icon_style;
+        }
+    }
+
+    /// Applies what the first-run wizard set up (default artist, export folder and target mod
+    /// release) to the current pack. Does nothing before setup has been completed.
+    pub fn apply_setup_defaults(&mut self) {
+        let Some(setup) = self.settings.setup.clone() else {
+            return;
+        };
+        self.export_settings.default_artist = setup.default_artist;
+        self.export_settings.target_mod_version = setup.target_mod_version;
+        if let Some(folder) = setup.export_folder {
+            // Chosen by the user, so exports may go there without asking.
+            self.path_scope.allow_folder(Path::new(&folder));
+            self.export_path = Some(folder);
+        }
+    }
+
+    /// Saves the first-run wizard's choices in the app settings and applies them right away.
+    /// The export folder has to be the one the wizard proposed (`proposed_folder`) or one the
+    /// user picked in a dialog.
+    pub fn complete_setup(&mut self, choices: &SetupChoices, proposed_folder: Option<&str>) -> Result<(), String> {
+        let choices = first_run::validate(choices)?;
+        if let Some(folder) = &choices.export_folder {
+            if Some(folder.as_str()) != proposed_folder && !self.path_scope.contains(Path::new(folder)) {
+                return Err(format!("Choose {} in the folder dialog to export there", folder));
+            }
+        }
+        self.settings.setup = Some(choices);
+        self.save_settings()?;
+        self.apply_setup_defaults();
+        Ok(())
+    }
+
+    /// Appends a group to the end of the library, giving it the next order number.
+    pub fn add_group(&mut self, mut group: SourceImageGroup) {
+        group.order = self.image_groups.iter().map(|g| g.order).max().unwrap_or(0) + 1;
+        self.allow_group_files(&group);
+        self.image_groups.push(group);
+    }
+
+    /// Lets the frontend name the files behind a library row: its source and the original it came from.
+    fn allow_group_files(&mut self, group: &SourceImageGroup) {
+        for file in group.files() {
+            self.path_scope.allow_file(Path::new(file));
+        }
+    }
+
+    /// Rearranges the library. `new_order` lists the current group indices in their new
+    /// order and must mention every group exactly once. Order numbers are reassigned from 1.
+    pub fn reorder_groups(&mut self, new_order: &[usize]) -> Result<(), String> {
+        let mut seen = vec![false; self.image_groups.len()];
+        for &index in new_order {
+            match seen.get_mut(index) {
+                Some(seen) if !*seen => *seen = true,
+                _ => return Err(format!("Invalid or repeated image index {} in the new order", index)),
+            }
+        }
+        if new_order.len() != self.image_groups.len() {
+            return Err(format!("The new order lists {} of {} images", new_order.len(), self.image_groups.len()));
+        }
+
+        let mut groups: Vec<Option<SourceImageGroup>> = self.image_groups.drain(..).map(Some).collect();
+        self.image_groups = new_order.iter().filter_map(|&index| groups[index].take()).collect();
+        for (position, group) in self.image_groups.iter_mut().enumerate() {
+            group.order = position as u32 + 1;
+        }
+        Ok(())
+    }
+
+    /// Changes the order custompaintings.json lists the paintings in: moves a library entry
+    /// (which also switches the pack to library order) or sorts the listing at export.
+    pub fn reorder_paintings(&mut self, reorder: PaintingReorder) -> Result<(), String> {
+        match reorder {
+            PaintingReorder::Move { from, to } => {
+                // The move itself is the pack list's; it's done on the library positions here.
+                let mut new_order: PackList<usize> = PackList::new(String::new(), String::new(), String::new(), String::new());
+                for index in 0..self.image_groups.len() {
+                    new_order.add_painting(index);
+                }
+                new_order.move_painting(from, to)?;
+                self.reorder_groups(new_order.paintings())?;
+                self.export_settings.painting_order = PaintingOrder::Library;
+            }
+            PaintingReorder::SortBy { order } => self.export_settings.painting_order = order,
+        }
+        Ok(())
+    }
+
+    /// Builds the list of selected crops to export, copying each group's
+    /// shared metadata onto its crops. Groups are taken in library order.
+    pub fn collect_export_items(&self) -> Vec<ExportItem> {
+        let mut items_to_export: Vec<ExportItem> = Vec::new();
+        let mut groups: Vec<&SourceImageGroup> = self.image_groups.iter().collect();
+        groups.sort_by_key(|group| group.order);
+        for group in groups {
+            for crop in &group.crops {
+                if crop.selected { // Check if the crop is selected
+                    let mut export_crop_data = crop.clone();
+                    // Assign the shared metadata from the group to the individual crop
+                    export_crop_data.name = Some(group.name.clone());
+                    export_crop_data.artist = Some(group.artist.clone());
+                    export_crop_data.description = Some(group.description.clone());
+                    export_crop_data.frame = group.frame.clone();
+                    export_crop_data.weight = group.weight;
+                    export_crop_data.animation = group.animation;
+                    export_crop_data.id = Some(group.name.clone());
+                    export_crop_data.filename = Some(group.name.clone());
+                    export_crop_data.deskew = group.deskew;
+                    export_crop_data.inset_percent = group.inset_percent;
+                    export_crop_data.bleed_percent = group.bleed_percent;
+                    export_crop_data.order = group.order;
+
+                    // Lossy working copies are only for editing; export from the full-quality file
+                    // unless it has gone missing since.
+                    let source_path = match group.full_quality_path.clone().filter(|path| Path::new(path).is_file()) {
+                        Some(full_quality_path) => {
+                            export_crop_data.crop_rect =
+                                export_crop_data.crop_rect.map(|rect| full_quality_rect(rect, &group.source_path, &full_quality_path));
+                            full_quality_path
+                        }
+                        None => group.source_path.clone(),
+                    };
+                    items_to_export.push(ExportItem {
+                        source_path,
+                        data: export_crop_data,
+                    });
+                }
+            }
+        }
+        items_to_export
+    }
+
+    /// The metadata of every library row, in the form snapshots and project files store it.
+    fn group_snapshots(&self) -> Vec<GroupSnapshot> {
+        self.image_groups
+            .iter()
+            .map(|group| GroupSnapshot {
+                source_path: group.source_path.clone(),
+                name: group.name.clone(),
+                artist: group.artist.clone(),
+                selected_sizes: group.crops.iter().filter(|crop| crop.selected).map(|crop| crop.image_size).collect(),
+                deskew: group.deskew,
+                original_path: group.original_path.clone(),
+                full_quality_path: group.full_quality_path.clone(),
+                working_copy_source: group.working_copy_source.clone(),
+                order: group.order,
+                content_hash: group.content_hash.clone(),
+                description: group.description.clone(),
+                inset_percent: group.inset_percent,
+                bleed_percent: group.bleed_percent,
+                frame: group.frame.clone(),
+                weight: group.weight,
+                animation: group.animation,
+                size_adjustments: group.crops.iter().flat_map(|crop| crop.size_adjustments.iter().copied()).collect(),
+                crop_offsets: group.crops.iter().filter_map(|crop| Some((crop.image_size, crop.crop_offset?))).collect(),
+                crop_rects: group.crops.iter().filter_map(|crop| Some((crop.image_size, crop.crop_rect?))).collect(),
+                fit_modes: group.crops.iter().filter(|crop| crop.fit_mode != FitMode::Crop).map(|crop| (crop.image_size, crop.fit_mode)).collect(),
+            })
+            .collect()
+    }
+
+    /// Replaces the library with the rows `saved` describes, in library order.
+    fn restore_groups(&mut self, saved: Vec<GroupSnapshot>) {
+        self.image_groups = saved
+            .into_iter()
+            .map(|saved| {
+                let mut group = SourceImageGroup::new(&saved.source_path);
+                group.name = saved.name;
+                group.artist = saved.artist;
+                group.deskew = saved.deskew;
+                group.original_path = saved.original_path;
+                group.full_quality_path = saved.full_quality_path;
+                group.working_copy_source = saved.working_copy_source;
+                group.order = saved.order;
+                group.content_hash = saved.content_hash;
+                group.description = saved.description;
+                group.inset_percent = saved.inset_percent;
+                group.bleed_percent = saved.bleed_percent;
+                group.frame = saved.frame;
+                group.weight = saved.weight;
+                group.animation = saved.animation;
+                for crop in &mut group.crops {
+                    crop.selected = saved.selected_sizes.contains(&crop.image_size);
+                    crop.size_adjustments = saved
+                        .size_adjustments
+                        .iter()
+                        .filter(|adjustment| crop.get_sizes().contains(&(adjustment.width, adjustment.height)))
+                        .copied()
+                        .collect();
+                    crop.crop_offset = saved.crop_offsets.iter().find(|(size, _)| *size == crop.image_size).map(|(_, offset)| *offset);
+                    crop.crop_rect = saved.crop_rects.iter().find(|(size, _)| *size == crop.image_size).map(|(_, rect)| *rect);
+                    crop.fit_mode = saved.fit_modes.iter().find(|(size, _)| *size == crop.image_size).map_or(FitMode::Crop, |(_, fit)| *fit);
+                }
+                group
+            })
+            .collect();
+        self.image_groups.sort_by_key(|group| group.order);
+        self.rebuild_working_copies();
+        for group in self.image_groups.clone() {
+            self.allow_group_files(&group);
+        }
+    }
+
+    /// Makes fresh working copies for rows whose copy went with an earlier session's temp folder,
+    /// from the file each was made from. Rows that can't be rebuilt are left for `relink_sources`.
+    fn rebuild_working_copies(&mut self) {
+        for index in 0..self.image_groups.len() {
+            let group = &self.image_groups[index];
+            if Path::new(&group.source_path).is_file() {
+                continue;
+            }
+            // Projects saved before `working_copy_source` existed only know the lossy copies' originals.
+            let Some(from) = group
+                .working_copy_source
+                .clone()
+                .or_else(|| group.full_quality_path.clone())
+                .filter(|path| Path::new(path).is_file())
+            else {
+                continue;
+            };
+            let rebuilt = self
+                .session_temp
+                .subdir("cache")
+                .and_then(|cache_dir| working_copy::normalize(Path::new(&from), &cache_dir, &self.import_settings));
+            match rebuilt {
+                Ok(working_path) => {
+                    let group = &mut self.image_groups[index];
+                    group.full_quality_path = working_copy::is_lossy(&working_path).then(|| from.clone());
+                    group.source_path = working_path.to_string_lossy().to_string();
+                    group.working_copy_source = Some(from);
+                }
+                Err(e) => eprintln!("[PROJECT] Couldn't rebuild the working copy of {}: {}", self.image_groups[index].name, e),
+            }
+        }
+    }
+
+    /// Records the current metadata and settings (not pixels) in the history.
+    /// Returns the new snapshot's ID.
+    pub fn take_snapshot(&mut self, reason: &str) -> u64 {
+        let snapshot = self.snapshot(reason);
+        self.history.push(snapshot)
+    }
+
+    /// The current metadata and settings as a snapshot, for recording in the history later
+    /// (e.g. once a background export has succeeded).
+    pub fn snapshot(&self, reason: &str) -> ProjectSnapshot {
+        let pack_meta = &self.pack_metadata;
+        ProjectSnapshot {
+            snapshot_id: 0,
+            created_at: timestamp::unix_seconds(),
+            reason: reason.to_string(),
+            pack_name: pack_meta.pack_name.clone(),
+            version: pack_meta.version.clone(),
+            pack_id: pack_meta.id.clone(),
+            description: pack_meta.description.clone(),
+            export_settings: self.export_settings.clone(),
+            groups: self.group_snapshots(),
+        }
+    }
+
+    /// Restores the metadata, settings and image list of a snapshot. The current state is
+    /// snapshotted first so the rollback itself can be undone.
+    pub fn rollback(&mut self, snapshot_id: u64) -> Result<(), String> {
+        let snapshot = self
+            .history
+            .get(snapshot_id)
+            .cloned()
+            .ok_or_else(|| format!("No snapshot with ID {}", snapshot_id))?;
+        self.take_snapshot("before rollback");
+
+        self.pack_metadata.pack_name = snapshot.pack_name;
+        self.pack_metadata.version = snapshot.version;
+        self.pack_metadata.id = snapshot.pack_id;
+        self.pack_metadata.description = snapshot.description;
+        self.export_settings = snapshot.export_settings;
+        self.restore_groups(snapshot.groups);
+        Ok(())
+    }
+
+    // The whole session as it is saved to a project file.
+    fn to_project(&self) -> Project {
+        let pack_meta = &self.pack_metadata;
+        Project {
+            pack_name: pack_meta.pack_name.clone(),
+            version: pack_meta.version.clone(),
+            pack_id: pack_meta.id.clone(),
+            description: pack_meta.description.clone(),
+            export_settings: self.export_settings.clone(),
+            import_settings: self.import_settings.clone(),
+            groups: self.group_snapshots(),
+            export_history: self.export_history.clone(),
+            history: self.history.clone(),
+        }
+    }
+
+    /// Writes the whole session to a project file at `path` (the project extension is added
+    /// if missing) and remembers it, so later saves go to the same file. Returns where it went.
+    pub fn save_project(&mut self, path: &Path) -> Result<PathBuf, String> {
+        let path = project::project_path(path);
+        let lock = self.lock_project(&path)?;
+        self.take_snapshot("save");
+        let project = self.to_project();
+        project.save(&path)?;
+        // Starts the autosave over from what was just saved.
+        project.save(&project::autosave_path(&path))?;
+        self.path_scope.allow_file(&path);
+        self.project_path = Some(path.clone());
+        self.project_lock = Some(lock);
+        Ok(path)
+    }
+
+    /// Claims the project file at `path` for this session, or fails if another instance of the
+    /// app has it open. The lock already held for `path` is reused; a lock on another project
+    /// file is kept until the caller stores the new one.
+    fn lock_project(&mut self, path: &Path) -> Result<SessionLock, String> {
+        match self.project_lock.take() {
+            Some(lock) if lock.target() == path => Ok(lock),
+            held => {
+                let lock = SessionLock::acquire(path);
+                self.project_lock = held;
+                Ok(lock?)
+            }
+        }
+    }
+
+    /// Saves the session next to its project file (see `project::autosave_path`), so a project
+    /// file that gets damaged can be recovered. Does nothing until the project has been saved.
+    /// Also refreshes the project's lock, so other instances see it is still open here.
+    pub fn autosave(&self) -> Result<(), String> {
+        let Some(path) = &self.project_path else {
+            return Ok(());
+        };
+        if let Some(lock) = &self.project_lock {
+            lock.refresh()?;
+        }
+        self.to_project().save(&project::autosave_path(path))
+    }
+
+    /// Replaces the session with a saved project. The current state is snapshotted first, so
+    /// opening the wrong project can be rolled back. A damaged project file is recovered as far
+    /// as possible (see `project_recovery`) instead of refused; the report says what was lost.
+    /// A project that is open in another instance of the app is refused.
+    pub fn open_project(&mut self, path: &Path) -> Result<Option<RecoveryReport>, String> {
+        let lock = self.lock_project(path)?;
+        let (project, recovery) = match Project::load(path) {
+            Ok(project) => (project, None),
+            Err(error) => {
+                let (project, report) = project_recovery::recover(path)
+                    .map_err(|recovery_error| format!("{}\nRecovery failed: {}", error, recovery_error))?;
+                (project, Some(report))
+            }
+        };
+        let before_open = self.take_snapshot("open project");
+        let before_open = self.history.get(before_open).cloned();
+        self.new_pack(None);
+        self.pack_metadata.pack_name = project.pack_name;
+        self.pack_metadata.version = project.version;
+        self.pack_metadata.id = project.pack_id;
+        self.pack_metadata.description = project.description;
+        self.export_settings = project.export_settings;
+        self.import_settings = project.import_settings;
+        self.restore_groups(project.groups);
+        self.export_history = project.export_history;
+        // The project's own history comes back with it, with what was open before on top so
+        // opening can still be rolled back.
+        self.history = project.history;
+        if let Some(snapshot) = before_open {
+            self.history.push(snapshot);
+        }
+        self.path_scope.allow_file(path);
+        self.project_path = Some(path.to_path_buf());
+        self.project_lock = Some(lock);
+        Ok(recovery)
+    }
+
+    /// Gives every image that has at least one crop selected exactly one shape,
+    /// spreading the pack across shapes according to `distribution`.
+    /// Returns the chosen shape per group; unselected or unreadable images get `None`.
+    pub fn balance_sizes(&mut self, distribution: &[SizeWeight]) -> Vec<Option<ImageSize>> {
+        // Only the image header is read to get the aspect ratio.
+        let candidates: Vec<(usize, f32)> = self
+            .image_groups
+            .iter()
+            .enumerate()
+            .filter(|(_, group)| group.crops.iter().any(|crop| crop.selected))
+            .filter_map(|(index, group)| {
+                let (width, height) = image::image_dimensions(&group.source_path).ok()?;
+                Some((index, width as f32 / height.max(1) as f32))
+            })
+            .collect();
+
+        let aspects: Vec<f32> = candidates.iter().map(|(_, aspect)| *aspect).collect();
+        let sizes = balance::assign_sizes(&aspects, distribution);
+
+        let mut assignments = vec![None; self.image_groups.len()];
+        for ((index, _), size) in candidates.into_iter().zip(sizes) {
+            for crop in &mut self.image_groups[index].crops {
+                crop.selected = crop.image_size == size;
+            }
+            assignments[index] = Some(size);
+        }
+        assignments
+    }
+
+    /// Selects only `size` on each of the given images (every image if `None`) and warns about the
+    /// ones whose orientation fights it, with the shapes that would suit them better. Images whose
+    /// source can't be read are assigned without a check.
+    pub fn assign_size(&mut self, group_indices: Option<&[usize]>, size: ImageSize) -> Result<Vec<OrientationWarning>, String> {
+        let all: Vec<usize> = (0..self.image_groups.len()).collect();
+        let group_indices = group_indices.unwrap_or(&all);
+        if let Some(index) = group_indices.iter().find(|index| **index >= self.image_groups.len()) {
+            return Err(format!("There is no image at position {}", index + 1));
+        }
+
+        let mut warnings = Vec::new();
+        for &group_index in group_indices {
+            let group = &mut self.image_groups[group_index];
+            for crop in &mut group.crops {
+                crop.selected = crop.image_size == size;
+            }
+            // Only the image header is read to get the aspect ratio.
+            let Ok((width, height)) = image::image_dimensions(&group.source_path) else {
+                continue;
+            };
+            if let Some(suggested) = balance::orientation_conflict(width as f32 / height.max(1) as f32, size) {
+                println!("[ASSIGN] {} is {}x{}, which doesn't suit {:?}", group.name, width, height, size);
+                warnings.push(OrientationWarning { group_index, name: group.name.clone(), assigned: size, suggested });
+            }
+        }
+        Ok(warnings)
+    }
+
+    /// Sets the adjustments of one block size of a painting, or clears them with `None`.
+    pub fn set_size_adjustments(&mut self, group_index: usize, width: u32, height: u32, adjustments: Option<Adjustments>) -> Result<(), String> {
+        if let Some(adjustments) = &adjustments {
+            adjustments.validate()?;
+        }
+        let group = self.image_groups.get_mut(group_index).ok_or_else(|| format!("No image at index {}", group_index))?;
+        let crop = group
+            .crops
+            .iter_mut()
+            .find(|crop| crop.get_sizes().contains(&(width, height)))
+            .ok_or_else(|| format!("No painting shape comes in {}x{} blocks", width, height))?;
+        crop.size_adjustments.retain(|adjustment| (adjustment.width, adjustment.height) != (width, height));
+        if let Some(adjustments) = adjustments.filter(|adjustments| !adjustments.is_identity()) {
+            crop.size_adjustments.push(SizeAdjustment { width, height, adjustments });
+        }
+        Ok(())
+    }
+
+    /// Every artist string in the library with its count, with likely spellings of the
+    /// same artist grouped together.
+    pub fn artist_groups(&self) -> Vec<ArtistGroup> {
+        artists::group_artists(self.image_groups.iter().map(|group| group.artist.as_str()))
+    }
+
+    /// Renames every painting credited to one of `variants` (matched exactly) to `canonical`,
+    /// snapshotting first so the merge can be rolled back. Returns how many paintings changed.
+    pub fn merge_artists(&mut self, variants: &[String], canonical: &str) -> Result<usize, String> {
+        let canonical = canonical.trim();
+        if canonical.is_empty() {
+            return Err(String::from("Choose a name to merge the artists into"));
+        }
+        let matches = |artist: &str| variants.iter().any(|variant| variant == artist) && artist != canonical;
+        if !self.image_groups.iter().any(|group| matches(&group.artist)) {
+            return Ok(0);
+        }
+
+        self.take_snapshot("merge artists");
+        let mut changed = 0;
+        for group in self.image_groups.iter_mut().filter(|group| matches(&group.artist)) {
+            group.artist = canonical.to_string();
+            changed += 1;
+        }
+        Ok(changed)
+    }
+
+    // Sanitized IDs of the paintings the pack would export, without size suffixes.
+    fn painting_ids(&self) -> Vec<String> {
+        let mut items = self.collect_export_items();
+        let _ = exporter::apply_defaults(&mut items, &self.export_settings);
+        let mut ids: Vec<String> = items
+            .iter()
+            .map(|item| validator::sanitize_id(item.data.id.as_deref().unwrap_or_default()))
+            .collect();
+        ids.dedup();
+        ids
+    }
+
+    /// A different pack the user exported earlier under the current pack's ID, if any.
+    pub fn pack_id_collision(&self) -> Option<PackCollision> {
+        let id = validator::sanitize_id(&self.pack_metadata.id);
+        self.settings.pack_registry.collision(&id, &self.pack_metadata.pack_name, &self.painting_ids())
+    }
+
+    /// Remembers the folders an export of the current pack into `export_path` writes to, so
+    /// the export can be offered for resuming if the app closes before it finishes.
+    pub fn note_export_started(&mut self, export_path: &str) -> Result<(), String> {
+        for dir in self.export_dirs(export_path, &self.pack_metadata.pack_name) {
+            if !self.settings.unfinished_exports.contains(&dir) {
+                self.settings.unfinished_exports.push(dir);
+            }
+        }
+        self.save_settings()
+    }
+
+    /// Exports that stopped part way and can still be resumed. Folders whose export has since
+    /// finished (or whose progress was deleted) are forgotten.
+    pub fn interrupted_exports(&mut self) -> Vec<InterruptedExport> {
+        let before = self.settings.unfinished_exports.len();
+        self.settings.unfinished_exports.retain(|dir| export_resume::load(dir).is_some());
+        if self.settings.unfinished_exports.len() != before {
+            if let Err(e) = self.save_settings() {
+                eprintln!("{}", e);
+            }
+        }
+        export_resume::find_interrupted(&self.settings.unfinished_exports)
+    }
+
+    /// The folders an export of `pack_name` into `export_path` writes: the pack's, and its Lite
+    /// variant's if the settings ask for one.
+    fn export_dirs(&self, export_path: &str, pack_name: &str) -> Vec<String> {
+        let mut dirs = vec![exporter::pack_dir(export_path, pack_name)];
+        if let Some(lite) = &self.export_settings.lite_variant {
+            dirs.push(exporter::pack_dir(export_path, &format!("{}{}", pack_name, lite.name_suffix)));
+        }
+        dirs
+    }
+
+    /// Refuses an export of `pack_name` into `export_path` unless every folder it would write
+    /// stays inside the path scope, whatever the pack name and suffixes hold.
+    pub fn check_export_scope(&self, export_path: &str, pack_name: &str) -> Result<(), String> {
+        for dir in self.export_dirs(export_path, pack_name) {
+            self.path_scope.check(&dir)?;
+        }
+        Ok(())
+    }
+
+    /// Refuses export settings that name a file outside the path scope, like a palette reference
+    /// image that was neither imported nor picked in a file dialog.
+    pub fn check_settings_scope(&self, settings: &ExportSettings) -> Result<(), String> {
+        if let Some(PaletteHarmonization { source: PaletteSource::Reference(path), .. }) = &settings.palette_harmonization {
+            self.path_scope.check(path)?;
+        }
+        Ok(())
+    }
+
+    /// Only folders the app itself recorded as unfinished exports may be resumed or discarded.
+    pub fn check_unfinished_export(&self, pack_dir: &str) -> Result<(), String> {
+        if self.settings.unfinished_exports.iter().any(|dir| dir == pack_dir) {
+            Ok(())
+        } else {
+            Err(format!("{} is not an interrupted export", pack_dir))
+        }
+    }
+
+    /// Gives up on resuming the export left in `pack_dir`. Its staged files are removed; a pack
+    /// already in `pack_dir` from an earlier export is kept.
+    pub fn discard_interrupted_export(&mut self, pack_dir: &str) -> Result<(), String> {
+        self.check_unfinished_export(pack_dir)?;
+        if let Some(manifest) = export_resume::load(pack_dir) {
+            // Drop the progress of every folder the export wrote to, e.g. its Lite variant too.
+            let same_export: Vec<String> = self
+                .settings
+                .unfinished_exports
+                .iter()
+                .filter(|dir| export_resume::load(dir).is_some_and(|other| other.spec == manifest.spec))
+                .cloned()
+                .collect();
+            for dir in same_export {
+                export_resume::remove(&dir);
+            }
+        }
+        self.settings.unfinished_exports.retain(|dir| dir != pack_dir && export_resume::load(dir).is_some());
+        self.save_settings()
+    }
+
+    /// Adds the current pack to the registry of exported pack IDs and saves it.
+    pub fn record_export(&mut self) -> Result<(), String> {
+        let record = self.pack_record();
+        self.record_pack(record)
+    }
+
+    /// The registry entry for the current pack, for recording later (see `record_pack`).
+    pub fn pack_record(&self) -> PackRecord {
+        PackRecord {
+            id: validator::sanitize_id(&self.pack_metadata.id),
+            pack_name: self.pack_metadata.pack_name.clone(),
+            painting_ids: self.painting_ids(),
+            last_exported: timestamp::unix_seconds(),
+        }
+    }
+
+    /// Adds an exported pack to the registry of exported pack IDs and saves it.
+    pub fn record_pack(&mut self, record: PackRecord) -> Result<(), String> {
+        // Finished exports leave no progress behind and needn't be remembered any more.
+        self.settings.unfinished_exports.retain(|dir| export_resume::load(dir).is_some());
+        self.settings.pack_registry.record(record);
+        self.save_settings()
+    }
+
+    /// Checks the pack against the limits of the targeted Custom Paintings release.
+    pub fn lint_pack(&self) -> Vec<LintIssue> {
+        let mut items = self.collect_export_items();
+        // Anything that can't be defaulted is caught by export itself; lint what can be.
+        let _ = exporter::apply_defaults(&mut items, &self.export_settings);
+        let rules = lint::rules_for(&self.export_settings.target_mod_version);
+        let mut issues = lint::lint(&self.pack_metadata.id, &items, rules, self.export_settings.max_texture_width);
+        issues.extend(lint::painting_cap_issue(&items, self.export_settings.painting_cap));
+        issues
+    }
+
+    /// Estimates the image count, texture memory and size on disk of the current pack before it
+    /// is exported, warning where it goes over the game's comfortable limits.
+    pub fn validate_pack(&self) -> PackBudget {
+        let items = self.collect_export_items();
+        pack_budget::estimate(&items, &self.export_settings, &pack_budget::DEFAULT_LIMITS)
+    }
+
+    /// Validates the current pack and captures everything needed to write it into
+    /// `export_path`, so it can be exported now or queued for later.
+    pub fn export_job(&self, export_path: &str) -> Result<ExportJob, String> {
+        self.export_job_with(export_path, &self.export_settings)
+    }
+
+    /// The export job of the current pack written into the folder of a past export, with
+    /// exactly the settings that export used rather than the current ones.
+    pub fn reexport_job(&self, record_id: u64) -> Result<ExportJob, String> {
+        let record = self
+            .export_history
+            .get(record_id)
+            .ok_or_else(|| format!("No export with ID {} in the history", record_id))?;
+        self.export_job_with(&record.export_path, &record.settings)
+    }
+
+    fn export_job_with(&self, export_path: &str, settings: &ExportSettings) -> Result<ExportJob, String> {
+        // Blank fields are filled from the pack defaults first so they are validated as exported.
+        let mut items_to_export = self.collect_export_items();
+        exporter::apply_defaults(&mut items_to_export, settings)?;
+        let pack_meta = &self.pack_metadata;
+        self.validated_job(pack_meta.pack_name.clone(), pack_meta.id.clone(), items_to_export, export_path, settings)
+    }
+
+    /// One export job per sub-pack of the current pack split by artist or size (see `pack_split`).
+    /// Each sub-pack is validated on its own, so a pack too big to export whole can be split.
+    pub fn split_export_jobs(&self, export_path: &str, mode: SplitMode) -> Result<Vec<ExportJob>, String> {
+        let mut items = self.collect_export_items();
+        exporter::apply_defaults(&mut items, &self.export_settings)?;
+        pack_split::split_pack(&self.pack_metadata.pack_name, &self.pack_metadata.id, items, mode, &self.export_settings)?
+            .into_iter()
+            .map(|sub_pack| {
+                let pack_name = sub_pack.pack_name.clone();
+                self.validated_job(sub_pack.pack_name, sub_pack.id, sub_pack.items, export_path, &self.export_settings)
+                    .map_err(|e| format!("{}: {}", pack_name, e))
+            })
+            .collect()
+    }
+
+    // The job writing `items_to_export` as the pack `pack_name`, if the mod would accept it.
+    fn validated_job(
+        &self,
+        pack_name: String,
+        id: String,
+        items_to_export: Vec<ExportItem>,
+        export_path: &str,
+        settings: &ExportSettings,
+    ) -> Result<ExportJob, String> {
+        // Refuse to write a pack the mod would reject, and say exactly why.
+        let violations = validator::validate_ids(&id, &items_to_export);
+        if !violations.is_empty() {
+            let messages: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+            return Err(format!("The pack has invalid IDs:\n{}", messages.join("\n")));
+        }
+
+        // The painting cap is the curator's own, so it holds; the mod's limits in `lint::RULES`
+        // aren't verified yet, so breaking them only warns.
+        if let Some(issue) = lint::painting_cap_issue(&items_to_export, settings.painting_cap) {
+            return Err(issue.to_string());
+        }
+        let rules = lint::rules_for(&settings.target_mod_version);
+        let warnings: Vec<String> = lint::lint(&id, &items_to_export, rules, settings.max_texture_width)
+            .iter()
+            .map(|issue| issue.to_string())
+            .collect();
+
+        let pack_meta = &self.pack_metadata;
+        Ok(ExportJob {
+            pack_name,
+            version: pack_meta.version.clone(),
+            id,
+            description: pack_meta.description.clone(),
+            items: items_to_export,
+            export_path: export_path.to_string(),
+            settings: settings.clone(),
+            on_stage: None,
+            running: None,
+            on_finished: None,
+            warnings,
+        })
+    }
+
+    /// Indices of the library entries whose previews need regenerating.
+    pub fn stale_previews(&self) -> Vec<usize> {
+        self.image_groups
+            .iter()
+            .enumerate()
+            .filter(|(_, group)| group.preview_is_stale())
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// The export folder with its `{pack_id}`, `{version}` and `{date}` tokens filled in for
+    /// the current pack. Resolved once up front so confirmations and resume tracking use the
+    /// same folder the export writes to.
+    pub fn resolve_export_path(&self, export_path: &str) -> String {
+        exporter::resolve_export_path(export_path, &self.pack_metadata.id, &self.pack_metadata.version)
+    }
+
+    /// Asks for confirmation before an export overwrites files already in its pack folder.
+    /// Shared by the Tauri commands and the headless server.
+    pub fn confirm_overwrite(&mut self, export_path: &str, confirm: Option<&str>) -> Option<Confirmation> {
+        let pack_name = self.pack_metadata.pack_name.clone();
+        self.confirm_overwrite_packs(export_path, &[pack_name], confirm)
+    }
+
+    /// Same as `confirm_overwrite`, for exports that write several packs (e.g. a split pack).
+    pub fn confirm_overwrite_packs(&mut self, export_path: &str, pack_names: &[String], confirm: Option<&str>) -> Option<Confirmation> {
+        let impact: Vec<String> = pack_names
+            .iter()
+            .flat_map(|pack_name| exporter::existing_pack_files(export_path, pack_name, &self.export_settings))
+            .collect();
+        let summary = format!("Overwrite {} existing files in {}?", impact.len(), export_path);
+        self.confirmations.require(confirm, "overwrite_export", export_path, summary, impact)
+    }
+
+    /// Validates the current pack and writes it into `export_path`.
+    /// Shared by the Tauri command and the headless server.
+    pub fn export_pack(&mut self, export_path: &str) -> Result<ExportReport, ExportError> {
+        let export_path = &self.resolve_export_path(export_path);
+        let job = self.export_job(export_path)?;
+        if let Err(e) = self.note_export_started(export_path) {
+            eprintln!("{}", e);
+        }
+        let (record, report) = job.run()?;
+        self.export_history.push(record);
+        Ok(report)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::cropper::PadColour;
+
+    #[test]
+    fn test_new_pack_from_template() {
+        let mut state = AppState::default();
+        state.add_group(SourceImageGroup::new("/art/old.png"));
+        let template = state.find_template("Meme pack").unwrap();
+        state.new_pack(Some(&template));
+
+        assert!(state.image_groups.is_empty());
+        assert_eq!(state.export_settings.pixels_per_block, Some(32));
+        assert_eq!(state.export_settings.default_artist, "Anonymous");
+        let cat = temp_file("cat.png", b"cat");
+        let Imported::Group(group) = state.new_group(&cat).unwrap() else { panic!("not a duplicate") };
+        let selected: Vec<ImageSize> = group.crops.iter().filter(|c| c.selected).map(|c| c.image_size).collect();
+        assert_eq!(selected, [ImageSize::Square, ImageSize::Wide]);
+    }
+
+    // Writes a throwaway file into a per-process temp folder and returns its path.
+    fn temp_file(name: &str, bytes: &[u8]) -> String {
+        let dir = std::env::temp_dir().join(format!("app_state_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, bytes).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_saved_project_reopens_the_session() {
+        let mut state = AppState::default();
+        state.pack_metadata.pack_name = String::from("Harbour");
+        let mut group = SourceImageGroup::new(&temp_file("boats.png", b"boats"));
+        group.artist = String::from("Ana");
+        group.crops[0].selected = false;
+        group.crops[1].crop_offset = Some(CropOffset { x: -0.5, y: 0.0 });
+        group.crops[2].crop_rect = Some(CropRect { x: 0, y: 0, width: 4, height: 3 });
+        group.crops[3].fit_mode = FitMode::Letterbox { pad: PadColour::Dominant };
+        state.add_group(group);
+        let sharpened = Adjustments { sharpen: 1.5, ..Adjustments::default() };
+        state.set_size_adjustments(0, 1, 1, Some(sharpened)).unwrap();
+        assert!(state.set_size_adjustments(0, 5, 5, Some(sharpened)).is_err());
+        let path = Path::new(&temp_file("harbour", b"")).to_path_buf();
+        let saved = state.save_project(&path).unwrap();
+        assert_eq!(saved.extension().unwrap(), "ppmproj");
+
+        // Open in one session at a time.
+        let mut reopened = AppState::default();
+        assert!(reopened.open_project(&saved).unwrap_err().contains("is open elsewhere"));
+        state.new_pack(None);
+        assert!(reopened.open_project(&saved).unwrap().is_none());
+        assert_eq!(reopened.pack_metadata.pack_name, "Harbour");
+        assert_eq!(reopened.image_groups[0].artist, "Ana");
+        assert!(!reopened.image_groups[0].crops[0].selected);
+        assert_eq!(reopened.image_groups[0].shape_crop_options()[1].offset, CropOffset { x: -0.5, y: 0.0 });
+        assert_eq!(reopened.image_groups[0].shape_crop_options()[2].rect, Some(CropRect { x: 0, y: 0, width: 4, height: 3 }));
+        assert_eq!(reopened.image_groups[0].crops[3].fit_mode, FitMode::Letterbox { pad: PadColour::Dominant });
+        assert_eq!(reopened.image_groups[0].crops[0].size_adjustments, vec![SizeAdjustment { width: 1, height: 1, adjustments: sharpened }]);
+        assert_eq!(reopened.project_path.as_deref(), Some(saved.as_path()));
+        let reasons: Vec<String> = reopened.history.list().into_iter().map(|snapshot| snapshot.reason).collect();
+        assert_eq!(reasons, ["open project", "save"]);
+
+        // A damaged project file opens from what is left of it plus the autosave.
+        std::fs::write(&saved, r#"{ "pack_name": "Harb"#).unwrap();
+        let report = reopened.open_project(&saved).unwrap().unwrap();
+        assert!(report.autosave_used);
+        assert_eq!(reopened.pack_metadata.pack_name, "Harbour");
+        assert_eq!(reopened.image_groups[0].artist, "Ana");
+    }
+
+    #[test]
+    fn test_new_group_skips_exact_duplicates() {
+        let mut state = AppState::default();
+        let original = temp_file("sunset.png", b"sunset bytes");
+        let renamed_copy = temp_file("sunset (1).png", b"sunset bytes");
+        let different = temp_file("dawn.png", b"dawn bytes");
+
+        let Imported::Group(group) = state.new_group(&original).unwrap() else { panic!("not a duplicate") };
+        state.add_group(*group);
+        assert!(matches!(state.new_group(&renamed_copy).unwrap(), Imported::Duplicate(name) if name == "sunset"));
+        assert!(matches!(state.new_group(&different).unwrap(), Imported::Group(_)));
+    }
+
+    #[test]
+    fn test_relink_sources_repairs_moved_files() {
+        let mut state = AppState::default();
+        let moved = temp_file("relink_moved.png", b"moved bytes");
+        let Imported::Group(group) = state.new_group(&moved).unwrap() else { panic!("not a duplicate") };
+        state.add_group(*group);
+        state.image_groups[0].source_path = String::from("/gone/relink_moved.png");
+        state.add_group(SourceImageGroup::new("/gone/never_found.png"));
+
+        let root = Path::new(&moved).parent().unwrap();
+        let report = state.relink_sources(root).unwrap();
+        assert_eq!(report.relinked.len(), 1);
+        assert_eq!(report.relinked[0].matched_by, RelinkMatch::Hash);
+        assert_eq!(state.image_groups[0].source_path, moved);
+        assert_eq!(report.still_missing, vec![String::from("never_found")]);
+    }
+
+    #[test]
+    fn test_export_presets_round_trip() {
+        let mut state = AppState::default();
+        state.export_settings.pixels_per_block = Some(32);
+        // Only folders the user picked before can be saved, so the frontend can't point exports anywhere.
+        assert!(state.save_export_preset(" Weekly ", Some(String::from("/packs"))).is_err());
+        state.path_scope.allow_folder(Path::new("/packs"));
+        state.save_export_preset(" Weekly ", Some(String::from("/packs"))).unwrap();
+        assert!(state.save_export_preset("Broken", Some(String::new())).is_err());
+
+        state.new_pack(None);
+        let preset = state.apply_export_preset("Weekly").unwrap();
+        assert_eq!(preset.settings.pixels_per_block, Some(32));
+        assert_eq!(state.export_settings.pixels_per_block, Some(32));
+        assert_eq!(state.export_path.as_deref(), Some("/packs"));
+
+        state.delete_export_preset("Weekly").unwrap();
+        assert!(state.apply_export_preset("Weekly").is_err());
+    }
+
+    #[test]
+    fn test_palette_reference_must_be_in_scope() {
+        let mut state = AppState::default();
+        let mut settings = ExportSettings::default();
+        assert!(state.check_settings_scope(&settings).is_ok());
+        settings.palette_harmonization = Some(PaletteHarmonization { source: PaletteSource::Reference(String::from("/etc/passwd")), strength: 0.5 });
+        assert!(state.check_settings_scope(&settings).is_err());
+        state.path_scope.allow_file(Path::new("/etc/passwd"));
+        assert!(state.check_settings_scope(&settings).is_ok());
+    }
+
+    #[test]
+    fn test_pack_id_collision_after_export() {
+        let mut state = AppState::default();
+        state.pack_metadata.set_pack_name("Forest Pack");
+        state.pack_metadata.id = String::from("nature");
+        state.add_group(SourceImageGroup::new("/art/oak.png"));
+        state.record_export().unwrap();
+        assert!(state.pack_id_collision().is_none());
+
+        state.new_pack(None);
+        state.pack_metadata.set_pack_name("Desert Pack");
+        state.pack_metadata.id = String::from("nature");
+        state.add_group(SourceImageGroup::new("/art/cactus.png"));
+        assert_eq!(state.pack_id_collision().unwrap().previous_pack_name, "Forest Pack");
+    }
+
+    #[test]
+    fn test_mod_limits_only_warn_but_the_painting_cap_refuses() {
+        let mut state = AppState::default();
+        state.pack_metadata.id = String::from("minecraft");
+        // Unreadable, so it breaks a lint rule too.
+        let mut group = SourceImageGroup::new("/art/oak.png");
+        for crop in &mut group.crops[1..] {
+            crop.selected = false;
+        }
+        state.add_group(group);
+        let Ok(job) = state.export_job("/packs") else { panic!("the export was refused") };
+        assert_eq!(job.warnings.len(), 2);
+        assert!(job.warnings[0].starts_with("[reserved_id]"));
+        assert!(job.warnings[1].starts_with("[unreadable_source]"));
+
+        state.export_settings.painting_cap = Some(1);
+        let Err(error) = state.export_job("/packs") else { panic!("the painting cap was ignored") };
+        assert!(error.contains("capped at 1"));
+    }
+
+    #[test]
+    fn test_export_is_recorded_and_can_be_repeated_with_its_settings() {
+        let mut state = AppState::default();
+        state.pack_metadata.set_pack_name("History Pack");
+        state.export_settings.pixels_per_block = Some(32);
+        let export_dir = std::env::temp_dir().join(format!("app_state_export_history_{}", std::process::id()));
+        std::fs::create_dir_all(&export_dir).unwrap();
+        state.export_pack(&export_dir.to_string_lossy()).unwrap();
+
+        let records = state.export_history.list();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].export_path, export_dir.to_string_lossy());
+        assert!(records[0].file_count > 0);
+
+        state.export_settings.pixels_per_block = Some(128);
+        let job = state.reexport_job(records[0].record_id).unwrap();
+        assert_eq!(job.settings.pixels_per_block, Some(32));
+        assert_eq!(job.export_path, records[0].export_path);
+        assert!(state.reexport_job(records[0].record_id + 1).is_err());
+        std::fs::remove_dir_all(&export_dir).ok();
+    }
+
+    #[test]
+    fn test_stale_previews() {
+        let mut state = AppState::default();
+        let path = temp_file("stale.png", b"stale");
+        state.add_group(SourceImageGroup::new(&path));
+        state.add_group(SourceImageGroup::new(&path));
+        // Nothing has been sent yet
+        assert_eq!(state.stale_previews(), [0, 1]);
+
+        for group in &mut state.image_groups {
+            group.preview_key = Some(group.current_preview_key());
+        }
+        assert!(state.stale_previews().is_empty());
+
+        state.image_groups[1].deskew = true;
+        assert_eq!(state.stale_previews(), [1]);
+    }
+
+    #[test]
+    fn test_reorder_groups_drives_export_order() {
+        let mut state = AppState::default();
+        for path in ["/art/a.png", "/art/b.png", "/art/c.png"] {
+            state.add_group(SourceImageGroup::new(path));
+        }
+        assert!(state.reorder_groups(&[0, 0, 1]).is_err());
+        assert!(state.reorder_groups(&[0, 1]).is_err());
+
+        state.reorder_groups(&[2, 0, 1]).unwrap();
+        let names: Vec<&str> = state.image_groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, ["c", "a", "b"]);
+
+        let items = state.collect_export_items();
+        let first_of_each: Vec<(String, u32)> = items
+            .iter()
+            .step_by(ImageSize::iter().count())
+            .map(|item| (item.data.name.clone().unwrap(), item.data.order))
+            .collect();
+        assert_eq!(first_of_each, [("c".to_string(), 1), ("a".to_string(), 2), ("b".to_string(), 3)]);
+
+        state.reorder_paintings(PaintingReorder::SortBy { order: PaintingOrder::Name }).unwrap();
+        state.reorder_paintings(PaintingReorder::Move { from: 2, to: 0 }).unwrap();
+        let names: Vec<&str> = state.image_groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, ["b", "c", "a"]);
+        assert_eq!(state.export_settings.painting_order, PaintingOrder::Library);
+        assert!(state.reorder_paintings(PaintingReorder::Move { from: 3, to: 0 }).is_err());
+    }
+
+    #[test]
+    fn test_assign_size_warns_about_conflicting_orientations() {
+        let dir = std::env::temp_dir().join(format!("assign_size_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut state = AppState::default();
+        for (file, width, height) in [("portrait.png", 30, 40), ("landscape.png", 40, 20)] {
+            let path = dir.join(file);
+            image::RgbaImage::new(width, height).save(&path).unwrap();
+            state.add_group(SourceImageGroup::new(&path.to_string_lossy()));
+        }
+
+        let warnings = state.assign_size(None, ImageSize::Wide).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!((warnings[0].group_index, warnings[0].suggested[0]), (0, ImageSize::TallRectangle));
+        let selected: Vec<ImageSize> = state.image_groups[1].crops.iter().filter(|crop| crop.selected).map(|crop| crop.image_size).collect();
+        assert_eq!(selected, [ImageSize::Wide]);
+        assert!(state.assign_size(Some(&[1]), ImageSize::Wide).unwrap().is_empty());
+        assert!(state.assign_size(Some(&[2]), ImageSize::Wide).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_manual_crop_rect_is_scaled_to_the_full_quality_original() {
+        let dir = std::env::temp_dir().join(format!("full_quality_rect_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let photo = dir.join("photo.png");
+        image::RgbImage::new(400, 200).save(&photo).unwrap();
+        let import_settings = ImportSettings {
+            normalize: true,
+            max_dimension: 100,
+            lossy_cache_quality: Some(80),
+            lossy_cache_min_bytes: 0,
+            ..ImportSettings::default()
+        };
+        let mut state = AppState { import_settings, ..AppState::default() };
+        let Imported::Group(mut group) = state.new_group(&photo.to_string_lossy()).unwrap() else { panic!("not a duplicate") };
+        assert!(working_copy::is_lossy(Path::new(&group.source_path)));
+        for crop in &mut group.crops {
+            crop.selected = crop.image_size == ImageSize::Square;
+        }
+        // Picked on the 100x50 working copy, so it covers the right half of the 400x200 original.
+        group.crops.iter_mut().find(|crop| crop.image_size == ImageSize::Square).unwrap().crop_rect =
+            Some(CropRect { x: 50, y: 0, width: 50, height: 50 });
+        state.add_group(*group);
+
+        let items = state.collect_export_items();
+        assert_eq!(items[0].source_path, photo.to_string_lossy());
+        assert_eq!(items[0].data.crop_rect, Some(CropRect { x: 200, y: 0, width: 200, height: 200 }));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_purge_temp_keeps_full_quality_originals() {
+        let root = std::env::temp_dir().join(format!("purge_full_quality_{}", std::process::id()));
+        let import_settings =
+            ImportSettings { normalize: true, max_dimension: 100, lossy_cache_quality: Some(80), lossy_cache_min_bytes: 0, ..ImportSettings::default() };
+        let mut state = AppState { session_temp: SessionTemp::in_root(&root), import_settings, ..AppState::default() };
+        // An upload only the session folder holds, imported with a lossy working copy.
+        let upload = state.session_temp.subdir("uploads").unwrap().join("photo.png");
+        image::RgbImage::new(400, 200).save(&upload).unwrap();
+        let stale = state.session_temp.subdir("uploads").unwrap().join("removed.png");
+        std::fs::write(&stale, b"removed").unwrap();
+        let Imported::Group(group) = state.new_group(&upload.to_string_lossy()).unwrap() else { panic!("not a duplicate") };
+        assert_eq!(group.full_quality_path.as_deref(), Some(&*upload.to_string_lossy()));
+        let working_copy = PathBuf::from(&group.source_path);
+        state.add_group(*group);
+
+        state.purge_temp().unwrap();
+        assert!(upload.is_file());
+        assert!(working_copy.is_file());
+        assert!(!stale.exists());
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_restored_rows_get_their_working_copies_rebuilt() {
+        let root = std::env::temp_dir().join(format!("rebuild_working_copy_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let photo = root.join("photo.png");
+        image::RgbImage::new(400, 200).save(&photo).unwrap();
+        let import_settings = ImportSettings { normalize: true, max_dimension: 100, ..ImportSettings::default() };
+        let mut state = AppState { session_temp: SessionTemp::in_root(&root.join("first")), import_settings, ..AppState::default() };
+        let Imported::Group(group) = state.new_group(&photo.to_string_lossy()).unwrap() else { panic!("not a duplicate") };
+        assert_eq!(group.full_quality_path, None);
+        state.add_group(*group);
+        let saved = state.group_snapshots();
+
+        // The next run starts with an empty temp folder.
+        state.session_temp.cleanup();
+        state.session_temp = SessionTemp::in_root(&root.join("second"));
+        state.restore_groups(saved);
+        let group = &state.image_groups[0];
+        assert!(group.source_path.starts_with(&*root.join("second").to_string_lossy()));
+        assert_eq!(image::image_dimensions(&group.source_path).unwrap(), (100, 50));
+        assert_eq!(group.working_copy_source.as_deref(), Some(&*photo.to_string_lossy()));
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_merge_artists_renames_every_variant() {
+        let mut state = AppState::default();
+        for artist in ["van gogh", "Monet", "V. van Gogh", "Van Gogh"] {
+            let mut group = SourceImageGroup::new("/library/a.png");
+            group.artist = artist.to_string();
+            state.image_groups.push(group);
+        }
+        assert_eq!(state.artist_groups()[0].variants.len(), 3);
+
+        let variants = vec![String::from("van gogh"), String::from("V. van Gogh"), String::from("Van Gogh")];
+        assert_eq!(state.merge_artists(&variants, " Van Gogh "), Ok(2));
+        let artists: Vec<&str> = state.image_groups.iter().map(|group| group.artist.as_str()).collect();
+        assert_eq!(artists, vec!["Van Gogh", "Monet", "Van Gogh", "Van Gogh"]);
+        assert_eq!(state.history.list().len(), 1);
+        assert!(state.merge_artists(&variants, "  ").is_err());
+    }
+
+    #[test]
+    fn test_rollback_restores_metadata_and_can_be_undone() {
+        let mut state = AppState::default();
+        state.pack_metadata.set_pack_name("Original");
+        let mut group = SourceImageGroup::new("/art/sunset.png");
+        group.crops[0].selected = false;
+        state.add_group(group);
+        let original = state.take_snapshot("export");
+
+        state.pack_metadata.set_pack_name("Renamed");
+        state.image_groups.clear();
+        state.rollback(original).unwrap();
+
+        assert_eq!(state.pack_metadata.pack_name, "Original");
+        assert_eq!(state.image_groups.len(), 1);
+        assert_eq!(state.image_groups[0].name, "sunset");
+        assert!(!state.image_groups[0].crops[0].selected);
+        assert!(state.image_groups[0].crops[1].selected);
+
+        // The state before the rollback was kept as its own snapshot
+        let before = state.history.list()[0].clone();
+        assert_eq!(before.reason, "before rollback");
+        assert_eq!(before.pack_name, "Renamed");
+        assert!(state.rollback(9999).is_err());
+    }
 }
@@ -1,20 +1,110 @@
-use std::sync::Mutex;
-use tauri::{State, Window, Emitter};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+use image::DynamicImage;
+use tauri::{ipc::Channel, State, Window, Emitter, Manager};
 use crate::{
-    app_state::{AppState, SourceImageGroup}, 
-    core::{cropper, exporter},
-    models::{image_data::ImageData, image_size::ImageSize},
-    core::exporter::ExportItem,
+    app_state::{AppState, HotFolderWatch, Imported, SourceImageGroup}, 
+    core::{
+        adjustments::Adjustments,
+        artists::ArtistGroup,
+        asset_check::{self, AssetReport},
+        balance::{self, OrientationWarning, SizeWeight},
+        capabilities::{self, Capabilities},
+        collage::{self, CollageOptions},
+        confirmation::Confirmation,
+        contact_sheet,
+        crop_report::{self, CropLoss},
+        cropper::{self, CropOffset, CropOptions, CropRect, FitMode},
+        export_history::ExportRecord,
+        export_presets::ExportPreset,
+        export_queue::{ExportJob, ExportQueue, FinishedSink, JobSummary, RunningExports, StageSink},
+        export_resume::{self, InterruptedExport},
+        exporter::{self, ExportReport, ExportStage},
+        first_run::{self, SetupChoices, SetupProposal},
+        flipbook::{self, Animation},
+        folder_naming::{self, FolderMetadata},
+        guides::{self, PreviewGuides},
+        history::SnapshotSummary,
+        hot_folder::{self, HotFolder},
+        lint::{self, LintIssue},
+        migration::PROJECT_EXTENSION,
+        mockup,
+        naming,
+        natural_sort,
+        pack_diff::{self, PackDiff},
+        pack_import,
+        pack_inspect::{self, PackInspection},
+        pack_registry::PackCollision,
+        pack_budget::PackBudget,
+        pack_split::SplitMode,
+        preview_cache,
+        preview_key::PreviewKey,
+        preview_strip,
+        palette::Framing,
+        procedural::{self, Pattern},
+        relink::RelinkReport,
+        resample,
+        search::{self, SearchEntry, SearchHit},
+        session_temp::PurgeReport,
+        splitter::{self, VerticalSplitOptions},
+        sprite_sheet::{self, SheetGrid},
+        suggest::{self, Suggestion},
+        templates::{self, PackTemplate},
+        text_painting::{self, TextFont, TextPaintingOptions},
+        timestamp,
+        vanilla,
+        validator::{self, IdViolation},
+    },
+    error::ExportError,
+    models::{export_settings::{ExportSettings, PaintingReorder}, image_size::ImageSize, import_settings::ImportSettings, painting::{self, PaintingFrame}},
 };
 
 // Payload for the event emitted after each image is processed.
 #[derive(Clone, serde::Serialize)]
 struct ImageProcessedPayload {
     previews: Vec<String>,
+    // Whether each crop starts out selected, in the same order as `previews`.
+    selected: Vec<bool>,
     name: String,
     artist: String,
+    description: String,
+    frame: Option<PaintingFrame>,
+    weight: Option<u32>,
+    // Set if the row is a flipbook; the previews show its first frame.
+    animation: Option<Animation>,
+    deskew: bool,
+    inset_percent: f32,
+    bleed_percent: f32,
+    // Each crop's offset, in preview order; `None` for a centred crop.
+    crop_offsets: Vec<Option<CropOffset>>,
+}
+
+// Payload for each file a watched folder picked up. `error` is set if it couldn't be imported.
+#[derive(Clone, serde::Serialize)]
+struct HotFolderImportPayload {
+    path: String,
+    // Name of the library image this file duplicates, if it was skipped as one.
+    duplicate_of: Option<String>,
+    error: Option<String>,
 }
 
+// Reported for each file skipped because an identical image is already in the library.
+#[derive(Clone, serde::Serialize)]
+struct DuplicateSkipped {
+    path: String,
+    existing: String,
+}
+
+// How often a watched folder is checked for new images.
+const HOT_FOLDER_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Source of `HotFolderWatch::id`, unique for the lifetime of the app.
+static NEXT_HOT_FOLDER_ID: AtomicU64 = AtomicU64::new(1);
+
 /*
 Test Command
  */
@@ -23,6 +113,247 @@ pub fn my_custom_command() {
   println!("I was invoked from JavaScript!");
 }
 
+// Where one file of an import batch has got to.
+#[derive(Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ImportStatus {
+    Decoded,
+    Thumbnailed,
+    Skipped,
+    Failed,
+}
+
+// Payload of the per-file `import-progress` event. `index` is 0-based within the batch.
+#[derive(Clone, serde::Serialize)]
+struct ImportProgressPayload {
+    path: String,
+    index: usize,
+    total: usize,
+    status: ImportStatus,
+    // Why a file was skipped or failed.
+    detail: Option<String>,
+}
+
+// Reports the progress of one file in an import batch to the frontend's import log.
+struct ImportProgress<'a> {
+    window: &'a Window,
+    path: &'a str,
+    index: usize,
+    total: usize,
+}
+
+impl ImportProgress<'_> {
+    fn report(&self, status: ImportStatus, detail: Option<String>) {
+        self.window.emit("import-progress", ImportProgressPayload {
+            path: self.path.to_string(),
+            index: self.index,
+            total: self.total,
+            status,
+            detail,
+        }).unwrap();
+    }
+}
+
+/*
+Generates transient crops for a group already in the state and emits them to the frontend as
+Base64 previews together with the group's metadata and selection, so the UI can (re)build its row.
+Reports each step to `progress` when the row is part of an import.
+*/
+fn emit_group_row(group: &SourceImageGroup, cache_dir: &Path, window: &Window, progress: Option<&ImportProgress>) -> Result<(), String> {
+    let key = group.current_preview_key();
+    let previews = match preview_cache::load(cache_dir, &key) {
+        Some(previews) => {
+            if let Some(progress) = progress {
+                progress.report(ImportStatus::Decoded, None);
+            }
+            previews
+        }
+        None => {
+            // 1. Generate cropped images in memory (transiently).
+            let cropped_images = cropper::generate_cropped_images(&group.source_path, &group.shape_crop_options())
+                .map_err(|e| format!("Failed to crop image {}: {}", group.source_path, e))?;
+            println!("[COMMAND] open_and_process_images image cropped commands.rs");
+            if let Some(progress) = progress {
+                progress.report(ImportStatus::Decoded, None);
+            }
+
+            // 2. Create Base64 previews from the transient images, cached under the group's preview key.
+            preview_cache::store(cache_dir, &key, &cropped_images)
+        }
+    };
+    println!("[COMMAND] open_and_process_images image converted base64 commands.rs");
+    if let Some(progress) = progress {
+        progress.report(ImportStatus::Thumbnailed, None);
+    }
+
+    // 3. EMIT an event with the previews and metadata for THIS image group.
+    // The frontend will listen for this and build the UI row by row.
+    window.emit("image-processed", ImageProcessedPayload {
+        previews,
+        selected: group.crops.iter().map(|crop| crop.selected).collect(),
+        name: group.name.clone(),
+        artist: group.artist.clone(),
+        description: group.description.clone(),
+        frame: group.frame.clone(),
+        weight: group.weight,
+        animation: group.animation,
+        deskew: group.deskew,
+        inset_percent: group.inset_percent,
+        bleed_percent: group.bleed_percent,
+        crop_offsets: group.crops.iter().map(|crop| crop.crop_offset).collect(),
+    }).unwrap();
+
+    // `cropped_images` is dropped here, freeing its memory.
+    Ok(())
+}
+
+/*
+Tells the frontend to clear its grid and rebuilds it row by row from the state, e.g. after a
+rollback or reorder.
+*/
+fn emit_project(app_state: &mut AppState, window: &Window) {
+    window.emit("project-cleared", &app_state.pack_metadata).unwrap();
+    let cache_dir = match app_state.preview_cache_dir() {
+        Ok(cache_dir) => cache_dir,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    for group in &mut app_state.image_groups {
+        let key = group.current_preview_key();
+        // A source that has since moved keeps its metadata but can't be previewed.
+        match emit_group_row(group, &cache_dir, window, None) {
+            Ok(()) => group.preview_key = Some(key),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+    window.emit("processing-finished", ()).unwrap();
+}
+
+/*
+Imports one source image: emits its previews and stores a metadata-only group in the state.
+Used by every import path. `prepare` can adjust the new group (selection, metadata) first.
+Returns the name of the library image it duplicates if it was skipped as an exact duplicate.
+Every outcome, including failures, is reported to the import log through `progress`.
+*/
+fn import_source(
+    progress: &ImportProgress,
+    prepare: impl FnOnce(&mut SourceImageGroup),
+    app_state: &mut AppState,
+) -> Result<Option<String>, String> {
+    let result = import_group(progress, prepare, app_state);
+    match &result {
+        Ok(Some(existing)) => progress.report(ImportStatus::Skipped, Some(format!("Already imported as \"{}\"", existing))),
+        Ok(None) => {}
+        Err(e) => progress.report(ImportStatus::Failed, Some(e.clone())),
+    }
+    result
+}
+
+fn import_group(
+    progress: &ImportProgress,
+    prepare: impl FnOnce(&mut SourceImageGroup),
+    app_state: &mut AppState,
+) -> Result<Option<String>, String> {
+    // Create the group with metadata-only ImageData structs for the app state.
+    let mut group = match app_state.new_group(progress.path)? {
+        Imported::Group(group) => *group,
+        Imported::Duplicate(existing) => {
+            println!("[IMPORT] skipped {}, it is already in the library as {}", progress.path, existing);
+            return Ok(Some(existing));
+        }
+    };
+    prepare(&mut group);
+
+    let key = group.current_preview_key();
+    emit_group_row(&group, &app_state.preview_cache_dir()?, progress.window, Some(progress))?;
+    group.preview_key = Some(key);
+    app_state.add_group(group);
+    Ok(None)
+}
+
+// Selects only `only_size` on a new group, if given.
+fn select_only(only_size: Option<ImageSize>) -> impl FnOnce(&mut SourceImageGroup) {
+    move |group| {
+        if let Some(only_size) = only_size {
+            for crop in &mut group.crops {
+                crop.selected = crop.image_size == only_size;
+            }
+        }
+    }
+}
+
+/*
+Folder for images the app creates itself (collages, generated paintings) before importing them.
+Saved projects point at these, so they live in the project store next to consumed originals
+rather than the session's temp folder; without a store (tests, headless mode) they stay temporary.
+*/
+fn generated_sources_dir(app_state: &AppState) -> Result<PathBuf, String> {
+    match &app_state.store_dir {
+        Some(store_dir) => {
+            let dir = store_dir.join("generated");
+            std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+            Ok(dir)
+        }
+        None => app_state.session_temp.subdir("generated"),
+    }
+}
+
+// Refuses a path the frontend sent unless it was imported or chosen in a dialog before.
+fn in_scope(path: Option<String>, state: &State<'_, Mutex<AppState>>) -> Result<Option<String>, String> {
+    path.map(|path| state.lock().unwrap().path_scope.check(&path)).transpose()
+}
+
+// Lets the frontend name a file the user just chose in a dialog.
+fn allow_picked_file(picked: Option<String>, state: &State<'_, Mutex<AppState>>) -> Option<String> {
+    if let Some(path) = &picked {
+        state.lock().unwrap().path_scope.allow_file(Path::new(path));
+    }
+    picked
+}
+
+/*
+Uses the given folder if it is in the path scope, or asks for one if the frontend didn't send a
+path. A picked folder is added to the scope, so it (and anything under it) can be named again.
+*/
+async fn folder_or_pick(path: Option<String>, title: &str, state: &State<'_, Mutex<AppState>>) -> Result<Option<String>, String> {
+    if path.is_some() {
+        return in_scope(path, state);
+    }
+    let picked = rfd::AsyncFileDialog::new()
+        .set_title(title)
+        .pick_folder()
+        .await
+        .map(|folder_handle| folder_handle.path().to_string_lossy().to_string());
+    if let Some(folder) = &picked {
+        state.lock().unwrap().path_scope.allow_folder(Path::new(folder));
+    }
+    Ok(picked)
+}
+
+/*
+Saves an image the app generated and imports it like any other source, so it shows up
+as a new row in the UI.
+*/
+fn import_generated(
+    image: &DynamicImage,
+    file_stem: &str,
+    only_size: Option<ImageSize>,
+    app_state: &mut AppState,
+    window: &Window,
+) -> Result<(), String> {
+    let file_name = format!("{}_{}.png", naming::sanitize_filename(file_stem), timestamp::format_compact(timestamp::unix_seconds()));
+    let path = generated_sources_dir(app_state)?.join(file_name);
+    image.save(&path).map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+
+    let path_str = path.to_string_lossy();
+    let progress = ImportProgress { window, path: &path_str, index: 0, total: 1 };
+    import_source(&progress, select_only(only_size), app_state)?;
+    window.emit("processing-finished", ()).unwrap();
+    Ok(())
+}
+
 /*
 Opens images, generates transient crops, and emits an event for each image
 with its Base64 previews. This avoids accumulating all previews in memory
@@ -33,58 +364,33 @@ pub async fn open_and_process_images(state: State<'_, Mutex<AppState>>, window:
     println!("[COMMAND] open_and_process_images command received commands.rs");
     let files = rfd::AsyncFileDialog::new()
         .set_title("Choose Images...")
-        .add_filter("Image Files", &["png", "jpg", "jpeg"])
+        .add_filter("Image Files", hot_folder::SUPPORTED_EXTENSIONS)
         .pick_files()
         .await;
     println!("[COMMAND] open_and_process_images images received commands.rs");
 
     if let Some(file_handles) = files {
-        let paths: Vec<String> = file_handles.into_iter().map(|h| h.path().to_string_lossy().to_string()).collect();
+        // The dialog returns picks in no particular order; import them in natural file name order.
+        let mut picked: Vec<PathBuf> = file_handles.into_iter().map(|h| h.path().to_path_buf()).collect();
+        natural_sort::sort_paths(&mut picked);
+        let paths: Vec<String> = picked.iter().map(|path| path.to_string_lossy().to_string()).collect();
         
         // The AppState is locked once outside the loop for efficiency.
         let mut app_state = state.lock().unwrap();
 
-        for path_str in paths {
-            // 1. Generate cropped images in memory (transiently).
-            let cropped_images = match cropper::generate_cropped_images(&path_str) {
-                Ok(images) => images,
-                Err(e) => {
-                    eprintln!("Failed to crop image {}: {}", path_str, e);
-                    continue; // Skip this image if it fails to open/crop
-                }
-            };
-            println!("[COMMAND] open_and_process_images image cropped commands.rs");
-            
-            // 2. Create Base64 previews from the transient images.
-            let previews = exporter::generate_base64_previews(&cropped_images);
-            println!("[COMMAND] open_and_process_images image converted base64 commands.rs");
-            
-            // 3. Create metadata-only ImageData structs for the app state.
-            let crop_metadata: Vec<ImageData> = ImageSize::iter()
-                .map(|size_variant| ImageData::new(*size_variant))
-                .collect();
-            
-            let name = std::path::Path::new(&path_str).file_stem().unwrap_or_default().to_string_lossy().to_string();
-            let artist = String::from("Artist Name");
-
-            // 4. EMIT an event with the previews and initial metadata for THIS image group.
-            // The frontend will listen for this and build the UI row by row.
-            window.emit("image-processed", ImageProcessedPayload {
-                previews: previews.clone(),
-                name: name.clone(),
-                artist: artist.clone(),
-            }).unwrap();
-
-            // 5. Create the group with the source path and metadata, then store in state.
-            let group = SourceImageGroup {
-                source_path: path_str.clone(),
-                name,
-                artist,
-                crops: crop_metadata,
-            };
-            app_state.image_groups.push(group);
-
-            // `cropped_images` is dropped here, freeing its memory.
+        let mut duplicates = Vec::new();
+        let total = paths.len();
+        for (index, path_str) in paths.into_iter().enumerate() {
+            // Skip this image if it fails to open/crop
+            let progress = ImportProgress { window: &window, path: &path_str, index, total };
+            match import_source(&progress, |_| {}, &mut app_state) {
+                Ok(Some(existing)) => duplicates.push(DuplicateSkipped { path: path_str, existing }),
+                Ok(None) => {}
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+        if !duplicates.is_empty() {
+            window.emit("duplicates-skipped", duplicates).unwrap();
         }
         
         // After the loop, emit a final event to signal completion.
@@ -100,109 +406,2033 @@ pub async fn open_and_process_images(state: State<'_, Mutex<AppState>>, window:
 }
 
 /*
-Ran whenever a photo in the GUI is deselected. Also allow for updating the photo as selected.
+Imports every image in a folder and its subfolders (asking for the folder if no path is sent).
+With a folder naming rule in the import settings, names and artists are read off the folder
+structure, e.g. `VanGogh/Starry Night.png` becomes "Starry Night" by VanGogh.
 */
 #[tauri::command]
-pub fn set_selected(group_index: usize, crop_index: usize, selected: bool, state: State<'_, Mutex<AppState>>) {
-    println!("[COMMAND] set_selected received commands.rs");
+pub async fn import_folder(path: Option<String>, state: State<'_, Mutex<AppState>>, window: Window) -> Result<(), String> {
+    println!("[COMMAND] import_folder received commands.rs");
+    let Some(root) = folder_or_pick(path, "Choose a Folder to Import...", &state).await? else {
+        window.emit("processing-finished", ()).unwrap();
+        return Ok(());
+    };
+    let root = PathBuf::from(root);
+    let images = folder_naming::find_images(&root)?;
+
     let mut app_state = state.lock().unwrap();
-    // Safely get the group, then the crop, and update its `selected` field
-    if let Some(group) = app_state.image_groups.get_mut(group_index) {
-        if let Some(crop) = group.crops.get_mut(crop_index) {
-            crop.selected = selected;
+    let rule = app_state.import_settings.folder_naming_rule.clone();
+    let mut duplicates = Vec::new();
+    let total = images.len();
+    for (index, image) in images.into_iter().enumerate() {
+        let path_str = image.to_string_lossy().to_string();
+        let metadata = match &rule {
+            Some(rule) => folder_naming::apply_rule(rule, image.strip_prefix(&root).unwrap_or(&image))?,
+            None => FolderMetadata::default(),
+        };
+        let progress = ImportProgress { window: &window, path: &path_str, index, total };
+        let name_from_folders = |group: &mut SourceImageGroup| {
+            if let Some(name) = metadata.name {
+                group.name = name;
+            }
+            if let Some(artist) = metadata.artist {
+                group.artist = artist;
+            }
+        };
+        match import_source(&progress, name_from_folders, &mut app_state) {
+            Ok(Some(existing)) => duplicates.push(DuplicateSkipped { path: path_str, existing }),
+            Ok(None) => {}
+            Err(e) => eprintln!("{}", e),
         }
     }
+    if !duplicates.is_empty() {
+        window.emit("duplicates-skipped", duplicates).unwrap();
+    }
+    window.emit("processing-finished", ()).unwrap();
+    Ok(())
 }
 
+/*
+Imports the vanilla paintings from a Minecraft client jar or resource pack (asking for the file
+if no path is sent) so they can be restyled or upscaled into a custom pack. Each painting keeps
+its vanilla name, credited to Mojang, with only its own shape selected.
+*/
 #[tauri::command]
-pub fn update_row_metadata(
-    group_index: usize, 
-    name: String, 
-    artist: String, 
-    state: State<'_, Mutex<AppState>>
-) {
-    println!("[COMMAND] update_row_metadata received commands.rs");
+pub async fn import_vanilla_paintings(path: Option<String>, state: State<'_, Mutex<AppState>>, window: Window) -> Result<(), String> {
+    println!("[COMMAND] import_vanilla_paintings received commands.rs");
+    let source = match in_scope(path, &state)? {
+        Some(path) => Some(path),
+        None => allow_picked_file(
+            rfd::AsyncFileDialog::new()
+                .set_title("Choose a Minecraft Jar or Resource Pack...")
+                .add_filter("Minecraft Jar or Resource Pack", &["jar", "zip"])
+                .pick_file()
+                .await
+                .map(|file_handle| file_handle.path().to_string_lossy().to_string()),
+            &state,
+        ),
+    };
+    let Some(source) = source else {
+        window.emit("processing-finished", ()).unwrap();
+        return Ok(());
+    };
+
+    let result = import_vanilla_from(&source, &state, &window);
+    window.emit("processing-finished", ()).unwrap();
+    result
+}
+
+/*
+Builds an animated painting from a numbered image sequence (asking for its first frame if no path
+is sent): every frame of the sequence is stacked into one flipbook source and imported as a single
+row that plays each frame for `frame_time` ticks.
+*/
+#[tauri::command]
+pub async fn import_flipbook(
+    first_frame: Option<String>,
+    frame_time: u32,
+    interpolate: bool,
+    state: State<'_, Mutex<AppState>>,
+    window: Window,
+) -> Result<(), String> {
+    println!("[COMMAND] import_flipbook received commands.rs");
+    let first_frame = match in_scope(first_frame, &state)? {
+        Some(path) => Some(path),
+        None => allow_picked_file(
+            rfd::AsyncFileDialog::new()
+                .set_title("Choose the First Frame...")
+                .add_filter("Image Files", hot_folder::SUPPORTED_EXTENSIONS)
+                .pick_file()
+                .await
+                .map(|file_handle| file_handle.path().to_string_lossy().to_string()),
+            &state,
+        ),
+    };
+    let Some(first_frame) = first_frame else {
+        window.emit("processing-finished", ()).unwrap();
+        return Ok(());
+    };
+
+    let result = import_flipbook_from(Path::new(&first_frame), frame_time, interpolate, &state, &window);
+    window.emit("processing-finished", ()).unwrap();
+    result
+}
+
+fn import_flipbook_from(first_frame: &Path, frame_time: u32, interpolate: bool, state: &State<'_, Mutex<AppState>>, window: &Window) -> Result<(), String> {
+    let sequence = flipbook::find_sequence(first_frame)?;
+    let animation = Animation { frames: sequence.len() as u32, frame_time, interpolate };
+    animation.validate()?;
+    let frames = sequence
+        .iter()
+        .map(|path| image::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e)))
+        .collect::<Result<Vec<DynamicImage>, String>>()?;
+    let strip = flipbook::build_flipbook(&frames)?;
+
     let mut app_state = state.lock().unwrap();
+    let stem = first_frame.file_stem().unwrap_or_default().to_string_lossy();
+    let name = stem.trim_end_matches(|c: char| c.is_ascii_digit()).trim_end_matches(['_', '-', ' ']).to_string();
+    let file_name = format!("{}_flipbook_{}.png", naming::sanitize_filename(&name), timestamp::format_compact(timestamp::unix_seconds()));
+    let path = generated_sources_dir(&app_state)?.join(file_name);
+    strip.save(&path).map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
 
-    // Safely get the correct group and update its name and artist fields
-    if let Some(group) = app_state.image_groups.get_mut(group_index) {
-        group.name = name;
-        group.artist = artist;
+    let path_str = path.to_string_lossy();
+    let progress = ImportProgress { window, path: &path_str, index: 0, total: 1 };
+    let prepare = |group: &mut SourceImageGroup| {
+        group.animation = Some(animation);
+        if !name.is_empty() {
+            group.name = name.clone();
+        }
+    };
+    import_source(&progress, prepare, &mut app_state)?;
+    Ok(())
+}
+
+/*
+Slices one sprite sheet (asking for it if no path is sent) into the cells of `grid` and imports
+every cell as its own row, named after the sheet and the cell's place in it, in reading order.
+*/
+#[tauri::command]
+pub async fn import_sprite_sheet(
+    path: Option<String>,
+    grid: SheetGrid,
+    state: State<'_, Mutex<AppState>>,
+    window: Window,
+) -> Result<(), String> {
+    println!("[COMMAND] import_sprite_sheet received commands.rs");
+    let sheet = match in_scope(path, &state)? {
+        Some(path) => Some(path),
+        None => allow_picked_file(
+            rfd::AsyncFileDialog::new()
+                .set_title("Choose a Sprite Sheet...")
+                .add_filter("Image Files", hot_folder::SUPPORTED_EXTENSIONS)
+                .pick_file()
+                .await
+                .map(|file_handle| file_handle.path().to_string_lossy().to_string()),
+            &state,
+        ),
+    };
+    let Some(sheet) = sheet else {
+        window.emit("processing-finished", ()).unwrap();
+        return Ok(());
+    };
+
+    let result = import_sprite_sheet_from(Path::new(&sheet), &grid, &state, &window);
+    window.emit("processing-finished", ()).unwrap();
+    result
+}
+
+fn import_sprite_sheet_from(sheet: &Path, grid: &SheetGrid, state: &State<'_, Mutex<AppState>>, window: &Window) -> Result<(), String> {
+    let image = cropper::open_full_resolution(&sheet.to_string_lossy()).map_err(|e| format!("Failed to open {}: {}", sheet.display(), e))?;
+    let cells = sprite_sheet::slice_sheet(&image, grid)?;
+
+    let mut app_state = state.lock().unwrap();
+    let name = sheet.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    // A folder per slicing, so slicing the same sheet again never overwrites cells already imported.
+    let cells_dir = generated_sources_dir(&app_state)?
+        .join("sheets")
+        .join(format!("{}_{}", naming::sanitize_filename(&name), timestamp::format_compact(timestamp::unix_seconds())));
+    std::fs::create_dir_all(&cells_dir).map_err(|e| format!("Failed to create {}: {}", cells_dir.display(), e))?;
+
+    let total = cells.len();
+    for (index, cell) in cells.into_iter().enumerate() {
+        let path = cells_dir.join(format!("r{}c{}.png", cell.row, cell.column));
+        cell.image.save(&path).map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+        let path_str = path.to_string_lossy();
+        let progress = ImportProgress { window, path: &path_str, index, total };
+        let cell_name = format!("{} r{}c{}", name, cell.row, cell.column);
+        if let Err(e) = import_source(&progress, |group: &mut SourceImageGroup| group.name = cell_name, &mut app_state) {
+            eprintln!("{}", e);
+        }
+    }
+    Ok(())
+}
+
+fn import_vanilla_from(source: &str, state: &State<'_, Mutex<AppState>>, window: &Window) -> Result<(), String> {
+    let vanilla_dir = generated_sources_dir(&state.lock().unwrap())?.join("vanilla");
+    let paintings = vanilla::extract_paintings(Path::new(source), &vanilla_dir)?;
+    let mut app_state = state.lock().unwrap();
+    let total = paintings.len();
+    for (index, painting) in paintings.into_iter().enumerate() {
+        let path_str = painting.path.to_string_lossy();
+        let progress = ImportProgress { window, path: &path_str, index, total };
+        let Some(size) = painting.size else {
+            let (w, h) = painting.blocks;
+            progress.report(ImportStatus::Skipped, Some(format!("{}x{} blocks doesn't match any painting shape", w, h)));
+            continue;
+        };
+        let name = vanilla::display_name(&painting.name);
+        let prepare = |group: &mut SourceImageGroup| {
+            select_only(Some(size))(group);
+            group.name = name;
+            group.artist = String::from("Mojang Studios");
+        };
+        if let Err(e) = import_source(&progress, prepare, &mut app_state) {
+            eprintln!("{}", e);
+        }
     }
+    Ok(())
 }
 
+/*
+Opens a pack exported earlier (asking for its zip archive or custompaintings.json if no path is
+sent) as the current project, so it can be edited and exported again: the pack's name, version,
+ID and description are restored and each painting becomes a row again with its metadata. Like
+`new_pack`, a library that isn't empty is only replaced once confirmed.
+*/
 #[tauri::command]
-pub fn update_pack_metadata(
-    pack_name: String,
-    version: String,
-    id: String,
-    description: String,
-    state: State<'_, Mutex<AppState>>
-) {
-    println!("[COMMAND] update_pack_metadata received commands.rs");
+pub async fn import_pack(
+    path: Option<String>,
+    confirm: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+    window: Window,
+) -> Result<Option<Confirmation>, String> {
+    println!("[COMMAND] import_pack received commands.rs");
+    let known_path = in_scope(path, &state)?.or_else(|| confirm.as_deref().and_then(|token| state.lock().unwrap().confirmations.target_of(token)));
+    let source = match known_path {
+        Some(path) => Some(path),
+        None => allow_picked_file(
+            rfd::AsyncFileDialog::new()
+                .set_title("Choose a Pack (its .zip or custompaintings.json)...")
+                .add_filter("Painting Pack", &["zip", "json"])
+                .pick_file()
+                .await
+                .map(|file_handle| file_handle.path().to_string_lossy().to_string()),
+            &state,
+        ),
+    };
+    let Some(source) = source else {
+        window.emit("processing-finished", ()).unwrap();
+        return Ok(None);
+    };
+
+    let result = import_pack_from(&source, confirm.as_deref(), &state, &window);
+    window.emit("processing-finished", ()).unwrap();
+    result
+}
+
+fn import_pack_from(source: &str, confirm: Option<&str>, state: &State<'_, Mutex<AppState>>, window: &Window) -> Result<Option<Confirmation>, String> {
     let mut app_state = state.lock().unwrap();
+    let impact: Vec<String> = app_state.image_groups.iter().map(|group| group.name.clone()).collect();
+    let summary = format!("Replace all {} images in the library with the pack?", impact.len());
+    if let Some(confirmation) = app_state.confirmations.require(confirm, "open_pack", source, summary, impact) {
+        return Ok(Some(confirmation));
+    }
 
-    let pack_metadata = &mut app_state.pack_metadata;
+    let dest_dir = generated_sources_dir(&app_state)?.join(format!("pack_{}", timestamp::format_compact(timestamp::unix_seconds())));
+    let pack = pack_import::import_pack(Path::new(source), &dest_dir)?;
+    app_state.take_snapshot("open pack");
+    app_state.new_pack(None);
+    app_state.pack_metadata = pack.metadata;
+    emit_project(&mut app_state, window);
 
-    pack_metadata.set_pack_name(&pack_name);
-    pack_metadata.set_version(&version);
-    pack_metadata.set_id(&id);
-    pack_metadata.set_description(&description);
+    let skipped = pack.skipped.len();
+    let total = skipped + pack.paintings.len();
+    for (index, reason) in pack.skipped.into_iter().enumerate() {
+        ImportProgress { window, path: source, index, total }.report(ImportStatus::Skipped, Some(reason));
+    }
+    for (index, imported) in pack.paintings.into_iter().enumerate() {
+        let path_str = imported.texture.to_string_lossy();
+        let progress = ImportProgress { window, path: &path_str, index: skipped + index, total };
+        let painting = imported.painting;
+        let prepare = |group: &mut SourceImageGroup| {
+            select_only(Some(imported.size))(group);
+            group.name = painting.name;
+            group.artist = painting.artist;
+            group.description = painting.description.unwrap_or_default();
+            group.frame = painting.frame;
+            group.weight = painting.weight;
+            group.animation = imported.animation;
+        };
+        if let Err(e) = import_source(&progress, prepare, &mut app_state) {
+            eprintln!("{}", e);
+        }
+    }
+    Ok(None)
+}
 
+/*
+Loads a pack (asking for its .zip or custompaintings.json if no path is sent) only to look at it:
+stats, a thumbnail gallery and the problems it would have in the targeted mod release. Nothing
+is imported and the current project is untouched. Returns `None` if the dialog is cancelled.
+*/
+#[tauri::command]
+pub async fn inspect_pack(path: Option<String>, state: State<'_, Mutex<AppState>>) -> Result<Option<PackInspection>, String> {
+    println!("[COMMAND] inspect_pack received commands.rs");
+    let source = match in_scope(path, &state)? {
+        Some(path) => Some(path),
+        None => allow_picked_file(
+            rfd::AsyncFileDialog::new()
+                .set_title("Choose a Pack to Inspect (its .zip or custompaintings.json)...")
+                .add_filter("Painting Pack", &["zip", "json"])
+                .pick_file()
+                .await
+                .map(|file_handle| file_handle.path().to_string_lossy().to_string()),
+            &state,
+        ),
+    };
+    let Some(source) = source else {
+        return Ok(None);
+    };
+    let target_mod_version = state.lock().unwrap().export_settings.target_mod_version.clone();
+    pack_inspect::inspect_pack(Path::new(&source), lint::rules_for(&target_mod_version)).map(Some)
 }
 
 /*
-Collects all metadata and source paths, then passes them to the exporter,
-which re-opens and re-crops images on-demand.
+Combines 2-4 already imported images into a single new painting and adds it to the library.
 */
 #[tauri::command]
-pub async fn export_pack(state: State<'_, Mutex<AppState>>) -> Result<(), String> {
-    println!("[COMMAND] export_pack received commands.rs");
+pub fn create_collage(
+    group_indices: Vec<usize>,
+    options: CollageOptions,
+    state: State<'_, Mutex<AppState>>,
+    window: Window,
+) -> Result<(), String> {
+    println!("[COMMAND] create_collage received commands.rs");
+    let mut app_state = state.lock().unwrap();
 
-    // 1. Open a native dialog to have the user pick the export directory
-    let folder = rfd::AsyncFileDialog::new()
-        .set_title("Choose Export Directory...")
-        .pick_folder()
-        .await;
+    let mut sources = Vec::new();
+    for index in &group_indices {
+        let group = app_state.image_groups.get(*index).ok_or_else(|| format!("No image at index {}", index))?;
+        let image = image::open(&group.source_path).map_err(|e| format!("Failed to open {}: {}", group.source_path, e))?;
+        sources.push(image);
+    }
 
-    // 2. Only proceed if the user selected a folder (didn't cancel)
-    if let Some(folder_handle) = folder {
-        let export_path = folder_handle.path().to_string_lossy().to_string();
-        let app_state = state.lock().unwrap();
+    let collage_image = collage::compose(&sources, &options)?;
+    import_generated(&collage_image, "collage", None, &mut app_state, &window)
+}
 
-        // 3. Create a list of items to be exported, including source paths for re-cropping.
-        let mut items_to_export: Vec<ExportItem> = Vec::new();
-        for group in &app_state.image_groups {
-            for crop in &group.crops {
-                if crop.selected { // Check if the crop is selected
-                    let mut export_crop_data = crop.clone();
-                    // Assign the shared metadata from the group to the individual crop
-                    export_crop_data.name = Some(group.name.clone());
-                    export_crop_data.artist = Some(group.artist.clone());
-                    export_crop_data.id = Some(group.name.clone());
-                    export_crop_data.filename = Some(group.name.clone());
-                    
-                    items_to_export.push(ExportItem {
-                        source_path: group.source_path.clone(),
-                        data: export_crop_data,
-                    });
-                }
-            }
-        }
+/*
+Slices a very tall imported image into stacked Tall/Square paintings (see `splitter::split_vertical`).
+Each slice is imported as its own row, top to bottom, with only its shape selected.
+*/
+#[tauri::command]
+pub fn split_tall_image(
+    group_index: usize,
+    options: VerticalSplitOptions,
+    state: State<'_, Mutex<AppState>>,
+    window: Window,
+) -> Result<(), String> {
+    println!("[COMMAND] split_tall_image received commands.rs");
+    let mut app_state = state.lock().unwrap();
+    let group = app_state.image_groups.get(group_index).ok_or_else(|| format!("No image at index {}", group_index))?;
+    let name = group.name.clone();
+    let image = cropper::open_source(&group.source_path, group.deskew)
+        .map_err(|e| format!("Failed to open {}: {}", group.source_path, e))?;
 
-        // 4. Call the exporter with the raw metadata and the list of items to process.
-        // This module no longer needs to know about the private `Painting` struct.
-        let pack_meta = &app_state.pack_metadata;
-        exporter::export(
-            pack_meta.pack_name.clone(),
-            pack_meta.version.clone(),
-            pack_meta.id.clone(),
-            pack_meta.description.clone(),
-            items_to_export,
-            &export_path,
-        );
+    for (part, (size, slice)) in splitter::split_vertical(&image, &options)?.into_iter().enumerate() {
+        import_generated(&slice, &format!("{}_part{}", name, part + 1), Some(size), &mut app_state, &window)?;
+    }
+    Ok(())
+}
+
+/*
+Renders text (a quote, server rules, a shop sign) as a new painting for each chosen size.
+Each size gets its own layout and is imported as its own row with only that size selected.
+*/
+#[tauri::command]
+pub fn create_text_painting(
+    options: TextPaintingOptions,
+    sizes: Vec<ImageSize>,
+    state: State<'_, Mutex<AppState>>,
+    window: Window,
+) -> Result<(), String> {
+    println!("[COMMAND] create_text_painting received commands.rs");
+    if let TextFont::File(path) = &options.font {
+        in_scope(Some(path.clone()), &state)?;
+    }
+    let paintings = text_painting::render_text_paintings(&options, &sizes)?;
+
+    let mut app_state = state.lock().unwrap();
+    // Name the files after the first few words so they're recognisable on disk.
+    let stem: String = options.text.split_whitespace().take(4).collect::<Vec<&str>>().join("_");
+    for (size, image) in paintings {
+        import_generated(&image, &stem, Some(size), &mut app_state, &window)?;
+    }
+    Ok(())
+}
+
+/*
+Asks for a TrueType or OpenType font file for text paintings and returns its path, or `None` if
+the dialog is cancelled. Only fonts chosen here can be used by `create_text_painting`.
+*/
+#[tauri::command]
+pub async fn pick_font_file(state: State<'_, Mutex<AppState>>) -> Result<Option<String>, String> {
+    println!("[COMMAND] pick_font_file received commands.rs");
+    Ok(allow_picked_file(
+        rfd::AsyncFileDialog::new()
+            .set_title("Choose a Font...")
+            .add_filter("Font", &["ttf", "otf"])
+            .pick_file()
+            .await
+            .map(|file_handle| file_handle.path().to_string_lossy().to_string()),
+        &state,
+    ))
+}
+
+/*
+Generates a decorative painting (solid colour, gradient or noise) for each chosen size,
+imported the same way as text paintings.
+*/
+#[tauri::command]
+pub fn create_pattern_painting(
+    pattern: Pattern,
+    sizes: Vec<ImageSize>,
+    state: State<'_, Mutex<AppState>>,
+    window: Window,
+) -> Result<(), String> {
+    println!("[COMMAND] create_pattern_painting received commands.rs");
+    if sizes.is_empty() {
+        return Err(String::from("Choose at least one painting size"));
+    }
+
+    let mut app_state = state.lock().unwrap();
+    for size in sizes {
+        let image = procedural::render_pattern(&pattern, size);
+        import_generated(&image, pattern.file_stem(), Some(size), &mut app_state, &window)?;
     }
-    
-    // If the user cancels the dialog, the function simply finishes without error.
     Ok(())
 }
+
+/*
+Ran whenever a photo in the GUI is deselected. Also allow for updating the photo as selected.
+*/
+#[tauri::command]
+pub fn set_selected(group_index: usize, crop_index: usize, selected: bool, state: State<'_, Mutex<AppState>>) {
+    println!("[COMMAND] set_selected received commands.rs");
+    let mut app_state = state.lock().unwrap();
+    // Safely get the group, then the crop, and update its `selected` field
+    if let Some(group) = app_state.image_groups.get_mut(group_index) {
+        if let Some(crop) = group.crops.get_mut(crop_index) {
+            crop.selected = selected;
+        }
+    }
+}
+
+/*
+Turns auto-straightening on or off for one image and returns its regenerated crop previews.
+*/
+#[tauri::command]
+pub async fn set_deskew(group_index: usize, enabled: bool, state: State<'_, Mutex<AppState>>) -> Result<Vec<String>, String> {
+    println!("[COMMAND] set_deskew received commands.rs");
+    recrop_group(&state, group_index, |group| {
+        group.deskew = enabled;
+        Ok(())
+    })
+}
+
+/*
+Sets how much of one image's border is trimmed before cropping (inset) and how far the crop
+is pulled in past it (bleed), both as a percentage of each edge, and returns fresh previews.
+*/
+#[tauri::command]
+pub async fn set_crop_margins(
+    group_index: usize,
+    inset_percent: f32,
+    bleed_percent: f32,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<Vec<String>, String> {
+    println!("[COMMAND] set_crop_margins received commands.rs");
+    recrop_group(&state, group_index, |group| {
+        CropOptions { inset_percent, bleed_percent, ..group.crop_options() }.validate()?;
+        group.inset_percent = inset_percent;
+        group.bleed_percent = bleed_percent;
+        Ok(())
+    })
+}
+
+/*
+Moves one crop's window off the centre of its source (see `CropOffset`), or back to the centre
+when `offset` is `None`, and returns the image's fresh previews.
+*/
+#[tauri::command]
+pub async fn set_crop_offset(
+    group_index: usize,
+    crop_index: usize,
+    offset: Option<CropOffset>,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<Vec<String>, String> {
+    println!("[COMMAND] set_crop_offset received commands.rs");
+    recrop_group(&state, group_index, |group| {
+        if let Some(offset) = &offset {
+            offset.validate()?;
+        }
+        let crop = group.crops.get_mut(crop_index).ok_or_else(|| format!("No crop at index {}", crop_index))?;
+        crop.crop_offset = offset.filter(|offset| !offset.is_centred());
+        Ok(())
+    })
+}
+
+/*
+Crops one shape of an image to a window picked by hand (see `CropRect`), or goes back to the
+automatic crop when `rect` is `None`, and returns the image's fresh previews. The window must lie
+within the source and have the shape's aspect ratio.
+*/
+#[tauri::command]
+pub async fn set_crop_rect(
+    group_index: usize,
+    crop_index: usize,
+    rect: Option<CropRect>,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<Vec<String>, String> {
+    println!("[COMMAND] set_crop_rect received commands.rs");
+    if let Some(rect) = &rect {
+        let (source_path, options, image_size) = crop_source(&state, group_index, crop_index)?;
+        // Decoding happens outside the lock, like cropping.
+        let dims = cropper::source_dimensions(&source_path, &options).map_err(|e| format!("Failed to open {}: {}", source_path, e))?;
+        rect.validate(dims, image_size.get_size()[0])?;
+    }
+    recrop_group(&state, group_index, |group| {
+        let crop = group.crops.get_mut(crop_index).ok_or_else(|| format!("No crop at index {}", crop_index))?;
+        crop.crop_rect = rect;
+        Ok(())
+    })
+}
+
+/*
+Chooses whether one shape of an image is cropped, letterboxed (padded out to the shape with the
+pad colour) or stretched, and returns the image's fresh previews.
+*/
+#[tauri::command]
+pub async fn set_fit_mode(
+    group_index: usize,
+    crop_index: usize,
+    fit_mode: FitMode,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<Vec<String>, String> {
+    println!("[COMMAND] set_fit_mode received commands.rs");
+    recrop_group(&state, group_index, |group| {
+        let crop = group.crops.get_mut(crop_index).ok_or_else(|| format!("No crop at index {}", crop_index))?;
+        crop.fit_mode = fit_mode;
+        Ok(())
+    })
+}
+
+// Where one shape of an image is cropped from its source, in source pixels.
+#[derive(Clone, serde::Serialize)]
+pub struct CropGeometry {
+    source_width: u32,
+    source_height: u32,
+    rect: CropRect,
+    // Whether `rect` was picked by hand rather than worked out from the crop settings.
+    manual: bool,
+}
+
+/*
+The source size and current crop window of one shape of an image, so a drag-to-crop editor can
+map its box to source pixels and start from the crop the export would use.
+*/
+#[tauri::command]
+pub async fn crop_geometry(group_index: usize, crop_index: usize, state: State<'_, Mutex<AppState>>) -> Result<CropGeometry, String> {
+    println!("[COMMAND] crop_geometry received commands.rs");
+    let (source_path, options, image_size) = crop_source(&state, group_index, crop_index)?;
+    let (source_width, source_height) =
+        cropper::source_dimensions(&source_path, &options).map_err(|e| format!("Failed to open {}: {}", source_path, e))?;
+    let (x, y, width, height) = cropper::crop_rect((source_width, source_height), image_size.get_size()[0], &options);
+    let manual = options.rect == Some(CropRect { x, y, width, height });
+    Ok(CropGeometry { source_width, source_height, rect: CropRect { x, y, width, height }, manual })
+}
+
+// The source path, crop options and shape of one crop of a library entry.
+fn crop_source(state: &State<'_, Mutex<AppState>>, group_index: usize, crop_index: usize) -> Result<(String, CropOptions, ImageSize), String> {
+    let app_state = state.lock().unwrap();
+    let group = app_state
+        .image_groups
+        .get(group_index)
+        .ok_or_else(|| format!("No image at index {}", group_index))?;
+    let crop = group.crops.get(crop_index).ok_or_else(|| format!("No crop at index {}", crop_index))?;
+    Ok((group.source_path.clone(), group.shape_crop_options()[crop_index], crop.image_size))
+}
+
+/*
+Applies a crop setting change to one group and returns its regenerated previews. If the settings
+change again before these previews are ready they are dropped with an error, so a slow job can
+never overwrite the previews of a newer one.
+*/
+fn recrop_group(
+    state: &State<'_, Mutex<AppState>>,
+    group_index: usize,
+    update: impl FnOnce(&mut SourceImageGroup) -> Result<(), String>,
+) -> Result<Vec<String>, String> {
+    let (source_path, options, key, cache_dir) = {
+        let mut app_state = state.lock().unwrap();
+        let cache_dir = app_state.preview_cache_dir()?;
+        let group = app_state
+            .image_groups
+            .get_mut(group_index)
+            .ok_or_else(|| format!("No image at index {}", group_index))?;
+        update(group)?;
+        (group.source_path.clone(), group.shape_crop_options(), group.current_preview_key(), cache_dir)
+    };
+
+    // Cropping happens outside the lock; straightening a large scan can take a moment.
+    let previews = cached_previews(&cache_dir, &source_path, &options, &key)?;
+    if !mark_preview_sent(state, group_index, &source_path, key) {
+        return Err(String::from("The crop settings changed while the previews were being made"));
+    }
+    Ok(previews)
+}
+
+// Previews of a source's crops, from the preview cache if they were made under `key` before.
+fn cached_previews(cache_dir: &Path, source_path: &str, options: &[CropOptions], key: &PreviewKey) -> Result<Vec<String>, String> {
+    if let Some(previews) = preview_cache::load(cache_dir, key) {
+        return Ok(previews);
+    }
+    let cropped_images = cropper::generate_cropped_images(source_path, options)
+        .map_err(|e| format!("Failed to crop image {}: {}", source_path, e))?;
+    Ok(preview_cache::store(cache_dir, key, &cropped_images))
+}
+
+/*
+Records the key a group's previews were sent under. Returns false, recording nothing, if the
+group moved, was replaced or had its crop settings changed meanwhile.
+*/
+fn mark_preview_sent(state: &State<'_, Mutex<AppState>>, group_index: usize, source_path: &str, key: PreviewKey) -> bool {
+    let mut app_state = state.lock().unwrap();
+    match app_state
+        .image_groups
+        .get_mut(group_index)
+        .filter(|group| group.source_path == source_path && group.current_preview_key() == key)
+    {
+        Some(group) => {
+            group.preview_key = Some(key);
+            true
+        }
+        None => false,
+    }
+}
+
+// Fresh crop previews for one library entry.
+#[derive(Clone, serde::Serialize)]
+pub struct RefreshedPreviews {
+    group_index: usize,
+    previews: Vec<String>,
+}
+
+/*
+Regenerates the previews of only those library entries whose source file or crop settings
+changed since their previews were last sent, and returns them. Entries that fail (e.g. a
+moved source) are left stale and retried on the next refresh.
+*/
+#[tauri::command]
+pub async fn refresh_previews(state: State<'_, Mutex<AppState>>) -> Result<Vec<RefreshedPreviews>, String> {
+    println!("[COMMAND] refresh_previews received commands.rs");
+    let (stale, cache_dir) = {
+        let app_state = state.lock().unwrap();
+        let stale: Vec<(usize, String, Vec<CropOptions>, PreviewKey)> = app_state
+            .stale_previews()
+            .into_iter()
+            .map(|index| {
+                let group = &app_state.image_groups[index];
+                (index, group.source_path.clone(), group.shape_crop_options(), group.current_preview_key())
+            })
+            .collect();
+        (stale, app_state.preview_cache_dir()?)
+    };
+
+    // Cropping happens outside the lock, one entry at a time. Entries changed meanwhile are
+    // left for the next refresh rather than sent with outdated previews.
+    let mut refreshed = Vec::new();
+    for (group_index, source_path, options, key) in stale {
+        match cached_previews(&cache_dir, &source_path, &options, &key) {
+            Ok(previews) => {
+                if mark_preview_sent(&state, group_index, &source_path, key) {
+                    refreshed.push(RefreshedPreviews { group_index, previews });
+                }
+            }
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+    Ok(refreshed)
+}
+
+/*
+Previews of one image's crops with guides burned in: the lines between Minecraft blocks (for the
+largest size of each shape) and/or rule-of-thirds lines. Guides are drawn after scaling down so
+they stay one crisp line wide.
+*/
+#[tauri::command]
+pub async fn guide_previews(group_index: usize, guides: PreviewGuides, state: State<'_, Mutex<AppState>>) -> Result<Vec<String>, String> {
+    println!("[COMMAND] guide_previews received commands.rs");
+    let (source_path, options) = {
+        let app_state = state.lock().unwrap();
+        let group = app_state
+            .image_groups
+            .get(group_index)
+            .ok_or_else(|| format!("No image at index {}", group_index))?;
+        (group.source_path.clone(), group.shape_crop_options())
+    };
+
+    let cropped_images = cropper::generate_cropped_images(&source_path, &options)
+        .map_err(|e| format!("Failed to crop image {}: {}", source_path, e))?;
+    let guided: Vec<DynamicImage> = cropped_images
+        .iter()
+        .zip(ImageSize::iter())
+        .map(|(image, size)| {
+            let preview = resample::fit_within(image, exporter::PREVIEW_MAX_SIZE, exporter::PREVIEW_MAX_SIZE);
+            let blocks = *size.get_size().last().expect("every shape has a size");
+            guides::draw_guides(&preview, blocks, &guides)
+        })
+        .collect();
+    Ok(exporter::generate_base64_previews(&guided))
+}
+
+// How much of one library image its crops discard, for the aspect-ratio report.
+#[derive(Clone, serde::Serialize)]
+pub struct GroupAspectReport {
+    group_index: usize,
+    name: String,
+    crops: Vec<CropLoss>,
+    // An unselected shape that would keep clearly more of the image.
+    better_fit: Option<ImageSize>,
+    // Base64 thumbnail: green where the selected crops keep the image, red where they discard it.
+    heatmap: String,
+}
+
+/*
+Lists how much of every source image its crops throw away, so paintings that would fit another
+shape better are easy to spot. Images whose source can't be opened are left out.
+*/
+#[tauri::command]
+pub async fn aspect_report(state: State<'_, Mutex<AppState>>) -> Result<Vec<GroupAspectReport>, String> {
+    println!("[COMMAND] aspect_report received commands.rs");
+    let groups: Vec<(String, String, Vec<CropOptions>, Vec<ImageSize>)> = {
+        let app_state = state.lock().unwrap();
+        app_state
+            .image_groups
+            .iter()
+            .map(|group| {
+                let selected = group.crops.iter().filter(|crop| crop.selected).map(|crop| crop.image_size).collect();
+                (group.name.clone(), group.source_path.clone(), group.shape_crop_options(), selected)
+            })
+            .collect()
+    };
+
+    let mut reports = Vec::new();
+    for (group_index, (name, source_path, shapes, selected)) in groups.into_iter().enumerate() {
+        let deskew = shapes.first().is_some_and(|options| options.deskew);
+        let source = match cropper::open_source(&source_path, deskew) {
+            Ok(source) => source,
+            Err(e) => {
+                println!("[REPORT] Skipping {}: {}", source_path, e);
+                continue;
+            }
+        };
+        let shapes: Vec<CropOptions> = shapes.iter().map(|options| options.for_decoded_source(&source_path)).collect();
+        let report = crop_report::aspect_report(&source, &selected, &shapes);
+        let heatmap = exporter::generate_base64_previews(&[report.heatmap]).remove(0);
+        reports.push(GroupAspectReport { group_index, name, crops: report.crops, better_fit: report.better_fit, heatmap });
+    }
+    Ok(reports)
+}
+
+/*
+The pack overview: every selected painting side by side at relative scale with its name, as a
+Data URI. The same image is written into the pack as preview.png if the export settings ask for it.
+*/
+#[tauri::command]
+pub async fn preview_strip(state: State<'_, Mutex<AppState>>) -> Result<String, String> {
+    println!("[COMMAND] preview_strip received commands.rs");
+    let items = state.lock().unwrap().collect_export_items();
+    if items.is_empty() {
+        return Err(String::from("No paintings are selected"));
+    }
+    let png = preview_strip::encode_png(&preview_strip::strip_for_items(&items))?;
+    Ok(exporter::preview_data_uri(&png))
+}
+
+// A framed wall preview of one crop and the colours chosen for its frame.
+#[derive(Clone, serde::Serialize)]
+pub struct WallMockup {
+    preview: String,
+    framing: Framing,
+}
+
+/*
+Renders one crop hanging on a wall, framed in colours derived from its dominant colour or in the
+painting's own frame tint if it has one. Frame textures come from the game and are not previewed.
+*/
+#[tauri::command]
+pub async fn wall_mockup(group_index: usize, crop_index: usize, state: State<'_, Mutex<AppState>>) -> Result<WallMockup, String> {
+    println!("[COMMAND] wall_mockup received commands.rs");
+    let (source_path, image_size, options, frame_tint) = {
+        let app_state = state.lock().unwrap();
+        let group = app_state
+            .image_groups
+            .get(group_index)
+            .ok_or_else(|| format!("No image at index {}", group_index))?;
+        let crop = group.crops.get(crop_index).ok_or_else(|| format!("No crop at index {}", crop_index))?;
+        (group.source_path.clone(), crop.image_size, group.shape_crop_options()[crop_index], group.frame.as_ref().and_then(PaintingFrame::tint))
+    };
+
+    let painting = cropper::crop_single_image(&source_path, &image_size, &options)
+        .map_err(|e| format!("Failed to crop image {}: {}", source_path, e))?;
+    let (image, framing) = mockup::render_wall_mockup(&painting, frame_tint);
+    let preview = exporter::generate_base64_previews(&[image]).remove(0);
+    Ok(WallMockup { preview, framing })
+}
+
+#[tauri::command]
+pub fn update_row_metadata(
+    group_index: usize, 
+    name: String, 
+    artist: String, 
+    description: Option<String>,
+    state: State<'_, Mutex<AppState>>
+) {
+    println!("[COMMAND] update_row_metadata received commands.rs");
+    let mut app_state = state.lock().unwrap();
+
+    // Safely get the correct group and update its name and artist fields
+    if let Some(group) = app_state.image_groups.get_mut(group_index) {
+        group.name = name;
+        group.artist = artist;
+        if let Some(description) = description {
+            group.description = description;
+        }
+    }
+}
+
+/*
+Gives many paintings the same description at once: the listed rows, or every row if none are
+listed. Descriptions are only exported for mod releases that support them.
+*/
+#[tauri::command]
+pub fn set_descriptions(group_indices: Option<Vec<usize>>, description: String, state: State<'_, Mutex<AppState>>) -> Result<(), String> {
+    println!("[COMMAND] set_descriptions received commands.rs");
+    let mut app_state = state.lock().unwrap();
+    let indices = group_indices.unwrap_or_else(|| (0..app_state.image_groups.len()).collect());
+    if let Some(&bad) = indices.iter().find(|&&index| index >= app_state.image_groups.len()) {
+        return Err(format!("No image at index {}", bad));
+    }
+    for index in indices {
+        app_state.image_groups[index].description = description.clone();
+    }
+    Ok(())
+}
+
+/*
+Every artist string in the library with how many paintings use it, likely spellings of the same
+artist grouped together with a suggested name to merge them into.
+*/
+#[tauri::command]
+pub fn list_artists(state: State<'_, Mutex<AppState>>) -> Vec<ArtistGroup> {
+    println!("[COMMAND] list_artists received commands.rs");
+    state.lock().unwrap().artist_groups()
+}
+
+/*
+Credits every painting by one of `variants` to `canonical` instead and refreshes the grid.
+A snapshot is taken first, so the merge can be rolled back. Returns how many paintings changed.
+*/
+#[tauri::command]
+pub fn merge_artists(variants: Vec<String>, canonical: String, state: State<'_, Mutex<AppState>>, window: Window) -> Result<usize, String> {
+    println!("[COMMAND] merge_artists received commands.rs");
+    let mut app_state = state.lock().unwrap();
+    let changed = app_state.merge_artists(&variants, &canonical)?;
+    if changed > 0 {
+        emit_project(&mut app_state, &window);
+    }
+    Ok(changed)
+}
+
+/*
+Fuzzy search over the library's names, artists, source filenames and descriptions. Returns the
+matching rows best first, each with its painting ID.
+*/
+#[tauri::command]
+pub fn search_paintings(query: String, state: State<'_, Mutex<AppState>>) -> Vec<SearchHit> {
+    println!("[COMMAND] search_paintings received commands.rs");
+    let app_state = state.lock().unwrap();
+    let ids: Vec<String> = app_state.image_groups.iter().map(|group| validator::sanitize_id(&group.name)).collect();
+    let filenames: Vec<String> = app_state
+        .image_groups
+        .iter()
+        .map(|group| {
+            let imported = group.original_path.as_deref().unwrap_or(&group.source_path);
+            Path::new(imported).file_name().unwrap_or_default().to_string_lossy().to_string()
+        })
+        .collect();
+    let entries: Vec<SearchEntry> = app_state
+        .image_groups
+        .iter()
+        .enumerate()
+        .map(|(index, group)| SearchEntry {
+            id: &ids[index],
+            name: &group.name,
+            artist: &group.artist,
+            filename: &filenames[index],
+            description: &group.description,
+        })
+        .collect();
+    search::search(&query, &entries)
+}
+
+/*
+Gives one painting its own frame tint and/or texture, or the mod's default frame again when
+`frame` is `None` or empty. Frames are only exported for mod releases that support them.
+*/
+#[tauri::command]
+pub fn set_frame(group_index: usize, frame: Option<PaintingFrame>, state: State<'_, Mutex<AppState>>) -> Result<(), String> {
+    println!("[COMMAND] set_frame received commands.rs");
+    if let Some(frame) = &frame {
+        frame.validate()?;
+    }
+    let mut app_state = state.lock().unwrap();
+    let group = app_state
+        .image_groups
+        .get_mut(group_index)
+        .ok_or_else(|| format!("No image at index {}", group_index))?;
+    group.frame = frame.filter(|frame| !frame.is_empty());
+    Ok(())
+}
+
+/*
+Sets how often one painting turns up when a painting is placed at random, relative to the
+default of 1 (`None` or 1). Weights are only exported for mod releases that support them.
+*/
+#[tauri::command]
+pub fn set_weight(group_index: usize, weight: Option<u32>, state: State<'_, Mutex<AppState>>) -> Result<(), String> {
+    println!("[COMMAND] set_weight received commands.rs");
+    if let Some(weight) = weight {
+        painting::validate_weight(weight)?;
+    }
+    let mut app_state = state.lock().unwrap();
+    let group = app_state
+        .image_groups
+        .get_mut(group_index)
+        .ok_or_else(|| format!("No image at index {}", group_index))?;
+    group.weight = weight.filter(|weight| *weight != 1);
+    Ok(())
+}
+
+/*
+Sets brightness, contrast and sharpening for one block size of a painting (e.g. only its 1x1
+texture), applied after that texture is scaled on export. `None` clears them again.
+*/
+#[tauri::command]
+pub fn set_size_adjustments(
+    group_index: usize,
+    width: u32,
+    height: u32,
+    adjustments: Option<Adjustments>,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<(), String> {
+    println!("[COMMAND] set_size_adjustments received commands.rs");
+    state.lock().unwrap().set_size_adjustments(group_index, width, height, adjustments)
+}
+
+#[tauri::command]
+pub fn update_pack_metadata(
+    pack_name: String,
+    version: String,
+    id: String,
+    description: String,
+    state: State<'_, Mutex<AppState>>
+) {
+    println!("[COMMAND] update_pack_metadata received commands.rs");
+    let mut app_state = state.lock().unwrap();
+
+    let pack_metadata = &mut app_state.pack_metadata;
+
+    pack_metadata.set_pack_name(&pack_name);
+    pack_metadata.set_version(&version);
+    pack_metadata.set_id(&id);
+    pack_metadata.set_description(&description);
+
+}
+
+/*
+Picks one shape per selected image so the pack gets a mix of sizes instead of every image in
+every shape. Uses the default mix unless the frontend sends its own weights. Returns the chosen
+shape per row so the grid can update its selection.
+*/
+#[tauri::command]
+pub fn balance_sizes(distribution: Option<Vec<SizeWeight>>, state: State<'_, Mutex<AppState>>) -> Vec<Option<ImageSize>> {
+    println!("[COMMAND] balance_sizes received commands.rs");
+    let distribution = distribution.unwrap_or_else(balance::default_distribution);
+    let mut app_state = state.lock().unwrap();
+    app_state.balance_sizes(&distribution)
+}
+
+/*
+Gives the chosen images (every image if `group_indices` is `None`) the one shape `size`, keeping
+no other shape selected. Returns a warning for each image that leans the other way, such as a
+portrait forced into Wide, with the shapes that would crop it less.
+*/
+#[tauri::command]
+pub fn assign_size(
+    group_indices: Option<Vec<usize>>,
+    size: ImageSize,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<Vec<OrientationWarning>, String> {
+    println!("[COMMAND] assign_size received commands.rs");
+    state.lock().unwrap().assign_size(group_indices.as_deref(), size)
+}
+
+/*
+Proposes a name and description for one imported image using the best available suggester.
+Nothing is changed in the state; the frontend decides whether to apply the suggestion.
+*/
+#[tauri::command]
+pub async fn suggest_metadata(group_index: usize, state: State<'_, Mutex<AppState>>) -> Result<Suggestion, String> {
+    println!("[COMMAND] suggest_metadata received commands.rs");
+    // Only hold the lock long enough to read the paths; suggesting may involve a network call.
+    let (source_path, imported_path) = {
+        let app_state = state.lock().unwrap();
+        let group = app_state
+            .image_groups
+            .get(group_index)
+            .ok_or_else(|| format!("No image at index {}", group_index))?;
+        // Titles come from the name the user imported, not a working copy's hashed cache name.
+        (group.source_path.clone(), group.original_path.clone().unwrap_or_else(|| group.source_path.clone()))
+    };
+
+    // Decoding and the API suggester's request both block, so keep them off the async runtime.
+    tauri::async_runtime::spawn_blocking(move || {
+        let image = image::open(&source_path).map_err(|e| format!("Failed to open {}: {}", source_path, e))?;
+        suggest::default_suggester().suggest(&imported_path, &image)
+    })
+    .await
+    .map_err(|e| format!("Suggesting metadata failed: {}", e))?
+}
+
+/*
+Checks the pack ID and every painting ID against the mod's rules without exporting,
+so the frontend can flag problems while the user is still editing.
+*/
+#[tauri::command]
+pub fn validate_pack_ids(state: State<'_, Mutex<AppState>>) -> Vec<IdViolation> {
+    println!("[COMMAND] validate_pack_ids received commands.rs");
+    let app_state = state.lock().unwrap();
+    let mut items = app_state.collect_export_items();
+    // Anything that can't be defaulted is caught by export itself; validate what can be.
+    let _ = exporter::apply_defaults(&mut items, &app_state.export_settings);
+    validator::validate_ids(&app_state.pack_metadata.id, &items)
+}
+
+/*
+Warns when the pack's ID was already used for a different pack the user exported, since two
+installed packs with the same ID conflict. Returns that earlier pack, if any.
+*/
+#[tauri::command]
+pub fn check_pack_id(state: State<'_, Mutex<AppState>>) -> Option<PackCollision> {
+    println!("[COMMAND] check_pack_id received commands.rs");
+    state.lock().unwrap().pack_id_collision()
+}
+
+/*
+Checks the pack against the known limits of the targeted mod release (painting count,
+texture size, reserved IDs) and explains each problem. Export runs the same checks.
+*/
+#[tauri::command]
+pub fn lint_pack(state: State<'_, Mutex<AppState>>) -> Vec<LintIssue> {
+    println!("[COMMAND] lint_pack received commands.rs");
+    let app_state = state.lock().unwrap();
+    app_state.lint_pack()
+}
+
+/*
+What the first-run wizard starts from: whether setup was done before, the Minecraft folder of the
+default launcher if it is installed, and the saved choices or defaults built on that folder.
+*/
+#[tauri::command]
+pub fn get_setup_proposal(state: State<'_, Mutex<AppState>>) -> SetupProposal {
+    println!("[COMMAND] get_setup_proposal received commands.rs");
+    let minecraft_dir = first_run::detect_minecraft_dir();
+    first_run::propose(state.lock().unwrap().settings.setup.as_ref(), minecraft_dir.as_deref())
+}
+
+// Asks for an export folder in a dialog, so the wizard (or anything else) can offer it afterwards.
+#[tauri::command]
+pub async fn pick_export_folder(state: State<'_, Mutex<AppState>>) -> Result<Option<String>, String> {
+    println!("[COMMAND] pick_export_folder received commands.rs");
+    folder_or_pick(None, "Choose Export Directory...", &state).await
+}
+
+/*
+Finishes the first-run wizard: saves the default artist, export folder and target mod release in
+the app settings and applies them to the current pack. The export folder must be the proposed one
+or one picked with `pick_export_folder`.
+*/
+#[tauri::command]
+pub fn complete_setup(choices: SetupChoices, state: State<'_, Mutex<AppState>>) -> Result<(), String> {
+    println!("[COMMAND] complete_setup received commands.rs");
+    let mut app_state = state.lock().unwrap();
+    let minecraft_dir = first_run::detect_minecraft_dir();
+    let proposal = first_run::propose(app_state.settings.setup.as_ref(), minecraft_dir.as_deref());
+    app_state.complete_setup(&choices, proposal.choices.export_folder.as_deref())
+}
+
+/*
+Describes the optional features compiled into this build and the limits of the pack format the
+export settings target, so the frontend can hide what isn't supported.
+*/
+#[tauri::command]
+pub fn get_capabilities(state: State<'_, Mutex<AppState>>) -> Capabilities {
+    println!("[COMMAND] get_capabilities received commands.rs");
+    capabilities::capabilities(&state.lock().unwrap().export_settings.target_mod_version)
+}
+
+/*
+Replaces the export settings with the values chosen in the frontend.
+*/
+#[tauri::command]
+pub fn update_export_settings(settings: ExportSettings, state: State<'_, Mutex<AppState>>) -> Result<(), String> {
+    println!("[COMMAND] update_export_settings received commands.rs");
+    settings.validate()?;
+
+    let mut app_state = state.lock().unwrap();
+    app_state.check_settings_scope(&settings)?;
+    app_state.export_settings = settings;
+    Ok(())
+}
+
+/*
+Asks for a reference image to take the shared palette from and returns its path, or `None` if the
+dialog is cancelled. Export settings may only name a reference image chosen here or imported.
+*/
+#[tauri::command]
+pub async fn pick_palette_reference(state: State<'_, Mutex<AppState>>) -> Result<Option<String>, String> {
+    println!("[COMMAND] pick_palette_reference received commands.rs");
+    Ok(allow_picked_file(
+        rfd::AsyncFileDialog::new()
+            .set_title("Choose a Palette Reference Image...")
+            .add_filter("Image Files", hot_folder::SUPPORTED_EXTENSIONS)
+            .pick_file()
+            .await
+            .map(|file_handle| file_handle.path().to_string_lossy().to_string()),
+        &state,
+    ))
+}
+
+#[tauri::command]
+pub fn update_import_settings(settings: ImportSettings, state: State<'_, Mutex<AppState>>) -> Result<(), String> {
+    println!("[COMMAND] update_import_settings received commands.rs");
+    settings.validate()?;
+    if let Some(rule) = &settings.folder_naming_rule {
+        folder_naming::validate_rule(rule)?;
+    }
+    state.lock().unwrap().import_settings = settings;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_import_settings(state: State<'_, Mutex<AppState>>) -> ImportSettings {
+    println!("[COMMAND] get_import_settings received commands.rs");
+    state.lock().unwrap().import_settings.clone()
+}
+
+#[tauri::command]
+pub fn get_export_settings(state: State<'_, Mutex<AppState>>) -> ExportSettings {
+    println!("[COMMAND] get_export_settings received commands.rs");
+    let app_state = state.lock().unwrap();
+    app_state.export_settings.clone()
+}
+
+/*
+Asks for an export folder (unless an applied preset has one), then validates and writes the pack into it. Each stage of the export
+is streamed over `stages` if the frontend passes a channel. If the export would overwrite
+existing files, nothing is written and a confirmation is returned instead; calling again with
+its token exports to the same folder.
+*/
+#[tauri::command]
+pub async fn export_pack(
+    stages: Option<Channel<ExportStage>>,
+    confirm: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+    running: State<'_, RunningExports>,
+    window: Window,
+) -> Result<Option<Confirmation>, ExportError> {
+    println!("[COMMAND] export_pack received commands.rs");
+
+    // 1. Use the confirmed or preset folder, or open a native dialog to have the user pick one
+    let known_path = {
+        let app_state = state.lock().unwrap();
+        confirm.as_deref().and_then(|token| app_state.confirmations.target_of(token)).or(app_state.export_path.clone())
+    };
+    let folder = folder_or_pick(known_path, "Choose Export Directory...", &state).await?;
+
+    // 2. Only proceed if the user selected a folder (didn't cancel)
+    if let Some(export_path) = folder {
+        let mut app_state = state.lock().unwrap();
+        let export_path = app_state.resolve_export_path(&export_path);
+        app_state.check_export_scope(&export_path, &app_state.pack_metadata.pack_name)?;
+        if let Some(confirmation) = app_state.confirm_overwrite(&export_path, confirm.as_deref()) {
+            return Ok(Some(confirmation));
+        }
+
+        // 3. Validate and write the pack. Source images are re-opened and re-cropped on demand.
+        let mut job = app_state.export_job(&export_path)?;
+        job.on_stage = Some(stage_sink(&job.pack_name, stages, window));
+        job.running = Some(running.inner().clone());
+        if let Err(e) = app_state.note_export_started(&export_path) {
+            eprintln!("{}", e);
+        }
+        let (record, _) = job.run()?;
+        app_state.export_history.push(record);
+        app_state.take_snapshot("export");
+        if let Err(e) = app_state.record_export() {
+            eprintln!("{}", e);
+        }
+    }
+    
+    // If the user cancels the dialog, the function simply finishes without error.
+    Ok(None)
+}
+
+// Payload of the "export-finished", "export-cancelled" and "export-failed" events sent when a started export ends.
+#[derive(Clone, serde::Serialize)]
+struct ExportOutcomePayload {
+    export_path: String,
+    // What was written; only sent with "export-finished".
+    report: Option<ExportReport>,
+    // Why the export failed; only sent with "export-failed".
+    error: Option<ExportError>,
+}
+
+// What `start_export` did.
+#[derive(Clone, serde::Serialize)]
+#[serde(tag = "status", content = "value", rename_all = "snake_case")]
+pub enum StartedExport {
+    Started(String),
+    NeedsConfirmation(Confirmation),
+    Cancelled,
+}
+
+/*
+Same as `export_pack`, but the pack is written on a background thread and the command returns as
+soon as the export has started, so the UI stays responsive on large packs. Stages stream over
+`stages` as usual; the end of the export is announced with an "export-finished",
+"export-cancelled" or "export-failed" event.
+*/
+#[tauri::command]
+pub async fn start_export(
+    stages: Option<Channel<ExportStage>>,
+    confirm: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+    running: State<'_, RunningExports>,
+    window: Window,
+) -> Result<StartedExport, String> {
+    println!("[COMMAND] start_export received commands.rs");
+    let known_path = {
+        let app_state = state.lock().unwrap();
+        confirm.as_deref().and_then(|token| app_state.confirmations.target_of(token)).or(app_state.export_path.clone())
+    };
+    let Some(export_path) = folder_or_pick(known_path, "Choose Export Directory...", &state).await? else {
+        return Ok(StartedExport::Cancelled);
+    };
+
+    let (mut job, snapshot, pack_record) = {
+        let mut app_state = state.lock().unwrap();
+        let export_path = app_state.resolve_export_path(&export_path);
+        app_state.check_export_scope(&export_path, &app_state.pack_metadata.pack_name)?;
+        if let Some(confirmation) = app_state.confirm_overwrite(&export_path, confirm.as_deref()) {
+            return Ok(StartedExport::NeedsConfirmation(confirmation));
+        }
+        // Like a queued job, the pack is captured as it is when the export starts, but it only goes
+        // into the history and the pack registry once the export has succeeded.
+        let job = app_state.export_job(&export_path)?;
+        if let Err(e) = app_state.note_export_started(&export_path) {
+            eprintln!("{}", e);
+        }
+        (job, app_state.snapshot("export"), app_state.pack_record())
+    };
+    job.on_stage = Some(stage_sink(&job.pack_name, stages, window.clone()));
+    job.running = Some(running.inner().clone());
+    job.on_finished = Some(history_sink(window.clone()));
+    let export_path = job.export_path.clone();
+
+    // The state is not locked while the pack is written, so the user can keep working.
+    let started_path = export_path.clone();
+    thread::spawn(move || {
+        // A bug that panics mid-export is reported like any other failure instead of going unnoticed.
+        let result = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| job.run())) {
+            Ok(result) => result,
+            Err(_) => Err(ExportError::Internal { message: String::from("The export stopped unexpectedly") }),
+        };
+        match result {
+            Ok((_, report)) => {
+                {
+                    let state = window.state::<Mutex<AppState>>();
+                    let mut app_state = state.lock().unwrap();
+                    app_state.history.push(snapshot);
+                    if let Err(e) = app_state.record_pack(pack_record) {
+                        eprintln!("{}", e);
+                    }
+                }
+                window.emit("export-finished", ExportOutcomePayload { export_path, report: Some(report), error: None }).unwrap();
+            }
+            Err(ExportError::Cancelled) => {
+                window.emit("export-cancelled", ExportOutcomePayload { export_path, report: None, error: None }).unwrap();
+            }
+            Err(e) => {
+                eprintln!("[EXPORT] {}", e);
+                window.emit("export-failed", ExportOutcomePayload { export_path, report: None, error: Some(e) }).unwrap();
+            }
+        }
+    });
+    Ok(StartedExport::Started(started_path))
+}
+
+/*
+Estimates what the pack will cost before it is exported: distinct images, decoded texture memory
+and size on disk, with a warning for each budget it goes over (about 20 images or 200 MB). Unlike
+`lint_pack` these don't stop an export; the frontend asks before going ahead.
+*/
+#[tauri::command]
+pub fn validate_pack(state: State<'_, Mutex<AppState>>) -> PackBudget {
+    println!("[COMMAND] validate_pack received commands.rs");
+    state.lock().unwrap().validate_pack()
+}
+
+/*
+Stops the exports being written before their next source image and removes their partial packs.
+Queued jobs that haven't started yet still run.
+*/
+#[tauri::command]
+pub fn cancel_export(running: State<'_, RunningExports>) {
+    println!("[COMMAND] cancel_export received commands.rs");
+    let cancelled = running.cancel_all();
+    println!("[EXPORT] Cancelling {} running export(s)", cancelled);
+}
+
+/*
+Exports that were cut short (the app closed or crashed mid-export) and can be picked up again.
+*/
+#[tauri::command]
+pub fn list_interrupted_exports(state: State<'_, Mutex<AppState>>) -> Vec<InterruptedExport> {
+    println!("[COMMAND] list_interrupted_exports received commands.rs");
+    state.lock().unwrap().interrupted_exports()
+}
+
+/*
+Finishes an interrupted export with exactly the pack and settings it started with, whatever the
+current project is. Paintings that were already written are kept, not encoded again.
+*/
+#[tauri::command]
+pub async fn resume_export(
+    pack_dir: String,
+    stages: Option<Channel<ExportStage>>,
+    state: State<'_, Mutex<AppState>>,
+    running: State<'_, RunningExports>,
+    window: Window,
+) -> Result<(), ExportError> {
+    println!("[COMMAND] resume_export received commands.rs");
+    state.lock().unwrap().check_unfinished_export(&pack_dir)?;
+    let manifest = export_resume::load(&pack_dir).ok_or_else(|| format!("{} has no export to resume", pack_dir))?;
+    let mut job = ExportJob::resume(manifest.spec);
+    job.on_stage = Some(stage_sink(&job.pack_name, stages, window));
+    job.running = Some(running.inner().clone());
+    // Not added to the export history: the resumed pack needn't be the open project.
+    job.run()?;
+
+    // Prunes the finished export from the list.
+    state.lock().unwrap().interrupted_exports();
+    Ok(())
+}
+
+// Forgets an interrupted export instead of resuming it and removes what it had staged.
+#[tauri::command]
+pub fn discard_interrupted_export(pack_dir: String, state: State<'_, Mutex<AppState>>) -> Result<(), String> {
+    println!("[COMMAND] discard_interrupted_export received commands.rs");
+    state.lock().unwrap().discard_interrupted_export(&pack_dir)
+}
+
+/*
+Adds the record of an export finished in the background to the project's export history and
+announces it with an "export-history-updated" event. The state is locked only once the pack is
+written, so the job must not be run while the caller holds it.
+*/
+fn history_sink(window: Window) -> FinishedSink {
+    Arc::new(move |record| {
+        let state = window.state::<Mutex<AppState>>();
+        state.lock().unwrap().export_history.push(record.clone());
+        let _ = window.emit("export-history-updated", ());
+    })
+}
+
+// Payload of the "export-progress" event sent after each painting of an export.
+#[derive(Clone, serde::Serialize)]
+struct ExportProgressPayload {
+    pack_name: String,
+    done: usize,
+    total: usize,
+    file: String,
+    percent: u32,
+}
+
+/*
+Forwards an export job's stages to the frontend over its own channel, if it passed one, and
+announces each finished painting as an "export-progress" event for the progress bar.
+*/
+fn stage_sink(pack_name: &str, channel: Option<Channel<ExportStage>>, window: Window) -> StageSink {
+    let pack_name = pack_name.to_string();
+    Arc::new(move |stage| {
+        // The window may have been closed mid-export; the export carries on regardless.
+        if let ExportStage::Progress { done, total, file, percent } = &stage {
+            let _ = window.emit("export-progress", ExportProgressPayload {
+                pack_name: pack_name.clone(),
+                done: *done,
+                total: *total,
+                file: file.clone(),
+                percent: *percent,
+            });
+        }
+        if let Some(channel) = &channel {
+            let _ = channel.send(stage);
+        }
+    })
+}
+
+/*
+Validates the current pack and adds it to the export queue instead of writing it right away.
+The job captures the pack as it is now, so the user can change settings or switch packs and
+queue more. Progress arrives through "export-job-updated" events, and the individual stages
+through `stages` if the frontend passes a channel. Like `export_pack`, a job that would overwrite
+existing files is only queued once confirmed.
+*/
+#[tauri::command]
+pub async fn queue_export(
+    export_path: Option<String>,
+    stages: Option<Channel<ExportStage>>,
+    confirm: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+    queue: State<'_, ExportQueue>,
+    running: State<'_, RunningExports>,
+    window: Window,
+) -> Result<QueuedExport, String> {
+    println!("[COMMAND] queue_export received commands.rs");
+
+    let export_path = export_path.or_else(|| {
+        confirm.as_deref().and_then(|token| state.lock().unwrap().confirmations.target_of(token))
+    });
+    // Cancelling the dialog queues nothing.
+    let Some(export_path) = folder_or_pick(export_path, "Choose Export Directory...", &state).await? else {
+        return Ok(QueuedExport::Cancelled);
+    };
+
+    let mut app_state = state.lock().unwrap();
+    let export_path = app_state.resolve_export_path(&export_path);
+    app_state.check_export_scope(&export_path, &app_state.pack_metadata.pack_name)?;
+    if let Some(confirmation) = app_state.confirm_overwrite(&export_path, confirm.as_deref()) {
+        return Ok(QueuedExport::NeedsConfirmation(confirmation));
+    }
+    let mut job = app_state.export_job(&export_path)?;
+    job.on_stage = Some(stage_sink(&job.pack_name, stages, window.clone()));
+    job.running = Some(running.inner().clone());
+    job.on_finished = Some(history_sink(window));
+    app_state.take_snapshot("export");
+    if let Err(e) = app_state.record_export() {
+        eprintln!("{}", e);
+    }
+    if let Err(e) = app_state.note_export_started(&export_path) {
+        eprintln!("{}", e);
+    }
+    queue.enqueue(job).map(QueuedExport::Queued)
+}
+
+/*
+Splits the current pack by artist (one pack per artist, or artists grouped within a painting
+budget) or into numbered parts within an image and megabyte budget, and queues one export per
+sub-pack, like `queue_export`. Every sub-pack is validated before any is queued.
+*/
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn queue_split_export(
+    mode: SplitMode,
+    export_path: Option<String>,
+    stages: Option<Channel<ExportStage>>,
+    confirm: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+    queue: State<'_, ExportQueue>,
+    running: State<'_, RunningExports>,
+    window: Window,
+) -> Result<QueuedSplitExport, String> {
+    println!("[COMMAND] queue_split_export received commands.rs");
+
+    let export_path = export_path.or_else(|| {
+        confirm.as_deref().and_then(|token| state.lock().unwrap().confirmations.target_of(token))
+    });
+    let Some(export_path) = folder_or_pick(export_path, "Choose Export Directory...", &state).await? else {
+        return Ok(QueuedSplitExport::Cancelled);
+    };
+
+    let mut app_state = state.lock().unwrap();
+    let export_path = app_state.resolve_export_path(&export_path);
+    let jobs = app_state.split_export_jobs(&export_path, mode)?;
+    let pack_names: Vec<String> = jobs.iter().map(|job| job.pack_name.clone()).collect();
+    for pack_name in &pack_names {
+        app_state.check_export_scope(&export_path, pack_name)?;
+    }
+    if let Some(confirmation) = app_state.confirm_overwrite_packs(&export_path, &pack_names, confirm.as_deref()) {
+        return Ok(QueuedSplitExport::NeedsConfirmation(confirmation));
+    }
+    app_state.take_snapshot("split export");
+
+    let mut job_ids = Vec::new();
+    for mut job in jobs {
+        job.on_stage = Some(stage_sink(&job.pack_name, stages.clone(), window.clone()));
+        job.running = Some(running.inner().clone());
+        job.on_finished = Some(history_sink(window.clone()));
+        job_ids.push(queue.enqueue(job)?);
+    }
+    Ok(QueuedSplitExport::Queued(job_ids))
+}
+
+// What `queue_split_export` did: the queued job of each sub-pack, in order.
+#[derive(Clone, serde::Serialize)]
+#[serde(tag = "status", content = "value", rename_all = "snake_case")]
+pub enum QueuedSplitExport {
+    Queued(Vec<u64>),
+    NeedsConfirmation(Confirmation),
+    Cancelled,
+}
+
+// What `queue_export` did.
+#[derive(Clone, serde::Serialize)]
+#[serde(tag = "status", content = "value", rename_all = "snake_case")]
+pub enum QueuedExport {
+    Queued(u64),
+    NeedsConfirmation(Confirmation),
+    Cancelled,
+}
+
+#[tauri::command]
+pub fn list_export_jobs(queue: State<'_, ExportQueue>) -> Vec<JobSummary> {
+    println!("[COMMAND] list_export_jobs received commands.rs");
+    queue.jobs()
+}
+
+#[tauri::command]
+pub fn clear_finished_export_jobs(queue: State<'_, ExportQueue>) {
+    println!("[COMMAND] clear_finished_export_jobs received commands.rs");
+    queue.clear_finished();
+}
+
+/*
+Compares two exported versions of a pack and reports added, removed and changed paintings.
+If `patch_dir` is given, a patch pack with only the changed files is written there as well.
+Returns `None` if the user cancels a folder dialog.
+*/
+#[tauri::command]
+pub async fn compare_packs(
+    old_pack_dir: Option<String>,
+    new_pack_dir: Option<String>,
+    patch_dir: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<Option<PackDiff>, String> {
+    println!("[COMMAND] compare_packs received commands.rs");
+    let Some(old_pack_dir) = folder_or_pick(old_pack_dir, "Choose the Old Pack...", &state).await? else {
+        return Ok(None);
+    };
+    let Some(new_pack_dir) = folder_or_pick(new_pack_dir, "Choose the New Pack...", &state).await? else {
+        return Ok(None);
+    };
+
+    let diff = pack_diff::diff_packs(Path::new(&old_pack_dir), Path::new(&new_pack_dir))?;
+    if let Some(patch_dir) = in_scope(patch_dir, &state)? {
+        pack_diff::write_patch_pack(Path::new(&new_pack_dir), &diff, Path::new(&patch_dir))?;
+    }
+    Ok(Some(diff))
+}
+
+/*
+Records the current metadata and settings as a named point the user can roll back to.
+*/
+#[tauri::command]
+pub fn take_snapshot(state: State<'_, Mutex<AppState>>) -> u64 {
+    println!("[COMMAND] take_snapshot received commands.rs");
+    let mut app_state = state.lock().unwrap();
+    app_state.take_snapshot("manual")
+}
+
+#[tauri::command]
+pub fn list_snapshots(state: State<'_, Mutex<AppState>>) -> Vec<SnapshotSummary> {
+    println!("[COMMAND] list_snapshots received commands.rs");
+    let app_state = state.lock().unwrap();
+    app_state.history.list()
+}
+
+/*
+Rolls the project back to a snapshot, then rebuilds the grid: "project-cleared" tells the
+frontend to empty it and each restored image is re-emitted as a row.
+*/
+#[tauri::command]
+pub fn rollback_snapshot(snapshot_id: u64, state: State<'_, Mutex<AppState>>, window: Window) -> Result<(), String> {
+    println!("[COMMAND] rollback_snapshot received commands.rs");
+    let mut app_state = state.lock().unwrap();
+    app_state.rollback(snapshot_id)?;
+    emit_project(&mut app_state, &window);
+    Ok(())
+}
+
+// Finished exports of the project, newest first.
+#[tauri::command]
+pub fn list_export_history(state: State<'_, Mutex<AppState>>) -> Vec<ExportRecord> {
+    println!("[COMMAND] list_export_history received commands.rs");
+    state.lock().unwrap().export_history.list()
+}
+
+/*
+Writes the current pack again into the folder of a past export, with exactly the settings that
+export used; the current export settings are left alone. Stages and overwrite confirmation work as
+in `export_pack`.
+*/
+#[tauri::command]
+pub async fn reexport_from_history(
+    record_id: u64,
+    stages: Option<Channel<ExportStage>>,
+    confirm: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+    running: State<'_, RunningExports>,
+    window: Window,
+) -> Result<Option<Confirmation>, ExportError> {
+    println!("[COMMAND] reexport_from_history received commands.rs");
+    let mut app_state = state.lock().unwrap();
+    let mut job = app_state.reexport_job(record_id)?;
+    app_state.check_export_scope(&job.export_path, &job.pack_name)?;
+    if let Some(confirmation) = app_state.confirm_overwrite(&job.export_path, confirm.as_deref()) {
+        return Ok(Some(confirmation));
+    }
+
+    job.on_stage = Some(stage_sink(&job.pack_name, stages, window));
+    job.running = Some(running.inner().clone());
+    if let Err(e) = app_state.note_export_started(&job.export_path) {
+        eprintln!("{}", e);
+    }
+    let (record, _) = job.run()?;
+    app_state.export_history.push(record);
+    app_state.take_snapshot("export");
+    if let Err(e) = app_state.record_export() {
+        eprintln!("{}", e);
+    }
+    Ok(None)
+}
+
+// Built-in templates first, then the user's own.
+#[tauri::command]
+pub fn list_pack_templates(state: State<'_, Mutex<AppState>>) -> Vec<PackTemplate> {
+    println!("[COMMAND] list_pack_templates received commands.rs");
+    templates::all_templates(&state.lock().unwrap().settings.pack_templates)
+}
+
+#[tauri::command]
+pub fn save_pack_template(template: PackTemplate, state: State<'_, Mutex<AppState>>) -> Result<(), String> {
+    println!("[COMMAND] save_pack_template received commands.rs");
+    state.lock().unwrap().save_template(template)
+}
+
+#[tauri::command]
+pub fn delete_pack_template(name: String, state: State<'_, Mutex<AppState>>) -> Result<(), String> {
+    println!("[COMMAND] delete_pack_template received commands.rs");
+    state.lock().unwrap().delete_template(&name)
+}
+
+#[tauri::command]
+pub fn list_export_presets(state: State<'_, Mutex<AppState>>) -> Vec<ExportPreset> {
+    println!("[COMMAND] list_export_presets received commands.rs");
+    state.lock().unwrap().settings.export_presets.clone()
+}
+
+/*
+Saves the current export settings under `name`, with the folder to export into if given.
+*/
+#[tauri::command]
+pub fn save_export_preset(name: String, export_path: Option<String>, state: State<'_, Mutex<AppState>>) -> Result<(), String> {
+    println!("[COMMAND] save_export_preset received commands.rs");
+    state.lock().unwrap().save_export_preset(&name, export_path)
+}
+
+/*
+Loads a saved preset's settings; the next export goes to its folder without asking, if it has one.
+*/
+#[tauri::command]
+pub fn apply_export_preset(name: String, state: State<'_, Mutex<AppState>>) -> Result<ExportPreset, String> {
+    println!("[COMMAND] apply_export_preset received commands.rs");
+    state.lock().unwrap().apply_export_preset(&name)
+}
+
+#[tauri::command]
+pub fn delete_export_preset(name: String, state: State<'_, Mutex<AppState>>) -> Result<(), String> {
+    println!("[COMMAND] delete_export_preset received commands.rs");
+    state.lock().unwrap().delete_export_preset(&name)
+}
+
+/*
+Clears the current pack and starts a new one, optionally from a named template. If the library
+isn't empty, nothing happens until the call is repeated with the token of the returned
+confirmation, which lists the images that would be removed.
+*/
+#[tauri::command]
+pub fn new_pack(
+    template_name: Option<String>,
+    confirm: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+    window: Window,
+) -> Result<Option<Confirmation>, String> {
+    println!("[COMMAND] new_pack received commands.rs");
+    let mut app_state = state.lock().unwrap();
+    let template = template_name.map(|name| app_state.find_template(&name)).transpose()?;
+
+    let impact: Vec<String> = app_state.image_groups.iter().map(|group| group.name.clone()).collect();
+    let summary = format!("Remove all {} images from the library?", impact.len());
+    if let Some(confirmation) = app_state.confirmations.require(confirm.as_deref(), "clear_library", "library", summary, impact) {
+        return Ok(Some(confirmation));
+    }
+
+    app_state.take_snapshot("new pack");
+    app_state.new_pack(template.as_ref());
+    emit_project(&mut app_state, &window);
+    Ok(None)
+}
+
+/*
+Saves the whole session (pack metadata, settings, and every image with its metadata and sizes) to
+a project file. Without a path it saves over the file the project was last saved to or opened
+from, asking for one the first time or when `save_as` is set. Returns where the project was
+saved, or `None` if the dialog is cancelled.
+*/
+#[tauri::command]
+pub async fn save_project(path: Option<String>, save_as: bool, state: State<'_, Mutex<AppState>>) -> Result<Option<String>, String> {
+    println!("[COMMAND] save_project received commands.rs");
+    let known_path = in_scope(path, &state)?
+        .map(PathBuf::from)
+        .or_else(|| if save_as { None } else { state.lock().unwrap().project_path.clone() });
+    let target = match known_path {
+        Some(path) => Some(path),
+        None => {
+            let pack_name = state.lock().unwrap().pack_metadata.pack_name.clone();
+            rfd::AsyncFileDialog::new()
+                .set_title("Save Project As...")
+                .set_file_name(format!("{}.{}", pack_name, PROJECT_EXTENSION))
+                .add_filter("Painting Pack Project", &[PROJECT_EXTENSION])
+                .save_file()
+                .await
+                .map(|file_handle| file_handle.path().to_path_buf())
+        }
+    };
+    let Some(target) = target else {
+        return Ok(None);
+    };
+    let saved = state.lock().unwrap().save_project(&target)?;
+    Ok(Some(saved.to_string_lossy().to_string()))
+}
+
+/*
+Opens a saved project (asking for the file if no path is sent), replacing the session with it and
+re-sending the library. Like `new_pack`, a library that isn't empty is only replaced once
+confirmed. Images whose sources have moved since keep their metadata and can be relinked. A
+damaged project file is recovered as far as possible and a "project-recovered" event reports
+what was lost.
+*/
+#[tauri::command]
+pub async fn open_project(
+    path: Option<String>,
+    confirm: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+    window: Window,
+) -> Result<Option<Confirmation>, String> {
+    println!("[COMMAND] open_project received commands.rs");
+    let known_path = in_scope(path, &state)?.or_else(|| confirm.as_deref().and_then(|token| state.lock().unwrap().confirmations.target_of(token)));
+    let source = match known_path {
+        Some(path) => Some(path),
+        None => allow_picked_file(
+            rfd::AsyncFileDialog::new()
+                .set_title("Open Project...")
+                .add_filter("Painting Pack Project", &[PROJECT_EXTENSION])
+                .pick_file()
+                .await
+                .map(|file_handle| file_handle.path().to_string_lossy().to_string()),
+            &state,
+        ),
+    };
+    let Some(source) = source else {
+        window.emit("processing-finished", ()).unwrap();
+        return Ok(None);
+    };
+
+    let result = open_project_from(&source, confirm.as_deref(), &state, &window);
+    window.emit("processing-finished", ()).unwrap();
+    result
+}
+
+fn open_project_from(source: &str, confirm: Option<&str>, state: &State<'_, Mutex<AppState>>, window: &Window) -> Result<Option<Confirmation>, String> {
+    let mut app_state = state.lock().unwrap();
+    let impact: Vec<String> = app_state.image_groups.iter().map(|group| group.name.clone()).collect();
+    let summary = format!("Replace all {} images in the library with the project?", impact.len());
+    if let Some(confirmation) = app_state.confirmations.require(confirm, "open_project", source, summary, impact) {
+        return Ok(Some(confirmation));
+    }
+    let recovery = app_state.open_project(Path::new(source))?;
+    emit_project(&mut app_state, window);
+    if let Some(report) = recovery {
+        window.emit("project-recovered", report).unwrap();
+    }
+    Ok(None)
+}
+
+/*
+Deletes the session's temp files (working copies, generated paintings, uploads) that no image in
+the library uses any more. Only the app's own temp folder is touched, never the user's files.
+*/
+#[tauri::command]
+pub fn purge_temp(state: State<'_, Mutex<AppState>>) -> Result<PurgeReport, String> {
+    println!("[COMMAND] purge_temp received commands.rs");
+    state.lock().unwrap().purge_temp()
+}
+
+/*
+Writes a printable PDF contact sheet of the pack: a thumbnail of every selected image with its
+name, artist and block sizes, for reviewing submissions offline or printing credits. Asks where to
+save it if no path is sent. Returns where it was written, or `None` if the dialog was cancelled.
+*/
+#[tauri::command]
+pub async fn export_contact_sheet(path: Option<String>, state: State<'_, Mutex<AppState>>) -> Result<Option<String>, String> {
+    println!("[COMMAND] export_contact_sheet received commands.rs");
+    let known_path = in_scope(path, &state)?.map(PathBuf::from);
+    let target = match known_path {
+        Some(path) => Some(path),
+        None => {
+            let pack_name = state.lock().unwrap().pack_metadata.pack_name.clone();
+            rfd::AsyncFileDialog::new()
+                .set_title("Save Contact Sheet As...")
+                .set_file_name(format!("{} contact sheet.pdf", pack_name))
+                .add_filter("PDF", &["pdf"])
+                .save_file()
+                .await
+                .map(|file_handle| file_handle.path().to_path_buf())
+        }
+    };
+    let Some(target) = target else {
+        return Ok(None);
+    };
+
+    // Thumbnails are cut outside the lock.
+    let (title, pack_id, items, default_artist) = {
+        let app_state = state.lock().unwrap();
+        let meta = &app_state.pack_metadata;
+        (format!("{} {}", meta.pack_name, meta.version), meta.id.clone(), app_state.collect_export_items(), app_state.export_settings.default_artist.clone())
+    };
+    let entries = contact_sheet::entries_for_items(&items, &default_artist);
+    let artists: HashSet<&str> = entries.iter().map(|entry| entry.artist.as_str()).collect();
+    let subtitle = format!("{} images by {} artists, pack ID {}", entries.len(), artists.len(), pack_id);
+    contact_sheet::write_contact_sheet(&target, &title, &subtitle, &entries)?;
+    Ok(Some(target.to_string_lossy().to_string()))
+}
+
+// Whether low-memory mode is on.
+#[tauri::command]
+pub fn get_low_memory_mode(state: State<'_, Mutex<AppState>>) -> bool {
+    println!("[COMMAND] get_low_memory_mode received commands.rs");
+    state.lock().unwrap().settings.low_memory
+}
+
+/*
+Turns low-memory mode on or off and saves the choice: sources are decoded at no more than
+`low_memory::MAX_DECODE_DIMENSION` px, exports use a single thread and previews are made one at a
+time. Meant for machines with little RAM and large libraries; exports of very large sources come
+out smaller while it is on, and sources too big to decode safely (see `low_memory::decode_limits`)
+are refused.
+*/
+#[tauri::command]
+pub fn set_low_memory_mode(enabled: bool, state: State<'_, Mutex<AppState>>) -> Result<(), String> {
+    println!("[COMMAND] set_low_memory_mode received commands.rs");
+    state.lock().unwrap().set_low_memory(enabled)
+}
+
+/*
+Checks the assets built into the app (the default pack icon and the pixel font) again, for the
+diagnostics view and the check the frontend runs on start.
+*/
+#[tauri::command]
+pub fn get_diagnostics() -> AssetReport {
+    println!("[COMMAND] get_diagnostics received commands.rs");
+    asset_check::check_bundled_assets()
+}
+
+/*
+Repairs library entries whose source files have moved: searches `root` (or a folder the user
+picks) and its subfolders for files with the same content or name and points the entries at
+them. The library is re-sent so the repaired rows get their previews back. Returns `None` if
+the dialog is cancelled.
+*/
+#[tauri::command]
+pub async fn relink_sources(
+    root: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+    window: Window,
+) -> Result<Option<RelinkReport>, String> {
+    println!("[COMMAND] relink_sources received commands.rs");
+    let Some(root) = folder_or_pick(root, "Find Moved Images In...", &state).await? else {
+        return Ok(None);
+    };
+    let mut app_state = state.lock().unwrap();
+    app_state.take_snapshot("relink sources");
+    let report = app_state.relink_sources(Path::new(&root))?;
+    if !report.relinked.is_empty() {
+        emit_project(&mut app_state, &window);
+    }
+    Ok(Some(report))
+}
+
+/*
+Moves library entries around (drag-reorder in the grid). `new_order` lists the current row
+indices in their new order. The grid is rebuilt so every row's index matches the state again.
+*/
+#[tauri::command]
+pub fn reorder_groups(new_order: Vec<usize>, state: State<'_, Mutex<AppState>>, window: Window) -> Result<(), String> {
+    println!("[COMMAND] reorder_groups received commands.rs");
+    let mut app_state = state.lock().unwrap();
+    app_state.reorder_groups(&new_order)?;
+    emit_project(&mut app_state, &window);
+    Ok(())
+}
+
+/*
+Controls the order paintings are listed in custompaintings.json, instead of leaving it to the order
+they were added: moves one library entry (`{ mode: "move", from, to }`) or sorts the listing by
+name or file name when the pack is exported (`{ mode: "sort_by", order: "Name" }`). The grid is
+rebuilt after a move.
+*/
+#[tauri::command]
+pub fn reorder_paintings(reorder: PaintingReorder, state: State<'_, Mutex<AppState>>, window: Window) -> Result<(), String> {
+    println!("[COMMAND] reorder_paintings received commands.rs");
+    let mut app_state = state.lock().unwrap();
+    app_state.reorder_paintings(reorder)?;
+    if let PaintingReorder::Move { .. } = reorder {
+        emit_project(&mut app_state, &window);
+    }
+    Ok(())
+}
+
+/*
+Binds the project to a "hot folder": images that appear there from now on are imported
+automatically, with the named template's metadata if one is given. Asks for the folder if
+none is sent. Each import is streamed to the frontend as it happens. Replaces any earlier
+watch. Returns the watched folder, or `None` if the picker was cancelled.
+*/
+#[tauri::command]
+pub async fn watch_folder(
+    path: Option<String>,
+    template_name: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+    window: Window,
+) -> Result<Option<String>, String> {
+    println!("[COMMAND] watch_folder received commands.rs");
+    let Some(path) = folder_or_pick(path, "Choose a Folder to Watch...", &state).await? else {
+        return Ok(None);
+    };
+    let folder = HotFolder::new(Path::new(&path))?;
+    let id = NEXT_HOT_FOLDER_ID.fetch_add(1, Ordering::Relaxed);
+    {
+        let mut app_state = state.lock().unwrap();
+        let template = template_name.map(|name| app_state.find_template(&name)).transpose()?;
+        app_state.hot_folder = Some(HotFolderWatch { id, folder, template });
+    }
+
+    thread::spawn(move || loop {
+        thread::sleep(HOT_FOLDER_POLL_INTERVAL);
+        let state = window.state::<Mutex<AppState>>();
+        let mut app_state = state.lock().unwrap();
+        // Stop once the watch has been cleared or replaced.
+        let Some(watch) = app_state.hot_folder.as_mut().filter(|watch| watch.id == id) else {
+            break;
+        };
+        let ready = match watch.folder.poll() {
+            Ok(ready) => ready,
+            Err(e) => {
+                // The folder was probably deleted or unmounted.
+                eprintln!("{}", e);
+                app_state.hot_folder = None;
+                window.emit("hot-folder-stopped", e).unwrap();
+                break;
+            }
+        };
+        let template = watch.template.clone();
+
+        for (index, file) in ready.iter().enumerate() {
+            let path_str = file.to_string_lossy().to_string();
+            let progress = ImportProgress { window: &window, path: &path_str, index, total: ready.len() };
+            let result = import_source(&progress, |group| {
+                if let Some(template) = &template {
+                    group.apply_template(template);
+                }
+            }, &mut app_state);
+            let payload = match result {
+                Ok(duplicate_of) => HotFolderImportPayload { path: path_str, duplicate_of, error: None },
+                Err(e) => HotFolderImportPayload { path: path_str, duplicate_of: None, error: Some(e) },
+            };
+            window.emit("hot-folder-imported", payload).unwrap();
+        }
+        if !ready.is_empty() {
+            window.emit("processing-finished", ()).unwrap();
+        }
+    });
+    Ok(Some(path))
+}
+
+#[tauri::command]
+pub fn unwatch_folder(state: State<'_, Mutex<AppState>>) {
+    println!("[COMMAND] unwatch_folder received commands.rs");
+    state.lock().unwrap().hot_folder = None;
+}
@@ -1,2 +0,0 @@
-pub mod cropper;
-pub mod exporter;
\ No newline at end of file
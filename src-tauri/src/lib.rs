@@ -1,45 +1,310 @@
 // src/lib.rs
 
-pub mod models;
-pub mod core;
+// The pipeline lives in the `painting-pack-core` crate; re-exported so `crate::core` keeps working here.
+pub use painting_pack_core::{core, error, models};
 pub mod commands;
 pub mod app_state; 
+#[cfg(feature = "server")]
+pub mod server;
 
 use app_state::AppState;
+use crate::core::{asset_check, export_queue::{ExportQueue, RunningExports}, low_memory, session_temp, settings::AppSettings};
 use std::sync::Mutex;
+use std::time::Duration;
 use tauri::menu::{MenuBuilder, MenuItemBuilder, SubmenuBuilder};
-use tauri::{App, Emitter};
+use tauri::{App, Emitter, Manager, RunEvent};
+
+// How often an open project that has been saved is autosaved.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(120);
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
   tauri::Builder::default()
     // MANAGE your AppState
     .manage(Mutex::new(AppState::default()))
+    // Shared by every export so `cancel_export` can stop whichever are running.
+    .manage(RunningExports::default())
     // SETUP the menu when the app starts
     .setup(|app| {
         build_menu(app)?;
+        // A broken build shows up in the log right away, not when the first pack is exported.
+        for problem in asset_check::check_bundled_assets().problems() {
+            eprintln!("[STARTUP] Bundled asset failed its check: {}", problem);
+        }
+        // Load the saved preferences (user templates etc.) from the app's config folder.
+        let settings_path = app.path().app_config_dir()?.join("settings.json");
+        {
+            let state = app.state::<Mutex<AppState>>();
+            let mut app_state = state.lock().unwrap();
+            app_state.settings = AppSettings::load(&settings_path).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                AppSettings::default()
+            });
+            app_state.settings_path = Some(settings_path);
+            low_memory::set_enabled(app_state.settings.low_memory);
+            // What the first-run wizard set up applies from the very first pack.
+            app_state.apply_setup_defaults();
+            // Consumed originals live on in the app's data folder rather than a temp folder.
+            app_state.store_dir = Some(app.path().app_local_data_dir()?.join("store"));
+            // Temp folders a crashed run left behind.
+            session_temp::remove_stale_sessions(&session_temp::temp_root(), &app_state.session_temp);
+        }
+        // Queued exports run one after another on a background worker.
+        let app_handle = app.handle().clone();
+        app.manage(ExportQueue::start(move |summary| {
+            app_handle.emit("export-job-updated", summary).unwrap();
+        }));
+        // A saved project is autosaved next to its file, so a damaged project file can be recovered.
+        let app_handle = app.handle().clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(AUTOSAVE_INTERVAL);
+            let state = app_handle.state::<Mutex<AppState>>();
+            let app_state = state.lock().unwrap();
+            // Keeps other instances from clearing this session's temp folder as stale.
+            app_state.session_temp.keep_alive();
+            if let Err(e) = app_state.autosave() {
+                eprintln!("{}", e);
+            }
+        });
         Ok(())
     })
     // REGISTER all your commands
     .invoke_handler(tauri::generate_handler![
       commands::my_custom_command,
       commands::open_and_process_images,
+      commands::create_collage,
+      commands::split_tall_image,
+      commands::create_text_painting,
+      commands::pick_font_file,
+      commands::create_pattern_painting,
       commands::set_selected,
+      commands::set_deskew,
+      commands::set_crop_margins,
+      commands::set_crop_offset,
+      commands::set_crop_rect,
+      commands::crop_geometry,
+      commands::set_fit_mode,
+      commands::refresh_previews,
+      commands::import_folder,
+      commands::import_vanilla_paintings,
+      commands::import_flipbook,
+      commands::import_sprite_sheet,
+      commands::import_pack,
+      commands::save_project,
+      commands::get_setup_proposal,
+      commands::pick_export_folder,
+      commands::complete_setup,
+      commands::open_project,
+      commands::inspect_pack,
+      commands::guide_previews,
+      commands::wall_mockup,
       commands::update_row_metadata,
+      commands::set_descriptions,
+      commands::set_frame,
+      commands::set_weight,
+      commands::set_size_adjustments,
+      commands::search_paintings,
+      commands::list_artists,
+      commands::merge_artists,
       commands::update_pack_metadata,
-      commands::export_pack
+      commands::balance_sizes,
+      commands::aspect_report,
+      commands::preview_strip,
+      commands::get_capabilities,
+      commands::update_export_settings,
+      commands::pick_palette_reference,
+      commands::update_import_settings,
+      commands::get_import_settings,
+      commands::get_export_settings,
+      commands::validate_pack_ids,
+      commands::check_pack_id,
+      commands::lint_pack,
+      commands::suggest_metadata,
+      commands::export_pack,
+      commands::start_export,
+      commands::queue_export,
+      commands::queue_split_export,
+      commands::validate_pack,
+      commands::export_contact_sheet,
+      commands::cancel_export,
+      commands::list_interrupted_exports,
+      commands::resume_export,
+      commands::discard_interrupted_export,
+      commands::list_export_history,
+      commands::reexport_from_history,
+      commands::list_export_jobs,
+      commands::clear_finished_export_jobs,
+      commands::compare_packs,
+      commands::take_snapshot,
+      commands::list_snapshots,
+      commands::rollback_snapshot,
+      commands::reorder_groups,
+      commands::reorder_paintings,
+      commands::list_pack_templates,
+      commands::save_pack_template,
+      commands::delete_pack_template,
+      commands::list_export_presets,
+      commands::save_export_preset,
+      commands::apply_export_preset,
+      commands::delete_export_preset,
+      commands::new_pack,
+      commands::purge_temp,
+      commands::get_low_memory_mode,
+      commands::set_low_memory_mode,
+      commands::relink_sources,
+      commands::get_diagnostics,
+      commands::assign_size,
+      commands::watch_folder,
+      commands::unwatch_folder
     ])
     // EMIT events from your menu to the frontend
     .on_menu_event(|app_handle, event| {
         match event.id().as_ref() {
             "quit" => { 
               println!("[COMMAND] quit received lib.rs");
-              std::process::exit(0); 
+              // Through the run loop, so the exit handler below cleans up after the session.
+              app_handle.exit(0); 
             }
             "open_and_process_images" => { 
               println!("[COMMAND] open_and_process_images received lib.rs");
               app_handle.emit("menu:open_and_process_images", ()).unwrap(); 
             }
+            "new_pack" => { 
+              println!("[COMMAND] new_pack received lib.rs");
+              app_handle.emit("menu:new_pack", ()).unwrap(); 
+            }
+            "import_folder" => { 
+              println!("[COMMAND] import_folder received lib.rs");
+              app_handle.emit("menu:import_folder", ()).unwrap(); 
+            }
+            "import_vanilla_paintings" => { 
+              println!("[COMMAND] import_vanilla_paintings received lib.rs");
+              app_handle.emit("menu:import_vanilla_paintings", ()).unwrap(); 
+            }
+            "first_run_setup" => { 
+              println!("[COMMAND] first_run_setup received lib.rs");
+              app_handle.emit("menu:first_run_setup", ()).unwrap(); 
+            }
+            "save_project" => { 
+              println!("[COMMAND] save_project received lib.rs");
+              app_handle.emit("menu:save_project", ()).unwrap(); 
+            }
+            "save_project_as" => { 
+              println!("[COMMAND] save_project_as received lib.rs");
+              app_handle.emit("menu:save_project_as", ()).unwrap(); 
+            }
+            "open_project" => { 
+              println!("[COMMAND] open_project received lib.rs");
+              app_handle.emit("menu:open_project", ()).unwrap(); 
+            }
+            "import_pack" => { 
+              println!("[COMMAND] import_pack received lib.rs");
+              app_handle.emit("menu:import_pack", ()).unwrap(); 
+            }
+            "inspect_pack" => { 
+              println!("[COMMAND] inspect_pack received lib.rs");
+              app_handle.emit("menu:inspect_pack", ()).unwrap(); 
+            }
+            "import_flipbook" => { 
+              println!("[COMMAND] import_flipbook received lib.rs");
+              app_handle.emit("menu:import_flipbook", ()).unwrap(); 
+            }
+            "import_sprite_sheet" => { 
+              println!("[COMMAND] import_sprite_sheet received lib.rs");
+              app_handle.emit("menu:import_sprite_sheet", ()).unwrap(); 
+            }
+            "normalize_artists" => { 
+              println!("[COMMAND] normalize_artists received lib.rs");
+              app_handle.emit("menu:normalize_artists", ()).unwrap(); 
+            }
+            "pixels_per_block" => { 
+              println!("[COMMAND] pixels_per_block received lib.rs");
+              app_handle.emit("menu:pixels_per_block", ()).unwrap(); 
+            }
+            "max_texture_size" => { 
+              println!("[COMMAND] max_texture_size received lib.rs");
+              app_handle.emit("menu:max_texture_size", ()).unwrap(); 
+            }
+            "resample_filter" => { 
+              println!("[COMMAND] resample_filter received lib.rs");
+              app_handle.emit("menu:resample_filter", ()).unwrap(); 
+            }
+            "pack_schema" => { 
+              println!("[COMMAND] pack_schema received lib.rs");
+              app_handle.emit("menu:pack_schema", ()).unwrap(); 
+            }
+            "painting_order" => { 
+              println!("[COMMAND] painting_order received lib.rs");
+              app_handle.emit("menu:painting_order", ()).unwrap(); 
+            }
+            "pack_overview" => { 
+              println!("[COMMAND] pack_overview received lib.rs");
+              app_handle.emit("menu:pack_overview", ()).unwrap(); 
+            }
+            "contact_sheet" => { 
+              println!("[COMMAND] contact_sheet received lib.rs");
+              app_handle.emit("menu:contact_sheet", ()).unwrap(); 
+            }
+            "export_history" => { 
+              println!("[COMMAND] export_history received lib.rs");
+              app_handle.emit("menu:export_history", ()).unwrap(); 
+            }
+            "split_size_export" => { 
+              println!("[COMMAND] split_size_export received lib.rs");
+              app_handle.emit("menu:split_size_export", ()).unwrap(); 
+            }
+            "split_export" => { 
+              println!("[COMMAND] split_export received lib.rs");
+              app_handle.emit("menu:split_export", ()).unwrap(); 
+            }
+            "set_descriptions" => { 
+              println!("[COMMAND] set_descriptions received lib.rs");
+              app_handle.emit("menu:set_descriptions", ()).unwrap(); 
+            }
+            "watch_folder" => { 
+              println!("[COMMAND] watch_folder received lib.rs");
+              app_handle.emit("menu:watch_folder", ()).unwrap(); 
+            }
+            "purge_temp" => { 
+              println!("[COMMAND] purge_temp received lib.rs");
+              app_handle.emit("menu:purge_temp", ()).unwrap(); 
+            }
+            "low_memory_mode" => { 
+              println!("[COMMAND] low_memory_mode received lib.rs");
+              app_handle.emit("menu:low_memory_mode", ()).unwrap(); 
+            }
+            "assign_size" => { 
+              println!("[COMMAND] assign_size received lib.rs");
+              app_handle.emit("menu:assign_size", ()).unwrap(); 
+            }
+            "diagnostics" => { 
+              println!("[COMMAND] diagnostics received lib.rs");
+              app_handle.emit("menu:diagnostics", ()).unwrap(); 
+            }
+            "relink_sources" => { 
+              println!("[COMMAND] relink_sources received lib.rs");
+              app_handle.emit("menu:relink_sources", ()).unwrap(); 
+            }
+            "unwatch_folder" => { 
+              println!("[COMMAND] unwatch_folder received lib.rs");
+              app_handle.emit("menu:unwatch_folder", ()).unwrap(); 
+            }
+            "balance_sizes" => { 
+              println!("[COMMAND] balance_sizes received lib.rs");
+              app_handle.emit("menu:balance_sizes", ()).unwrap(); 
+            }
+            "aspect_report" => { 
+              println!("[COMMAND] aspect_report received lib.rs");
+              app_handle.emit("menu:aspect_report", ()).unwrap(); 
+            }
+            "save_export_preset" => { 
+              println!("[COMMAND] save_export_preset received lib.rs");
+              app_handle.emit("menu:save_export_preset", ()).unwrap(); 
+            }
+            "apply_export_preset" => { 
+              println!("[COMMAND] apply_export_preset received lib.rs");
+              app_handle.emit("menu:apply_export_preset", ()).unwrap(); 
+            }
             "export_pack" => { 
               println!("[COMMAND] export_pack received lib.rs");
               app_handle.emit("menu:export_pack", ()).unwrap(); 
@@ -47,21 +312,99 @@ pub fn run() {
             _ => {}
         }
     })
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    .build(tauri::generate_context!())
+    .expect("error while building tauri application")
+    .run(|app_handle, event| {
+        // The session's temp files only live as long as the app.
+        if let RunEvent::Exit = event {
+            let state = app_handle.state::<Mutex<AppState>>();
+            let mut app_state = state.lock().unwrap();
+            app_state.session_temp.cleanup();
+            // Lets other instances open the project straight away.
+            app_state.project_lock = None;
+        }
+    });
 }
 
 fn build_menu(app: &App) -> tauri::Result<()> {
     let quit_item = MenuItemBuilder::new("Quit").id("quit").build(app)?;
+    let new_item = MenuItemBuilder::new("New Pack...").id("new_pack").build(app)?;
     let open_item = MenuItemBuilder::new("Open Image(s)").id("open_and_process_images").build(app)?;
+    let folder_item = MenuItemBuilder::new("Import Folder...").id("import_folder").build(app)?;
+    let vanilla_item = MenuItemBuilder::new("Import Vanilla Paintings...").id("import_vanilla_paintings").build(app)?;
+    let open_project_item = MenuItemBuilder::new("Open Project...").id("open_project").build(app)?;
+    let save_project_item = MenuItemBuilder::new("Save Project").id("save_project").build(app)?;
+    let save_project_as_item = MenuItemBuilder::new("Save Project As...").id("save_project_as").build(app)?;
+    let import_pack_item = MenuItemBuilder::new("Open Exported Pack...").id("import_pack").build(app)?;
+    let inspect_pack_item = MenuItemBuilder::new("Inspect Pack...").id("inspect_pack").build(app)?;
+    let flipbook_item = MenuItemBuilder::new("Import Image Sequence as Animation...").id("import_flipbook").build(app)?;
+    let sprite_sheet_item = MenuItemBuilder::new("Import Sprite Sheet...").id("import_sprite_sheet").build(app)?;
+    let watch_item = MenuItemBuilder::new("Watch Folder...").id("watch_folder").build(app)?;
+    let unwatch_item = MenuItemBuilder::new("Stop Watching Folder").id("unwatch_folder").build(app)?;
+    let setup_item = MenuItemBuilder::new("Setup Wizard...").id("first_run_setup").build(app)?;
+    let purge_item = MenuItemBuilder::new("Purge Temp Files").id("purge_temp").build(app)?;
+    let low_memory_item = MenuItemBuilder::new("Low-Memory Mode...").id("low_memory_mode").build(app)?;
+    let diagnostics_item = MenuItemBuilder::new("Diagnostics").id("diagnostics").build(app)?;
+    let relink_item = MenuItemBuilder::new("Relink Missing Images...").id("relink_sources").build(app)?;
+    let balance_item = MenuItemBuilder::new("Balance Sizes").id("balance_sizes").build(app)?;
+    let assign_size_item = MenuItemBuilder::new("Use One Shape for All...").id("assign_size").build(app)?;
+    let aspect_item = MenuItemBuilder::new("Aspect Ratio Report").id("aspect_report").build(app)?;
+    let overview_item = MenuItemBuilder::new("Pack Overview").id("pack_overview").build(app)?;
+    let artists_item = MenuItemBuilder::new("Normalize Artists...").id("normalize_artists").build(app)?;
+    let descriptions_item = MenuItemBuilder::new("Set Description for All Paintings...").id("set_descriptions").build(app)?;
+    let pixels_per_block_item = MenuItemBuilder::new("Pixels Per Block...").id("pixels_per_block").build(app)?;
+    let texture_size_item = MenuItemBuilder::new("Max Texture Size...").id("max_texture_size").build(app)?;
+    let resample_filter_item = MenuItemBuilder::new("Resampling Filter...").id("resample_filter").build(app)?;
+    let schema_item = MenuItemBuilder::new("Schema and Custom Fields...").id("pack_schema").build(app)?;
+    let painting_order_item = MenuItemBuilder::new("Painting Order...").id("painting_order").build(app)?;
     let export_item = MenuItemBuilder::new("Export Pack").id("export_pack").build(app)?;
+    let split_export_item = MenuItemBuilder::new("Export Split by Artist...").id("split_export").build(app)?;
+    let split_size_item = MenuItemBuilder::new("Export Split by Size...").id("split_size_export").build(app)?;
+    let contact_sheet_item = MenuItemBuilder::new("Export Contact Sheet (PDF)...").id("contact_sheet").build(app)?;
+    let export_history_item = MenuItemBuilder::new("Export History...").id("export_history").build(app)?;
+    let save_preset_item = MenuItemBuilder::new("Save Export Preset...").id("save_export_preset").build(app)?;
+    let apply_preset_item = MenuItemBuilder::new("Apply Export Preset...").id("apply_export_preset").build(app)?;
 
     let file_menu = SubmenuBuilder::new(app, "File")
+        .item(&new_item)
+        .item(&open_project_item)
+        .item(&save_project_item)
+        .item(&save_project_as_item)
+        .item(&import_pack_item)
+        .item(&inspect_pack_item)
         .item(&open_item)
+        .item(&folder_item)
+        .item(&vanilla_item)
+        .item(&flipbook_item)
+        .item(&sprite_sheet_item)
+        .item(&watch_item)
+        .item(&unwatch_item)
+        .item(&purge_item)
+        .item(&low_memory_item)
+        .item(&setup_item)
+        .item(&relink_item)
+        .item(&diagnostics_item)
         .build()?;
 
     let pack_menu = SubmenuBuilder::new(app, "Pack")
+        .item(&balance_item)
+        .item(&assign_size_item)
+        .item(&aspect_item)
+        .item(&overview_item)
+        .item(&descriptions_item)
+        .item(&artists_item)
+        .item(&pixels_per_block_item)
+        .item(&texture_size_item)
+        .item(&resample_filter_item)
+        .item(&schema_item)
+        .item(&painting_order_item)
         .item(&export_item)
+        .item(&split_export_item)
+        .item(&split_size_item)
+        .item(&contact_sheet_item)
+        .item(&export_history_item)
+        .item(&save_preset_item)
+        .item(&apply_preset_item)
         .build()?;
 
     let menu = MenuBuilder::new(app)
@@ -3,6 +3,16 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // `--serve [address]` runs the headless HTTP API instead of opening a window
+    #[cfg(feature = "server")]
+    if let Some(address) = app_lib::server::address_from_args(std::env::args()) {
+        if let Err(e) = app_lib::server::run(&address) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     // This calls the `run` function in your `lib.rs` file
     app_lib::run();
 }
\ No newline at end of file
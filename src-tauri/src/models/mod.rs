@@ -1,3 +0,0 @@
-pub mod image_data;
-pub mod pack_list;
-pub mod image_size;
\ No newline at end of file
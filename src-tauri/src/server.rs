@@ -0,0 +1,576 @@
+// src/server.rs
+
+/*
+Headless mode. Exposes the import -> crop -> export pipeline over a small local HTTP API so a
+Discord bot or web service can build painting packs from submissions without the GUI.
+Only compiled with the `server` feature and started with `app --serve [address]`.
+
+Endpoints (all bodies are JSON):
+  GET    /state          pack metadata, export and import settings and the imported images
+  POST   /import         { "paths": [...], "uploads": [{ "filename", "data" (base64) }] }
+                         exact duplicates of imported images are skipped and listed
+  PUT    /pack           { "pack_name", "version", "id", "description" }
+  PUT    /settings       ExportSettings
+  PUT    /import-settings ImportSettings (applies to later imports)
+  PUT    /groups/{index} { "name", "artist", "selected_sizes": ["Square", ...], "deskew" }
+  PUT    /groups/order   { "new_order": [2, 0, 1] } current indices in their new order
+  DELETE /groups         { "confirm" } removes every imported image; without a valid token it
+                         answers 409 with the confirmation whose token must be sent back
+  POST   /export         { "export_path", "confirm" } overwriting an existing pack answers 409
+                         with a confirmation the same way as DELETE /groups
+
+If `PPM_SERVER_TOKEN` is set, every request must send `Authorization: Bearer <token>`. Without a
+token the server only listens on loopback addresses. `/import` paths and `/export` folders must lie
+in `PPM_SERVER_ROOT` (by default the folder the server was started in); uploads are always allowed.
+Request bodies over `MAX_BODY_BYTES` are refused with 413.
+*/
+
+use std::env::current_dir;
+use std::fs::write;
+use std::io::Read;
+use std::net::ToSocketAddrs;
+use std::path::{Path, PathBuf};
+use base64::{Engine as _, engine::general_purpose};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tiny_http::{Header, Request, Response, Server};
+use crate::{
+    app_state::{AppState, Imported},
+    core::{cropper::CropOptions, naming},
+    models::{export_settings::ExportSettings, image_size::ImageSize, import_settings::ImportSettings, painting::{self, PaintingFrame}},
+};
+
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:7878";
+const TOKEN_ENV_VAR: &str = "PPM_SERVER_TOKEN";
+const ROOT_ENV_VAR: &str = "PPM_SERVER_ROOT";
+// Largest request body read, enough for a batch of base64 uploads to `/import`.
+const MAX_BODY_BYTES: u64 = 64 * 1024 * 1024;
+
+/*
+Returns the address to serve on if the app was started with `--serve`.
+`--serve` on its own uses the default loopback address.
+*/
+pub fn address_from_args(args: impl IntoIterator<Item = String>) -> Option<String> {
+    let mut args = args.into_iter().skip_while(|arg| arg != "--serve");
+    args.next()?;
+    Some(match args.next() {
+        Some(address) if !address.starts_with("--") => address,
+        _ => String::from(DEFAULT_ADDRESS),
+    })
+}
+
+// Whether `address` only resolves to addresses on this machine, so nothing else can reach it.
+fn is_loopback(address: &str) -> bool {
+    address
+        .to_socket_addrs()
+        .map(|addrs| addrs.collect::<Vec<_>>())
+        .is_ok_and(|addrs| !addrs.is_empty() && addrs.iter().all(|addr| addr.ip().is_loopback()))
+}
+
+// Refuses to serve the unauthenticated API anywhere other machines could reach it.
+fn check_exposure(address: &str, token: Option<&str>) -> Result<(), String> {
+    if token.is_none() && !is_loopback(address) {
+        return Err(format!("Set {} before serving on {}, which other machines can reach", TOKEN_ENV_VAR, address));
+    }
+    Ok(())
+}
+
+// The folder `/import` and `/export` may name paths in.
+fn serve_root() -> Result<PathBuf, String> {
+    match std::env::var(ROOT_ENV_VAR).ok().filter(|root| !root.is_empty()) {
+        Some(root) => Ok(PathBuf::from(root)),
+        None => current_dir().map_err(|e| format!("Failed to find the working folder: {}", e)),
+    }
+}
+
+struct ApiResponse {
+    status: u16,
+    body: Value,
+}
+
+impl ApiResponse {
+    fn ok(body: Value) -> Self {
+        ApiResponse { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        ApiResponse { status, body: json!({ "error": message.into() }) }
+    }
+}
+
+#[derive(Deserialize)]
+struct Upload {
+    filename: String,
+    data: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ImportRequest {
+    paths: Vec<String>,
+    uploads: Vec<Upload>,
+}
+
+#[derive(Deserialize)]
+struct PackRequest {
+    pack_name: String,
+    version: String,
+    id: String,
+    description: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct GroupRequest {
+    name: Option<String>,
+    artist: Option<String>,
+    description: Option<String>,
+    selected_sizes: Option<Vec<ImageSize>>,
+    deskew: Option<bool>,
+    inset_percent: Option<f32>,
+    bleed_percent: Option<f32>,
+    // An empty frame (`{}`) goes back to the mod's default frame.
+    frame: Option<PaintingFrame>,
+    // 1 goes back to the default weight.
+    weight: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct ReorderRequest {
+    new_order: Vec<usize>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ClearRequest {
+    confirm: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ExportRequest {
+    export_path: String,
+    #[serde(default)]
+    confirm: Option<String>,
+}
+
+fn parse_body<T: for<'de> Deserialize<'de>>(body: &str) -> Result<T, ApiResponse> {
+    serde_json::from_str(body).map_err(|e| ApiResponse::error(400, format!("Invalid request body: {}", e)))
+}
+
+// Decodes an uploaded file and stores it under a safe, unused name in the session's upload folder.
+fn save_upload(upload: &Upload, state: &AppState) -> Result<String, String> {
+    let bytes = general_purpose::STANDARD
+        .decode(&upload.data)
+        .map_err(|e| format!("{}: invalid base64 data ({})", upload.filename, e))?;
+
+    let dir = state.session_temp.subdir("uploads")?;
+
+    let file_name = Path::new(&upload.filename).file_name().unwrap_or_default().to_string_lossy().to_string();
+    let safe_name = naming::strip_unsafe_filename_chars(&file_name);
+    let mut path = dir.join(&safe_name);
+    let mut attempt = 2;
+    while path.exists() {
+        path = dir.join(format!("{}_{}", attempt, safe_name));
+        attempt += 1;
+    }
+
+    write(&path, bytes).map_err(|e| format!("{}: failed to save upload ({})", upload.filename, e))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+fn state_json(state: &AppState) -> Value {
+    let groups: Vec<Value> = state
+        .image_groups
+        .iter()
+        .enumerate()
+        .map(|(index, group)| {
+            let selected: Vec<ImageSize> = group.crops.iter().filter(|c| c.selected).map(|c| c.image_size).collect();
+            json!({
+                "index": index,
+                "source_path": group.source_path,
+                "original_path": group.original_path,
+                "name": group.name,
+                "artist": group.artist,
+                "description": group.description,
+                "selected_sizes": selected,
+                "deskew": group.deskew,
+                "inset_percent": group.inset_percent,
+                "bleed_percent": group.bleed_percent,
+                "frame": group.frame,
+                "weight": group.weight,
+                "animation": group.animation,
+                "order": group.order,
+            })
+        })
+        .collect();
+
+    json!({
+        "pack": state.pack_metadata,
+        "settings": state.export_settings,
+        "import_settings": state.import_settings,
+        "groups": groups,
+    })
+}
+
+fn import(state: &mut AppState, request: ImportRequest) -> ApiResponse {
+    let mut imported = Vec::new();
+    let mut duplicates = Vec::new();
+    let mut failed = Vec::new();
+
+    // Local paths must be in the server's root; uploads are saved into its own folder.
+    let mut paths = Vec::new();
+    for path in request.paths {
+        match state.path_scope.check(&path) {
+            Ok(path) => paths.push(path),
+            Err(e) => failed.push(json!({ "path": path, "error": e })),
+        }
+    }
+    for upload in &request.uploads {
+        match save_upload(upload, state) {
+            Ok(path) => paths.push(path),
+            Err(e) => failed.push(json!({ "path": upload.filename, "error": e })),
+        }
+    }
+
+    for path in paths {
+        // Reading the header is enough to reject files the cropper couldn't open later.
+        let group = image::image_dimensions(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|_| state.new_group(&path));
+        match group {
+            Ok(Imported::Group(group)) => {
+                state.add_group(*group);
+                imported.push(json!({ "index": state.image_groups.len() - 1, "path": path }));
+            }
+            Ok(Imported::Duplicate(existing)) => duplicates.push(json!({ "path": path, "existing": existing })),
+            Err(e) => failed.push(json!({ "path": path, "error": e })),
+        }
+    }
+
+    ApiResponse::ok(json!({ "imported": imported, "duplicates": duplicates, "failed": failed }))
+}
+
+fn update_group(state: &mut AppState, index: usize, request: GroupRequest) -> ApiResponse {
+    let Some(group) = state.image_groups.get_mut(index) else {
+        return ApiResponse::error(404, format!("No image at index {}", index));
+    };
+
+    if let Some(Err(e)) = request.frame.as_ref().map(PaintingFrame::validate) {
+        return ApiResponse::error(400, e);
+    }
+    if let Some(Err(e)) = request.weight.map(painting::validate_weight) {
+        return ApiResponse::error(400, e);
+    }
+
+    if let Some(name) = request.name {
+        group.name = name;
+    }
+    if let Some(frame) = request.frame {
+        group.frame = Some(frame).filter(|frame| !frame.is_empty());
+    }
+    if let Some(weight) = request.weight {
+        group.weight = Some(weight).filter(|weight| *weight != 1);
+    }
+    if let Some(artist) = request.artist {
+        group.artist = artist;
+    }
+    if let Some(description) = request.description {
+        group.description = description;
+    }
+    if let Some(deskew) = request.deskew {
+        group.deskew = deskew;
+    }
+    let options = CropOptions {
+        inset_percent: request.inset_percent.unwrap_or(group.inset_percent),
+        bleed_percent: request.bleed_percent.unwrap_or(group.bleed_percent),
+        ..group.crop_options()
+    };
+    if let Err(e) = options.validate() {
+        return ApiResponse::error(400, e);
+    }
+    group.inset_percent = options.inset_percent;
+    group.bleed_percent = options.bleed_percent;
+    if let Some(sizes) = request.selected_sizes {
+        for crop in &mut group.crops {
+            crop.selected = sizes.contains(&crop.image_size);
+        }
+    }
+
+    ApiResponse::ok(json!({ "updated": index }))
+}
+
+/*
+Dispatches one request against the headless state. Kept free of any HTTP types so the
+routing can be exercised directly.
+*/
+fn route(state: &mut AppState, method: &str, path: &str, body: &str) -> ApiResponse {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    let result = match (method, segments.as_slice()) {
+        ("GET", ["state"]) => Ok(ApiResponse::ok(state_json(state))),
+        ("POST", ["import"]) => parse_body(body).map(|request| import(state, request)),
+        ("PUT", ["pack"]) => parse_body::<PackRequest>(body).map(|request| {
+            let pack_metadata = &mut state.pack_metadata;
+            pack_metadata.set_pack_name(&request.pack_name);
+            pack_metadata.set_version(&request.version);
+            pack_metadata.set_id(&request.id);
+            pack_metadata.set_description(&request.description);
+            ApiResponse::ok(json!(state.pack_metadata))
+        }),
+        ("PUT", ["settings"]) => parse_body::<ExportSettings>(body).map(|settings| {
+            if let Err(e) = state.check_settings_scope(&settings) {
+                return ApiResponse::error(403, e);
+            }
+            match settings.validate() {
+                Ok(()) => {
+                    state.export_settings = settings;
+                    ApiResponse::ok(json!(state.export_settings))
+                }
+                Err(e) => ApiResponse::error(400, e),
+            }
+        }),
+        ("PUT", ["import-settings"]) => parse_body::<ImportSettings>(body).map(|settings| {
+            if let Err(e) = settings.validate() {
+                return ApiResponse::error(400, e);
+            }
+            state.import_settings = settings;
+            ApiResponse::ok(json!(state.import_settings))
+        }),
+        ("PUT", ["groups", "order"]) => parse_body::<ReorderRequest>(body).map(|request| {
+            match state.reorder_groups(&request.new_order) {
+                Ok(()) => ApiResponse::ok(state_json(state)),
+                Err(e) => ApiResponse::error(400, e),
+            }
+        }),
+        ("PUT", ["groups", index]) => match index.parse::<usize>() {
+            Ok(index) => parse_body(body).map(|request| update_group(state, index, request)),
+            Err(_) => Ok(ApiResponse::error(400, format!("Invalid image index \"{}\"", index))),
+        },
+        ("DELETE", ["groups"]) => {
+            let request = if body.trim().is_empty() { Ok(ClearRequest::default()) } else { parse_body::<ClearRequest>(body) };
+            request.map(|request| {
+                // Same confirmation as clearing the library in the app.
+                let impact: Vec<String> = state.image_groups.iter().map(|group| group.name.clone()).collect();
+                let summary = format!("Remove all {} images from the library?", impact.len());
+                if let Some(confirmation) = state.confirmations.require(request.confirm.as_deref(), "clear_library", "library", summary, impact) {
+                    return ApiResponse { status: 409, body: json!({ "error": "Send the confirmation token to clear the library", "confirmation": confirmation }) };
+                }
+                let removed = state.image_groups.len();
+                state.image_groups.clear();
+                ApiResponse::ok(json!({ "removed": removed }))
+            })
+        }
+        ("POST", ["export"]) => parse_body::<ExportRequest>(body).map(|request| {
+            // Reusing another pack's ID doesn't stop a headless export, but the caller is told.
+            let collision = state.pack_id_collision();
+            let export_path = state.resolve_export_path(&request.export_path);
+            if let Err(e) = state.check_export_scope(&export_path, &state.pack_metadata.pack_name) {
+                return ApiResponse::error(403, e);
+            }
+            // Same confirmation as overwriting a pack from the app.
+            if let Some(confirmation) = state.confirm_overwrite(&export_path, request.confirm.as_deref()) {
+                return ApiResponse { status: 409, body: json!({ "error": "Send the confirmation token to overwrite the existing pack", "confirmation": confirmation }) };
+            }
+            match state.export_pack(&export_path) {
+                Ok(report) => {
+                    state.take_snapshot("export");
+                    if let Err(e) = state.record_export() {
+                        eprintln!("{}", e);
+                    }
+                    ApiResponse::ok(json!({ "exported_to": export_path, "pack_id_collision": collision, "report": report }))
+                }
+                Err(e) => ApiResponse::error(422, e.to_string()),
+            }
+        }),
+        _ => Ok(ApiResponse::error(404, format!("No route for {} {}", method, path))),
+    };
+
+    result.unwrap_or_else(|error_response| error_response)
+}
+
+fn body_too_large() -> ApiResponse {
+    ApiResponse::error(413, format!("Request bodies are limited to {} MiB", MAX_BODY_BYTES / (1024 * 1024)))
+}
+
+fn is_authorized(request: &Request, token: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+    let expected = format!("Bearer {}", token);
+    request
+        .headers()
+        .iter()
+        .any(|header| header.field.equiv("Authorization") && constant_time_eq(header.value.as_str().as_bytes(), expected.as_bytes()))
+}
+
+// Compares secrets without returning early, so response times don't give away how much of a guess was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/*
+Runs the headless server until the process is stopped. Requests are handled one at a time
+against a single in-memory project, mirroring how the GUI locks its state per command.
+*/
+pub fn run(address: &str) -> Result<(), String> {
+    let token = std::env::var(TOKEN_ENV_VAR).ok().filter(|t| !t.is_empty());
+    check_exposure(address, token.as_deref())?;
+    let root = serve_root()?;
+    let server = Server::http(address).map_err(|e| format!("Failed to bind {}: {}", address, e))?;
+    let mut state = AppState::default();
+    state.path_scope.allow_folder(&root);
+
+    println!("[SERVER] listening on http://{}, serving files in {}", address, root.display());
+    if token.is_none() {
+        println!("[SERVER] {} is not set, requests are not authenticated", TOKEN_ENV_VAR);
+    }
+
+    for mut request in server.incoming_requests() {
+        let method = request.method().as_str().to_uppercase();
+        let url = request.url().to_string();
+        let path = url.split('?').next().unwrap_or_default().to_string();
+        println!("[SERVER] {} {}", method, path);
+
+        let response = if !is_authorized(&request, token.as_deref()) {
+            ApiResponse::error(401, "Missing or invalid bearer token")
+        } else if request.body_length().is_some_and(|length| length as u64 > MAX_BODY_BYTES) {
+            body_too_large()
+        } else {
+            // Chunked bodies don't announce their length, so never read past the cap either.
+            let mut body = String::new();
+            match request.as_reader().take(MAX_BODY_BYTES + 1).read_to_string(&mut body) {
+                Ok(read) if read as u64 > MAX_BODY_BYTES => body_too_large(),
+                Ok(_) => route(&mut state, &method, &path, &body),
+                Err(e) => ApiResponse::error(400, format!("Failed to read request body: {}", e)),
+            }
+        };
+
+        let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+        let http_response = Response::from_string(response.body.to_string())
+            .with_status_code(response.status)
+            .with_header(content_type);
+        if let Err(e) = request.respond(http_response) {
+            eprintln!("[SERVER] failed to send response: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_address_from_args() {
+        assert_eq!(address_from_args(args(&["app"])), None);
+        assert_eq!(address_from_args(args(&["app", "--serve"])), Some(DEFAULT_ADDRESS.to_string()));
+        assert_eq!(address_from_args(args(&["app", "--serve", "0.0.0.0:9000"])), Some("0.0.0.0:9000".to_string()));
+    }
+
+    #[test]
+    fn test_clearing_the_library_needs_confirmation() {
+        let mut state = AppState::default();
+        state.add_group(crate::app_state::SourceImageGroup::new("/art/oak.png"));
+        let response = route(&mut state, "DELETE", "/groups", "");
+        assert_eq!(response.status, 409);
+        assert_eq!(state.image_groups.len(), 1);
+        let token = response.body["confirmation"]["token"].as_str().unwrap().to_string();
+
+        assert_eq!(route(&mut state, "DELETE", "/groups", r#"{ "confirm": "wrong" }"#).status, 409);
+        assert_eq!(state.image_groups.len(), 1);
+        let body = json!({ "confirm": token }).to_string();
+        let response = route(&mut state, "DELETE", "/groups", &body);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["removed"], 1);
+        assert!(state.image_groups.is_empty());
+    }
+
+    #[test]
+    fn test_reachable_addresses_need_a_token() {
+        assert!(check_exposure(DEFAULT_ADDRESS, None).is_ok());
+        assert!(check_exposure("[::1]:7878", None).is_ok());
+        assert!(check_exposure("0.0.0.0:9000", None).unwrap_err().contains(TOKEN_ENV_VAR));
+        assert!(check_exposure("192.168.1.20:9000", None).is_err());
+        assert!(check_exposure("0.0.0.0:9000", Some("secret")).is_ok());
+    }
+
+    #[test]
+    fn test_constant_time_eq_matches_only_identical_tokens() {
+        assert!(constant_time_eq(b"Bearer abc", b"Bearer abc"));
+        assert!(!constant_time_eq(b"Bearer abd", b"Bearer abc"));
+        assert!(!constant_time_eq(b"Bearer ab", b"Bearer abc"));
+    }
+
+    #[test]
+    fn test_import_and_export_stay_inside_the_root() {
+        let root = std::env::temp_dir().join(format!("server_root_test_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let outside = std::env::temp_dir().join(format!("server_outside_test_{}.png", std::process::id()));
+        image::RgbaImage::new(16, 16).save(&outside).unwrap();
+        let mut state = AppState::default();
+        state.path_scope.allow_folder(&root);
+
+        let body = json!({ "paths": [outside] }).to_string();
+        let response = route(&mut state, "POST", "/import", &body);
+        assert_eq!(response.body["imported"].as_array().unwrap().len(), 0);
+        assert!(response.body["failed"][0]["error"].as_str().unwrap().contains("won't touch it"));
+        assert!(outside.exists());
+
+        let body = json!({ "export_path": std::env::temp_dir() }).to_string();
+        assert_eq!(route(&mut state, "POST", "/export", &body).status, 403);
+        let _ = std::fs::remove_file(&outside);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_overwriting_an_export_needs_confirmation() {
+        let root = std::env::temp_dir().join(format!("server_overwrite_test_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let source = root.join("oak.png");
+        image::RgbaImage::new(16, 16).save(&source).unwrap();
+        let mut state = AppState::default();
+        state.path_scope.allow_folder(&root);
+        state.pack_metadata.set_pack_name("Bot Pack");
+        state.add_group(crate::app_state::SourceImageGroup::new(&source.to_string_lossy()));
+
+        let body = json!({ "export_path": root.join("out") }).to_string();
+        assert_eq!(route(&mut state, "POST", "/export", &body).status, 200);
+        let response = route(&mut state, "POST", "/export", &body);
+        assert_eq!(response.status, 409);
+        let token = response.body["confirmation"]["token"].as_str().unwrap().to_string();
+        let body = json!({ "export_path": root.join("out"), "confirm": token }).to_string();
+        assert_eq!(route(&mut state, "POST", "/export", &body).status, 200);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_route_updates_pack_and_reports_state() {
+        let mut state = AppState::default();
+        let body = r#"{ "pack_name": "Bot Pack", "version": "2.0.0", "id": "bot_pack", "description": "From submissions" }"#;
+        assert_eq!(route(&mut state, "PUT", "/pack", body).status, 200);
+
+        let response = route(&mut state, "GET", "/state", "");
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["pack"]["name"], "Bot Pack");
+        assert_eq!(response.body["groups"].as_array().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_route_errors() {
+        let mut state = AppState::default();
+        assert_eq!(route(&mut state, "GET", "/nope", "").status, 404);
+        assert_eq!(route(&mut state, "PUT", "/groups/0", "{}").status, 404);
+        assert_eq!(route(&mut state, "PUT", "/groups/abc", "{}").status, 400);
+        assert_eq!(route(&mut state, "POST", "/import", "not json").status, 400);
+
+        // Missing files are reported per path rather than failing the whole request
+        let response = route(&mut state, "POST", "/import", r#"{ "paths": ["missing.png"] }"#);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["failed"].as_array().unwrap().len(), 1);
+    }
+}